@@ -25,6 +25,7 @@ echo "Enforcing network isolation policies..."
 # Generate mitmproxy filter script from configuration
 cat > /tmp/mitmproxy_filter.py << 'FILTER_SCRIPT_EOF'
 from mitmproxy import http
+import ipaddress
 import os
 import json
 import time
@@ -37,6 +38,19 @@ ALLOWED_DOMAINS = [d.strip() for d in os.environ.get("ALLOWED_DOMAINS", "").spli
 BLOCKED_DOMAINS = [d.strip() for d in os.environ.get("BLOCKED_DOMAINS", "").split(",") if d.strip()]
 BYPASS_DOMAINS = [d.strip() for d in os.environ.get("BYPASS_DOMAINS", "").split(",") if d.strip()]
 
+def parse_networks(value):
+    """Parse comma-separated CIDR ranges, skipping invalid ones (claude-vm warns about them)"""
+    networks = []
+    for cidr in value.split(","):
+        try:
+            networks.append(ipaddress.ip_network(cidr.strip(), strict=False))
+        except ValueError:
+            pass
+    return networks
+
+ALLOWED_CIDRS = parse_networks(os.environ.get("ALLOWED_CIDRS", ""))
+BLOCKED_CIDRS = parse_networks(os.environ.get("BLOCKED_CIDRS", ""))
+
 # Statistics tracking
 STATS_FILE = Path("/tmp/mitmproxy_stats.json")
 stats = {
@@ -97,6 +111,29 @@ def matches_any(host, patterns):
     """Check if host matches any pattern in the list"""
     return any(matches_pattern(host, p) for p in patterns)
 
+def flow_addresses(flow):
+    """IP addresses of a request, without resolving names: the host when it is an
+    IP literal, and the address of the server connection when already connected.
+    A separate DNS lookup could answer differently than the one used to connect."""
+    addresses = []
+    try:
+        addresses.append(ipaddress.ip_address(flow.request.pretty_host.strip("[]")))
+    except ValueError:
+        pass
+    peername = flow.server_conn.peername if flow.server_conn else None
+    if peername:
+        try:
+            addresses.append(ipaddress.ip_address(peername[0]))
+        except ValueError:
+            pass
+    return addresses
+
+def matches_cidr(flow, networks):
+    """Check if the request goes to an address in any of the CIDR ranges"""
+    if not networks:
+        return False
+    return any(addr in net for addr in flow_addresses(flow) for net in networks)
+
 def request(flow: http.HTTPFlow) -> None:
     """Filter requests based on domain policy"""
     stats["requests_total"] += 1
@@ -111,7 +148,7 @@ def request(flow: http.HTTPFlow) -> None:
 
     if MODE == "allowlist":
         # Block unless explicitly allowed
-        if not matches_any(host, ALLOWED_DOMAINS):
+        if not (matches_any(host, ALLOWED_DOMAINS) or matches_cidr(flow, ALLOWED_CIDRS)):
             stats["requests_blocked"] += 1
             update_stats()
             flow.response = http.Response.make(
@@ -122,7 +159,7 @@ def request(flow: http.HTTPFlow) -> None:
             return
     elif MODE == "denylist":
         # Allow unless explicitly blocked
-        if matches_any(host, BLOCKED_DOMAINS):
+        if matches_any(host, BLOCKED_DOMAINS) or matches_cidr(flow, BLOCKED_CIDRS):
             stats["requests_blocked"] += 1
             update_stats()
             flow.response = http.Response.make(
@@ -148,16 +185,37 @@ export POLICY_MODE="${POLICY_MODE:-denylist}"
 export ALLOWED_DOMAINS="${ALLOWED_DOMAINS:-}"
 export BLOCKED_DOMAINS="${BLOCKED_DOMAINS:-}"
 export BYPASS_DOMAINS="${BYPASS_DOMAINS:-}"
-
-# Rotate log file if it exists and is too large (>10MB)
-# Prevents disk space issues from unbounded log growth
-if [ -f /tmp/mitmproxy.log ]; then
-    LOG_SIZE=$(stat -c%s /tmp/mitmproxy.log 2>/dev/null || stat -f%z /tmp/mitmproxy.log 2>/dev/null || echo 0)
-    if [ "$LOG_SIZE" -gt 10485760 ]; then
-        echo "  Rotating large log file ($(($LOG_SIZE / 1048576))MB)"
-        mv /tmp/mitmproxy.log /tmp/mitmproxy.log.old
-    fi
-fi
+export ALLOWED_CIDRS="${ALLOWED_CIDRS:-}"
+export BLOCKED_CIDRS="${BLOCKED_CIDRS:-}"
+
+# Write proxy output to /tmp/mitmproxy.log, rotating it once it reaches
+# NETWORK_LOG_MAX_MB (0 disables rotation). Rotated logs are kept as
+# mitmproxy.log.1 (most recent) to mitmproxy.log.3 (oldest), so long sessions
+# cannot fill the VM disk. 'claude-vm network logs' reads across them.
+write_proxy_log() {
+    local LC_ALL=C
+    local log=/tmp/mitmproxy.log
+    local keep=3
+    local max_bytes=$(( ${NETWORK_LOG_MAX_MB:-10} * 1048576 ))
+    local size=0 line i
+
+    rm -f "$log".[0-9]*
+    : > "$log"
+    while IFS= read -r line; do
+        printf '%s\n' "$line" >> "$log"
+        size=$(( size + ${#line} + 1 ))
+        if [ "$max_bytes" -gt 0 ] && [ "$size" -ge "$max_bytes" ]; then
+            for (( i = keep - 1; i >= 1; i-- )); do
+                if [ -f "$log.$i" ]; then
+                    mv -f "$log.$i" "$log.$(( i + 1 ))"
+                fi
+            done
+            mv -f "$log" "$log.1"
+            : > "$log"
+            size=0
+        fi
+    done
+}
 
 # Build mitmproxy ignore_hosts option for true bypass (no TLS interception)
 # ignore_hosts expects a REGEX pattern matching "host:port" format
@@ -197,7 +255,7 @@ mitmdump \
   --ssl-insecure \
   $IGNORE_HOSTS_ARG \
   -s /tmp/mitmproxy_filter.py \
-  > /tmp/mitmproxy.log 2>&1 &
+  > >(write_proxy_log) 2>&1 &
 
 PROXY_PID=$!
 
@@ -269,6 +327,13 @@ else
     echo "    Bypass: none"
 fi
 
+if [ -n "${ALLOWED_CIDRS:-}" ]; then
+    echo "    Allowed ranges: ${ALLOWED_CIDRS}"
+fi
+if [ -n "${BLOCKED_CIDRS:-}" ]; then
+    echo "    Blocked ranges: ${BLOCKED_CIDRS}"
+fi
+
 echo ""
 echo "  Protocol Blocks:"
 
@@ -0,0 +1,201 @@
+//! Host-side log of a template build, for diagnosing failed setups after the fact.
+//!
+//! With `[setup] build_log` or `setup --build-log <path>`, everything setup
+//! prints (progress, warnings and the output of commands run in the VM) is also
+//! written to the file, one timestamped line at a time. Each setup step starts
+//! with a `==> <step>` marker so a failure can be located quickly.
+
+use crate::config::BuildLogMode;
+use crate::error::{ClaudeVmError, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Open build log, while a setup with a build log runs
+static BUILD_LOG: Mutex<Option<BuildLog>> = Mutex::new(None);
+
+/// Previous logs kept with `build_log_mode = "rotate"` (`<path>.1` is the newest)
+const KEEP_ROTATED: usize = 5;
+
+/// A build log file
+pub struct BuildLog {
+    file: File,
+}
+
+impl BuildLog {
+    /// Create the log at `path`, replacing the previous one or rotating it away
+    pub fn create(path: &Path, mode: BuildLogMode) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        if mode == BuildLogMode::Rotate && path.exists() {
+            rotate(path)?;
+        }
+        let file = File::create(path).map_err(|e| {
+            ClaudeVmError::InvalidConfig(format!(
+                "Cannot write build log {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(Self { file })
+    }
+
+    /// Append `text`, one timestamped line per line of text
+    pub fn record(&mut self, text: &str) {
+        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        for line in text.lines() {
+            // Best effort: a full disk must not abort the setup
+            let _ = writeln!(self.file, "[{}] {}", timestamp, line);
+        }
+    }
+
+    /// Mark the start of a setup step
+    pub fn phase(&mut self, name: &str) {
+        self.record(&format!("==> {}", name));
+    }
+}
+
+/// `<path>.<n>`, the n-th most recent rotated log
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// Shift `<path>` to `<path>.1`, `<path>.1` to `<path>.2`, ... dropping the oldest
+fn rotate(path: &Path) -> Result<()> {
+    for n in (1..KEEP_ROTATED).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            std::fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    std::fs::rename(path, rotated_path(path, 1))?;
+    Ok(())
+}
+
+/// Start logging to `path` for the rest of the setup
+pub fn start(path: &Path, mode: BuildLogMode) -> Result<()> {
+    let log = BuildLog::create(path, mode)?;
+    *BUILD_LOG.lock().unwrap_or_else(|e| e.into_inner()) = Some(log);
+    Ok(())
+}
+
+/// Close the build log
+pub fn stop() {
+    *BUILD_LOG.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Whether a build log is being written
+pub fn is_active() -> bool {
+    BUILD_LOG
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some()
+}
+
+/// Append `text` to the build log (no-op unless started)
+pub fn record(text: &str) {
+    if let Some(log) = BUILD_LOG.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        log.record(text);
+    }
+}
+
+/// Mark the start of a setup step in the build log (no-op unless started)
+pub fn phase(name: &str) {
+    if let Some(log) = BUILD_LOG.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        log.phase(name);
+    }
+}
+
+/// Writer forwarding everything to `inner` and recording each complete line in
+/// the build log, so streamed command output is logged as the user sees it
+pub struct Tee<W: Write> {
+    inner: W,
+    pending: Vec<u8>,
+}
+
+/// Wrap `inner` so what is written to it also goes to the build log
+pub fn tee<W: Write>(inner: W) -> Tee<W> {
+    Tee {
+        inner,
+        pending: Vec::new(),
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.pending.extend_from_slice(&buf[..written]);
+        if let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') {
+            let lines: Vec<u8> = self.pending.drain(..=end).collect();
+            record(&String::from_utf8_lossy(&lines));
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for Tee<W> {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            record(&String::from_utf8_lossy(&self.pending));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_log_records_phase_markers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("logs").join("setup.log");
+
+        let mut log = BuildLog::create(&path, BuildLogMode::Overwrite).unwrap();
+        log.phase("base packages");
+        log.record("Reading package lists...\nDone");
+        log.phase("vm setup");
+        drop(log);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with('[') && lines[0].ends_with("] ==> base packages"));
+        assert!(lines[2].ends_with("] Done"));
+        assert!(lines[3].ends_with("] ==> vm setup"));
+
+        // Overwrite mode replaces the previous build's log
+        BuildLog::create(&path, BuildLogMode::Overwrite)
+            .unwrap()
+            .phase("next build");
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("base packages"));
+        assert!(!rotated_path(&path, 1).exists());
+    }
+
+    #[test]
+    fn test_build_log_rotation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("setup.log");
+
+        for build in 0..KEEP_ROTATED + 2 {
+            BuildLog::create(&path, BuildLogMode::Rotate)
+                .unwrap()
+                .phase(&format!("build {}", build));
+        }
+
+        let newest = std::fs::read_to_string(&path).unwrap();
+        assert!(newest.contains(&format!("build {}", KEEP_ROTATED + 1)));
+        let previous = std::fs::read_to_string(rotated_path(&path, 1)).unwrap();
+        assert!(previous.contains(&format!("build {}", KEEP_ROTATED)));
+        assert!(rotated_path(&path, KEEP_ROTATED).exists());
+        assert!(!rotated_path(&path, KEEP_ROTATED + 1).exists());
+    }
+}
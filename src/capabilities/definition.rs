@@ -3,6 +3,7 @@
 //! These types define the schema for capability definitions.
 
 use serde::Deserialize;
+use std::collections::BTreeMap;
 
 /// A capability definition loaded from a TOML file.
 ///
@@ -28,6 +29,11 @@ pub struct Capability {
     #[serde(default)]
     pub vm_runtime: Option<ScriptConfig>,
 
+    /// Optional post-install check (runs in VM after all vm_setup hooks).
+    /// A non-zero exit fails the template build.
+    #[serde(default)]
+    pub verify: Option<ScriptConfig>,
+
     /// MCP servers to register
     #[serde(default)]
     pub mcp: Vec<McpServer>,
@@ -48,6 +54,18 @@ pub struct CapabilityMeta {
 
     #[serde(default)]
     pub conflicts: Vec<String>,
+
+    /// Arguments added to every agent invocation while the capability is enabled
+    #[serde(default)]
+    pub agent_args: Vec<String>,
+
+    /// Smallest VM disk in GB the capability works with
+    #[serde(default)]
+    pub min_disk: Option<u32>,
+
+    /// Smallest VM memory in GB the capability works with
+    #[serde(default)]
+    pub min_memory: Option<u32>,
 }
 
 /// Package specifications for a capability.
@@ -97,6 +115,22 @@ pub struct PackageSpec {
     /// Example: Docker needs a custom repository setup
     #[serde(default)]
     pub setup_script: Option<String>,
+
+    /// Optional signing key for the repository added by `setup_script`.
+    /// Verified against its fingerprint and installed to
+    /// `/etc/apt/keyrings/<capability-id>.gpg` before `setup_script` runs.
+    #[serde(default)]
+    pub keyring: Option<KeyringSpec>,
+}
+
+/// Repository signing key, pinned by fingerprint
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyringSpec {
+    /// HTTPS URL of the key (ASCII-armored or binary)
+    pub url: String,
+
+    /// Expected fingerprint of the primary key (spaces are ignored)
+    pub fingerprint: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -116,6 +150,10 @@ pub struct McpServer {
     pub command: String,
     pub args: Vec<String>,
 
+    /// Environment variables passed to the server process
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
     #[serde(default)]
     pub enabled_when: Option<String>,
 }
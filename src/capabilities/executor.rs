@@ -1,7 +1,9 @@
-use super::definition::{Capability, McpServer, ScriptConfig};
+use super::definition::{Capability, KeyringSpec, McpServer, ScriptConfig};
+use crate::config::Config;
 use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
 use crate::scripts::runner;
+use crate::status;
 use crate::version;
 use crate::vm::limactl::LimaCtl;
 use std::collections::HashMap;
@@ -38,11 +40,13 @@ impl CapabilityPhase {
 /// Build environment variables for capability scripts
 fn build_capability_env_vars(
     project: &Project,
+    config: &Config,
     vm_name: &str,
     capability_id: &str,
     phase: CapabilityPhase,
 ) -> Result<HashMap<String, String>> {
-    let mut env_vars = HashMap::new();
+    // User options from [capabilities.<id>] (CAPABILITY_CONFIG_*)
+    let mut env_vars = config.capability_env(capability_id);
 
     // VM identification
     env_vars.insert(
@@ -125,29 +129,39 @@ fn build_capability_env_vars(
 }
 
 /// Execute a capability's host_setup hook (runs on host machine)
-pub fn execute_host_setup(project: &Project, capability: &Arc<Capability>) -> Result<()> {
+pub fn execute_host_setup(
+    project: &Project,
+    config: &Config,
+    capability: &Arc<Capability>,
+) -> Result<()> {
     let Some(host_setup) = &capability.host_setup else {
         return Ok(());
     };
 
-    println!("Running host setup for {}...", capability.capability.name);
+    status!("Running host setup for {}...", capability.capability.name);
 
-    execute_host_script(project, host_setup, &capability.capability.id)?;
+    execute_host_script(project, config, host_setup, &capability.capability.id)?;
 
     Ok(())
 }
 
 /// Execute a capability's vm_setup hook (runs in VM)
-pub fn execute_vm_setup(project: &Project, capability: &Arc<Capability>) -> Result<()> {
+pub fn execute_vm_setup(
+    project: &Project,
+    config: &Config,
+    capability: &Arc<Capability>,
+    prefix_output: bool,
+) -> Result<()> {
     let Some(vm_setup) = &capability.vm_setup else {
         return Ok(());
     };
 
-    println!("Setting up {}...", capability.capability.name);
+    status!("Setting up {}...", capability.capability.name);
 
     let vm_name = project.template_name();
     let env_vars = build_capability_env_vars(
         project,
+        config,
         vm_name,
         &capability.capability.id,
         CapabilityPhase::Setup,
@@ -157,88 +171,38 @@ pub fn execute_vm_setup(project: &Project, capability: &Arc<Capability>) -> Resu
         vm_name,
         vm_setup,
         &capability.capability.id,
-        false,
+        prefix_output,
         &env_vars,
     )?;
 
     Ok(())
 }
 
-/// Execute a capability's vm_runtime hook (runs in VM before each session)
-pub fn execute_vm_runtime(project: &Project, capability: &Arc<Capability>) -> Result<()> {
-    let Some(vm_runtime) = &capability.vm_runtime else {
-        return Ok(());
+/// Run a capability's verify check in the template VM, capturing its output.
+/// Returns None when the capability defines no check.
+pub fn execute_verify(
+    project: &Project,
+    config: &Config,
+    capability: &Arc<Capability>,
+) -> Result<Option<runner::CapturedScript>> {
+    let Some(verify) = &capability.verify else {
+        return Ok(None);
     };
 
     let vm_name = project.template_name();
     let env_vars = build_capability_env_vars(
         project,
+        config,
         vm_name,
         &capability.capability.id,
-        CapabilityPhase::Runtime,
-    )?;
-
-    // Runtime scripts are executed silently unless there's an error
-    execute_vm_script(
-        vm_name,
-        vm_runtime,
-        &capability.capability.id,
-        true,
-        &env_vars,
+        CapabilityPhase::Setup,
     )?;
 
-    Ok(())
-}
-
-/// Execute a capability's vm_runtime hook in a specific VM instance
-pub fn execute_vm_runtime_in_vm(vm_name: &str, capability: &Arc<Capability>) -> Result<()> {
-    let Some(vm_runtime) = &capability.vm_runtime else {
-        return Ok(());
-    };
-
-    // Build minimal env vars (no Project available in this context)
-    //
-    // This function is called when running runtime scripts in an ephemeral VM
-    // where the Project context isn't available yet. This happens during VM
-    // initialization before the project directory is mounted.
-    //
-    // We provide essential runtime information (VM name, capability ID, phase, version)
-    // but set project-related variables to empty strings to ensure scripts don't
-    // fail on undefined variables. Scripts should check if these vars are non-empty
-    // before using them.
-    let mut env_vars = HashMap::new();
-    env_vars.insert("LIMA_INSTANCE".to_string(), vm_name.to_string());
-    env_vars.insert(
-        "CAPABILITY_ID".to_string(),
-        capability.capability.id.clone(),
-    );
-    env_vars.insert(
-        "CLAUDE_VM_PHASE".to_string(),
-        CapabilityPhase::Runtime.as_str().to_string(),
-    );
-    env_vars.insert(
-        "CLAUDE_VM_VERSION".to_string(),
-        version::VERSION.to_string(),
-    );
-
-    // Project-related vars are set to empty strings in this minimal context
-    // since the Project object isn't available during early VM initialization
-    ensure_env_var(&mut env_vars, "TEMPLATE_NAME");
-    ensure_env_var(&mut env_vars, "PROJECT_ROOT");
-    ensure_env_var(&mut env_vars, "PROJECT_NAME");
-    ensure_env_var(&mut env_vars, "PROJECT_WORKTREE_ROOT");
-    ensure_env_var(&mut env_vars, "PROJECT_WORKTREE");
-
-    // Runtime scripts are executed silently unless there's an error
-    execute_vm_script(
-        vm_name,
-        vm_runtime,
-        &capability.capability.id,
-        true,
-        &env_vars,
-    )?;
+    let script_content = get_script_content(verify, &capability.capability.id)?;
+    let wrapped_script = wrap_script_with_env_vars(&script_content, &env_vars);
+    let filename = format!("{}_verify.sh", capability.capability.id);
 
-    Ok(())
+    runner::execute_script_captured(vm_name, &wrapped_script, &filename).map(Some)
 }
 
 /// Install vm_runtime scripts into the template at /usr/local/share/claude-vm/runtime/
@@ -304,7 +268,7 @@ pub fn install_vm_runtime_scripts_to_template(
         // Propagate error after cleanup
         result?;
 
-        println!("  ✓ Installed {}", script_name);
+        status!("  ✓ Installed {}", script_name);
     }
 
     Ok(())
@@ -313,6 +277,7 @@ pub fn install_vm_runtime_scripts_to_template(
 /// Execute a script on the host machine
 fn execute_host_script(
     project: &Project,
+    config: &Config,
     script_config: &ScriptConfig,
     capability_id: &str,
 ) -> Result<()> {
@@ -329,6 +294,7 @@ fn execute_host_script(
         .env("TEMPLATE_NAME", template_name)
         .env("LIMA_INSTANCE", template_name)
         .env("CAPABILITY_ID", capability_id)
+        .envs(config.capability_env(capability_id))
         .output()
         .map_err(|e| {
             ClaudeVmError::LimaExecution(format!(
@@ -345,20 +311,23 @@ fn execute_host_script(
         )));
     }
 
-    // Print stdout if any
+    // Print stdout if any, as progress output (stdout may carry JSON events)
     if !output.stdout.is_empty() {
-        print!("{}", String::from_utf8_lossy(&output.stdout));
+        crate::status!("{}", String::from_utf8_lossy(&output.stdout).trim_end());
     }
 
     Ok(())
 }
 
-/// Execute a script in the VM with environment variables
+/// Execute a script in the VM with environment variables.
+///
+/// Output is streamed, with each line prefixed by `[<capability-id>]`
+/// when `prefix_output` is set.
 fn execute_vm_script(
     vm_name: &str,
     script_config: &ScriptConfig,
     capability_id: &str,
-    silent: bool,
+    prefix_output: bool,
     env_vars: &std::collections::HashMap<String, String>,
 ) -> Result<()> {
     let script_content = get_script_content(script_config, capability_id)?;
@@ -368,11 +337,11 @@ fn execute_vm_script(
 
     let filename = format!("{}_{}.sh", capability_id, "script");
 
-    if silent {
-        // For runtime scripts, execute without printing output unless there's an error
-        runner::execute_script_silent(vm_name, &wrapped_script, &filename)?;
+    if prefix_output {
+        // Stream output line by line, attributed to the capability
+        runner::execute_script_prefixed(vm_name, &wrapped_script, &filename, capability_id)?;
     } else {
-        // For setup scripts, show output
+        // `--no-prefix`: raw output straight to the terminal
         runner::execute_script(vm_name, &wrapped_script, &filename)?;
     }
 
@@ -462,13 +431,18 @@ pub fn configure_mcp_in_vm(project: &Project, servers: &[McpServer]) -> Result<(
     let mut jq_updates = Vec::new();
 
     for server in servers {
-        let args_json = serde_json::to_string(&server.args).map_err(|e| {
-            ClaudeVmError::InvalidConfig(format!("Failed to serialize MCP args: {}", e))
-        })?;
+        let mut entry = serde_json::json!({
+            "command": server.command,
+            "args": server.args,
+        });
+        if !server.env.is_empty() {
+            entry["env"] = serde_json::json!(server.env);
+        }
 
         jq_updates.push(format!(
-            r#".mcpServers["{}"] = {{"command": "{}", "args": {}}}"#,
-            server.id, server.command, args_json
+            r#".mcpServers[{}] = {}"#,
+            serde_json::Value::String(server.id.clone()),
+            entry
         ));
     }
 
@@ -498,17 +472,28 @@ echo "MCP servers configured in $CONFIG"
     Ok(())
 }
 
-/// Execute repository setup scripts (adds custom apt sources before apt-get update)
+/// Execute repository setup scripts (adds custom apt sources before apt-get update).
+/// Declared repository keys are verified and installed first, so the scripts can
+/// reference them with `signed-by=`.
 pub fn execute_repository_setups(
     project: &Project,
+    config: &Config,
+    keyrings: &[(String, KeyringSpec)],
     repo_setups: &[(String, String)],
+    prefix_output: bool,
 ) -> Result<()> {
+    for (capability_id, spec) in keyrings {
+        status!("  Verifying repository key for {}...", capability_id);
+        super::keyring::install(project.template_name(), capability_id, spec)?;
+    }
+
     for (capability_id, setup_script) in repo_setups {
-        println!("  Setting up repositories for {}...", capability_id);
+        status!("  Setting up repositories for {}...", capability_id);
 
         let template_name = project.template_name();
         let env_vars = build_capability_env_vars(
             project,
+            config,
             template_name,
             capability_id,
             CapabilityPhase::Setup,
@@ -522,7 +507,7 @@ pub fn execute_repository_setups(
                 script_file: None,
             },
             capability_id,
-            false,
+            prefix_output,
             &env_vars,
         )
         .map_err(|e| {
@@ -555,7 +540,7 @@ pub fn batch_install_system_packages(project: &Project, packages: &[String]) ->
     let template_name = project.template_name();
 
     // Phase 1: Update package lists with detailed error context
-    println!("  Running apt-get update...");
+    status!("  Running apt-get update...");
     LimaCtl::shell(
         template_name,
         None,
@@ -581,12 +566,12 @@ pub fn batch_install_system_packages(project: &Project, packages: &[String]) ->
     })?;
 
     // Phase 2: Install packages with detailed error context
-    println!(
+    status!(
         "  Installing {} packages: {}",
         packages.len(),
         packages.join(", ")
     );
-    println!("  (This may take several minutes for large packages)");
+    status!("  (This may take several minutes for large packages)");
 
     // Build command: sudo DEBIAN_FRONTEND=noninteractive apt-get install -y pkg1 pkg2 ...
     let mut args = vec!["DEBIAN_FRONTEND=noninteractive", "apt-get", "install", "-y"];
@@ -614,10 +599,67 @@ pub fn batch_install_system_packages(project: &Project, packages: &[String]) ->
         ))
     })?;
 
-    println!("  ✓ System packages installed successfully");
+    status!("  ✓ System packages installed successfully");
     Ok(())
 }
 
+/// Script installing `packages` with the package manager of `capability`
+/// (node: npm, python: pip, rust: cargo). Package specs pass through verbatim.
+pub fn language_install_script(capability: &str, packages: &[String]) -> String {
+    let packages: Vec<String> = packages
+        .iter()
+        .map(|p| crate::utils::shell::escape(p))
+        .collect();
+    let install = match capability {
+        // Volta shims global installs, same environment as the node vm_runtime script
+        "node" => {
+            "export VOLTA_HOME=\"$HOME/.volta\"\n\
+                   export PATH=\"$VOLTA_HOME/bin:$PATH\"\n\
+                   npm install -g"
+        }
+        // Debian marks the system Python as externally managed: install for the user
+        "python" => "python3 -m pip install --user --break-system-packages",
+        "rust" => {
+            "export PATH=\"$HOME/.cargo/bin:$PATH\"\n\
+                   cargo install"
+        }
+        other => unreachable!("no package manager for capability {}", other),
+    };
+    format!("set -e\n{} {}\n", install, packages.join(" "))
+}
+
+/// Install npm, pip and cargo packages, each with its own package manager
+pub fn install_language_packages(
+    project: &Project,
+    capability: &str,
+    packages: &[String],
+) -> Result<()> {
+    if packages.is_empty() {
+        return Ok(());
+    }
+
+    status!(
+        "  Installing {} packages: {}",
+        capability,
+        packages.join(", ")
+    );
+    LimaCtl::shell(
+        project.template_name(),
+        None,
+        "bash",
+        &["-c", &language_install_script(capability, packages)],
+        false,
+    )
+    .map_err(|e| {
+        ClaudeVmError::LimaExecution(format!(
+            "Failed to install {} packages: {}\n\nAttempted to install: {}",
+            capability,
+            e,
+            packages.join(", ")
+        ))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -797,4 +839,21 @@ mod tests {
         assert!(wrapped.contains("set -e"));
         assert!(wrapped.contains("echo \"$PROJECT_NAME\""));
     }
+
+    #[test]
+    fn test_language_install_script_passes_specs_verbatim() {
+        let npm = language_install_script(
+            "node",
+            &["lodash@4.17.21".to_string(), "typescript".to_string()],
+        );
+        assert!(npm.contains("VOLTA_HOME=\"$HOME/.volta\""));
+        assert!(npm.ends_with("npm install -g 'lodash@4.17.21' 'typescript'\n"));
+
+        let pip = language_install_script("python", &["requests==2.31.0".to_string()]);
+        assert!(pip.contains("pip install --user --break-system-packages 'requests==2.31.0'"));
+
+        let cargo = language_install_script("rust", &["cargo-nextest@0.9.70".to_string()]);
+        assert!(cargo.contains("$HOME/.cargo/bin"));
+        assert!(cargo.ends_with("cargo install 'cargo-nextest@0.9.70'\n"));
+    }
 }
@@ -0,0 +1,237 @@
+//! Verified apt signing keys for capability repositories.
+//!
+//! A capability that adds an apt repository can declare the repository key:
+//!
+//! ```toml
+//! [packages.keyring]
+//! url = "https://download.docker.com/linux/debian/gpg"
+//! fingerprint = "9DC8 5822 9FC7 DD38 854A  E2D8 8D81 803C 0EBF CD88"
+//! ```
+//!
+//! The key is downloaded in the VM and its fingerprint checked before it is
+//! installed to `/etc/apt/keyrings/<capability-id>.gpg`. A key that does not
+//! match aborts the setup instead of trusting whatever the URL served.
+
+use super::definition::KeyringSpec;
+use crate::error::{ClaudeVmError, Result};
+use crate::utils::shell::escape as shell_escape;
+use crate::vm::limactl::LimaCtl;
+
+/// Directory holding verified repository keys in the VM
+pub const KEYRING_DIR: &str = "/etc/apt/keyrings";
+
+/// Path of a capability's verified key in the VM
+pub fn keyring_path(capability_id: &str) -> String {
+    format!("{}/{}.gpg", KEYRING_DIR, capability_id)
+}
+
+/// Uppercase hex fingerprint without spaces; rejects anything but a full v4 fingerprint
+pub fn normalize_fingerprint(fingerprint: &str) -> Result<String> {
+    let normalized: String = fingerprint
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase();
+
+    if normalized.len() != 40 || !normalized.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Invalid keyring fingerprint '{}': expected 40 hex characters",
+            fingerprint
+        )));
+    }
+    Ok(normalized)
+}
+
+/// Fingerprints of the primary keys in `gpg --show-keys --with-colons` output.
+/// Subkey fingerprints are skipped.
+fn primary_fingerprints(colons: &str) -> Vec<String> {
+    let mut fingerprints = Vec::new();
+    let mut in_primary = false;
+    for line in colons.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first() {
+            Some(&"pub") => in_primary = true,
+            Some(&"sub") => in_primary = false,
+            Some(&"fpr") if in_primary => {
+                if let Some(fpr) = fields.get(9) {
+                    fingerprints.push(fpr.to_ascii_uppercase());
+                }
+                in_primary = false;
+            }
+            _ => {}
+        }
+    }
+    fingerprints
+}
+
+/// Check that the downloaded key file holds exactly the expected key
+fn verify(capability_id: &str, colons: &str, expected: &str) -> Result<()> {
+    let found = primary_fingerprints(colons);
+    if found.len() == 1 && found[0] == expected {
+        return Ok(());
+    }
+
+    Err(ClaudeVmError::InvalidConfig(format!(
+        "Repository key for '{}' does not match the expected fingerprint.\n\
+         Expected: {}\n\
+         Found:    {}\n\
+         The key was not installed. The key server may have rotated its key, \
+         or the download was tampered with.",
+        capability_id,
+        expected,
+        if found.is_empty() {
+            "no valid key".to_string()
+        } else {
+            found.join(", ")
+        }
+    )))
+}
+
+/// Script downloading the key to a temporary file and listing its fingerprints
+fn download_script(url: &str, tmp_path: &str) -> String {
+    format!(
+        "set -e\ncurl -fsSL {url} -o {tmp}\ngpg --show-keys --with-colons {tmp}\n",
+        url = shell_escape(url),
+        tmp = shell_escape(tmp_path)
+    )
+}
+
+/// Script moving a verified key into the keyring directory, dearmoring it if needed
+fn install_script(tmp_path: &str, dest: &str) -> String {
+    format!(
+        "set -e\n\
+         sudo install -d -m 755 {dir}\n\
+         if grep -q 'BEGIN PGP PUBLIC KEY BLOCK' {tmp}; then\n\
+         \x20   sudo gpg --dearmor --yes -o {dest} {tmp}\n\
+         else\n\
+         \x20   sudo install -m 644 {tmp} {dest}\n\
+         fi\n\
+         sudo chmod a+r {dest}\n\
+         rm -f {tmp}\n",
+        dir = KEYRING_DIR,
+        tmp = shell_escape(tmp_path),
+        dest = shell_escape(dest)
+    )
+}
+
+/// Download, verify and install a capability's repository key in the VM
+pub fn install(vm_name: &str, capability_id: &str, spec: &KeyringSpec) -> Result<()> {
+    if !spec.url.starts_with("https://") {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Keyring URL for '{}' must use https: {}",
+            capability_id, spec.url
+        )));
+    }
+    let expected = normalize_fingerprint(&spec.fingerprint)?;
+    let tmp_path = format!("/tmp/claude-vm-keyring-{}", capability_id);
+
+    let output = LimaCtl::shell_output(
+        vm_name,
+        "bash",
+        &["-c", &download_script(&spec.url, &tmp_path)],
+    )?;
+    if !output.status.success() {
+        return Err(ClaudeVmError::LimaExecution(format!(
+            "Failed to download repository key for '{}' from {}: {}",
+            capability_id,
+            spec.url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    if let Err(e) = verify(
+        capability_id,
+        &String::from_utf8_lossy(&output.stdout),
+        &expected,
+    ) {
+        let _ = LimaCtl::shell_output(vm_name, "rm", &["-f", &tmp_path]);
+        return Err(e);
+    }
+
+    let dest = keyring_path(capability_id);
+    LimaCtl::shell(
+        vm_name,
+        None,
+        "bash",
+        &["-c", &install_script(&tmp_path, &dest)],
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCKER_FPR: &str = "9DC858229FC7DD38854AE2D88D81803C0EBFCD88";
+
+    fn colons(primary: &[&str]) -> String {
+        let mut out = String::new();
+        for fpr in primary {
+            out.push_str("pub:-:4096:1:8D81803C0EBFCD88:1487788586:::-:::scESA::::::23::0:\n");
+            out.push_str(&format!("fpr:::::::::{}:\n", fpr));
+            out.push_str("uid:-::::1487792064::0000::Docker Release (CE deb):::::::::0:\n");
+            out.push_str("sub:-:4096:1:7EA0A9C3F273FCD8:1487788586::::::s::::::23:\n");
+            out.push_str("fpr:::::::::D3306A018370199E527AE7997EA0A9C3F273FCD8:\n");
+        }
+        out
+    }
+
+    #[test]
+    fn test_normalize_fingerprint() {
+        assert_eq!(
+            normalize_fingerprint("9dc8 5822 9fc7 dd38 854a  e2d8 8d81 803c 0ebf cd88").unwrap(),
+            DOCKER_FPR
+        );
+        assert!(normalize_fingerprint("0EBFCD88").is_err());
+        assert!(normalize_fingerprint(&"Z".repeat(40)).is_err());
+    }
+
+    #[test]
+    fn test_primary_fingerprints_skip_subkeys() {
+        assert_eq!(
+            primary_fingerprints(&colons(&[DOCKER_FPR])),
+            vec![DOCKER_FPR]
+        );
+    }
+
+    #[test]
+    fn test_verify_accepts_expected_key() {
+        assert!(verify("docker", &colons(&[DOCKER_FPR]), DOCKER_FPR).is_ok());
+    }
+
+    #[test]
+    fn test_verify_rejects_fingerprint_mismatch() {
+        let other = "0123456789ABCDEF0123456789ABCDEF01234567";
+        let err = verify("docker", &colons(&[other]), DOCKER_FPR)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("does not match the expected fingerprint"));
+        assert!(err.contains(other));
+
+        // The subkey fingerprint is not enough
+        let subkey_only = "D3306A018370199E527AE7997EA0A9C3F273FCD8";
+        assert!(verify("docker", &colons(&[DOCKER_FPR]), subkey_only).is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_extra_or_missing_keys() {
+        let other = "0123456789ABCDEF0123456789ABCDEF01234567";
+        assert!(verify("docker", &colons(&[DOCKER_FPR, other]), DOCKER_FPR).is_err());
+        assert!(verify("docker", "", DOCKER_FPR)
+            .unwrap_err()
+            .to_string()
+            .contains("no valid key"));
+    }
+
+    #[test]
+    fn test_scripts_escape_paths() {
+        let script = download_script("https://example.com/key?a=1&b=2", "/tmp/k");
+        assert!(script.contains("curl -fsSL 'https://example.com/key?a=1&b=2' -o '/tmp/k'"));
+
+        let script = install_script("/tmp/k", &keyring_path("docker"));
+        assert!(
+            script.contains("sudo gpg --dearmor --yes -o '/etc/apt/keyrings/docker.gpg' '/tmp/k'")
+        );
+        assert!(script.contains("sudo install -d -m 755 /etc/apt/keyrings"));
+    }
+}
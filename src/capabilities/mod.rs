@@ -35,20 +35,25 @@
 
 pub mod definition;
 pub mod executor;
+pub mod keyring;
 pub mod registry;
 
-use crate::config::Config;
-use crate::error::Result;
+use crate::config::{Config, VmConfig};
+use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
+use crate::status;
 use crate::vm::port_forward::PortForward;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
 
 /// Execute all enabled capabilities' host setup hooks
 pub fn execute_host_setup(project: &Project, config: &Config) -> Result<()> {
     let registry = registry::CapabilityRegistry::load()?;
-    let enabled = registry.get_enabled_capabilities(config)?;
+    let enabled = registry.get_phase_capabilities(config)?;
 
     for capability in enabled {
-        executor::execute_host_setup(project, &capability)?;
+        executor::execute_host_setup(project, config, &capability)?;
     }
 
     Ok(())
@@ -57,43 +62,340 @@ pub fn execute_host_setup(project: &Project, config: &Config) -> Result<()> {
 /// Execute all enabled capabilities' vm_setup hooks in VM
 pub fn execute_vm_setup(project: &Project, config: &Config) -> Result<()> {
     let registry = registry::CapabilityRegistry::load()?;
-    let enabled = registry.get_enabled_capabilities(config)?;
+    let enabled = registry.get_phase_capabilities(config)?;
 
     for capability in enabled {
-        executor::execute_vm_setup(project, &capability)?;
+        executor::execute_vm_setup(project, config, &capability, !config.no_prefix)?;
     }
 
     Ok(())
 }
 
-/// Execute all enabled capabilities' vm_runtime hooks in VM
-/// vm_name: The actual VM instance name (e.g., ephemeral session name)
-pub fn execute_vm_runtime(vm_name: &str, config: &Config) -> Result<()> {
+/// Warn about enabled capabilities whose setup hooks are skipped.
+/// Their packages and forwards are installed, but some (e.g. docker, gpg)
+/// need their hooks to work.
+pub fn warn_skipped_phases(config: &Config) -> Result<()> {
+    if config.setup.skip_capability_phases.is_empty() {
+        return Ok(());
+    }
     let registry = registry::CapabilityRegistry::load()?;
-    let enabled = registry.get_enabled_capabilities(config)?;
 
-    for capability in enabled {
-        executor::execute_vm_runtime_in_vm(vm_name, &capability)?;
+    let known = registry.all();
+    for id in &config.setup.skip_capability_phases {
+        if id != "*" && !known.iter().any(|cap| &cap.capability.id == id) {
+            crate::diagnostics::warn(
+                "capability-phases-skipped",
+                format!("Unknown capability '{}' in skip_capability_phases", id),
+            );
+        }
     }
 
+    let skipped: Vec<String> = registry
+        .get_enabled_capabilities(config)?
+        .iter()
+        .filter(|cap| config.setup.skips_capability_phases(&cap.capability.id))
+        .filter(|cap| {
+            cap.host_setup.is_some()
+                || cap.vm_setup.is_some()
+                || cap.verify.is_some()
+                || cap.vm_runtime.is_some()
+        })
+        .map(|cap| cap.capability.id.clone())
+        .collect();
+    if !skipped.is_empty() {
+        crate::diagnostics::warn(
+            "capability-phases-skipped",
+            format!(
+                "Skipping setup hooks of {}: their packages and forwards are installed, \
+                 but they may not work until set up by hand",
+                skipped.join(", ")
+            ),
+        );
+    }
     Ok(())
 }
 
+/// A resource minimum and the capability that needs it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceMinimum {
+    pub gb: u32,
+    pub capability: String,
+}
+
+/// Disk and memory the enabled capabilities need. Each minimum is a size for
+/// the whole VM, so the largest one wins.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ResourceMinimums {
+    pub disk: Option<ResourceMinimum>,
+    pub memory: Option<ResourceMinimum>,
+}
+
+impl ResourceMinimums {
+    /// Take the minimums of a capability into account
+    pub fn add(&mut self, meta: &definition::CapabilityMeta) {
+        for (minimum, gb) in [
+            (&mut self.disk, meta.min_disk),
+            (&mut self.memory, meta.min_memory),
+        ] {
+            let Some(gb) = gb else { continue };
+            if minimum.as_ref().is_none_or(|current| gb > current.gb) {
+                *minimum = Some(ResourceMinimum {
+                    gb,
+                    capability: meta.id.clone(),
+                });
+            }
+        }
+    }
+
+    /// Minimums the VM sizing does not meet, as (resource, minimum, allocated GB)
+    pub fn shortfalls(&self, vm: &VmConfig) -> Vec<(&'static str, &ResourceMinimum, u32)> {
+        [
+            ("disk", &self.disk, vm.disk),
+            ("memory", &self.memory, vm.memory),
+        ]
+        .into_iter()
+        .filter_map(|(resource, minimum, allocated)| {
+            minimum
+                .as_ref()
+                .filter(|m| m.gb > allocated)
+                .map(|m| (resource, m, allocated))
+        })
+        .collect()
+    }
+
+    /// Raise the VM sizing to the minimums
+    pub fn apply(&self, vm: &mut VmConfig) {
+        if let Some(disk) = &self.disk {
+            vm.disk = vm.disk.max(disk.gb);
+        }
+        if let Some(memory) = &self.memory {
+            vm.memory = vm.memory.max(memory.gb);
+        }
+    }
+}
+
+/// Compare the VM sizing with what enabled capabilities need. Warns about
+/// each shortfall, or raises the sizing to match with `--auto-size`.
+pub fn size_vm(config: &Config) -> Result<Config> {
+    let registry = registry::CapabilityRegistry::load()?;
+    let minimums = registry.resource_minimums(config)?;
+    let mut sized = config.clone();
+
+    for (resource, minimum, allocated) in minimums.shortfalls(&config.vm) {
+        if config.auto_size {
+            status!(
+                "Raising VM {} from {}GB to {}GB for {}",
+                resource,
+                allocated,
+                minimum.gb,
+                minimum.capability
+            );
+        } else {
+            crate::diagnostics::warn(
+                "capability-resources",
+                format!(
+                    "{} needs at least {}GB of {} but the VM has {}GB: the build may fail. \
+                     Raise [vm] {} or pass --{} {}, or use setup --auto-size",
+                    minimum.capability,
+                    minimum.gb,
+                    resource,
+                    allocated,
+                    resource,
+                    resource,
+                    minimum.gb
+                ),
+            );
+        }
+    }
+    if config.auto_size {
+        minimums.apply(&mut sized.vm);
+    }
+    Ok(sized)
+}
+
+/// Outcome of a capability's verify check
+#[derive(Debug)]
+pub struct VerifyResult {
+    pub id: String,
+    /// Output of the failed check, None when it passed
+    pub failure: Option<String>,
+}
+
+/// Run the verify checks of all enabled capabilities and print a summary.
+/// Fails when any check fails.
+pub fn verify_capabilities(project: &Project, config: &Config) -> Result<()> {
+    let registry = registry::CapabilityRegistry::load()?;
+    let enabled = registry.get_phase_capabilities(config)?;
+
+    let mut results = Vec::new();
+    for capability in enabled.iter().filter(|c| c.verify.is_some()) {
+        status!("Verifying {}...", capability.capability.name);
+        let failure = match executor::execute_verify(project, config, capability)? {
+            Some(captured) if !captured.success() => Some(captured.output),
+            _ => None,
+        };
+        results.push(VerifyResult {
+            id: capability.capability.id.clone(),
+            failure,
+        });
+    }
+
+    if results.is_empty() {
+        return Ok(());
+    }
+
+    status!("\nCapability verification:");
+    for result in &results {
+        match result.failure {
+            None => status!("  ✓ {}", result.id),
+            Some(_) => status!("  ✗ {}", result.id),
+        }
+    }
+
+    check_verification(&results)
+}
+
+/// Turn failed verify checks into an error naming each capability with its output
+fn check_verification(results: &[VerifyResult]) -> Result<()> {
+    let failed: Vec<&VerifyResult> = results.iter().filter(|r| r.failure.is_some()).collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = String::from("Capability verification failed:");
+    for result in failed {
+        message.push_str(&format!("\n  {}: installed but not usable", result.id));
+        let output = result.failure.as_deref().unwrap_or_default();
+        let (lines, _) = crate::scripts::runner::tail_lines(output, 5);
+        for line in lines {
+            message.push_str(&format!("\n    {}", line));
+        }
+    }
+    Err(ClaudeVmError::CommandFailed(message))
+}
+
 /// Get all MCP servers from enabled capabilities
 pub fn get_mcp_servers(config: &Config) -> Result<Vec<definition::McpServer>> {
     let registry = registry::CapabilityRegistry::load()?;
     registry.get_mcp_servers(config)
 }
 
-/// Configure all MCP servers in the VM's .claude.json
+/// Get the agent arguments added by enabled capabilities
+pub fn get_agent_args(config: &Config) -> Result<Vec<String>> {
+    let registry = registry::CapabilityRegistry::load()?;
+    registry.get_agent_args(config)
+}
+
+/// Project MCP files, in lookup order: the first one found is used
+const PROJECT_MCP_FILES: &[&str] = &[".mcp.json", ".claude/mcp.json"];
+
+/// `mcpServers` entry of a project MCP file. Only stdio servers (with a
+/// `command`) can run in the VM; http and sse servers have a `url` instead.
+#[derive(Debug, Deserialize)]
+struct ProjectMcpServer {
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    #[serde(default, rename = "type")]
+    transport: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectMcpFile {
+    #[serde(default, rename = "mcpServers")]
+    mcp_servers: BTreeMap<String, ProjectMcpServer>,
+}
+
+/// MCP servers declared by the project in `.mcp.json` (or `.claude/mcp.json`)
+pub fn load_project_mcp_servers(project_root: &Path) -> Result<Vec<definition::McpServer>> {
+    let Some(path) = PROJECT_MCP_FILES
+        .iter()
+        .map(|name| project_root.join(name))
+        .find(|path| path.is_file())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let content = std::fs::read_to_string(&path)?;
+    let file: ProjectMcpFile = serde_json::from_str(&content).map_err(|e| {
+        ClaudeVmError::InvalidConfig(format!(
+            "Invalid MCP file {}: {} (expected {{\"mcpServers\": {{\"<name>\": {{\"command\": ..., \"args\": [...]}}}}}})",
+            path.display(),
+            e
+        ))
+    })?;
+
+    Ok(file
+        .mcp_servers
+        .into_iter()
+        .filter_map(|(id, server)| {
+            let Some(command) = server.command else {
+                crate::diagnostics::warn(
+                    "mcp-unsupported",
+                    format!(
+                        "MCP server '{}' in {} uses the {} transport, only stdio servers are supported: skipping",
+                        id,
+                        path.display(),
+                        server.transport.as_deref().unwrap_or("http")
+                    ),
+                );
+                return None;
+            };
+            Some(definition::McpServer {
+                id,
+                command,
+                args: server.args,
+                env: server.env,
+                enabled_when: None,
+            })
+        })
+        .collect())
+}
+
+/// Capability MCP servers followed by the project ones. A project server
+/// replaces the capability server of the same name; the replaced names are returned.
+pub fn merge_mcp_servers(
+    capability: Vec<definition::McpServer>,
+    project: Vec<definition::McpServer>,
+) -> (Vec<definition::McpServer>, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let mut servers: Vec<definition::McpServer> = capability
+        .into_iter()
+        .filter(|server| {
+            let overridden = project.iter().any(|p| p.id == server.id);
+            if overridden {
+                conflicts.push(server.id.clone());
+            }
+            !overridden
+        })
+        .collect();
+    servers.extend(project);
+    (servers, conflicts)
+}
+
+/// Configure all MCP servers in the VM's .claude.json: those of enabled
+/// capabilities and those of the project's `.mcp.json`
 pub fn configure_mcp_servers(project: &Project, config: &Config) -> Result<()> {
-    let servers = get_mcp_servers(config)?;
+    let project_servers = load_project_mcp_servers(project.root())?;
+    let (servers, conflicts) = merge_mcp_servers(get_mcp_servers(config)?, project_servers);
+    for id in conflicts {
+        crate::diagnostics::warn(
+            "mcp-conflict",
+            format!(
+                "MCP server '{}' is defined by a capability and by the project: using the project definition",
+                id
+            ),
+        );
+    }
 
     if servers.is_empty() {
         return Ok(());
     }
 
-    println!("Configuring MCP servers...");
+    status!("Configuring MCP servers...");
     executor::configure_mcp_in_vm(project, &servers)?;
 
     Ok(())
@@ -102,7 +404,7 @@ pub fn configure_mcp_servers(project: &Project, config: &Config) -> Result<()> {
 /// Install vm_runtime scripts into the template
 pub fn install_vm_runtime_scripts(project: &Project, config: &Config) -> Result<()> {
     let registry = registry::CapabilityRegistry::load()?;
-    let enabled = registry.get_enabled_capabilities(config)?;
+    let enabled = registry.get_phase_capabilities(config)?;
 
     // Filter capabilities that have vm_runtime scripts
     let capabilities_with_runtime: Vec<_> = enabled
@@ -114,7 +416,7 @@ pub fn install_vm_runtime_scripts(project: &Project, config: &Config) -> Result<
         return Ok(());
     }
 
-    println!("Installing runtime scripts into template...");
+    status!("Installing runtime scripts into template...");
     executor::install_vm_runtime_scripts_to_template(project, &capabilities_with_runtime)?;
 
     Ok(())
@@ -151,14 +453,21 @@ pub fn get_port_forwards(config: &Config) -> Result<Vec<PortForward>> {
 /// This runs BEFORE apt-get update to add custom sources (Docker, Node, gh, etc.)
 pub fn setup_repositories(project: &Project, config: &Config) -> Result<()> {
     let registry = registry::CapabilityRegistry::load()?;
+    let keyrings = registry.get_keyrings(config)?;
     let repo_setups = registry.get_repo_setups(config)?;
 
-    if repo_setups.is_empty() {
+    if keyrings.is_empty() && repo_setups.is_empty() {
         return Ok(());
     }
 
-    println!("Setting up package repositories...");
-    executor::execute_repository_setups(project, &repo_setups)?;
+    status!("Setting up package repositories...");
+    executor::execute_repository_setups(
+        project,
+        config,
+        &keyrings,
+        &repo_setups,
+        !config.no_prefix,
+    )?;
 
     Ok(())
 }
@@ -173,8 +482,204 @@ pub fn install_system_packages(project: &Project, config: &Config) -> Result<()>
         return Ok(());
     }
 
-    println!("Installing system packages: {}", packages.join(", "));
+    status!("Installing system packages: {}", packages.join(", "));
     executor::batch_install_system_packages(project, &packages)?;
 
     Ok(())
 }
+
+/// Install the `[packages]` npm, pip and cargo lists, after the system packages
+/// so the package managers installed by the node, python and rust capabilities exist
+pub fn install_language_packages(project: &Project, config: &Config) -> Result<()> {
+    config.packages.check_tools(&config.tools)?;
+
+    for (_, capability, packages) in config.packages.language_packages() {
+        executor::install_language_packages(project, capability, packages)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_parses_from_capability_toml() {
+        let capability: definition::Capability = toml::from_str(
+            r#"
+            [capability]
+            id = "broken"
+            name = "Broken"
+            description = "test"
+
+            [verify]
+            script = "exit 1"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            capability.verify.and_then(|v| v.script),
+            Some("exit 1".to_string())
+        );
+    }
+
+    fn meta(
+        id: &str,
+        min_disk: Option<u32>,
+        min_memory: Option<u32>,
+    ) -> definition::CapabilityMeta {
+        definition::CapabilityMeta {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            requires: Vec::new(),
+            conflicts: Vec::new(),
+            agent_args: Vec::new(),
+            min_disk,
+            min_memory,
+        }
+    }
+
+    #[test]
+    fn test_resource_minimums_against_vm_config() {
+        let mut minimums = ResourceMinimums::default();
+        minimums.add(&meta("docker", Some(30), None));
+        minimums.add(&meta("chromium", Some(25), Some(12)));
+        minimums.add(&meta("git", None, None));
+
+        // The largest minimum wins
+        assert_eq!(
+            minimums.disk,
+            Some(ResourceMinimum {
+                gb: 30,
+                capability: "docker".to_string()
+            })
+        );
+        assert_eq!(minimums.memory.as_ref().unwrap().capability, "chromium");
+
+        let mut vm = VmConfig {
+            disk: 20,
+            memory: 16,
+            ..Default::default()
+        };
+        let shortfalls = minimums.shortfalls(&vm);
+        assert_eq!(shortfalls.len(), 1);
+        assert_eq!(shortfalls[0].0, "disk");
+        assert_eq!(shortfalls[0].1.gb, 30);
+        assert_eq!(shortfalls[0].2, 20);
+
+        // Auto-sizing raises what is short and never lowers the rest
+        minimums.apply(&mut vm);
+        assert_eq!((vm.disk, vm.memory), (30, 16));
+        assert!(minimums.shortfalls(&vm).is_empty());
+    }
+
+    #[test]
+    fn test_check_verification_passes() {
+        let results = vec![VerifyResult {
+            id: "docker".to_string(),
+            failure: None,
+        }];
+        assert!(check_verification(&results).is_ok());
+        assert!(check_verification(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_check_verification_reports_failed_capability() {
+        let results = vec![
+            VerifyResult {
+                id: "node".to_string(),
+                failure: None,
+            },
+            VerifyResult {
+                id: "docker".to_string(),
+                failure: Some(
+                    "line 1\nline 2\nline 3\nline 4\nline 5\nCannot connect to the Docker daemon\n"
+                        .to_string(),
+                ),
+            },
+        ];
+
+        let message = check_verification(&results).unwrap_err().to_string();
+        assert!(message.contains("docker: installed but not usable"));
+        assert!(message.contains("Cannot connect to the Docker daemon"));
+        assert!(!message.contains("line 1"));
+        assert!(!message.contains("node"));
+    }
+
+    #[test]
+    fn test_builtin_capabilities_define_verify() {
+        let registry = registry::CapabilityRegistry::load().unwrap();
+        let mut config = Config::default();
+        config.tools.docker = true;
+        config.tools.node = true;
+
+        let enabled = registry.get_enabled_capabilities(&config).unwrap();
+        assert!(enabled.iter().all(|c| c.verify.is_some()));
+    }
+
+    #[test]
+    fn test_project_mcp_servers_merge_with_capability_servers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(load_project_mcp_servers(dir.path()).unwrap().is_empty());
+
+        std::fs::write(
+            dir.path().join(".mcp.json"),
+            r#"{"mcpServers": {
+                "chrome-devtools": {"command": "node", "args": ["devtools.js"]},
+                "db": {"command": "db-mcp"}
+            }}"#,
+        )
+        .unwrap();
+        let project = load_project_mcp_servers(dir.path()).unwrap();
+        assert_eq!(project.len(), 2);
+
+        let capability = vec![
+            definition::McpServer {
+                id: "chrome-devtools".to_string(),
+                command: "npx".to_string(),
+                args: vec!["chrome-devtools-mcp@latest".to_string()],
+                env: BTreeMap::new(),
+                enabled_when: None,
+            },
+            definition::McpServer {
+                id: "postgres".to_string(),
+                command: "npx".to_string(),
+                args: Vec::new(),
+                env: BTreeMap::new(),
+                enabled_when: None,
+            },
+        ];
+        let (servers, conflicts) = merge_mcp_servers(capability, project);
+        assert_eq!(conflicts, vec!["chrome-devtools"]);
+        let ids: Vec<&str> = servers.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["postgres", "chrome-devtools", "db"]);
+        // The project definition wins
+        assert_eq!(servers[1].command, "node");
+        assert!(servers[2].args.is_empty());
+
+        std::fs::write(dir.path().join(".mcp.json"), "{\"mcpServers\": [").unwrap();
+        let err = load_project_mcp_servers(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("Invalid MCP file"));
+    }
+
+    #[test]
+    fn test_project_mcp_servers_skip_remote_transports() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(".mcp.json"),
+            r#"{"mcpServers": {
+                "db": {"type": "stdio", "command": "db-mcp", "env": {"DB_URL": "postgres://db"}},
+                "docs": {"type": "http", "url": "https://example.com/mcp"},
+                "events": {"type": "sse", "url": "https://example.com/sse"}
+            }}"#,
+        )
+        .unwrap();
+
+        let servers = load_project_mcp_servers(dir.path()).unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].id, "db");
+        assert_eq!(servers[0].env["DB_URL"], "postgres://db");
+    }
+}
@@ -1,4 +1,5 @@
-use super::definition::{Capability, McpServer};
+use super::definition::{Capability, KeyringSpec, McpServer};
+use super::ResourceMinimums;
 use crate::config::Config;
 use crate::error::{ClaudeVmError, Result};
 use std::collections::{HashMap, HashSet};
@@ -53,6 +54,15 @@ fn validate_package_name(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Config setting that disables a capability
+fn disable_hint(id: &str) -> String {
+    if id == "network-isolation" {
+        "'enabled = false' in [security.network]".to_string()
+    } else {
+        format!("'{} = false' in [tools]", id)
+    }
+}
+
 pub struct CapabilityRegistry {
     capabilities: HashMap<String, Arc<Capability>>,
 }
@@ -110,19 +120,35 @@ impl CapabilityRegistry {
         Ok(Self { capabilities })
     }
 
+    /// All registered capabilities, in id order
+    pub fn all(&self) -> Vec<Arc<Capability>> {
+        let mut all: Vec<Arc<Capability>> = self.capabilities.values().cloned().collect();
+        all.sort_by(|a, b| a.capability.id.cmp(&b.capability.id));
+        all
+    }
+
     /// Get list of enabled capabilities based on config, sorted by dependencies
     pub fn get_enabled_capabilities(&self, config: &Config) -> Result<Vec<Arc<Capability>>> {
         let mut enabled = Vec::new();
 
+        // Iterate in id order so conflict errors are deterministic
+        let mut ids: Vec<&String> = self.capabilities.keys().collect();
+        ids.sort();
+
         // Check each tool in config
-        for (id, capability) in &self.capabilities {
+        for id in ids {
+            let capability = &self.capabilities[id];
             if self.is_enabled(id, config) {
                 // Check for conflicts
                 for conflict_id in &capability.capability.conflicts {
                     if self.is_enabled(conflict_id, config) {
                         return Err(ClaudeVmError::InvalidConfig(format!(
-                            "Capability '{}' conflicts with '{}'",
-                            id, conflict_id
+                            "Capabilities '{}' and '{}' are both enabled but cannot be used together.\n\
+                             Disable one of them: {} or {}",
+                            id,
+                            conflict_id,
+                            disable_hint(id),
+                            disable_hint(conflict_id)
                         )));
                     }
                 }
@@ -137,6 +163,24 @@ impl CapabilityRegistry {
         Ok(enabled)
     }
 
+    /// Enabled capabilities whose setup hooks run: those skipped with
+    /// `[setup] skip_capability_phases` (or --no-capability-phases) are left out
+    pub fn get_phase_capabilities(&self, config: &Config) -> Result<Vec<Arc<Capability>>> {
+        // Without its hooks, the proxy and firewall rules never start
+        if config.security.network.enabled
+            && config.setup.skips_capability_phases("network-isolation")
+        {
+            return Err(ClaudeVmError::InvalidConfig(
+                "Cannot skip the setup hooks of network-isolation while security.network.enabled \
+                 is set: they enforce the network policy"
+                    .to_string(),
+            ));
+        }
+        let mut enabled = self.get_enabled_capabilities(config)?;
+        enabled.retain(|cap| !config.setup.skips_capability_phases(&cap.capability.id));
+        Ok(enabled)
+    }
+
     /// Check if a capability is enabled in the config
     fn is_enabled(&self, id: &str, config: &Config) -> bool {
         // Special case: network-isolation is configured via [security.network].enabled
@@ -236,6 +280,24 @@ impl CapabilityRegistry {
         Ok(servers)
     }
 
+    /// Largest disk and memory minimums among enabled capabilities
+    pub fn resource_minimums(&self, config: &Config) -> Result<ResourceMinimums> {
+        let mut minimums = ResourceMinimums::default();
+        for cap in self.get_enabled_capabilities(config)? {
+            minimums.add(&cap.capability);
+        }
+        Ok(minimums)
+    }
+
+    /// Collect agent arguments from enabled capabilities, in dependency order
+    pub fn get_agent_args(&self, config: &Config) -> Result<Vec<String>> {
+        let enabled = self.get_enabled_capabilities(config)?;
+        Ok(enabled
+            .iter()
+            .flat_map(|cap| cap.capability.agent_args.iter().cloned())
+            .collect())
+    }
+
     /// Collect all system packages from enabled capabilities and user config.
     /// Returns packages in dependency order (respects capability.requires).
     /// Duplicates are removed while preserving order (first occurrence wins).
@@ -276,6 +338,19 @@ impl CapabilityRegistry {
         Ok(packages)
     }
 
+    /// Repository keys declared by enabled capabilities (in dependency order).
+    /// Returns tuples of (capability_id, keyring).
+    pub fn get_keyrings(&self, config: &Config) -> Result<Vec<(String, KeyringSpec)>> {
+        let enabled = self.get_enabled_capabilities(config)?;
+        Ok(enabled
+            .iter()
+            .filter_map(|capability| {
+                let keyring = capability.packages.as_ref()?.keyring.clone()?;
+                Some((capability.capability.id.clone(), keyring))
+            })
+            .collect())
+    }
+
     /// Get capabilities that need repository setup (in dependency order).
     /// Returns tuples of (capability_id, setup_script).
     ///
@@ -307,6 +382,101 @@ impl CapabilityRegistry {
 mod tests {
     use super::*;
 
+    fn test_capability(id: &str, conflicts: &[&str]) -> Arc<Capability> {
+        let toml = format!(
+            "[capability]\nid = \"{}\"\nname = \"{}\"\ndescription = \"test\"\nconflicts = {:?}\n",
+            id, id, conflicts
+        );
+        Arc::new(toml::from_str(&toml).unwrap())
+    }
+
+    #[test]
+    fn test_conflicting_capabilities_error() {
+        let registry = CapabilityRegistry {
+            capabilities: HashMap::from([
+                ("docker".to_string(), test_capability("docker", &["node"])),
+                ("node".to_string(), test_capability("node", &[])),
+            ]),
+        };
+
+        let mut config = Config::default();
+        config.tools.docker = true;
+        config.tools.node = true;
+
+        let err = registry.get_enabled_capabilities(&config).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("'docker' and 'node'"));
+        assert!(message.contains("'docker = false' in [tools]"));
+        assert!(message.contains("'node = false' in [tools]"));
+
+        // Only one of the pair enabled is fine
+        config.tools.node = false;
+        assert_eq!(registry.get_enabled_capabilities(&config).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_phase_capabilities() {
+        let registry = CapabilityRegistry {
+            capabilities: HashMap::from([
+                ("docker".to_string(), test_capability("docker", &[])),
+                ("node".to_string(), test_capability("node", &[])),
+            ]),
+        };
+        let mut config = Config::default();
+        config.tools.docker = true;
+        config.tools.node = true;
+
+        let ids = |config: &Config| -> Vec<String> {
+            registry
+                .get_phase_capabilities(config)
+                .unwrap()
+                .iter()
+                .map(|cap| cap.capability.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&config), vec!["docker", "node"]);
+
+        config.setup.skip_capability_phases = vec!["docker".to_string()];
+        assert_eq!(ids(&config), vec!["node"]);
+        // Skipping hooks does not disable the capability
+        assert_eq!(registry.get_enabled_capabilities(&config).unwrap().len(), 2);
+
+        config.setup.skip_capability_phases = vec!["*".to_string()];
+        assert!(ids(&config).is_empty());
+
+        // "*" keeps network isolation, skipping it explicitly is an error
+        assert!(!config.setup.skips_capability_phases("network-isolation"));
+        config.security.network.enabled = true;
+        config.setup.skip_capability_phases = vec!["network-isolation".to_string()];
+        let err = registry.get_phase_capabilities(&config).unwrap_err();
+        assert!(err.to_string().contains("network-isolation"));
+    }
+
+    #[test]
+    fn test_get_agent_args() {
+        let with_args: Capability = toml::from_str(
+            "[capability]\nid = \"docker\"\nname = \"docker\"\ndescription = \"test\"\n\
+             agent_args = [\"--add-dir\", \"/var/run\"]\n",
+        )
+        .unwrap();
+        let registry = CapabilityRegistry {
+            capabilities: HashMap::from([
+                ("docker".to_string(), Arc::new(with_args)),
+                ("node".to_string(), test_capability("node", &[])),
+            ]),
+        };
+
+        let mut config = Config::default();
+        config.tools.node = true;
+        assert!(registry.get_agent_args(&config).unwrap().is_empty());
+
+        config.tools.docker = true;
+        assert_eq!(
+            registry.get_agent_args(&config).unwrap(),
+            vec!["--add-dir", "/var/run"]
+        );
+    }
+
     #[test]
     fn test_collect_packages_deduplication() {
         let registry = CapabilityRegistry::load().unwrap();
@@ -395,6 +565,27 @@ mod tests {
         assert!(setups[0].1.contains("ppa:my-ppa/custom"));
     }
 
+    #[test]
+    fn test_get_keyrings() {
+        let registry = CapabilityRegistry::load().unwrap();
+
+        let mut config = Config::default();
+        assert!(registry.get_keyrings(&config).unwrap().is_empty());
+
+        config.tools.docker = true;
+        let keyrings = registry.get_keyrings(&config).unwrap();
+        assert_eq!(keyrings.len(), 1);
+        assert_eq!(keyrings[0].0, "docker");
+        assert!(super::super::keyring::normalize_fingerprint(&keyrings[0].1.fingerprint).is_ok());
+
+        config.tools.gh = true;
+        let keyrings = registry.get_keyrings(&config).unwrap();
+        assert_eq!(keyrings.len(), 2);
+        let gh = keyrings.iter().find(|(id, _)| id == "gh").unwrap();
+        assert!(gh.1.url.starts_with("https://cli.github.com/"));
+        assert!(super::super::keyring::normalize_fingerprint(&gh.1.fingerprint).is_ok());
+    }
+
     #[test]
     fn test_get_repo_setups_mixed() {
         let registry = CapabilityRegistry::load().unwrap();
@@ -0,0 +1,212 @@
+use crate::error::{ClaudeVmError, Result};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+
+/// Aliases expanding to other aliases are followed this many times at most
+const MAX_EXPANSION_DEPTH: usize = 10;
+
+/// Expand a user-defined alias in the first argument, before routing.
+///
+/// Aliases come from `[aliases]` in `.claude-vm.toml`: `review = "agent --read-only-project"`
+/// turns `claude-vm review -- /review` into `claude-vm agent --read-only-project -- /review`.
+/// Like the router, only `args[1]` is looked at. Built-in subcommands cannot be
+/// shadowed, and an alias may expand to another alias (cycles are an error).
+///
+/// # Examples
+///
+/// ```text
+/// [aliases]
+/// review = "agent --read-only-project"
+/// rv = "review --verbose"
+///
+/// claude-vm review /review   -> claude-vm agent --read-only-project /review
+/// claude-vm rv /review       -> claude-vm agent --read-only-project --verbose /review
+/// claude-vm shell ls         -> claude-vm shell ls (unchanged)
+/// ```
+pub fn expand_aliases(
+    args: Vec<OsString>,
+    aliases: &BTreeMap<String, String>,
+) -> Result<Vec<OsString>> {
+    let mut args = args;
+    let mut seen: Vec<String> = Vec::new();
+
+    while let Some(name) = args.get(1).and_then(|a| a.to_str()).map(str::to_string) {
+        let Some(expansion) = aliases.get(&name) else {
+            break;
+        };
+        if is_builtin(&name) {
+            break;
+        }
+        if seen.contains(&name) {
+            seen.push(name);
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Alias cycle: {}",
+                seen.join(" -> ")
+            )));
+        }
+        if seen.len() == MAX_EXPANSION_DEPTH {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Alias '{}' expands through more than {} aliases",
+                seen[0], MAX_EXPANSION_DEPTH
+            )));
+        }
+
+        let words = split_words(expansion).map_err(|e| {
+            ClaudeVmError::InvalidConfig(format!("Invalid alias '{}': {}", name, e))
+        })?;
+        if words.is_empty() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Alias '{}' is empty",
+                name
+            )));
+        }
+        seen.push(name);
+
+        let mut expanded = Vec::with_capacity(args.len() + words.len());
+        expanded.push(args[0].clone());
+        expanded.extend(words.into_iter().map(OsString::from));
+        expanded.extend_from_slice(&args[2..]);
+        args = expanded;
+    }
+
+    Ok(args)
+}
+
+/// Aliases named like a subcommand are never expanded
+fn is_builtin(name: &str) -> bool {
+    super::router::is_known_subcommand(name)
+}
+
+/// Split an alias definition into arguments, on whitespace outside quotes
+fn split_words(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(format!("unterminated {} quote", q));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, expansion)| (name.to_string(), expansion.to_string()))
+            .collect()
+    }
+
+    fn expand(args: &[&str], aliases: &BTreeMap<String, String>) -> Result<Vec<String>> {
+        let args = args.iter().map(OsString::from).collect();
+        Ok(expand_aliases(args, aliases)?
+            .into_iter()
+            .map(|a| a.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn test_alias_injects_subcommand() {
+        let aliases = aliases(&[("review", "agent --read-only-project --audit")]);
+        assert_eq!(
+            expand(&["claude-vm", "review", "--", "/review"], &aliases).unwrap(),
+            vec![
+                "claude-vm",
+                "agent",
+                "--read-only-project",
+                "--audit",
+                "--",
+                "/review"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alias_expansion_leaves_other_args_alone() {
+        let aliases = aliases(&[("review", "agent --audit"), ("shell", "agent")]);
+
+        // Not an alias, or past the first argument
+        assert_eq!(
+            expand(&["claude-vm", "/clear"], &aliases).unwrap(),
+            vec!["claude-vm", "/clear"]
+        );
+        assert_eq!(
+            expand(&["claude-vm", "agent", "review"], &aliases).unwrap(),
+            vec!["claude-vm", "agent", "review"]
+        );
+        // Built-in subcommands cannot be shadowed
+        assert_eq!(
+            expand(&["claude-vm", "shell", "ls"], &aliases).unwrap(),
+            vec!["claude-vm", "shell", "ls"]
+        );
+        assert_eq!(expand(&["claude-vm"], &aliases).unwrap(), vec!["claude-vm"]);
+    }
+
+    #[test]
+    fn test_alias_chains_and_quotes() {
+        let aliases = aliases(&[
+            ("review", "agent --read-only-project"),
+            ("rv", "review --env 'GREETING=hello world'"),
+        ]);
+        assert_eq!(
+            expand(&["claude-vm", "rv", "/review"], &aliases).unwrap(),
+            vec![
+                "claude-vm",
+                "agent",
+                "--read-only-project",
+                "--env",
+                "GREETING=hello world",
+                "/review"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alias_cycles_and_invalid_definitions() {
+        let cycle = aliases(&[("a", "b --x"), ("b", "a")]);
+        let err = expand(&["claude-vm", "a"], &cycle).unwrap_err();
+        assert!(err.to_string().contains("Alias cycle: a -> b -> a"));
+
+        let own = aliases(&[("loop", "loop --verbose")]);
+        assert!(expand(&["claude-vm", "loop"], &own).is_err());
+
+        let deep: BTreeMap<String, String> = (0..=MAX_EXPANSION_DEPTH)
+            .map(|i| (format!("a{}", i), format!("a{}", i + 1)))
+            .collect();
+        let err = expand(&["claude-vm", "a0"], &deep).unwrap_err();
+        assert!(err.to_string().contains("more than 10 aliases"));
+
+        let unterminated = aliases(&[("bad", "agent \"oops")]);
+        assert!(expand(&["claude-vm", "bad"], &unterminated).is_err());
+        let empty = aliases(&[("none", "  ")]);
+        assert!(expand(&["claude-vm", "none"], &empty).is_err());
+    }
+}
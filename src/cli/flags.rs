@@ -1,5 +1,6 @@
+use crate::vm::mount::TmpfsMount;
 use clap::Parser;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Runtime flags shared by agent and shell commands.
 /// These flags configure the ephemeral VM session.
@@ -21,10 +22,26 @@ pub struct RuntimeFlags {
     #[arg(short = 'A', long = "forward-ssh-agent")]
     pub forward_ssh_agent: bool,
 
-    /// Custom mount in docker-style format: /host/path[:vm/path][:ro|rw]
+    /// Forward the host's git HTTPS credentials for the project's remotes
+    /// (held in memory in the VM, usable by anything running there)
+    #[arg(long = "git-credentials")]
+    pub git_credentials: bool,
+
+    /// Custom mount in docker-style format: /host/path[:/vm/path][:ro|rw],
+    /// or /host/path=/vm/path[:ro|rw] when the paths contain colons
     #[arg(long = "mount")]
     pub mounts: Vec<String>,
 
+    /// Memory-backed scratch directory in the VM: /vm/path[:size] (e.g. /tmp/build:2g).
+    /// Contents are lost when the VM is torn down.
+    #[arg(long = "mount-tmpfs", value_name = "VM_PATH[:SIZE]", value_parser = TmpfsMount::from_spec)]
+    pub mount_tmpfs: Vec<TmpfsMount>,
+
+    /// Fail before creating the VM when a mount's host path does not exist
+    /// (instead of warning)
+    #[arg(long = "strict-mounts")]
+    pub strict_mounts: bool,
+
     /// Set environment variable (KEY=VALUE)
     #[arg(long = "env")]
     pub env: Vec<String>,
@@ -45,6 +62,17 @@ pub struct RuntimeFlags {
     #[arg(long = "auto-setup")]
     pub auto_setup: bool,
 
+    /// Mount the project read-only with a writable overlay in the VM.
+    /// Changes made in the VM are discarded when the session ends.
+    /// Not available with --use-vm, whose mounts are not claude-vm's.
+    #[arg(long, conflicts_with = "use_vm")]
+    pub scratch: bool,
+
+    /// Run inside an existing, running Lima VM instead of an ephemeral one.
+    /// Template creation and VM teardown are skipped.
+    #[arg(long = "use-vm", value_name = "INSTANCE")]
+    pub use_vm: Option<String>,
+
     /// Create or resume worktree for branch development.
     ///
     /// Usage: --worktree <branch> [base]
@@ -68,6 +96,61 @@ pub struct RuntimeFlags {
     pub worktree: Vec<String>,
 }
 
+impl RuntimeFlags {
+    /// Command-line arguments that reproduce these flags in a child
+    /// invocation (everything but --worktree and --use-vm, which pick where
+    /// the session runs). Relative file paths are resolved against `cwd`.
+    pub fn to_args(&self, cwd: &Path) -> Vec<String> {
+        let mut args = Vec::new();
+        let sizes = [
+            ("--disk", self.disk),
+            ("--memory", self.memory),
+            ("--cpus", self.cpus),
+        ];
+        for (flag, value) in sizes {
+            if let Some(value) = value {
+                args.push(flag.to_string());
+                args.push(value.to_string());
+            }
+        }
+        let switches = [
+            ("--forward-ssh-agent", self.forward_ssh_agent),
+            ("--git-credentials", self.git_credentials),
+            ("--strict-mounts", self.strict_mounts),
+            ("--auto-setup", self.auto_setup),
+            ("--scratch", self.scratch),
+        ];
+        for (flag, set) in switches {
+            if set {
+                args.push(flag.to_string());
+            }
+        }
+        for mount in &self.mounts {
+            args.push(format!("--mount={}", mount));
+        }
+        for tmpfs in &self.mount_tmpfs {
+            let spec = match &tmpfs.size {
+                Some(size) => format!("{}:{}", tmpfs.path.display(), size),
+                None => tmpfs.path.display().to_string(),
+            };
+            args.push(format!("--mount-tmpfs={}", spec));
+        }
+        for env in &self.env {
+            args.push(format!("--env={}", env));
+        }
+        for file in &self.env_file {
+            args.push(format!("--env-file={}", cwd.join(file).display()));
+        }
+        for name in &self.inherit_env {
+            args.push(format!("--inherit-env={}", name));
+        }
+        for script in &self.runtime_scripts {
+            args.push(format!("--runtime-script={}", cwd.join(script).display()));
+        }
+        args
+    }
+}
+
 /// VM sizing flags for the setup command.
 /// Setup only needs disk, memory, and cpus — not runtime-specific flags.
 #[derive(Parser, Debug, Clone, Default)]
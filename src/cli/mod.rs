@@ -1,6 +1,8 @@
+use crate::vm::limactl::FileCopy;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+pub mod alias;
 pub mod flags;
 pub mod router;
 pub use flags::{RuntimeFlags, SetupVmFlags};
@@ -11,10 +13,104 @@ pub enum ConfigCommands {
     Validate {
         /// Optional path to a specific config file to validate
         file: Option<PathBuf>,
+
+        /// In a worktree, also report settings where the worktree config
+        /// disagrees with the main repository config
+        #[arg(long, conflicts_with = "file")]
+        worktree: bool,
     },
 
     /// Show effective configuration after merging all sources
     Show,
+
+    /// Suggest best-practice changes to a valid configuration (never fails)
+    Lint,
+
+    /// Compare two config sources field by field (default: global vs project)
+    Diff {
+        /// First config file (lower precedence)
+        #[arg(requires = "right")]
+        left: Option<PathBuf>,
+
+        /// Second config file (higher precedence)
+        right: Option<PathBuf>,
+    },
+
+    /// Export the project config and the files it references as a shareable bundle
+    Export {
+        /// Bundle file to write
+        path: PathBuf,
+    },
+
+    /// Import a bundle created with 'config export' into this project
+    Import {
+        /// Bundle file to read
+        path: PathBuf,
+
+        /// Overwrite existing config and files
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Set one key of the project .claude-vm.toml (e.g. 'config set vm.memory 16')
+    Set {
+        /// Dotted key, e.g. vm.disk, tools.docker or security.network.enabled
+        key: String,
+
+        /// New value, parsed with the type of the field (arrays as TOML, e.g. '["a"]')
+        value: String,
+    },
+
+    /// Write a starter .claude-vm.toml with the default values, commented
+    Init {
+        /// Write ~/.claude-vm.toml instead of the project config
+        #[arg(long)]
+        global: bool,
+
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateCommands {
+    /// Compare the build metadata of two templates
+    Diff {
+        /// First template name (as shown by 'claude-vm list')
+        left: String,
+
+        /// Second template name
+        right: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConversationsCommands {
+    /// Remove conversation folders of projects that no longer exist
+    Prune {
+        /// Also remove folders without activity for this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+
+        /// List the folders that would be removed without removing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CapabilitiesCommands {
+    /// Show the requires/conflicts graph across all capabilities
+    Graph {
+        /// Output the graph as JSON (nodes, edges, cycles)
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -45,6 +141,11 @@ pub enum NetworkCommands {
     Test {
         /// Domain to test (e.g., example.com or *.example.com)
         domain: String,
+
+        /// Print the evaluation trace: policy mode, firewall blocks, and the
+        /// config entry that decided
+        #[arg(long)]
+        explain: bool,
     },
 }
 
@@ -57,12 +158,25 @@ pub enum WorktreeCommands {
 
         /// Base branch or commit to create from (default: current HEAD)
         base: Option<String>,
+
+        /// Build the project template after creating the worktree if it doesn't exist yet
+        #[arg(long)]
+        setup: bool,
+
+        /// Run the agent in the new worktree once it is created
+        #[arg(long)]
+        agent: bool,
+
+        /// Arguments to pass to Claude with --agent (after --)
+        #[arg(last = true, requires = "agent")]
+        claude_args: Vec<String>,
     },
 
     /// List all worktrees
     List {
         /// Show only worktrees for branches merged into base
-        #[arg(long)]
+        /// (defaults to [worktree] default_base, then the current branch)
+        #[arg(long, num_args(0..=1), default_missing_value = "")]
         merged: Option<String>,
 
         /// Show only locked worktrees
@@ -80,7 +194,8 @@ pub enum WorktreeCommands {
         /// Branch name(s) of the worktree(s) to remove
         branches: Vec<String>,
 
-        /// Remove worktrees for branches merged into base (defaults to current branch)
+        /// Remove worktrees for branches merged into base
+        /// (defaults to [worktree] default_base, then the current branch)
         #[arg(long, conflicts_with = "branches", num_args(0..=1), default_missing_value = "")]
         merged: Option<String>,
 
@@ -115,6 +230,14 @@ EXAMPLES:
   claude-vm agent --disk 50 /clear   Same as above (explicit form)
   claude-vm shell                    Open an interactive VM shell
 
+EXIT CODES:
+  0  Success
+  1  Any other error
+  2  Invalid command line
+  3  Invalid configuration
+  4  Project detection failed
+  5  A command run by claude-vm failed
+
 For details about a specific command, use:
   claude-vm <command> --help")]
 pub struct Cli {
@@ -122,6 +245,34 @@ pub struct Cli {
     #[arg(short = 'v', long = "verbose", global = true)]
     pub verbose: bool,
 
+    /// Emit newline-delimited JSON lifecycle events on stdout (human output goes to stderr)
+    #[arg(long = "events-json", global = true)]
+    pub events_json: bool,
+
+    /// Emit warnings as JSON lines on stderr (code, severity, message) instead of text
+    #[arg(long = "warnings-json", global = true)]
+    pub warnings_json: bool,
+
+    /// Fail instead of warning when startup checks find a problem (e.g. running as root)
+    #[arg(long = "deny-warnings", global = true)]
+    pub deny_warnings: bool,
+
+    /// Use a named config variant from [flavors.<name>] (separate template per flavor)
+    #[arg(long, global = true, env = "CLAUDE_VM_FLAVOR", value_name = "NAME")]
+    pub flavor: Option<String>,
+
+    /// Apply a named config profile from [profiles.<name>] (overrides the config files, shares the template)
+    #[arg(long, global = true, env = "CLAUDE_VM_PROFILE", value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Record every limactl invocation (args, exit code, duration, output) to a JSON-lines file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub trace: Option<PathBuf>,
+
+    /// Print how the arguments are routed to a subcommand, then exit without running it
+    #[arg(long = "explain-routing", global = true)]
+    pub explain_routing: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -146,8 +297,27 @@ pub enum Commands {
     /// Set up a new template VM for this project
     Setup(SetupCmd),
 
+    /// Re-authenticate Claude in the template without starting a session
+    Auth {
+        /// Delete the cached credentials before logging in again
+        #[arg(long)]
+        reset: bool,
+
+        /// Skip the confirmation prompt of --reset
+        #[arg(short = 'y', long, requires = "reset")]
+        yes: bool,
+    },
+
     /// Show information about the current project's template
-    Info,
+    Info {
+        /// Print the template's build metadata as JSON
+        #[arg(long)]
+        metadata: bool,
+
+        /// Print project information as JSON, including the capability audit
+        #[arg(long, conflicts_with = "metadata")]
+        json: bool,
+    },
 
     /// Configuration management commands
     Config {
@@ -164,6 +334,28 @@ pub enum Commands {
         /// Show disk usage information
         #[arg(long)]
         disk_usage: bool,
+
+        /// Print the templates as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Inspect templates
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+
+    /// Manage Claude conversation folders on the host
+    Conversations {
+        #[command(subcommand)]
+        command: ConversationsCommands,
+    },
+
+    /// Inspect built-in capabilities
+    Capabilities {
+        #[command(subcommand)]
+        command: CapabilitiesCommands,
     },
 
     /// Clean the template for this project
@@ -171,13 +363,34 @@ pub enum Commands {
         /// Skip confirmation prompt
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Only remove the template if it has not been used in this many days (default: 30)
+        #[arg(
+            long,
+            value_name = "DAYS",
+            num_args = 0..=1,
+            default_missing_value = "30"
+        )]
+        unused: Option<u32>,
+
+        /// Remove this project's session VMs left behind by crashed runs, instead of the template
+        #[arg(long, conflicts_with = "unused")]
+        orphans: bool,
+
+        /// With --orphans, remove every orphaned VM without prompting
+        #[arg(long, requires = "orphans")]
+        all: bool,
     },
 
     /// Clean all claude-vm templates
     CleanAll {
-        /// Skip confirmation prompt
+        /// Skip confirmation prompt (requires --confirm-destroy or CLAUDE_VM_CONFIRM_DESTROY=1)
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Allow --yes to delete every template without prompting
+        #[arg(long, requires = "yes")]
+        confirm_destroy: bool,
     },
 
     /// Check claude-vm version and updates
@@ -214,6 +427,45 @@ pub enum Commands {
         #[command(subcommand)]
         command: WorktreeCommands,
     },
+
+    /// Run agent sessions across several worktrees in parallel
+    Batch(BatchCmd),
+}
+
+#[derive(Parser, Debug)]
+#[command(group(
+    clap::ArgGroup::new("targets")
+        .required(true)
+        .args(["branches", "all_worktrees"])
+))]
+pub struct BatchCmd {
+    /// Runtime configuration flags, passed on to every session
+    #[command(flatten)]
+    pub runtime: RuntimeFlags,
+
+    /// Branch to run on (repeatable). Its worktree is created or resumed as needed
+    #[arg(short = 'b', long = "branch")]
+    pub branches: Vec<String>,
+
+    /// Run on every existing worktree except the main one
+    #[arg(long = "all-worktrees")]
+    pub all_worktrees: bool,
+
+    /// Maximum number of concurrent agent sessions (one VM each)
+    #[arg(long, default_value = "2", value_parser = clap::value_parser!(u32).range(1..))]
+    pub parallel: u32,
+
+    /// Directory for per-branch session logs (relative to the project root)
+    #[arg(long = "report-dir", default_value = ".claude-vm/batch")]
+    pub report_dir: PathBuf,
+
+    /// Skip worktrees with no new commits or working tree changes since their last successful batch session
+    #[arg(long = "only-changed")]
+    pub only_changed: bool,
+
+    /// Arguments to pass to Claude in every session
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub claude_args: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -226,6 +478,40 @@ pub struct AgentCmd {
     #[arg(long = "no-conversations")]
     pub no_conversations: bool,
 
+    /// Mount the conversation history of the project at this path instead of
+    /// the current one's (e.g. the main repository's, from a worktree)
+    #[arg(
+        long = "conversation-project",
+        value_name = "PATH",
+        conflicts_with_all = ["no_conversations", "fresh_home", "drop_privileges"]
+    )]
+    pub conversation_project: Option<PathBuf>,
+
+    /// Run Claude with a clean, temporary config directory: no conversation
+    /// mount and no state from earlier sessions (credentials are kept)
+    #[arg(long = "fresh-home")]
+    pub fresh_home: bool,
+
+    /// Run Claude as an unprivileged guest user without sudo (no apt or
+    /// system changes during the session)
+    #[arg(long = "drop-privileges")]
+    pub drop_privileges: bool,
+
+    /// Restart Claude in the same VM up to N times when it crashes
+    /// (killed by a signal such as SIGSEGV or the OOM killer; not on Ctrl+C or exit codes)
+    #[arg(long = "retry-on-crash", value_name = "N")]
+    pub retry_on_crash: Option<u32>,
+
+    /// Ignore [defaults] claude_args (including the built-in
+    /// --dangerously-skip-permissions) and pass only the arguments given here
+    #[arg(long = "no-default-args")]
+    pub no_default_args: bool,
+
+    /// Read the prompt from a host file and pass it to Claude
+    /// (combine with `-p` for a non-interactive run)
+    #[arg(long = "input-file", value_name = "PATH")]
+    pub input_file: Option<PathBuf>,
+
     /// Arguments to pass to Claude
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub claude_args: Vec<String>,
@@ -237,11 +523,73 @@ pub struct ShellCmd {
     #[command(flatten)]
     pub runtime: RuntimeFlags,
 
+    /// Compare the guest's mounts with the expected mounts, then exit
+    #[arg(
+        long = "show-mounts",
+        alias = "print-mounts-table",
+        conflicts_with = "command"
+    )]
+    pub show_mounts: bool,
+
+    /// Show the agent's log from a running VM of this project instead of opening a shell
+    #[arg(long = "agent-logs", conflicts_with_all = ["command", "show_mounts"])]
+    pub agent_logs: bool,
+
+    /// Number of agent log lines to show (with --agent-logs)
+    #[arg(long, default_value = "50", requires = "agent_logs")]
+    pub lines: usize,
+
+    /// Follow the agent log in real-time, like tail -f (with --agent-logs)
+    #[arg(long, requires = "agent_logs")]
+    pub follow: bool,
+
+    /// Run the command in every running VM of this project instead of a new VM
+    #[arg(
+        long = "all-running",
+        requires = "command",
+        conflicts_with_all = ["show_mounts", "agent_logs"]
+    )]
+    pub all_running: bool,
+
+    /// With --all-running: include the running VMs of every project
+    #[arg(long = "all-templates", requires = "all_running")]
+    pub all_templates: bool,
+
+    /// Print the environment variables the session would export, then exit
+    #[arg(
+        long = "dump-env",
+        conflicts_with_all = ["command", "show_mounts", "agent_logs", "all_running"]
+    )]
+    pub dump_env: bool,
+
+    /// Show the values of the variables (with --dump-env; redacted by default)
+    #[arg(long = "show-values", requires = "dump_env")]
+    pub show_values: bool,
+
+    /// Copy a host file into the VM before the shell starts: host_path:/vm/path (repeatable)
+    #[arg(
+        long = "copy",
+        value_name = "HOST:GUEST",
+        value_parser = FileCopy::from_spec,
+        conflicts_with_all = ["show_mounts", "agent_logs", "all_running", "dump_env"]
+    )]
+    pub copy: Vec<FileCopy>,
+
     /// Command to execute (optional, opens interactive shell if not provided)
     #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     pub command: Vec<String>,
 }
 
+/// Format of the report printed after a successful setup
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum SummaryFormat {
+    /// Readable summary after the setup output
+    #[default]
+    Human,
+    /// A single JSON object on stdout; progress output is suppressed
+    Json,
+}
+
 #[derive(Parser, Debug)]
 pub struct SetupCmd {
     /// VM sizing flags
@@ -296,6 +644,59 @@ pub struct SetupCmd {
     #[arg(long = "mount")]
     pub mounts: Vec<String>,
 
+    /// Reuse downloaded apt packages from a host cache (~/.claude-vm/apt-cache)
+    #[arg(long = "apt-cache")]
+    pub apt_cache: bool,
+
+    /// Empty the host apt package cache before setting up
+    #[arg(long = "clear-apt-cache")]
+    pub clear_apt_cache: bool,
+
+    /// Don't prefix setup script output lines with [<capability>] or [<phase>]
+    #[arg(long = "no-prefix")]
+    pub no_prefix: bool,
+
+    /// Allow setup phase repos pinned to a branch or tag instead of a commit hash
+    #[arg(long = "allow-unpinned")]
+    pub allow_unpinned: bool,
+
+    /// Keep the template when setup fails, and continue a failed setup from its
+    /// first incomplete step instead of rebuilding from scratch
+    #[arg(long = "resume-on-failure")]
+    pub resume_on_failure: bool,
+
+    /// Skip the setup hooks of the given capabilities (comma-separated), or of
+    /// all capabilities but network-isolation when no id is given. Packages and
+    /// forwards are still installed.
+    #[arg(
+        long = "no-capability-phases",
+        value_name = "IDS",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ','
+    )]
+    pub no_capability_phases: Option<Vec<String>>,
+
+    /// Warn about failed [[setup.checks]] instead of failing the setup
+    #[arg(long = "soft-checks")]
+    pub soft_checks: bool,
+
+    /// Raise disk and memory to what the enabled capabilities need, instead of warning
+    #[arg(long = "auto-size")]
+    pub auto_size: bool,
+
+    /// Also write the full setup output, timestamped, to this host file
+    #[arg(long = "build-log", value_name = "PATH")]
+    pub build_log: Option<PathBuf>,
+
+    /// Print the VM sizing, capabilities, packages and setup phases without creating a VM
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// Format of the summary printed when setup succeeds (json implies quiet output)
+    #[arg(long = "summary-format", value_enum, default_value_t = SummaryFormat::Human)]
+    pub summary_format: SummaryFormat,
+
     /// Skip Claude Code agent installation (dev builds only)
     #[cfg(debug_assertions)]
     #[arg(long)]
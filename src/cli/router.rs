@@ -6,18 +6,28 @@ const KNOWN_SUBCOMMANDS: &[&str] = &[
     "agent",
     "shell",
     "setup",
+    "auth",
     "info",
     "config",
     "list",
     "clean",
     "clean-all",
+    "template",
+    "capabilities",
+    "conversations",
     "version",
     "update",
     "network",
     "worktree",
     "w", // Short alias for worktree
+    "batch",
 ];
 
+/// Whether `name` is one of claude-vm's own subcommands
+pub fn is_known_subcommand(name: &str) -> bool {
+    KNOWN_SUBCOMMANDS.contains(&name)
+}
+
 /// Route CLI arguments to the appropriate command.
 ///
 /// This function implements backward compatibility by inserting "agent" as the default
@@ -30,6 +40,8 @@ const KNOWN_SUBCOMMANDS: &[&str] = &[
 ///
 /// - If `args[1]` is `--help`, `-h`, `--version`, or `-V`: unchanged (preserve main help/version)
 /// - If `args[1]` is a known subcommand: unchanged
+/// - If `args[1]` is a leading global option with a value (`--flavor <name>`, `--trace <path>`):
+///   the same rules apply to the argument after it
 /// - If `args[1]` starts with `-` (any flag): insert "agent" after program name
 /// - If `args[1]` is anything else (not a known subcommand): insert "agent" after program name
 ///
@@ -42,6 +54,9 @@ const KNOWN_SUBCOMMANDS: &[&str] = &[
 /// claude-vm agent /clear        -> claude-vm agent /clear (unchanged)
 /// claude-vm shell ls            -> claude-vm shell ls (unchanged)
 /// claude-vm --help              -> claude-vm --help (unchanged)
+/// claude-vm --flavor api setup  -> claude-vm --flavor api setup (unchanged)
+/// claude-vm --flavor api /clear -> claude-vm agent --flavor api /clear
+/// claude-vm --trace t.log list  -> claude-vm --trace t.log list (unchanged)
 /// ```
 ///
 /// # Known Trade-off
@@ -51,13 +66,18 @@ const KNOWN_SUBCOMMANDS: &[&str] = &[
 /// - Users can write `claude-vm agent --verbose /clear` instead
 /// - This edge case is uncommon
 /// - The simplicity benefit outweighs this minor issue
+///
+/// `--explain-routing` shows how a given command line is routed (see [`explain`]).
 pub fn route_args<I, T>(args: I) -> Vec<OsString>
 where
     I: IntoIterator<Item = T>,
     T: Into<OsString> + Clone,
 {
-    let args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    route(args.into_iter().map(Into::into).collect()).0
+}
 
+/// Route the arguments, also returning whether "agent" was inserted
+fn route(args: Vec<OsString>) -> (Vec<OsString>, bool) {
     // If no args provided (just program name), default to agent
     if args.len() < 2 {
         let mut routed = Vec::with_capacity(2);
@@ -65,19 +85,23 @@ where
             routed.push(args[0].clone());
         }
         routed.push("agent".into());
-        return routed;
+        return (routed, true);
     }
 
     let first_arg = args[1].to_string_lossy();
 
     // Preserve main --help and --version
     if first_arg == "--help" || first_arg == "-h" || first_arg == "--version" || first_arg == "-V" {
-        return args;
+        return (args, false);
     }
 
+    // Leading global options apply to any subcommand:
+    // look past them for an explicit subcommand
+    let command_arg = args.get(command_index(&args)).map(|a| a.to_string_lossy());
+
     // If first arg is a known subcommand, normalize and return
-    if KNOWN_SUBCOMMANDS.contains(&first_arg.as_ref()) {
-        return normalize_worktree_args(args);
+    if command_arg.is_some_and(|arg| KNOWN_SUBCOMMANDS.contains(&arg.as_ref())) {
+        return (normalize_worktree_args(args), false);
     }
 
     // If first arg starts with '-' (any flag) OR is not a known subcommand,
@@ -88,7 +112,150 @@ where
     routed.extend_from_slice(&args[1..]);
 
     // Normalize --worktree arguments before passing to clap
-    normalize_worktree_args(routed)
+    (normalize_worktree_args(routed), true)
+}
+
+/// Flag that prints how the arguments were routed instead of running the command
+pub const EXPLAIN_ROUTING_FLAG: &str = "--explain-routing";
+
+/// Remove `--explain-routing` from the arguments, returning whether it was given.
+/// Occurrences after `--` belong to the trailing args and are kept.
+pub fn take_explain_routing<I, T>(args: I) -> (Vec<OsString>, bool)
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut found = false;
+    let mut after_separator = false;
+    let mut kept = Vec::new();
+    for arg in args.into_iter().map(Into::into) {
+        if arg == "--" {
+            after_separator = true;
+        } else if !after_separator && arg == EXPLAIN_ROUTING_FLAG {
+            found = true;
+            continue;
+        }
+        kept.push(arg);
+    }
+    (kept, found)
+}
+
+/// How the router interpreted a command line, for `--explain-routing`
+#[derive(Debug, PartialEq)]
+pub struct Routing {
+    pub input: Vec<OsString>,
+    pub routed: Vec<OsString>,
+    /// Subcommand clap will run (None for the top-level --help/--version)
+    pub subcommand: Option<String>,
+    pub agent_inserted: bool,
+}
+
+/// Route the arguments and describe the result
+pub fn explain<I, T>(args: I) -> Routing
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let input: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    let (routed, agent_inserted) = route(input.clone());
+    let subcommand = routed
+        .get(command_index(&routed))
+        .map(|a| a.to_string_lossy().to_string())
+        .filter(|arg| KNOWN_SUBCOMMANDS.contains(&arg.as_str()));
+
+    Routing {
+        input,
+        routed,
+        subcommand,
+        agent_inserted,
+    }
+}
+
+impl Routing {
+    /// Why "agent" was inserted
+    fn insertion_reason(&self) -> String {
+        match self.input.get(command_index(&self.input)) {
+            None => "no subcommand given".to_string(),
+            Some(arg) if arg.to_string_lossy().starts_with('-') => format!(
+                "'{}' is an option, so the subcommand defaults to agent",
+                arg.to_string_lossy()
+            ),
+            Some(arg) => format!("'{}' is not a subcommand", arg.to_string_lossy()),
+        }
+    }
+
+    /// Arguments the chosen subcommand passes through (Claude args, shell command),
+    /// or the parse error clap reports for the routed arguments
+    fn trailing_args(&self) -> std::result::Result<Vec<String>, String> {
+        use super::{Cli, Commands};
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from(&self.routed).map_err(|e| {
+            e.render()
+                .to_string()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        })?;
+        Ok(match cli.command {
+            Some(Commands::Agent(cmd)) => cmd.claude_args,
+            Some(Commands::Shell(cmd)) => cmd.command,
+            Some(Commands::Batch(cmd)) => cmd.claude_args,
+            _ => Vec::new(),
+        })
+    }
+}
+
+impl std::fmt::Display for Routing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |args: &[OsString]| {
+            args.iter()
+                .skip(1)
+                .map(|a| a.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        writeln!(f, "Input:      {}", join(&self.input))?;
+        writeln!(f, "Routed:     {}", join(&self.routed))?;
+        match &self.subcommand {
+            Some(subcommand) if self.agent_inserted => writeln!(
+                f,
+                "Subcommand: {} (inserted: {})",
+                subcommand,
+                self.insertion_reason()
+            )?,
+            Some(subcommand) => writeln!(f, "Subcommand: {}", subcommand)?,
+            None => return write!(f, "Subcommand: none (top-level help or version)"),
+        }
+        match self.trailing_args() {
+            Ok(args) if args.is_empty() => write!(f, "Trailing:   (none)"),
+            Ok(args) => write!(f, "Trailing:   {:?}", args),
+            Err(error) => write!(f, "Parse error: {}", error),
+        }
+    }
+}
+
+/// Global options taking a value that may come before the subcommand
+const LEADING_VALUE_OPTIONS: &[&str] = &["--flavor", "--trace"];
+
+/// Index of the first argument after any leading global options
+fn command_index(args: &[OsString]) -> usize {
+    let mut index = 1;
+    while let Some(arg) = args.get(index).map(|a| a.to_string_lossy()) {
+        if LEADING_VALUE_OPTIONS.contains(&arg.as_ref()) {
+            index += 2;
+        } else if LEADING_VALUE_OPTIONS
+            .iter()
+            .any(|option| arg.starts_with(&format!("{}=", option)))
+        {
+            index += 1;
+        } else {
+            break;
+        }
+    }
+    index
 }
 
 /// Normalize --worktree arguments to --worktree=value format.
@@ -330,6 +497,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_flavor_before_explicit_subcommand_unchanged() {
+        let input = args(&["claude-vm", "--flavor", "backend", "agent", "/clear"]);
+        assert_eq!(route_args(input.clone()), input);
+
+        let input = args(&["claude-vm", "--flavor=backend", "setup"]);
+        assert_eq!(route_args(input.clone()), input);
+    }
+
+    #[test]
+    fn test_flavor_without_subcommand_routes_to_agent() {
+        let input = args(&["claude-vm", "--flavor", "backend", "/clear"]);
+        let expected = args(&["claude-vm", "agent", "--flavor", "backend", "/clear"]);
+        assert_eq!(route_args(input), expected);
+
+        let input = args(&["claude-vm", "--flavor", "backend"]);
+        let expected = args(&["claude-vm", "agent", "--flavor", "backend"]);
+        assert_eq!(route_args(input), expected);
+    }
+
+    #[test]
+    fn test_trace_before_explicit_subcommand_unchanged() {
+        let input = args(&["claude-vm", "--trace", "/tmp/t.log", "list"]);
+        assert_eq!(route_args(input.clone()), input);
+
+        let input = args(&[
+            "claude-vm",
+            "--trace=/tmp/t.log",
+            "--flavor",
+            "api",
+            "setup",
+        ]);
+        assert_eq!(route_args(input.clone()), input);
+
+        let input = args(&["claude-vm", "--trace", "/tmp/t.log", "/clear"]);
+        let expected = args(&["claude-vm", "agent", "--trace", "/tmp/t.log", "/clear"]);
+        assert_eq!(route_args(input), expected);
+    }
+
     // Synchronization test
 
     #[test]
@@ -563,4 +769,59 @@ mod tests {
         let output = route_args(input);
         assert_eq!(output, expected);
     }
+
+    // --explain-routing
+
+    #[test]
+    fn test_take_explain_routing() {
+        let (rest, found) = take_explain_routing(args(&["claude-vm", "--explain-routing", "list"]));
+        assert!(found);
+        assert_eq!(rest, args(&["claude-vm", "list"]));
+
+        // After --, the flag belongs to the trailing args
+        let input = args(&["claude-vm", "shell", "--", "echo", "--explain-routing"]);
+        let (rest, found) = take_explain_routing(input.clone());
+        assert!(!found);
+        assert_eq!(rest, input);
+    }
+
+    #[test]
+    fn test_explain_flag_then_subcommand_name() {
+        let routing = explain(args(&["claude-vm", "--verbose", "shell"]));
+        assert_eq!(routing.subcommand.as_deref(), Some("agent"));
+        assert!(routing.agent_inserted);
+        assert_eq!(routing.trailing_args().unwrap(), vec!["shell"]);
+
+        let text = routing.to_string();
+        assert!(text.contains("Routed:     agent --verbose shell"));
+        assert!(text.contains("inserted: '--verbose' is an option"));
+    }
+
+    #[test]
+    fn test_explain_explicit_subcommand() {
+        let routing = explain(args(&[
+            "claude-vm",
+            "--flavor",
+            "api",
+            "shell",
+            "ls",
+            "-la",
+        ]));
+        assert_eq!(routing.subcommand.as_deref(), Some("shell"));
+        assert!(!routing.agent_inserted);
+        assert_eq!(routing.trailing_args().unwrap(), vec!["ls", "-la"]);
+    }
+
+    #[test]
+    fn test_explain_implicit_agent_and_help() {
+        let routing = explain(args(&["claude-vm", "/clear"]));
+        assert!(routing.agent_inserted);
+        assert!(routing
+            .to_string()
+            .contains("inserted: '/clear' is not a subcommand"));
+
+        let routing = explain(args(&["claude-vm", "--help"]));
+        assert_eq!(routing.subcommand, None);
+        assert!(!routing.agent_inserted);
+    }
 }
@@ -1,15 +1,102 @@
 use crate::cli::AgentCmd;
-use crate::commands::helpers;
+use crate::commands::{conversations, helpers, last_run};
 use crate::config::Config;
-use crate::error::Result;
+use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
 use crate::scripts::runner;
 use crate::utils::env as env_utils;
-use crate::vm::session::VmSession;
+use crate::vm::limactl::LimaCtl;
+use std::path::Path;
+
+/// Agent binary invoked inside the VM
+const AGENT_COMMAND: &str = "claude";
+
+/// Build the agent arguments: configured defaults first, then the args of enabled
+/// capabilities, then user-provided args.
+/// Capability args already present in the defaults are dropped; user args are kept as given.
+/// With `--no-default-args`, only the user-provided args are used.
+/// A prompt read from `--input-file` is appended last, as Claude's positional prompt.
+fn build_agent_args(
+    config: &Config,
+    cmd: &AgentCmd,
+    capability_args: &[String],
+    prompt: Option<String>,
+) -> Vec<String> {
+    let mut args = Vec::new();
+    if !cmd.no_default_args {
+        args = config.defaults.claude_args();
+        for group in option_groups(capability_args) {
+            if !args.windows(group.len()).any(|window| window == group) {
+                args.extend(group.iter().cloned());
+            }
+        }
+    }
+    args.extend(cmd.claude_args.iter().cloned());
+    args.extend(prompt);
+    args
+}
+
+/// Split arguments into options with their values (`--model opus`), so duplicates
+/// are detected per option rather than per word
+fn option_groups(args: &[String]) -> Vec<&[String]> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    for (i, arg) in args.iter().enumerate().skip(1) {
+        if arg.starts_with('-') {
+            groups.push(&args[start..i]);
+            start = i;
+        }
+    }
+    if start < args.len() {
+        groups.push(&args[start..]);
+    }
+    groups
+}
+
+/// Read the prompt passed with `--input-file`
+fn read_input_file(path: &Path) -> Result<String> {
+    if !path.is_file() {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Input file not found: {}",
+            path.display()
+        )));
+    }
+    let prompt = std::fs::read_to_string(path)?;
+    if prompt.trim().is_empty() {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Input file is empty: {}",
+            path.display()
+        )));
+    }
+    Ok(prompt)
+}
+
+/// Load the config of an agent run in `project`: the config files, the
+/// remembered sizing, then the command's flags
+pub fn load_config(project: &Project, cmd: &AgentCmd, verbose: bool) -> Result<Config> {
+    let config = Config::load_with_main_repo(project.root(), project.main_repo_root())?
+        .with_last_run(project.root())
+        .with_runtime_overrides(&cmd.runtime, verbose)
+        .with_conversations(!cmd.no_conversations)
+        .with_conversation_project(cmd.conversation_project.as_deref())?
+        .with_fresh_home(cmd.fresh_home)
+        .with_drop_privileges(cmd.drop_privileges)
+        .with_retry_on_crash(cmd.retry_on_crash);
+    Ok(config)
+}
 
 pub fn execute(project: &Project, config: &Config, cmd: &AgentCmd) -> Result<()> {
+    LimaCtl::ensure_available()?;
+    let use_vm = cmd.runtime.use_vm.as_deref();
+
+    // Read the prompt before creating anything, so a bad path fails fast
+    let prompt = cmd.input_file.as_deref().map(read_input_file).transpose()?;
+
     // Ensure template exists (create if missing and user confirms)
-    helpers::ensure_template_exists(project, config)?;
+    // An existing VM does not need the project template
+    if use_vm.is_none() {
+        helpers::ensure_template_exists(project, config)?;
+    }
 
     // Resolve worktree if --worktree flag present
     if !cmd.runtime.worktree.is_empty() {
@@ -17,31 +104,25 @@ pub fn execute(project: &Project, config: &Config, cmd: &AgentCmd) -> Result<()>
         std::env::set_current_dir(&worktree_path)?;
     }
 
-    if !config.verbose {
+    // Keep the conversation folder of the project being mounted
+    if let Some(root) =
+        crate::vm::mount::project_mount_locations(config.project_root_fallback()).first()
+    {
+        conversations::auto_prune(config, root);
+    }
+
+    if !config.verbose && use_vm.is_none() {
         eprintln!("Starting ephemeral VM session...");
     }
 
     // Create session
-    let session = VmSession::new(
-        project,
-        config.verbose,
-        config.mount_conversations,
-        &config.mounts,
-    )?;
+    let session = helpers::open_session(project, config, use_vm)?;
     let _cleanup = session.ensure_cleanup();
 
     // Build Claude command with arguments
-    let mut args: Vec<&str> = Vec::new();
-
-    // Add default Claude args from config
-    for arg in &config.defaults.claude_args {
-        args.push(arg.as_str());
-    }
-
-    // Add user-provided Claude args
-    for arg in &cmd.claude_args {
-        args.push(arg.as_str());
-    }
+    let capability_args = crate::capabilities::get_agent_args(config)?;
+    let args = build_agent_args(config, cmd, &capability_args, prompt);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
 
     eprintln!("Running Claude in VM: {}", session.name());
 
@@ -50,12 +131,12 @@ pub fn execute(project: &Project, config: &Config, cmd: &AgentCmd) -> Result<()>
         session.name(),
         None,
         "command",
-        &["-v", "claude"],
+        &["-v", AGENT_COMMAND],
         false,
     );
 
     if check_claude.is_err() {
-        return Err(crate::error::ClaudeVmError::CommandFailed(
+        return Err(ClaudeVmError::CommandFailed(
             "Claude CLI is not installed in the VM.\n\
              \n\
              If you used --no-agent-install during setup, you cannot run 'claude-vm agent'.\n\
@@ -85,10 +166,141 @@ pub fn execute(project: &Project, config: &Config, cmd: &AgentCmd) -> Result<()>
         config,
         &session,
         workdir,
-        "claude",
+        AGENT_COMMAND,
         &args,
         &env_vars,
     )?;
 
+    last_run::remember(
+        project.root(),
+        &config.vm,
+        config.defaults.remembers_runtime(),
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_agent_args_order() {
+        let mut config = Config::default();
+        config.defaults.claude_args = Some(vec!["--default".to_string()]);
+        let cmd = AgentCmd {
+            runtime: Default::default(),
+            no_conversations: false,
+            conversation_project: None,
+            fresh_home: false,
+            drop_privileges: false,
+            retry_on_crash: None,
+            no_default_args: false,
+            input_file: None,
+            claude_args: vec!["--user".to_string(), "prompt".to_string()],
+        };
+
+        assert_eq!(
+            build_agent_args(&config, &cmd, &[], None),
+            vec!["--default", "--user", "prompt"]
+        );
+    }
+
+    #[test]
+    fn test_build_agent_args_no_default_args() {
+        let config = Config::default();
+        let cmd = AgentCmd {
+            runtime: Default::default(),
+            no_conversations: false,
+            conversation_project: None,
+            fresh_home: false,
+            drop_privileges: false,
+            retry_on_crash: None,
+            no_default_args: true,
+            input_file: None,
+            claude_args: vec!["--user".to_string()],
+        };
+
+        assert_eq!(build_agent_args(&config, &cmd, &[], None), vec!["--user"]);
+    }
+
+    #[test]
+    fn test_build_agent_args_input_file_prompt_last() {
+        let mut config = Config::default();
+        config.defaults.claude_args = Some(vec!["--default".to_string()]);
+        let cmd = AgentCmd {
+            runtime: Default::default(),
+            no_conversations: false,
+            conversation_project: None,
+            fresh_home: false,
+            drop_privileges: false,
+            retry_on_crash: None,
+            no_default_args: false,
+            input_file: Some("prompt.md".into()),
+            claude_args: vec!["-p".to_string()],
+        };
+
+        assert_eq!(
+            build_agent_args(&config, &cmd, &[], Some("fix the tests\n".to_string())),
+            vec!["--default", "-p", "fix the tests\n"]
+        );
+    }
+
+    #[test]
+    fn test_build_agent_args_capability_args() {
+        let mut config = Config::default();
+        config.defaults.claude_args = Some(vec![
+            "--default".to_string(),
+            "--add-dir".to_string(),
+            "/data".to_string(),
+        ]);
+        let capability_args: Vec<String> = ["--add-dir", "/data", "--add-dir", "/cache", "--cap"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut cmd = AgentCmd {
+            runtime: Default::default(),
+            no_conversations: false,
+            conversation_project: None,
+            fresh_home: false,
+            drop_privileges: false,
+            retry_on_crash: None,
+            no_default_args: false,
+            input_file: None,
+            claude_args: vec!["--cap".to_string()],
+        };
+
+        // Defaults, then capability args not already in the defaults, then user args
+        assert_eq!(
+            build_agent_args(&config, &cmd, &capability_args, None),
+            vec![
+                "--default",
+                "--add-dir",
+                "/data",
+                "--add-dir",
+                "/cache",
+                "--cap",
+                "--cap"
+            ]
+        );
+
+        cmd.no_default_args = true;
+        assert_eq!(
+            build_agent_args(&config, &cmd, &capability_args, None),
+            vec!["--cap"]
+        );
+    }
+
+    #[test]
+    fn test_read_input_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("prompt.md");
+
+        assert!(read_input_file(&path).is_err());
+
+        std::fs::write(&path, "  \n").unwrap();
+        assert!(read_input_file(&path).is_err());
+
+        std::fs::write(&path, "review the diff").unwrap();
+        assert_eq!(read_input_file(&path).unwrap(), "review the diff");
+    }
+}
@@ -0,0 +1,143 @@
+use crate::commands::network::{find_running_vms, select_vm};
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use crate::vm::limactl::LimaCtl;
+use std::process::Command;
+
+/// Where the agent keeps its logs and credentials inside the VM
+pub struct AgentPaths;
+
+impl AgentPaths {
+    /// File in which a session with its own config directory (--fresh-home)
+    /// records it, for commands run from other shells in the VM
+    pub const SESSION_CONFIG_DIR_FILE: &'static str = "/tmp/claude-vm-config-dir";
+
+    /// Claude's config directory: CLAUDE_CONFIG_DIR like Claude itself, then
+    /// the running session's, then the default
+    pub const CONFIG_DIR: &'static str =
+        "${CLAUDE_CONFIG_DIR:-$(cat /tmp/claude-vm-config-dir 2>/dev/null || echo \"$HOME/.claude\")}";
+
+    /// Per-session debug logs, one `<session-id>.txt` file per session
+    pub fn log_dir() -> String {
+        format!("{}/debug", Self::CONFIG_DIR)
+    }
+
+    /// OAuth credentials written by the login flow
+    pub fn credentials_file() -> String {
+        format!("{}/.credentials.json", Self::CONFIG_DIR)
+    }
+
+    /// Global state, including the logged-in account (next to the config
+    /// directory, or inside it when CLAUDE_CONFIG_DIR is set)
+    pub fn state_file() -> String {
+        "${CLAUDE_CONFIG_DIR:-$(cat /tmp/claude-vm-config-dir 2>/dev/null || echo \"$HOME\")}/.claude.json"
+            .to_string()
+    }
+}
+
+/// Exit code of the read script when the VM has no agent log yet
+const NO_LOGS_EXIT: i32 = 3;
+
+pub fn execute(project: &Project, lines: usize, follow: bool) -> Result<()> {
+    let running_vms = find_running_vms(project)?;
+
+    if running_vms.is_empty() {
+        eprintln!("No ephemeral VMs are currently running for this project.");
+        eprintln!("Agent logs are only available while a VM is running.");
+        eprintln!();
+        eprintln!("Start a VM with:");
+        eprintln!("  claude-vm        # Run Claude");
+        return Err(ClaudeVmError::CommandFailed("No running VMs".to_string()));
+    }
+
+    let instance_name = select_vm(&running_vms)?;
+
+    if follow {
+        eprintln!(
+            "Agent logs for VM {} (Ctrl+C to stop following)",
+            instance_name
+        );
+    }
+
+    let status = LimaCtl::run_status(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "sh",
+        "-c",
+        &read_script(lines, follow),
+    ]))
+    .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to read agent logs: {}", e)))?;
+
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(NO_LOGS_EXIT) => {
+            eprintln!(
+                "No agent logs found in {} (inside VM).",
+                AgentPaths::log_dir()
+            );
+            eprintln!();
+            eprintln!("Claude writes a log per session once it has started.");
+            eprintln!("If the agent is running, try starting it with: claude-vm --debug");
+            Ok(())
+        }
+        // Interrupting `tail -f` with Ctrl+C is the normal way to stop following
+        None | Some(130) if follow => Ok(()),
+        _ => Err(ClaudeVmError::CommandFailed(
+            "Failed to read agent logs".to_string(),
+        )),
+    }
+}
+
+/// Script printing the last lines of the most recent agent log
+fn read_script(lines: usize, follow: bool) -> String {
+    format!(
+        "latest=$(ls -1t {dir}/*.txt 2>/dev/null | head -n 1)\n\
+         [ -n \"$latest\" ] || exit {no_logs}\n\
+         echo \"==> $latest <==\" >&2\n\
+         exec tail -n {lines}{follow} \"$latest\"\n",
+        dir = AgentPaths::log_dir(),
+        no_logs = NO_LOGS_EXIT,
+        lines = lines,
+        follow = if follow { " -F" } else { "" }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_script_tails_latest_log() {
+        let script = read_script(20, false);
+        assert!(script.contains(&format!("ls -1t {}/debug/*.txt", AgentPaths::CONFIG_DIR)));
+        assert!(script.contains("exit 3"));
+        assert!(script.contains("exec tail -n 20 \"$latest\""));
+    }
+
+    #[test]
+    fn test_config_dir_resolution() {
+        let resolve = |env: Option<&str>| {
+            let mut command = std::process::Command::new("sh");
+            command
+                .args(["-c", &format!("echo {}", AgentPaths::CONFIG_DIR)])
+                .env("HOME", "/home/me")
+                .env_remove("CLAUDE_CONFIG_DIR");
+            if let Some(dir) = env {
+                command.env("CLAUDE_CONFIG_DIR", dir);
+            }
+            let output = command.output().unwrap();
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+
+        assert!(AgentPaths::CONFIG_DIR.contains(AgentPaths::SESSION_CONFIG_DIR_FILE));
+        assert_eq!(resolve(Some("/tmp/fresh/.claude")), "/tmp/fresh/.claude");
+        if !std::path::Path::new(AgentPaths::SESSION_CONFIG_DIR_FILE).exists() {
+            assert_eq!(resolve(None), "/home/me/.claude");
+        }
+    }
+
+    #[test]
+    fn test_read_script_follow() {
+        assert!(read_script(50, true).contains("exec tail -n 50 -F \"$latest\""));
+    }
+}
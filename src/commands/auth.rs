@@ -0,0 +1,135 @@
+use crate::commands::agent_logs::AgentPaths;
+use crate::commands::setup;
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use crate::status;
+use crate::vm::{limactl::LimaCtl, template};
+use std::io::{self, Write};
+
+/// Re-authenticate Claude in the project template without running a session.
+///
+/// Credentials live in the template, and every session VM is cloned from it,
+/// so re-authenticating the template fixes expired auth for all later sessions.
+/// With `reset`, the cached credentials are deleted before logging in again,
+/// for when the agent is stuck in a bad auth state.
+pub fn execute(project: &Project, reset: bool, yes: bool) -> Result<()> {
+    LimaCtl::ensure_available()?;
+
+    let name = project.template_name();
+    if !template::exists(name)? {
+        return Err(ClaudeVmError::TemplateNotFound(name.to_string()));
+    }
+
+    let running = LimaCtl::list()?
+        .iter()
+        .any(|vm| vm.name == name && vm.status == "Running");
+    if running {
+        return Err(ClaudeVmError::CommandFailed(format!(
+            "Template '{}' is running. Stop it first with: limactl stop {}",
+            name, name
+        )));
+    }
+
+    if reset && !yes && !confirm_reset(name) {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    status!("Starting template VM...");
+    LimaCtl::start(name, false)?;
+
+    let result = if reset {
+        reset_credentials(name)
+    } else {
+        Ok(())
+    }
+    .and_then(|()| setup::authenticate_claude(project));
+
+    status!("Stopping template VM...");
+    let stopped = LimaCtl::stop(name, false);
+
+    result?;
+    stopped?;
+
+    status!("✓ Claude re-authenticated for template: {}", name);
+    Ok(())
+}
+
+fn confirm_reset(name: &str) -> bool {
+    println!("Template: {}", name);
+    println!(
+        "This will delete Claude's cached credentials ({} and the account in {}).",
+        AgentPaths::credentials_file(),
+        AgentPaths::state_file()
+    );
+    print!("Reset authentication? [y/N] ");
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok();
+    let input = input.trim().to_lowercase();
+    input == "y" || input == "yes"
+}
+
+/// Delete the cached credentials in the running template
+fn reset_credentials(name: &str) -> Result<()> {
+    status!("Removing cached credentials...");
+    LimaCtl::shell(name, None, "sh", &["-c", &reset_script()], false)?;
+    Ok(())
+}
+
+/// Script deleting the credentials file and the logged-in account from the
+/// agent's state, keeping its other settings (onboarding, MCP servers)
+fn reset_script() -> String {
+    format!(
+        "credentials=\"{credentials}\"\n\
+         state=\"{state}\"\n\
+         if [ -f \"$credentials\" ]; then rm -f \"$credentials\" && echo \"Removed $credentials\"; fi\n\
+         if [ -f \"$state\" ]; then\n\
+         \x20 jq 'del(.oauthAccount, .primaryApiKey, .customApiKeyResponses)' \"$state\" > \"$state.tmp\" \\\n\
+         \x20   && mv \"$state.tmp\" \"$state\" && echo \"Removed the account from $state\"\n\
+         fi\n",
+        credentials = AgentPaths::credentials_file(),
+        state = AgentPaths::state_file(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_reset_script_clears_credentials_only() {
+        let home = tempfile::TempDir::new().unwrap();
+        let config_dir = home.path().join(".claude");
+        std::fs::create_dir_all(config_dir.join("projects")).unwrap();
+        std::fs::write(config_dir.join(".credentials.json"), "{\"token\": 1}").unwrap();
+        std::fs::write(config_dir.join("settings.json"), "{}").unwrap();
+        let state = home.path().join(".claude.json");
+        std::fs::write(
+            &state,
+            r#"{"oauthAccount": {"email": "a@b.c"}, "hasCompletedOnboarding": true}"#,
+        )
+        .unwrap();
+
+        let status = Command::new("sh")
+            .args(["-c", &reset_script()])
+            .env("HOME", home.path())
+            .env_remove("CLAUDE_CONFIG_DIR")
+            .status()
+            .unwrap();
+
+        assert!(!config_dir.join(".credentials.json").exists());
+        assert!(config_dir.join("settings.json").exists());
+        assert!(config_dir.join("projects").is_dir());
+
+        // Stripping the account needs jq, which the template always has
+        if Command::new("jq").arg("--version").output().is_ok() {
+            assert!(status.success());
+            let state = std::fs::read_to_string(&state).unwrap();
+            assert!(!state.contains("oauthAccount"));
+            assert!(state.contains("hasCompletedOnboarding"));
+        }
+    }
+}
@@ -0,0 +1,486 @@
+use crate::cli::BatchCmd;
+use crate::commands::batch_state::{self, BatchState, WorktreeSnapshot};
+use crate::commands::helpers;
+use crate::config::Config;
+use crate::diagnostics;
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use crate::status;
+use crate::vm::limactl::LimaCtl;
+use crate::worktree::{filter, recovery, validation};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A worktree to run an agent session in
+struct BatchJob {
+    branch: String,
+    path: PathBuf,
+}
+
+/// Outcome of one agent session
+struct BatchResult {
+    branch: String,
+    exit_code: Option<i32>,
+    duration: Duration,
+    report: PathBuf,
+    error: Option<String>,
+}
+
+impl BatchResult {
+    fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+pub fn execute(
+    project: &Project,
+    config: &Config,
+    cmd: &BatchCmd,
+    deny_warnings: bool,
+) -> Result<()> {
+    if !cmd.runtime.worktree.is_empty() || cmd.runtime.use_vm.is_some() {
+        return Err(ClaudeVmError::InvalidConfig(
+            "--worktree and --use-vm are not available with batch: every session \
+             runs in its own worktree and ephemeral VM. Use --branch to pick worktrees."
+                .to_string(),
+        ));
+    }
+
+    let mut jobs = collect_jobs(project, config, cmd)?;
+    if jobs.is_empty() {
+        status!("No worktrees to run. Pass --branch <name> or --all-worktrees.");
+        return Ok(());
+    }
+
+    // Only --only-changed depends on the state: otherwise a broken state file
+    // must not stop the batch, and is replaced once it completes
+    let mut state = if cmd.only_changed {
+        BatchState::load(project.template_name())?
+    } else {
+        BatchState::load(project.template_name()).unwrap_or_else(|e| {
+            diagnostics::warn(
+                "batch-state",
+                format!("{} Starting from an empty state.", e),
+            );
+            BatchState::default()
+        })
+    };
+    if cmd.only_changed {
+        jobs = changed_jobs(jobs, &state);
+        if jobs.is_empty() {
+            status!("Nothing changed since the last batch run.");
+            return Ok(());
+        }
+    }
+
+    LimaCtl::ensure_available()?;
+
+    // Fail now rather than in the sessions that would go over the limit
+    let parallel = cmd.parallel as usize;
+    if let Some(limit) = config.vm.ephemeral_limit() {
+        let running = crate::commands::network::find_running_vms(project)?;
+        check_parallel_limit(running.len(), parallel.min(jobs.len()), limit)?;
+    }
+
+    // Build the template once so sessions don't race to create it
+    helpers::ensure_template_exists(project, config)?;
+
+    let report_dir = if cmd.report_dir.is_absolute() {
+        cmd.report_dir.clone()
+    } else {
+        project.root().join(&cmd.report_dir)
+    };
+    std::fs::create_dir_all(&report_dir)?;
+
+    let exe = std::env::current_exe()?;
+    let agent_args = agent_args(cmd, config.verbose, deny_warnings)?;
+
+    status!(
+        "Running {} agent session{} ({} at a time)",
+        jobs.len(),
+        if jobs.len() == 1 { "" } else { "s" },
+        parallel.min(jobs.len())
+    );
+    status!("Reports: {}\n", report_dir.display());
+
+    let paths: Vec<PathBuf> = jobs.iter().map(|job| job.path.clone()).collect();
+    let results = run_bounded(jobs, parallel, |job| {
+        run_session(&exe, &job, &report_dir, &agent_args)
+    });
+
+    print_summary(&results);
+    record_state(&mut state, project, &paths, &results);
+
+    let failed = results.iter().filter(|r| !r.success()).count();
+    if failed > 0 {
+        return Err(ClaudeVmError::CommandFailed(format!(
+            "{} of {} batch sessions failed",
+            failed,
+            results.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check `concurrent` more sessions fit in the ephemeral VM limit
+fn check_parallel_limit(running: usize, concurrent: usize, limit: u32) -> Result<()> {
+    if running + concurrent <= limit as usize {
+        return Ok(());
+    }
+    Err(ClaudeVmError::InvalidConfig(format!(
+        "Running {} sessions at a time would go over the ephemeral VM limit \
+         ({} already running, max_ephemeral = {}). Lower --parallel, stop running \
+         VMs, or raise [vm] max_ephemeral in .claude-vm.toml (0 = unlimited).",
+        concurrent, running, limit
+    )))
+}
+
+/// Resolve the worktrees to run in: requested branches (created or resumed),
+/// or every existing worktree except the main one
+fn collect_jobs(project: &Project, config: &Config, cmd: &BatchCmd) -> Result<Vec<BatchJob>> {
+    validation::check_git_version()?;
+
+    if cmd.all_worktrees {
+        let worktrees = recovery::ensure_clean_state()?;
+        return Ok(filter::skip_main(worktrees.iter())
+            .filter(|w| !w.is_bare)
+            .map(|w| BatchJob {
+                branch: w.branch.clone().unwrap_or_else(|| {
+                    w.path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "<detached>".to_string())
+                }),
+                path: w.path.clone(),
+            })
+            .collect());
+    }
+
+    // Create worktrees sequentially: concurrent `git worktree add` calls contend on the repo lock
+    cmd.branches
+        .iter()
+        .map(|branch| {
+            let path = helpers::resolve_worktree(std::slice::from_ref(branch), config, project)?;
+            Ok(BatchJob {
+                branch: branch.clone(),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Keep the jobs whose worktree changed since its last successful session,
+/// printing why each one runs or is skipped
+fn changed_jobs(jobs: Vec<BatchJob>, state: &BatchState) -> Vec<BatchJob> {
+    let mut changed = Vec::new();
+    for job in jobs {
+        let snapshot = match WorktreeSnapshot::capture(&job.path) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                status!("• {}: runs (cannot read worktree state: {})", job.branch, e);
+                changed.push(job);
+                continue;
+            }
+        };
+
+        match batch_state::change_reason(state.get(&job.path), &snapshot) {
+            Some(reason) => {
+                status!("• {}: runs ({})", job.branch, reason);
+                changed.push(job);
+            }
+            None => status!(
+                "⊘ {}: skipped (unchanged since the last batch run at {})",
+                job.branch,
+                batch_state::short(&snapshot.head)
+            ),
+        }
+    }
+    status!();
+    changed
+}
+
+/// Remember the state of worktrees whose session succeeded, so the next
+/// `--only-changed` run only picks up what changed after it
+fn record_state(
+    state: &mut BatchState,
+    project: &Project,
+    paths: &[PathBuf],
+    results: &[BatchResult],
+) {
+    for (path, result) in paths.iter().zip(results) {
+        if !result.success() {
+            continue;
+        }
+        match WorktreeSnapshot::capture(path) {
+            Ok(snapshot) => state.record(path, snapshot),
+            Err(e) => diagnostics::warn(
+                "batch-state",
+                format!("Cannot record the state of {}: {}", result.branch, e),
+            ),
+        }
+    }
+    if let Err(e) = state.save(project.template_name()) {
+        diagnostics::warn("batch-state", format!("Failed to save batch state: {}", e));
+    }
+}
+
+/// Arguments of every child `claude-vm agent` invocation: the parent's
+/// global and runtime flags, then the Claude arguments
+fn agent_args(cmd: &BatchCmd, verbose: bool, deny_warnings: bool) -> Result<Vec<String>> {
+    let mut args = vec!["agent".to_string()];
+    if verbose {
+        args.push("--verbose".to_string());
+    }
+    if deny_warnings {
+        args.push("--deny-warnings".to_string());
+    }
+    // Children run in their worktree, so relative paths must not move with them
+    args.extend(cmd.runtime.to_args(&std::env::current_dir()?));
+    args.push("--".to_string());
+    args.extend(cmd.claude_args.iter().cloned());
+    Ok(args)
+}
+
+/// Run one agent session as a child `claude-vm agent` process in the worktree.
+/// Each child gets its own PID, hence its own ephemeral VM and temp files.
+fn run_session(
+    exe: &Path,
+    job: &BatchJob,
+    report_dir: &Path,
+    agent_args: &[String],
+) -> BatchResult {
+    let report = report_dir.join(report_file_name(&job.branch));
+    let started = Instant::now();
+
+    let result = (|| -> Result<Option<i32>> {
+        let log = File::create(&report)?;
+        let status = Command::new(exe)
+            .current_dir(&job.path)
+            .args(agent_args)
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .status()?;
+        Ok(status.code())
+    })();
+
+    let (exit_code, error) = match result {
+        Ok(code) => (code, None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let mark = if exit_code == Some(0) { "✓" } else { "✗" };
+    status!("{} {} finished", mark, job.branch);
+
+    BatchResult {
+        branch: job.branch.clone(),
+        exit_code,
+        duration: started.elapsed(),
+        report,
+        error,
+    }
+}
+
+/// Run `f` over `items` with at most `parallel` concurrent calls.
+/// Results are returned in the same order as `items`.
+pub(crate) fn run_bounded<T, R, F>(items: Vec<T>, parallel: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let total = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate().collect::<VecDeque<_>>());
+    let results = Mutex::new(Vec::with_capacity(total));
+
+    std::thread::scope(|scope| {
+        for _ in 0..parallel.clamp(1, total.max(1)) {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().pop_front();
+                let Some((index, item)) = next else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap().push((index, result));
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Report file name for a branch. Slashes and other unsafe characters become
+/// '-', with a hash of the branch name appended so that e.g. `feature/login`
+/// and `feature-login` don't share a report.
+fn report_file_name(branch: &str) -> String {
+    let safe: String = branch
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if safe == branch {
+        return format!("{}.log", safe);
+    }
+    let digest = format!("{:x}", md5::compute(branch.as_bytes()));
+    format!("{}-{}.log", safe, &digest[..8])
+}
+
+fn print_summary(results: &[BatchResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.branch.len())
+        .max()
+        .unwrap_or(0)
+        .max("BRANCH".len());
+
+    status!("\nBatch summary:");
+    status!(
+        "  {:<width$}  {:<6}  {:>4}  {:>8}  REPORT",
+        "BRANCH",
+        "STATUS",
+        "EXIT",
+        "DURATION",
+        width = width
+    );
+    for result in results {
+        let status = if result.success() { "ok" } else { "failed" };
+        let exit = result
+            .exit_code
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        status!(
+            "  {:<width$}  {:<6}  {:>4}  {:>7}s  {}",
+            result.branch,
+            status,
+            exit,
+            result.duration.as_secs(),
+            result.report.display(),
+            width = width
+        );
+        if let Some(ref error) = result.error {
+            status!("  {:<width$}  error: {}", "", error, width = width);
+        }
+    }
+
+    let succeeded = results.iter().filter(|r| r.success()).count();
+    status!("\n{}/{} sessions succeeded", succeeded, results.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_report_file_name() {
+        assert_eq!(report_file_name("feature"), "feature.log");
+        assert_eq!(report_file_name("feature-login"), "feature-login.log");
+
+        let slashed = report_file_name("feature/login");
+        assert!(slashed.starts_with("feature-login-"));
+        assert!(slashed.ends_with(".log"));
+        assert_ne!(slashed, report_file_name("feature-login"));
+        assert_ne!(slashed, report_file_name("feature:login"));
+        assert!(report_file_name("fix bug#1").starts_with("fix-bug-1-"));
+    }
+
+    #[test]
+    fn test_agent_args_forward_parent_flags() {
+        use clap::Parser;
+
+        let cmd = BatchCmd::parse_from([
+            "batch",
+            "-b",
+            "feature",
+            "--memory",
+            "16",
+            "-A",
+            "--env",
+            "MODE=ci",
+            "--runtime-script",
+            "scripts/setup.sh",
+            "--",
+            "-p",
+            "hi",
+        ]);
+        let args = agent_args(&cmd, true, true).unwrap();
+        let cwd = std::env::current_dir().unwrap();
+
+        assert_eq!(&args[..3], ["agent", "--verbose", "--deny-warnings"]);
+        assert!(args.windows(2).any(|w| w == ["--memory", "16"]));
+        assert!(args.contains(&"--forward-ssh-agent".to_string()));
+        assert!(args.contains(&"--env=MODE=ci".to_string()));
+        assert!(args.contains(&format!(
+            "--runtime-script={}",
+            cwd.join("scripts/setup.sh").display()
+        )));
+        assert_eq!(&args[args.len() - 3..], ["--", "-p", "hi"]);
+    }
+
+    #[test]
+    fn test_run_bounded_preserves_order() {
+        let results = run_bounded((0..10).collect(), 3, |n| {
+            std::thread::sleep(Duration::from_millis((10 - n) as u64));
+            n * 2
+        });
+        assert_eq!(results, (0..10).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_run_bounded_respects_limit() {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        run_bounded((0..8).collect::<Vec<_>>(), 2, |_| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            running.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_check_parallel_limit() {
+        assert!(check_parallel_limit(0, 5, 5).is_ok());
+        assert!(check_parallel_limit(2, 3, 5).is_ok());
+
+        let err = check_parallel_limit(2, 4, 5).unwrap_err().to_string();
+        assert!(err.contains("Running 4 sessions at a time"));
+        assert!(err.contains("2 already running, max_ephemeral = 5"));
+        assert!(err.contains("--parallel"));
+    }
+
+    #[test]
+    fn test_run_bounded_empty() {
+        let results: Vec<i32> = run_bounded(Vec::<i32>::new(), 4, |n| n);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_batch_result_success() {
+        let result = |code| BatchResult {
+            branch: "b".to_string(),
+            exit_code: code,
+            duration: Duration::ZERO,
+            report: PathBuf::from("b.log"),
+            error: None,
+        };
+        assert!(result(Some(0)).success());
+        assert!(!result(Some(1)).success());
+        assert!(!result(None).success());
+    }
+}
@@ -0,0 +1,236 @@
+//! What each worktree looked like after its last successful batch session,
+//! used by `batch --only-changed` to skip worktrees where nothing changed.
+
+use crate::error::{ClaudeVmError, Result};
+use crate::utils::git;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Worktree state after a session: checked-out commit and uncommitted changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorktreeSnapshot {
+    /// Commit checked out in the worktree
+    pub head: String,
+    /// Hash of the uncommitted changes (status and diff against HEAD)
+    pub changes: String,
+}
+
+impl WorktreeSnapshot {
+    /// Read the current state of a worktree
+    pub fn capture(path: &Path) -> Result<Self> {
+        let dir = git::path_to_str(path, "worktree path")?;
+        let head = git::run_git_command(&["-C", dir, "rev-parse", "HEAD"], "read worktree HEAD")?;
+        let status = git::run_git_command(
+            &["-C", dir, "status", "--porcelain", "--untracked-files=all"],
+            "read worktree status",
+        )?;
+        let diff = git::run_git_command(
+            &["-C", dir, "diff", "HEAD", "--binary"],
+            "read worktree changes",
+        )?;
+
+        Ok(Self {
+            head: head.trim().to_string(),
+            changes: format!("{:x}", md5::compute(format!("{}\0{}", status, diff))),
+        })
+    }
+}
+
+/// Why a worktree needs a new session
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeReason {
+    /// No successful batch session recorded for this worktree
+    FirstRun,
+    /// HEAD moved since the last session
+    NewCommits { from: String, to: String },
+    /// Same HEAD, different uncommitted changes
+    WorkingTreeChanged,
+}
+
+impl std::fmt::Display for ChangeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeReason::FirstRun => write!(f, "no previous batch run"),
+            ChangeReason::NewCommits { from, to } => {
+                write!(f, "new commits ({} -> {})", short(from), short(to))
+            }
+            ChangeReason::WorkingTreeChanged => write!(f, "working tree changed"),
+        }
+    }
+}
+
+/// Abbreviated commit hash for display
+pub fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(8)]
+}
+
+/// Compare a worktree with its last recorded state (None: unchanged)
+pub fn change_reason(
+    previous: Option<&WorktreeSnapshot>,
+    current: &WorktreeSnapshot,
+) -> Option<ChangeReason> {
+    match previous {
+        None => Some(ChangeReason::FirstRun),
+        Some(previous) if previous.head != current.head => Some(ChangeReason::NewCommits {
+            from: previous.head.clone(),
+            to: current.head.clone(),
+        }),
+        Some(previous) if previous.changes != current.changes => {
+            Some(ChangeReason::WorkingTreeChanged)
+        }
+        Some(_) => None,
+    }
+}
+
+/// Last recorded state of each worktree of a project, keyed by worktree path
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchState {
+    #[serde(default)]
+    pub worktrees: BTreeMap<String, WorktreeSnapshot>,
+}
+
+impl BatchState {
+    /// Load the state of a project (empty when there is none yet)
+    pub fn load(template_name: &str) -> Result<Self> {
+        match state_path(template_name) {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            ClaudeVmError::CommandFailed(format!(
+                "Unreadable batch state ({}): {}. Delete it to start over.",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    pub fn save(&self, template_name: &str) -> Result<()> {
+        let path = state_path(template_name).ok_or_else(|| {
+            ClaudeVmError::InvalidConfig("HOME environment variable not set".to_string())
+        })?;
+        self.save_to(&path)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| {
+            ClaudeVmError::CommandFailed(format!("Failed to encode batch state: {}", e))
+        })?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+
+    pub fn get(&self, worktree: &Path) -> Option<&WorktreeSnapshot> {
+        self.worktrees.get(&worktree.to_string_lossy().to_string())
+    }
+
+    pub fn record(&mut self, worktree: &Path, snapshot: WorktreeSnapshot) {
+        self.worktrees
+            .insert(worktree.to_string_lossy().to_string(), snapshot);
+    }
+}
+
+/// State file of a project: `~/.claude-vm/state/batch-<template>.json`
+fn state_path(template_name: &str) -> Option<PathBuf> {
+    std::env::var("HOME").ok().map(|home| {
+        PathBuf::from(home)
+            .join(".claude-vm")
+            .join("state")
+            .join(format!("batch-{}.json", template_name))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(head: &str, changes: &str) -> WorktreeSnapshot {
+        WorktreeSnapshot {
+            head: head.to_string(),
+            changes: changes.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_change_reason() {
+        let last = snapshot("aaaaaaaaaaaa", "clean");
+
+        assert_eq!(change_reason(None, &last), Some(ChangeReason::FirstRun));
+        assert_eq!(change_reason(Some(&last), &last.clone()), None);
+        assert_eq!(
+            change_reason(Some(&last), &snapshot("aaaaaaaaaaaa", "dirty")),
+            Some(ChangeReason::WorkingTreeChanged)
+        );
+
+        let reason = change_reason(Some(&last), &snapshot("bbbbbbbbbbbb", "clean")).unwrap();
+        assert_eq!(reason.to_string(), "new commits (aaaaaaaa -> bbbbbbbb)");
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("state").join("batch-tpl.json");
+        assert_eq!(BatchState::load_from(&path).unwrap(), BatchState::default());
+
+        let mut state = BatchState::default();
+        state.record(Path::new("/work/feature"), snapshot("abc", "clean"));
+        state.save_to(&path).unwrap();
+
+        let loaded = BatchState::load_from(&path).unwrap();
+        assert_eq!(
+            loaded.get(Path::new("/work/feature")),
+            Some(&snapshot("abc", "clean"))
+        );
+        assert_eq!(loaded.get(Path::new("/work/other")), None);
+    }
+
+    #[test]
+    fn test_capture_tracks_commits_and_changes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let repo = dir.path();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(repo)
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        fs::write(repo.join("a.txt"), "one").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "init"]);
+
+        let clean = WorktreeSnapshot::capture(repo).unwrap();
+        assert_eq!(WorktreeSnapshot::capture(repo).unwrap(), clean);
+
+        fs::write(repo.join("a.txt"), "two").unwrap();
+        let dirty = WorktreeSnapshot::capture(repo).unwrap();
+        assert_eq!(
+            change_reason(Some(&clean), &dirty),
+            Some(ChangeReason::WorkingTreeChanged)
+        );
+
+        git(&["commit", "-qam", "second"]);
+        let committed = WorktreeSnapshot::capture(repo).unwrap();
+        assert!(matches!(
+            change_reason(Some(&dirty), &committed),
+            Some(ChangeReason::NewCommits { .. })
+        ));
+    }
+}
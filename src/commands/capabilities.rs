@@ -0,0 +1,275 @@
+use crate::capabilities::definition::Capability;
+use crate::capabilities::registry::CapabilityRegistry;
+use crate::cli::CapabilitiesCommands;
+use crate::error::{ClaudeVmError, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+pub fn execute(command: &CapabilitiesCommands) -> Result<()> {
+    match command {
+        CapabilitiesCommands::Graph { json } => {
+            let registry = CapabilityRegistry::load()?;
+            let graph = CapabilityGraph::build(&registry.all());
+            if *json {
+                let json = serde_json::to_string_pretty(&graph).map_err(|e| {
+                    ClaudeVmError::CommandFailed(format!("Failed to encode graph: {}", e))
+                })?;
+                println!("{}", json);
+            } else {
+                print_graph(&graph);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Dependency and conflict graph across all registered capabilities
+#[derive(Debug, Serialize)]
+struct CapabilityGraph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    /// `requires` cycles, each listed as a path starting and ending on the same id
+    cycles: Vec<Vec<String>>,
+    /// Edges pointing to capabilities that are not registered
+    dangling: Vec<Edge>,
+}
+
+#[derive(Debug, Serialize)]
+struct Node {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Edge {
+    from: String,
+    to: String,
+    kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum EdgeKind {
+    Requires,
+    Conflicts,
+}
+
+impl CapabilityGraph {
+    fn build(capabilities: &[Arc<Capability>]) -> Self {
+        let ids: BTreeSet<&str> = capabilities
+            .iter()
+            .map(|c| c.capability.id.as_str())
+            .collect();
+
+        let nodes = capabilities
+            .iter()
+            .map(|c| Node {
+                id: c.capability.id.clone(),
+                name: c.capability.name.clone(),
+            })
+            .collect();
+
+        let mut edges = Vec::new();
+        let mut dangling = Vec::new();
+        for c in capabilities {
+            let meta = &c.capability;
+            let requires = meta.requires.iter().map(|to| (to, EdgeKind::Requires));
+            let conflicts = meta.conflicts.iter().map(|to| (to, EdgeKind::Conflicts));
+            for (to, kind) in requires.chain(conflicts) {
+                let edge = Edge {
+                    from: meta.id.clone(),
+                    to: to.clone(),
+                    kind,
+                };
+                if ids.contains(to.as_str()) {
+                    edges.push(edge);
+                } else {
+                    dangling.push(edge);
+                }
+            }
+        }
+
+        let cycles = find_cycles(&edges);
+        Self {
+            nodes,
+            edges,
+            cycles,
+            dangling,
+        }
+    }
+}
+
+/// Find `requires` cycles with a depth-first search.
+/// Each cycle is reported once, starting from its first node in id order.
+fn find_cycles(edges: &[Edge]) -> Vec<Vec<String>> {
+    let mut requires: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for edge in edges.iter().filter(|e| e.kind == EdgeKind::Requires) {
+        requires.entry(&edge.from).or_default().push(&edge.to);
+    }
+
+    let mut cycles = Vec::new();
+    let mut done = BTreeSet::new();
+    for start in requires.keys() {
+        let mut path = Vec::new();
+        visit(start, &requires, &mut path, &mut done, &mut cycles);
+    }
+    cycles
+}
+
+fn visit<'a>(
+    id: &'a str,
+    requires: &BTreeMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    done: &mut BTreeSet<&'a str>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    if let Some(pos) = path.iter().position(|p| *p == id) {
+        let mut cycle: Vec<String> = path[pos..].iter().map(|s| s.to_string()).collect();
+        cycle.push(id.to_string());
+        cycles.push(cycle);
+        return;
+    }
+    if done.contains(id) {
+        return;
+    }
+
+    path.push(id);
+    for dep in requires.get(id).into_iter().flatten() {
+        visit(dep, requires, path, done, cycles);
+    }
+    path.pop();
+    done.insert(id);
+}
+
+fn print_graph(graph: &CapabilityGraph) {
+    println!("Capabilities:");
+    for node in &graph.nodes {
+        let related = |kind: EdgeKind| -> Vec<&str> {
+            graph
+                .edges
+                .iter()
+                .chain(&graph.dangling)
+                .filter(|e| e.from == node.id && e.kind == kind)
+                .map(|e| e.to.as_str())
+                .collect()
+        };
+
+        println!("  {} ({})", node.id, node.name);
+        let requires = related(EdgeKind::Requires);
+        if !requires.is_empty() {
+            println!("    requires: {}", requires.join(", "));
+        }
+        let conflicts = related(EdgeKind::Conflicts);
+        if !conflicts.is_empty() {
+            println!("    conflicts: {}", conflicts.join(", "));
+        }
+    }
+
+    println!();
+    if graph.cycles.is_empty() {
+        println!("✓ No dependency cycles");
+    } else {
+        for cycle in &graph.cycles {
+            println!("✗ Dependency cycle: {}", cycle.join(" -> "));
+        }
+    }
+    for edge in &graph.dangling {
+        println!(
+            "✗ '{}' {} unknown capability '{}'",
+            edge.from,
+            match edge.kind {
+                EdgeKind::Requires => "requires",
+                EdgeKind::Conflicts => "conflicts with",
+            },
+            edge.to
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capability(id: &str, requires: &[&str], conflicts: &[&str]) -> Arc<Capability> {
+        let toml = format!(
+            "[capability]\nid = \"{}\"\nname = \"{}\"\ndescription = \"test\"\nrequires = {:?}\nconflicts = {:?}\n",
+            id,
+            id.to_uppercase(),
+            requires,
+            conflicts
+        );
+        Arc::new(toml::from_str(&toml).unwrap())
+    }
+
+    #[test]
+    fn test_graph_nodes_and_edges() {
+        let graph = CapabilityGraph::build(&[
+            capability("a", &["b"], &["c"]),
+            capability("b", &[], &[]),
+            capability("c", &[], &[]),
+        ]);
+
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.nodes[0].name, "A");
+        assert_eq!(
+            graph.edges,
+            vec![
+                Edge {
+                    from: "a".to_string(),
+                    to: "b".to_string(),
+                    kind: EdgeKind::Requires,
+                },
+                Edge {
+                    from: "a".to_string(),
+                    to: "c".to_string(),
+                    kind: EdgeKind::Conflicts,
+                },
+            ]
+        );
+        assert!(graph.cycles.is_empty());
+        assert!(graph.dangling.is_empty());
+    }
+
+    #[test]
+    fn test_graph_detects_cycles() {
+        let graph = CapabilityGraph::build(&[
+            capability("a", &["b"], &[]),
+            capability("b", &["c"], &[]),
+            capability("c", &["a"], &[]),
+            capability("d", &["d"], &[]),
+            capability("e", &["a"], &[]),
+        ]);
+
+        assert_eq!(graph.cycles, vec![vec!["a", "b", "c", "a"], vec!["d", "d"]]);
+    }
+
+    #[test]
+    fn test_graph_reports_dangling_edges() {
+        let graph = CapabilityGraph::build(&[capability("a", &["missing"], &[])]);
+
+        assert!(graph.edges.is_empty());
+        assert_eq!(graph.dangling.len(), 1);
+        assert_eq!(graph.dangling[0].to, "missing");
+    }
+
+    #[test]
+    fn test_graph_json_shape() {
+        let graph =
+            CapabilityGraph::build(&[capability("a", &["b"], &[]), capability("b", &[], &[])]);
+        let json = serde_json::to_value(&graph).unwrap();
+
+        assert_eq!(json["nodes"][0]["id"], "a");
+        assert_eq!(json["edges"][0]["kind"], "requires");
+        assert_eq!(json["cycles"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_builtin_capabilities_have_no_cycles() {
+        let registry = CapabilityRegistry::load().unwrap();
+        let graph = CapabilityGraph::build(&registry.all());
+
+        assert!(graph.cycles.is_empty());
+        assert!(graph.dangling.is_empty());
+    }
+}
@@ -1,15 +1,32 @@
-use crate::error::Result;
+use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
+use crate::status;
+use crate::vm::limactl::{LimaCtl, VmInfo};
 use crate::vm::template;
 use std::io::{self, Write};
+use std::process::{Command, Stdio};
 
-pub fn execute(project: &Project, yes: bool) -> Result<()> {
+/// Delete the project template. With `unused`, only when it has not been
+/// used in that many days.
+pub fn execute(project: &Project, yes: bool, unused: Option<u32>) -> Result<()> {
+    LimaCtl::ensure_available()?;
     if !template::exists(project.template_name())? {
         println!("Template does not exist: {}", project.template_name());
         return Ok(());
     }
 
+    if let Some(days) = unused {
+        if let Some(message) = recently_used(project.template_name(), days) {
+            println!("{}", message);
+            return Ok(());
+        }
+    }
+
     println!("Template: {}", project.template_name());
+    println!(
+        "Last used: {}",
+        template::format_last_used(project.template_name())
+    );
     println!("This will delete the template VM.");
     println!();
 
@@ -34,3 +51,201 @@ pub fn execute(project: &Project, yes: bool) -> Result<()> {
 
     Ok(())
 }
+
+/// Why `clean --unused` keeps a template, if it was used in the last `days` days
+fn recently_used(template_name: &str, days: u32) -> Option<String> {
+    if template::is_unused_for(template_name, days) {
+        return None;
+    }
+    Some(format!(
+        "Template {} was used in the last {} days (last used: {}): nothing to remove.",
+        template_name,
+        days,
+        template::format_last_used(template_name)
+    ))
+}
+
+/// Stop and delete the project's session VMs whose agent process is gone
+/// (left behind by a crashed run). With `all`, every orphan is removed
+/// without prompting.
+pub fn orphans(project: &Project, all: bool) -> Result<()> {
+    LimaCtl::ensure_available()?;
+    let orphans = orphaned_sessions(LimaCtl::list()?, project.template_name(), process_alive);
+    if orphans.is_empty() {
+        println!(
+            "No orphaned session VMs for template: {}",
+            project.template_name()
+        );
+        return Ok(());
+    }
+
+    let selected = if all {
+        orphans
+    } else {
+        let names: Vec<String> = orphans.iter().map(|vm| vm.name.clone()).collect();
+        let Some(chosen) = select_orphans(&names)? else {
+            println!("Aborted.");
+            return Ok(());
+        };
+        orphans
+            .into_iter()
+            .filter(|vm| chosen.contains(&vm.name))
+            .collect()
+    };
+
+    for vm in &selected {
+        status!("Removing orphaned VM: {}", vm.name);
+        if vm.status == "Running" {
+            LimaCtl::stop(&vm.name, false)?;
+        }
+        LimaCtl::delete(&vm.name, true, false)?;
+    }
+    println!(
+        "Removed {} orphaned VM{}",
+        selected.len(),
+        if selected.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
+
+/// Session VMs (`<template>-<pid>`) of `template` whose process is not alive,
+/// in name order
+pub fn orphaned_sessions(
+    vms: Vec<VmInfo>,
+    template: &str,
+    is_alive: impl Fn(u32) -> bool,
+) -> Vec<VmInfo> {
+    let prefix = format!("{}-", template);
+    let mut orphans: Vec<VmInfo> = vms
+        .into_iter()
+        .filter(|vm| {
+            vm.name
+                .strip_prefix(&prefix)
+                .filter(|pid| !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|pid| pid.parse().ok())
+                .is_some_and(|pid| !is_alive(pid))
+        })
+        .collect();
+    orphans.sort_by(|a, b| a.name.cmp(&b.name));
+    orphans
+}
+
+/// Whether a process with this id is running on the host
+fn process_alive(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+    Command::new("ps")
+        .args(["-p", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        // Without ps, keep the VM rather than delete a live session
+        .unwrap_or(true)
+}
+
+/// Ask which orphans to remove. None when the user cancels.
+fn select_orphans(names: &[String]) -> Result<Option<Vec<String>>> {
+    println!("Orphaned session VMs found:");
+    for (i, name) in names.iter().enumerate() {
+        println!("  {}. {}", i + 1, name);
+    }
+    println!();
+    print!("Remove VM (1-{}, a = all, empty to cancel): ", names.len());
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    parse_selection(&input, names)
+}
+
+/// The VMs an answer to the orphan prompt selects
+fn parse_selection(input: &str, names: &[String]) -> Result<Option<Vec<String>>> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    if input == "a" || input == "all" {
+        return Ok(Some(names.to_vec()));
+    }
+    match input.parse::<usize>() {
+        Ok(n) if n > 0 && n <= names.len() => Ok(Some(vec![names[n - 1].clone()])),
+        _ => Err(ClaudeVmError::CommandFailed(
+            "Invalid selection".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm(name: &str, status: &str) -> VmInfo {
+        VmInfo {
+            name: name.to_string(),
+            status: status.to_string(),
+            memory: None,
+        }
+    }
+
+    #[test]
+    fn test_orphaned_sessions_filtering() {
+        let vms = vec![
+            vm("claude-tpl_demo-400", "Running"),
+            vm("claude-tpl_demo", "Stopped"),
+            vm("claude-tpl_demo-100", "Running"),
+            vm("claude-tpl_demo-200", "Stopped"),
+            vm("claude-tpl_demo-backend-300", "Running"),
+            vm("claude-tpl_other-500", "Running"),
+            vm("claude-tpl_demo-dev", "Running"),
+        ];
+        // Process 400 is still running
+        let orphans = orphaned_sessions(vms, "claude-tpl_demo", |pid| pid == 400);
+        let names: Vec<&str> = orphans.iter().map(|vm| vm.name.as_str()).collect();
+        assert_eq!(names, vec!["claude-tpl_demo-100", "claude-tpl_demo-200"]);
+        assert_eq!(orphans[1].status, "Stopped");
+    }
+
+    #[test]
+    fn test_orphan_selection() {
+        let names = vec!["vm-1".to_string(), "vm-2".to_string()];
+        assert_eq!(parse_selection("\n", &names).unwrap(), None);
+        assert_eq!(parse_selection("A\n", &names).unwrap(), Some(names.clone()));
+        assert_eq!(
+            parse_selection("2\n", &names).unwrap(),
+            Some(vec!["vm-2".to_string()])
+        );
+        assert!(parse_selection("3", &names).is_err());
+        assert!(process_alive(std::process::id()));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_clean_unused_keeps_fresh_template() {
+        let home = tempfile::TempDir::new().unwrap();
+        let old_home = std::env::var("HOME").ok();
+        std::env::set_var("HOME", home.path());
+        let dir = home.path().join(".lima").join("claude-tpl_demo");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let message = recently_used("claude-tpl_demo", 30).unwrap();
+        assert!(message.contains("nothing to remove"));
+        assert!(message.contains("last used: today"));
+
+        // Not used in 40 days
+        let forty_days = std::time::Duration::from_secs(40 * 24 * 60 * 60);
+        std::fs::File::open(&dir)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - forty_days)
+            .unwrap();
+        assert!(recently_used("claude-tpl_demo", 30).is_none());
+        assert!(recently_used("claude-tpl_demo", 60).is_some());
+
+        match old_home {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
+    }
+}
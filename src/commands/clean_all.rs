@@ -1,8 +1,21 @@
-use crate::error::Result;
-use crate::vm::template;
+use crate::error::{ClaudeVmError, Result};
+use crate::vm::{limactl::LimaCtl, template};
 use std::io::{self, Write};
 
-pub fn execute(yes: bool) -> Result<()> {
+/// Environment opt-in allowing `--yes` without `--confirm-destroy` (e.g. in scripts)
+const CONFIRM_DESTROY_ENV: &str = "CLAUDE_VM_CONFIRM_DESTROY";
+
+pub fn execute(yes: bool, confirm_destroy: bool) -> Result<()> {
+    // Refuse an unattended wipe unless it is explicitly acknowledged
+    if yes && !yes_allowed(confirm_destroy, std::env::var(CONFIRM_DESTROY_ENV).ok()) {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "clean-all --yes deletes the templates of every project without prompting.\n\
+             Add --confirm-destroy (or set {}=1) to confirm, or drop --yes to be prompted.",
+            CONFIRM_DESTROY_ENV
+        )));
+    }
+
+    LimaCtl::ensure_available()?;
     let templates = template::list_all()?;
 
     if templates.is_empty() {
@@ -15,18 +28,24 @@ pub fn execute(yes: bool) -> Result<()> {
     for template_name in &templates {
         println!("  - {}", template_name);
     }
+    println!(
+        "\nTotal: {} template(s) across all projects.",
+        templates.len()
+    );
     println!();
 
-    // Prompt for confirmation unless --yes was provided
+    // Require typing the template count unless --yes was confirmed
     if !yes {
-        print!("Delete {} template(s)? [y/N] ", templates.len());
+        print!(
+            "Type the number of templates ({}) to confirm deletion: ",
+            templates.len()
+        );
         let _ = io::stdout().flush();
 
         let mut input = String::new();
         io::stdin().read_line(&mut input).ok();
-        let input = input.trim().to_lowercase();
 
-        if input != "y" && input != "yes" {
+        if !confirmation_matches(&input, templates.len()) {
             println!("Aborted.");
             return Ok(());
         }
@@ -41,3 +60,38 @@ pub fn execute(yes: bool) -> Result<()> {
     println!("All templates cleaned successfully.");
     Ok(())
 }
+
+/// Whether `--yes` may skip the prompt: needs `--confirm-destroy` or the env opt-in
+fn yes_allowed(confirm_destroy: bool, env: Option<String>) -> bool {
+    confirm_destroy || matches!(env.as_deref().map(str::trim), Some("1") | Some("true"))
+}
+
+/// Whether the typed confirmation is the template count
+fn confirmation_matches(input: &str, count: usize) -> bool {
+    input.trim().parse::<usize>() == Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yes_allowed() {
+        assert!(yes_allowed(true, None));
+        assert!(yes_allowed(false, Some("1".to_string())));
+        assert!(yes_allowed(false, Some("true".to_string())));
+        assert!(!yes_allowed(false, None));
+        assert!(!yes_allowed(false, Some("0".to_string())));
+        assert!(!yes_allowed(false, Some("yes please".to_string())));
+    }
+
+    #[test]
+    fn test_confirmation_matches() {
+        assert!(confirmation_matches("3\n", 3));
+        assert!(confirmation_matches("  12 ", 12));
+        assert!(!confirmation_matches("y\n", 3));
+        assert!(!confirmation_matches("yes", 3));
+        assert!(!confirmation_matches("4", 3));
+        assert!(!confirmation_matches("", 0));
+    }
+}
@@ -1,17 +1,27 @@
 use crate::cli::ConfigCommands;
+use crate::commands::{config_bundle, config_init, config_lint, config_set};
 use crate::config::Config;
+use crate::diagnostics;
 use crate::error::Result;
 use crate::project::Project;
-use std::path::PathBuf;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
 pub fn execute(command: &ConfigCommands) -> Result<()> {
     match command {
-        ConfigCommands::Validate { file } => validate(file.as_deref()),
+        ConfigCommands::Validate { file, worktree } => validate(file.as_deref(), *worktree),
         ConfigCommands::Show => show(),
+        ConfigCommands::Lint => config_lint::lint(),
+        ConfigCommands::Diff { left, right } => diff(left.as_deref(), right.as_deref()),
+        ConfigCommands::Export { path } => config_bundle::export(path),
+        ConfigCommands::Import { path, force } => config_bundle::import(path, *force),
+        ConfigCommands::Init { global, force } => config_init::init(*global, *force),
+        ConfigCommands::Set { key, value } => config_set::set(key, value),
     }
 }
 
-fn validate(file: Option<&std::path::Path>) -> Result<()> {
+fn validate(file: Option<&std::path::Path>, worktree: bool) -> Result<()> {
     // If a specific file is provided, validate only that file
     if let Some(path) = file {
         println!("Validating configuration file: {}\n", path.display());
@@ -25,8 +35,9 @@ fn validate(file: Option<&std::path::Path>) -> Result<()> {
         }
 
         match Config::from_file(path) {
-            Ok(_) => {
+            Ok(config) => {
                 println!("✓ Configuration is valid!");
+                warn_usable_issues(&config);
                 Ok(())
             }
             Err(e) => {
@@ -68,8 +79,12 @@ fn validate(file: Option<&std::path::Path>) -> Result<()> {
         // Try to load merged config - this will validate all files
         println!("\nLoading and validating configuration...");
         match Config::load_with_main_repo(project.root(), project.main_repo_root()) {
-            Ok(_) => {
+            Ok(config) => {
                 println!("✓ Configuration is valid!");
+                warn_usable_issues(&config);
+                if worktree {
+                    report_worktree_conflicts(&project)?;
+                }
                 Ok(())
             }
             Err(e) => {
@@ -81,6 +96,101 @@ fn validate(file: Option<&std::path::Path>) -> Result<()> {
     }
 }
 
+/// Compare the main repository config with the worktree config loaded on top of it
+fn report_worktree_conflicts(project: &Project) -> Result<()> {
+    if project.main_repo_root() == project.root() {
+        println!("\nNot in a worktree: only the project config applies.");
+        return Ok(());
+    }
+
+    let main_path = project.main_repo_root().join(".claude-vm.toml");
+    let worktree_path = project.root().join(".claude-vm.toml");
+    let main = load_for_diff(&main_path, false)?;
+    let worktree = load_for_diff(&worktree_path, false)?;
+
+    println!("\nWorktree config conflicts:");
+    println!("  main:     {}", main_path.display());
+    println!("  worktree: {}", worktree_path.display());
+
+    let conflicts = worktree_conflicts(
+        &main,
+        &set_keys(&main_path)?,
+        &worktree,
+        &set_keys(&worktree_path)?,
+    );
+    if conflicts.is_empty() {
+        println!("✓ The worktree config does not override any main repository setting");
+        return Ok(());
+    }
+
+    for field in &conflicts {
+        println!("{}", field.path);
+        println!("  main:     {}", format_value(field.left.as_ref()));
+        println!("  worktree: {}", format_value(field.right.as_ref()));
+        match field.winner {
+            Winner::Left => println!("  → main wins"),
+            Winner::Right => println!("  → worktree wins"),
+            Winner::Combined => println!("  → combined: {}", format_value(field.merged.as_ref())),
+        }
+    }
+    diagnostics::warn(
+        "worktree-config-conflict",
+        format!(
+            "{} setting{} differ between the main repository and worktree configs",
+            conflicts.len(),
+            if conflicts.len() == 1 { "" } else { "s" }
+        ),
+    );
+    Ok(())
+}
+
+/// Fields written in both the main repository and the worktree config files,
+/// with different values. `*_keys` are the dotted keys present in each file.
+fn worktree_conflicts(
+    main: &Config,
+    main_keys: &BTreeSet<String>,
+    worktree: &Config,
+    worktree_keys: &BTreeSet<String>,
+) -> Vec<FieldDiff> {
+    let is_set = |keys: &BTreeSet<String>, path: &str| {
+        keys.iter()
+            .any(|key| path == key || path.starts_with(&format!("{}.", key)))
+    };
+    diff_configs(main, worktree)
+        .into_iter()
+        .filter(|field| is_set(main_keys, &field.path) && is_set(worktree_keys, &field.path))
+        .collect()
+}
+
+/// Dotted keys written in a config file (none if the file does not exist)
+fn set_keys(path: &Path) -> Result<BTreeSet<String>> {
+    if !path.exists() {
+        return Ok(BTreeSet::new());
+    }
+    let table: toml::Table = toml::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(table_keys(&table))
+}
+
+fn table_keys(table: &toml::Table) -> BTreeSet<String> {
+    let mut fields = BTreeMap::new();
+    let value = serde_json::to_value(table).unwrap_or(Value::Null);
+    flatten_value("", &value, &mut fields);
+    fields.into_keys().collect()
+}
+
+/// Report settings that load fine but probably don't do what was intended
+fn warn_usable_issues(config: &Config) {
+    for warning in config.vm.validate() {
+        diagnostics::warn("vm-config", warning);
+    }
+    for (code, warning) in config.security.network.diagnostics() {
+        diagnostics::warn(code, warning);
+    }
+    for warning in config.git.validate(&config.tools) {
+        diagnostics::warn("git-identity", warning);
+    }
+}
+
 fn show() -> Result<()> {
     let project = Project::detect()?;
     let config = Config::load_with_main_repo(project.root(), project.main_repo_root())?;
@@ -91,6 +201,12 @@ fn show() -> Result<()> {
     println!("VM:");
     println!("  disk: {}GB", config.vm.disk);
     println!("  memory: {}GB", config.vm.memory);
+    if let Some(swap) = config.vm.swap() {
+        println!("  swap: {}GB", swap);
+    }
+    if let Some(mins) = config.vm.idle_shutdown() {
+        println!("  idle_shutdown: {} min", mins);
+    }
 
     println!("\nTools:");
     println!("  docker: {}", config.tools.docker);
@@ -138,6 +254,26 @@ fn show() -> Result<()> {
         println!("  {}", config.context.instructions_file);
     }
 
+    if !config.context.instructions_files.is_empty() {
+        println!("\nContext Instructions Files:");
+        for file in &config.context.instructions_files {
+            println!("  - {}", file);
+        }
+    }
+
+    if config.git.is_configured() {
+        println!("\nGit Identity:");
+        if let Some(ref name) = config.git.user_name {
+            println!("  user_name: {}", name);
+        }
+        if let Some(ref email) = config.git.user_email {
+            println!("  user_email: {}", email);
+        }
+        if let Some(ref key) = config.git.signing_key {
+            println!("  signing_key: {}", key);
+        }
+    }
+
     if config.security.network.enabled {
         println!("\nNetwork Isolation:");
         println!("  enabled: {}", config.security.network.enabled);
@@ -175,6 +311,158 @@ fn show() -> Result<()> {
     Ok(())
 }
 
+fn diff(left: Option<&Path>, right: Option<&Path>) -> Result<()> {
+    let (left_path, right_path, required) = match (left, right) {
+        (Some(l), Some(r)) => (l.to_path_buf(), r.to_path_buf(), true),
+        _ => {
+            let project = Project::detect()?;
+            let global_config = std::env::var("HOME")
+                .ok()
+                .map(|h| PathBuf::from(h).join(".claude-vm.toml"))
+                .unwrap_or_else(|| PathBuf::from("~/.claude-vm.toml"));
+            (global_config, project.root().join(".claude-vm.toml"), false)
+        }
+    };
+
+    let left_config = load_for_diff(&left_path, required)?;
+    let right_config = load_for_diff(&right_path, required)?;
+
+    println!("Comparing configuration:");
+    println!("  A: {}", left_path.display());
+    println!("  B: {}", right_path.display());
+    println!("(B is merged on top of A)\n");
+
+    let diffs = diff_configs(&left_config, &right_config);
+    if diffs.is_empty() {
+        println!("✓ No differences in effective configuration");
+        return Ok(());
+    }
+
+    for field in &diffs {
+        println!("{}", field.path);
+        println!("  A: {}", format_value(field.left.as_ref()));
+        println!("  B: {}", format_value(field.right.as_ref()));
+        match field.winner {
+            Winner::Left => println!("  → A wins"),
+            Winner::Right => println!("  → B wins"),
+            Winner::Combined => println!("  → combined: {}", format_value(field.merged.as_ref())),
+        }
+    }
+
+    println!(
+        "\n{} field{} differ",
+        diffs.len(),
+        if diffs.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
+
+/// Load a single config source on top of built-in defaults.
+/// Missing files count as empty unless explicitly requested.
+fn load_for_diff(path: &Path, required: bool) -> Result<Config> {
+    if path.exists() {
+        Config::from_file(path)
+    } else if required {
+        Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+            "File not found: {}",
+            path.display()
+        )))
+    } else {
+        Ok(Config::default())
+    }
+}
+
+/// Which source provides the value after merging
+#[derive(Debug, PartialEq, Eq)]
+enum Winner {
+    Left,
+    Right,
+    /// Merge combines both values (e.g. lists are appended, tools are OR'd)
+    Combined,
+}
+
+#[derive(Debug)]
+struct FieldDiff {
+    path: String,
+    left: Option<Value>,
+    right: Option<Value>,
+    merged: Option<Value>,
+    winner: Winner,
+}
+
+/// Compare two configs field by field, using the same merge as config loading
+/// to decide which source wins for each differing field.
+fn diff_configs(left: &Config, right: &Config) -> Vec<FieldDiff> {
+    let merged = left.clone().merge(right.clone());
+
+    let left = flatten_config(left);
+    let right = flatten_config(right);
+    let merged = flatten_config(&merged);
+
+    let mut paths: Vec<&String> = left.keys().chain(right.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter(|path| left.get(*path) != right.get(*path))
+        .map(|path| {
+            let l = left.get(path).cloned();
+            let r = right.get(path).cloned();
+            let m = merged.get(path).cloned();
+            let winner = if m == r {
+                Winner::Right
+            } else if m == l {
+                Winner::Left
+            } else {
+                Winner::Combined
+            };
+            FieldDiff {
+                path: path.clone(),
+                left: l,
+                right: r,
+                merged: m,
+                winner,
+            }
+        })
+        .collect()
+}
+
+/// Flatten a config into dotted paths (e.g. `security.network.mode`).
+/// Arrays are kept as leaf values.
+fn flatten_config(config: &Config) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    let value = serde_json::to_value(config).unwrap_or(Value::Null);
+    flatten_value("", &value, &mut fields);
+    fields
+}
+
+pub(crate) fn flatten_value(prefix: &str, value: &Value, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_value(&path, child, fields);
+            }
+        }
+        _ => {
+            fields.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+pub(crate) fn format_value(value: Option<&Value>) -> String {
+    match value {
+        None => "(unset)".to_string(),
+        Some(v) => v.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,11 +475,122 @@ mod tests {
 
         // We can't actually run these without a project setup,
         // but we can verify the match statement compiles correctly
-        let _validate = ConfigCommands::Validate { file: None };
+        let _validate = ConfigCommands::Validate {
+            file: None,
+            worktree: false,
+        };
         let _validate_with_file = ConfigCommands::Validate {
             file: Some(PathBuf::from("/tmp/test.toml")),
+            worktree: false,
         };
         let _show = ConfigCommands::Show;
+        let _diff = ConfigCommands::Diff {
+            left: None,
+            right: None,
+        };
+        let _export = ConfigCommands::Export {
+            path: PathBuf::from("/tmp/bundle.json"),
+        };
+        let _import = ConfigCommands::Import {
+            path: PathBuf::from("/tmp/bundle.json"),
+            force: false,
+        };
+        let _init = ConfigCommands::Init {
+            global: false,
+            force: false,
+        };
+        let _set = ConfigCommands::Set {
+            key: "vm.memory".to_string(),
+            value: "16".to_string(),
+        };
+    }
+
+    #[test]
+    fn test_worktree_conflicts_only_reports_fields_set_in_both() {
+        let main_toml = r#"
+            [vm]
+            disk = 40
+            cpus = 4
+            memory = 8
+
+            [security.network]
+            enabled = true
+            "#;
+        let worktree_toml = r#"
+            [vm]
+            disk = 60
+            memory = 16
+
+            [security.network]
+            enabled = true
+            "#;
+        let main: Config = toml::from_str(main_toml).unwrap();
+        let worktree: Config = toml::from_str(worktree_toml).unwrap();
+        let main_keys = table_keys(&toml::from_str(main_toml).unwrap());
+        let worktree_keys = table_keys(&toml::from_str(worktree_toml).unwrap());
+
+        let conflicts = worktree_conflicts(&main, &main_keys, &worktree, &worktree_keys);
+        let paths: Vec<&str> = conflicts.iter().map(|f| f.path.as_str()).collect();
+        // vm.memory = 8 is the default, but it is written in both files
+        assert_eq!(paths, vec!["vm.disk", "vm.memory"]);
+        assert_eq!(conflicts[0].winner, Winner::Right);
+    }
+
+    #[test]
+    fn test_diff_configs_identical() {
+        let config = Config::default();
+        assert!(diff_configs(&config, &config.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_configs_winner() {
+        let left: Config = toml::from_str(
+            r#"
+            [vm]
+            disk = 40
+
+            [tools]
+            docker = true
+
+            [context]
+            instructions = "global"
+            "#,
+        )
+        .unwrap();
+        let right: Config = toml::from_str(
+            r#"
+            [vm]
+            memory = 16
+
+            [context]
+            instructions = "project"
+            "#,
+        )
+        .unwrap();
+
+        let diffs = diff_configs(&left, &right);
+        let find = |path: &str| diffs.iter().find(|d| d.path == path).unwrap();
+
+        // Project overrides a non-default scalar
+        assert_eq!(find("context.instructions").winner, Winner::Right);
+        assert_eq!(find("vm.memory").winner, Winner::Right);
+        // Default value in B does not override A
+        assert_eq!(find("vm.disk").winner, Winner::Left);
+        // Tools are OR'd, so enabling in A survives
+        assert_eq!(find("tools.docker").winner, Winner::Left);
+        // Unchanged fields are not reported
+        assert!(diffs.iter().all(|d| d.path != "vm.cpus"));
+    }
+
+    #[test]
+    fn test_diff_configs_combined_lists() {
+        let left: Config = toml::from_str(r#"packages = { system = ["curl"] }"#).unwrap();
+        let right: Config = toml::from_str(r#"packages = { system = ["jq"] }"#).unwrap();
+
+        let diffs = diff_configs(&left, &right);
+        let field = diffs.iter().find(|d| d.path == "packages.system").unwrap();
+        assert_eq!(field.winner, Winner::Combined);
+        assert_eq!(field.merged, Some(serde_json::json!(["curl", "jq"])));
     }
 
     #[test]
@@ -0,0 +1,637 @@
+use crate::capabilities::registry::CapabilityRegistry;
+use crate::config::Config;
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use toml::{Table, Value};
+
+/// Bundle format version, bumped on incompatible changes
+const BUNDLE_FORMAT: u32 = 1;
+
+/// Where files referenced from outside the project are placed (relative to the project root)
+const EXTERNAL_FILES_DIR: &str = ".claude-vm/bundle";
+
+/// Project config file name
+const PROJECT_CONFIG: &str = ".claude-vm.toml";
+
+/// A project configuration and the files it references, as a single JSON document
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub format: u32,
+    pub claude_vm_version: String,
+    /// Capabilities enabled in the exporter's effective configuration
+    pub capabilities: Vec<String>,
+    /// Project `.claude-vm.toml`, with file references rewritten relative to the project root
+    pub config: String,
+    /// Referenced file contents, keyed by path relative to the project root
+    pub files: BTreeMap<String, String>,
+}
+
+/// Export the project configuration and its referenced files to `output`
+pub fn export(output: &Path) -> Result<()> {
+    let project = Project::detect()?;
+    let config_path = project.root().join(PROJECT_CONFIG);
+    if !config_path.exists() {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "No project configuration to export: {} not found",
+            config_path.display()
+        )));
+    }
+
+    let effective = Config::load_with_main_repo(project.root(), project.main_repo_root())?;
+    let capabilities = enabled_capabilities(&effective)?;
+
+    let content = std::fs::read_to_string(&config_path)?;
+    let (bundle, warnings) = build_bundle(&content, project.root(), capabilities)?;
+    for warning in &warnings {
+        crate::diagnostics::warn("config-bundle", warning);
+    }
+
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| {
+        ClaudeVmError::CommandFailed(format!("Failed to encode config bundle: {}", e))
+    })?;
+    std::fs::write(output, json)?;
+
+    println!("✓ Exported configuration to {}", output.display());
+    for path in bundle.files.keys() {
+        println!("  + {}", path);
+    }
+    if !bundle.capabilities.is_empty() {
+        println!("  Capabilities: {}", bundle.capabilities.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Import a bundle into the current project
+pub fn import(input: &Path, force: bool) -> Result<()> {
+    let project = Project::detect()?;
+    let root = project.root();
+    let bundle = read_bundle(input)?;
+
+    let config_path = root.join(PROJECT_CONFIG);
+    if !force {
+        let mut conflicts = Vec::new();
+        if config_path.exists() {
+            conflicts.push(PROJECT_CONFIG.to_string());
+        }
+        for (path, content) in &bundle.files {
+            if let Ok(existing) = std::fs::read_to_string(root.join(path)) {
+                if existing != *content {
+                    conflicts.push(path.clone());
+                }
+            } else if root.join(path).exists() {
+                conflicts.push(path.clone());
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Import would overwrite existing files: {}\nUse --force to overwrite them.",
+                conflicts.join(", ")
+            )));
+        }
+    }
+
+    // Write referenced files first: validation reads domain files relative to the config
+    let mut created = Vec::new();
+    for (path, content) in &bundle.files {
+        let target = root.join(path);
+        if !target.exists() {
+            created.push(target.clone());
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, content)?;
+    }
+
+    // Validate the config in place before it replaces the project config
+    println!("Validating imported configuration...");
+    let staged = root.join(format!("{}.import", PROJECT_CONFIG));
+    std::fs::write(&staged, &bundle.config)?;
+    let imported = match Config::from_file(&staged) {
+        Ok(config) => config,
+        Err(e) => {
+            let _ = std::fs::remove_file(&staged);
+            for path in &created {
+                let _ = std::fs::remove_file(path);
+            }
+            println!("✗ Configuration is invalid!");
+            println!("  Error: {}", e);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&staged, &config_path)?;
+    println!("✓ Configuration is valid!");
+
+    let mut warnings = host_specific_paths(&bundle.config, root)?;
+    warnings.extend(imported.security.network.validate());
+    warnings.extend(imported.git.validate(&imported.tools));
+    warnings.extend(imported.vm.validate());
+
+    let effective = Config::load_with_main_repo(root, project.main_repo_root())?;
+    let enabled = enabled_capabilities(&effective)?;
+    for id in &bundle.capabilities {
+        if !enabled.contains(id) {
+            warnings.push(format!(
+                "Capability '{}' was enabled by the exporter but is not enabled here \
+                (it likely came from their global config). Enable it in [tools] if needed.",
+                id
+            ));
+        }
+    }
+    for warning in &warnings {
+        crate::diagnostics::warn("config-bundle", warning);
+    }
+
+    println!("\n✓ Imported configuration into {}", config_path.display());
+    for path in bundle.files.keys() {
+        println!("  + {}", path);
+    }
+    println!("\nRun 'claude-vm setup' to build the template.");
+
+    Ok(())
+}
+
+fn enabled_capabilities(config: &Config) -> Result<Vec<String>> {
+    let registry = CapabilityRegistry::load()?;
+    Ok(registry
+        .get_enabled_capabilities(config)?
+        .iter()
+        .map(|c| c.capability.id.clone())
+        .collect())
+}
+
+/// Parse a bundle and reject unsupported formats and paths escaping the project
+fn read_bundle(input: &Path) -> Result<ConfigBundle> {
+    let json = std::fs::read_to_string(input).map_err(|e| {
+        ClaudeVmError::InvalidConfig(format!("Failed to read bundle {}: {}", input.display(), e))
+    })?;
+    let bundle: ConfigBundle = serde_json::from_str(&json).map_err(|e| {
+        ClaudeVmError::InvalidConfig(format!("Invalid bundle {}: {}", input.display(), e))
+    })?;
+
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Unsupported bundle format {} (expected {}). It was exported by claude-vm {}.",
+            bundle.format, BUNDLE_FORMAT, bundle.claude_vm_version
+        )));
+    }
+    for path in bundle.files.keys() {
+        let safe = Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+        if !safe || path.is_empty() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Bundle contains a path outside the project: {}",
+                path
+            )));
+        }
+    }
+
+    Ok(bundle)
+}
+
+/// Build a bundle from the project config content.
+/// Returns the bundle and warnings about references that could not be bundled.
+fn build_bundle(
+    content: &str,
+    root: &Path,
+    capabilities: Vec<String>,
+) -> Result<(ConfigBundle, Vec<String>)> {
+    let mut table: Table = toml::from_str(content).map_err(|e| {
+        ClaudeVmError::InvalidConfig(format!("Failed to parse {}: {}", PROJECT_CONFIG, e))
+    })?;
+
+    let mut collector = FileCollector {
+        root,
+        files: BTreeMap::new(),
+        external: BTreeMap::new(),
+        warnings: Vec::new(),
+    };
+    for_each_path_ref(&mut table, &mut |field, value| {
+        collector.bundle(field, value)
+    })?;
+    collector
+        .warnings
+        .extend(host_specific_mounts(&table, root));
+
+    let config = toml::to_string(&table).map_err(|e| {
+        ClaudeVmError::CommandFailed(format!("Failed to encode {}: {}", PROJECT_CONFIG, e))
+    })?;
+
+    let bundle = ConfigBundle {
+        format: BUNDLE_FORMAT,
+        claude_vm_version: crate::version::VERSION.to_string(),
+        capabilities,
+        config,
+        files: collector.files,
+    };
+    Ok((bundle, collector.warnings))
+}
+
+/// Collects referenced files and rewrites their references relative to the project root
+struct FileCollector<'a> {
+    root: &'a Path,
+    files: BTreeMap<String, String>,
+    /// Files outside the project, mapped to their key in the bundle
+    external: BTreeMap<PathBuf, String>,
+    warnings: Vec<String>,
+}
+
+impl FileCollector<'_> {
+    fn bundle(&mut self, field: &str, value: &mut String) -> Result<()> {
+        let expanded = crate::utils::path::expand_tilde(value.as_str())
+            .unwrap_or_else(|| PathBuf::from(value.as_str()));
+        let resolved = normalize(&self.root.join(expanded));
+
+        // Directories (mount locations) are never bundled, only kept relative to the project
+        if resolved.is_dir() {
+            match resolved.strip_prefix(self.root) {
+                Ok(rel) => *value = format!("./{}", rel.to_string_lossy().replace('\\', "/")),
+                Err(_) => self.warnings.push(format!(
+                    "{} '{}' is a directory outside the project, it is not included in the bundle",
+                    field, value
+                )),
+            }
+            return Ok(());
+        }
+
+        if !resolved.is_file() {
+            self.warnings.push(format!(
+                "{} '{}' not found, it is not included in the bundle",
+                field, value
+            ));
+            return Ok(());
+        }
+
+        let key = match resolved.strip_prefix(self.root) {
+            Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+            Err(_) => {
+                let key = self.external_key(&resolved);
+                self.warnings.push(format!(
+                    "{} '{}' is outside the project, bundled as ./{}",
+                    field, value, key
+                ));
+                key
+            }
+        };
+
+        let content = std::fs::read_to_string(&resolved).map_err(|e| {
+            ClaudeVmError::InvalidConfig(format!("Failed to read {} '{}': {}", field, value, e))
+        })?;
+        self.files.insert(key.clone(), content);
+        *value = format!("./{}", key);
+        Ok(())
+    }
+
+    /// Bundle key for a file outside the project, unique per source file
+    fn external_key(&mut self, source: &Path) -> String {
+        if let Some(key) = self.external.get(source) {
+            return key.clone();
+        }
+        let name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string());
+        let mut key = format!("{}/{}", EXTERNAL_FILES_DIR, name);
+        let mut n = 2;
+        while self.external.values().any(|k| *k == key) {
+            key = format!("{}/{}-{}", EXTERNAL_FILES_DIR, n, name);
+            n += 1;
+        }
+        self.external.insert(source.to_path_buf(), key.clone());
+        key
+    }
+}
+
+/// Call `f` with every file reference of a project config (field name, path)
+fn for_each_path_ref(
+    table: &mut Table,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<()>,
+) -> Result<()> {
+    const FIELDS: &[&[&str]] = &[
+        &["extends"],
+        &["context", "instructions_file"],
+        &["context", "instructions_files"],
+        &["setup", "scripts"],
+        &["runtime", "scripts"],
+        &["security", "network", "allowed_domains_file"],
+        &["security", "network", "blocked_domains_file"],
+        &["security", "network", "bypass_domains_file"],
+    ];
+    for path in FIELDS {
+        if let Some(value) = get_mut(table, path) {
+            visit_strings(value, &path.join("."), f)?;
+        }
+    }
+
+    // Only the host side of "host_path:vm_path" is a host file
+    if let Some(Value::Array(dotfiles)) = get_mut(table, &["runtime", "dotfiles"]) {
+        for item in dotfiles.iter_mut() {
+            let Value::String(spec) = item else {
+                continue;
+            };
+            let (mut host, target) = match spec.split_once(':') {
+                Some((host, target)) => (host.to_string(), Some(target.to_string())),
+                None => (spec.clone(), None),
+            };
+            f("runtime.dotfiles", &mut host)?;
+            *spec = match target {
+                Some(target) => format!("{}:{}", host, target),
+                None => host,
+            };
+        }
+    }
+
+    // Project-relative mount locations; others are host-specific (see host_specific_mounts)
+    const MOUNTS: &[&[&str]] = &[&["mounts"], &["setup", "mounts"], &["shared_mounts"]];
+    for path in MOUNTS {
+        let Some(Value::Array(mounts)) = get_mut(table, path) else {
+            continue;
+        };
+        for mount in mounts.iter_mut() {
+            if let Some(Value::String(location)) = mount.get_mut("location") {
+                if location.starts_with("./") {
+                    f(&format!("{}.location", path.join(".")), location)?;
+                }
+            }
+        }
+    }
+
+    for kind in ["before_all", "setup", "runtime"] {
+        if let Some(Value::Array(phases)) = get_mut(table, &["phase", kind]) {
+            for phase in phases.iter_mut() {
+                if let Some(files) = phase.get_mut("script_files") {
+                    visit_strings(files, &format!("phase.{}.script_files", kind), f)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_mut<'a>(table: &'a mut Table, path: &[&str]) -> Option<&'a mut Value> {
+    let (last, parents) = path.split_last()?;
+    let mut current = table;
+    for key in parents {
+        current = current.get_mut(*key)?.as_table_mut()?;
+    }
+    current.get_mut(*last)
+}
+
+fn visit_strings(
+    value: &mut Value,
+    field: &str,
+    f: &mut dyn FnMut(&str, &mut String) -> Result<()>,
+) -> Result<()> {
+    match value {
+        Value::String(s) if !s.is_empty() => f(field, s),
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                if let Value::String(s) = item {
+                    f(field, s)?;
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Warnings for mounts pointing outside the project (directories are never bundled)
+fn host_specific_mounts(table: &Table, root: &Path) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let sections = [
+        table.get("mounts"),
+        table.get("setup").and_then(|s| s.get("mounts")),
+        table.get("shared_mounts"),
+    ];
+    for mounts in sections.into_iter().flatten() {
+        let Some(mounts) = mounts.as_array() else {
+            continue;
+        };
+        for location in mounts.iter().filter_map(|m| m.get("location")?.as_str()) {
+            let expanded = crate::utils::path::expand_tilde(location)
+                .unwrap_or_else(|| PathBuf::from(location));
+            if expanded.is_absolute() && !expanded.starts_with(root) {
+                warnings.push(format!(
+                    "Mount '{}' is a host-specific path and may not exist on another machine",
+                    location
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+/// Warnings for absolute references in an imported config that won't exist on this host
+fn host_specific_paths(config: &str, root: &Path) -> Result<Vec<String>> {
+    let mut table: Table = toml::from_str(config)?;
+    let mut warnings = Vec::new();
+    for_each_path_ref(&mut table, &mut |field, value| {
+        let path = crate::utils::path::expand_tilde(value.as_str())
+            .unwrap_or_else(|| PathBuf::from(value.as_str()));
+        if path.is_absolute() && !path.exists() {
+            warnings.push(format!(
+                "{} '{}' is a host-specific path that does not exist here",
+                field, value
+            ));
+        }
+        Ok(())
+    })?;
+    warnings.extend(host_specific_mounts(&table, root));
+    Ok(warnings)
+}
+
+/// Lexically normalize a path, resolving `.` and `..` components
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_build_bundle_collects_and_rewrites_references() {
+        let project = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let root = project.path();
+        write(root, "scripts/setup.sh", "echo setup");
+        write(root, "CONTEXT.md", "be nice");
+        write(root, "domains.txt", "example.com");
+        write(root, "dotfiles/bashrc", "alias ll='ls -l'");
+        write(root, "data/seed.sql", "select 1;");
+        write(outside.path(), "shared.sh", "echo shared");
+        write(outside.path(), "gitconfig", "[user]");
+
+        let content = format!(
+            r#"
+            [context]
+            instructions_file = "CONTEXT.md"
+
+            [security.network]
+            allowed_domains_file = "./domains.txt"
+
+            [runtime]
+            dotfiles = ["./dotfiles/bashrc:~/.bashrc", "{0}/gitconfig"]
+
+            [[mounts]]
+            location = "./scripts/../data"
+
+            [[shared_mounts]]
+            location = "./no-cache"
+
+            [[phase.setup]]
+            name = "tools"
+            script_files = ["./scripts/../scripts/setup.sh", "{0}/shared.sh", "./missing.sh"]
+            "#,
+            outside.path().display()
+        );
+
+        let (bundle, warnings) = build_bundle(&content, root, vec!["docker".to_string()]).unwrap();
+
+        assert_eq!(
+            bundle.files.keys().collect::<Vec<_>>(),
+            vec![
+                ".claude-vm/bundle/gitconfig",
+                ".claude-vm/bundle/shared.sh",
+                "CONTEXT.md",
+                "domains.txt",
+                "dotfiles/bashrc",
+                "scripts/setup.sh"
+            ]
+        );
+        assert_eq!(bundle.files["scripts/setup.sh"], "echo setup");
+        assert_eq!(bundle.capabilities, vec!["docker"]);
+
+        let config: Config = toml::from_str(&bundle.config).unwrap();
+        assert_eq!(config.context.instructions_file, "./CONTEXT.md");
+        assert_eq!(
+            config.runtime.dotfiles,
+            vec![
+                "./dotfiles/bashrc:~/.bashrc",
+                "./.claude-vm/bundle/gitconfig"
+            ]
+        );
+        // Mount directories are referenced, not bundled
+        assert_eq!(config.mounts[0].location, "./data");
+        assert_eq!(config.shared_mounts[0].location, "./no-cache");
+        assert_eq!(
+            config.phase.setup[0].script_files,
+            vec![
+                "./scripts/setup.sh",
+                "./.claude-vm/bundle/shared.sh",
+                "./missing.sh"
+            ]
+        );
+
+        assert_eq!(warnings.len(), 4);
+        assert!(warnings[0].contains("runtime.dotfiles"));
+        assert!(warnings[0].contains("outside the project"));
+        assert!(warnings[1].contains("shared_mounts.location './no-cache' not found"));
+        assert!(warnings[2].contains("outside the project"));
+        assert!(warnings[3].contains("'./missing.sh' not found"));
+    }
+
+    #[test]
+    fn test_build_bundle_warns_about_host_mounts() {
+        let project = TempDir::new().unwrap();
+        let content = r#"
+            [[mounts]]
+            location = "/definitely/not/the/project"
+        "#;
+
+        let (bundle, warnings) = build_bundle(content, project.path(), vec![]).unwrap();
+        assert!(bundle.files.is_empty());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("host-specific"));
+    }
+
+    #[test]
+    fn test_external_key_is_unique_per_source() {
+        let root = TempDir::new().unwrap();
+        let mut collector = FileCollector {
+            root: root.path(),
+            files: BTreeMap::new(),
+            external: BTreeMap::new(),
+            warnings: Vec::new(),
+        };
+
+        let a = collector.external_key(Path::new("/a/run.sh"));
+        let b = collector.external_key(Path::new("/b/run.sh"));
+        assert_eq!(a, ".claude-vm/bundle/run.sh");
+        assert_eq!(b, ".claude-vm/bundle/2-run.sh");
+        assert_eq!(collector.external_key(Path::new("/a/run.sh")), a);
+    }
+
+    #[test]
+    fn test_read_bundle_rejects_unsafe_paths_and_formats() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("bundle.json");
+        let bundle = |format: u32, file: &str| {
+            serde_json::json!({
+                "format": format,
+                "claude_vm_version": "0.0.0",
+                "capabilities": [],
+                "config": "",
+                "files": { file: "x" },
+            })
+            .to_string()
+        };
+
+        std::fs::write(&path, bundle(BUNDLE_FORMAT, "scripts/ok.sh")).unwrap();
+        assert!(read_bundle(&path).is_ok());
+
+        std::fs::write(&path, bundle(BUNDLE_FORMAT, "../escape.sh")).unwrap();
+        assert!(read_bundle(&path).is_err());
+
+        std::fs::write(&path, bundle(BUNDLE_FORMAT, "/etc/passwd")).unwrap();
+        assert!(read_bundle(&path).is_err());
+
+        std::fs::write(&path, bundle(BUNDLE_FORMAT + 1, "scripts/ok.sh")).unwrap();
+        assert!(read_bundle(&path).is_err());
+    }
+
+    #[test]
+    fn test_host_specific_paths() {
+        let root = TempDir::new().unwrap();
+        let config = r#"
+            [runtime]
+            scripts = ["/nonexistent/host/script.sh", "./local.sh"]
+        "#;
+
+        let warnings = host_specific_paths(config, root.path()).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("/nonexistent/host/script.sh"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("/p/./scripts/../a.sh")),
+            PathBuf::from("/p/a.sh")
+        );
+    }
+}
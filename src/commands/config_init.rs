@@ -0,0 +1,139 @@
+//! `config init`: write a starter `.claude-vm.toml` holding the default values,
+//! each section introduced by a comment, so key names never have to be guessed.
+
+use crate::config::Config;
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use serde::Serialize;
+use std::path::PathBuf;
+use toml::{Table, Value};
+
+/// Config file name, for projects and in the home directory
+const CONFIG_FILE: &str = ".claude-vm.toml";
+
+const HEADER: &str = "\
+# claude-vm configuration
+#
+# Values below are the defaults: change what you need and delete the rest.
+# Precedence: CLI flags > project .claude-vm.toml > ~/.claude-vm.toml > defaults.
+# See docs/configuration.md for every option.
+";
+
+/// Write the starter config to the project root, or to the home directory with `global`
+pub fn init(global: bool, force: bool) -> Result<()> {
+    let path = if global {
+        let home = std::env::var("HOME").map_err(|_| {
+            ClaudeVmError::InvalidConfig("HOME is not set: cannot locate ~/.claude-vm.toml".into())
+        })?;
+        PathBuf::from(home).join(CONFIG_FILE)
+    } else {
+        Project::detect()?.root().join(CONFIG_FILE)
+    };
+
+    if path.exists() && !force {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "{} already exists (use --force to overwrite it)",
+            path.display()
+        )));
+    }
+
+    std::fs::write(&path, starter_config()?)?;
+    println!("✓ Wrote {}", path.display());
+    println!("  Check it with: claude-vm config validate");
+    Ok(())
+}
+
+/// Default configuration as commented TOML
+pub fn starter_config() -> Result<String> {
+    let config = Config::default();
+    let mut out = String::from(HEADER);
+
+    section(
+        &mut out,
+        "VM resources for templates and sessions (disk and memory in GB)",
+        &["vm"],
+        &config.vm,
+    )?;
+    section(
+        &mut out,
+        "Tools installed in the template by 'claude-vm setup'",
+        &["tools"],
+        &config.tools,
+    )?;
+    section(
+        &mut out,
+        "Extra Debian packages installed in the template, e.g. system = [\"jq\", \"ripgrep\"]",
+        &["packages"],
+        &config.packages,
+    )?;
+    section(
+        &mut out,
+        "Template build: extra setup scripts and mounts, failure output, apt cache",
+        &["setup"],
+        &config.setup,
+    )?;
+    section(
+        &mut out,
+        "Sessions: scripts run at every start, dotfiles copied at setup",
+        &["runtime"],
+        &config.runtime,
+    )?;
+    section(
+        &mut out,
+        "Network isolation (needs the network-isolation tool): filter outbound traffic by domain",
+        &["security", "network"],
+        &config.security.network,
+    )?;
+
+    Ok(out)
+}
+
+/// Append `value` as the table at `path`, preceded by `comment`
+fn section<T: Serialize>(out: &mut String, comment: &str, path: &[&str], value: &T) -> Result<()> {
+    let mut value = Value::try_from(value).map_err(encode_error)?;
+    for key in path.iter().rev() {
+        let mut table = Table::new();
+        table.insert(key.to_string(), value);
+        value = Value::Table(table);
+    }
+
+    out.push_str(&format!("\n# {}\n", comment));
+    out.push_str(&toml::to_string(&value).map_err(encode_error)?);
+    Ok(())
+}
+
+fn encode_error(e: toml::ser::Error) -> ClaudeVmError {
+    ClaudeVmError::InvalidConfig(format!("Failed to encode the default config: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_starter_config_round_trips() {
+        let content = starter_config().unwrap();
+        assert!(content.starts_with("# claude-vm configuration"));
+        assert!(content.contains("\n[vm]\n"));
+        assert!(content.contains("\n[security.network]\n"));
+        assert!(!content.contains("\n[network]\n"));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        std::fs::write(&path, &content).unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.vm.disk, defaults.vm.disk);
+        assert_eq!(config.vm.memory, defaults.vm.memory);
+        assert_eq!(
+            config.security.network.enabled,
+            defaults.security.network.enabled
+        );
+
+        // The same checks as 'config validate'
+        assert!(config.vm.validate().is_empty());
+        assert!(config.security.network.diagnostics().is_empty());
+        assert!(config.git.validate(&config.tools).is_empty());
+    }
+}
@@ -0,0 +1,337 @@
+//! `config lint`: opinionated suggestions for a configuration that is valid but risky.
+//!
+//! Unlike `config validate`, findings never fail the command. Each lint has a
+//! stable code (`CVML001`) that can be silenced with `[lint] disable = ["CVML001"]`.
+
+use crate::config::Config;
+use crate::diagnostics;
+use crate::error::Result;
+use crate::project::Project;
+use std::path::{Path, PathBuf};
+
+/// Network isolation is off although the project handles secrets
+pub const NETWORK_WITH_SECRETS: &str = "CVML001";
+/// The agent skips permission prompts without network isolation
+pub const SKIP_PERMISSIONS_OPEN_NETWORK: &str = "CVML002";
+/// A mount exposes credentials or the whole home directory
+pub const SENSITIVE_MOUNT: &str = "CVML010";
+/// `vm.disk` is unusually large
+pub const LARGE_DISK: &str = "CVML020";
+/// `vm.memory` is unusually large
+pub const LARGE_MEMORY: &str = "CVML021";
+
+const CODES: [&str; 5] = [
+    NETWORK_WITH_SECRETS,
+    SKIP_PERMISSIONS_OPEN_NETWORK,
+    SENSITIVE_MOUNT,
+    LARGE_DISK,
+    LARGE_MEMORY,
+];
+
+/// Disk size (GB) above which a lint is reported
+const LARGE_DISK_GB: u32 = 200;
+
+/// Memory (GB) above which a lint is reported
+const LARGE_MEMORY_GB: u32 = 32;
+
+/// Home directories holding credentials, relative to `$HOME`
+const SENSITIVE_HOME_DIRS: [&str; 7] = [
+    ".ssh",
+    ".aws",
+    ".gnupg",
+    ".kube",
+    ".docker",
+    ".config/gcloud",
+    ".config/gh",
+];
+
+/// Environment variable names that look like they carry a secret
+const SECRET_ENV_MARKERS: [&str; 5] = ["TOKEN", "SECRET", "PASSWORD", "API_KEY", "PRIVATE_KEY"];
+
+/// A best-practice suggestion
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub code: &'static str,
+    pub message: String,
+    /// How to address it
+    pub hint: String,
+}
+
+/// Lint the effective configuration of the current project
+pub fn lint() -> Result<()> {
+    let project = Project::detect()?;
+    let config = Config::load_with_main_repo(project.root(), project.main_repo_root())?;
+    let home = std::env::var("HOME").ok().map(PathBuf::from);
+
+    for code in unknown_disabled_codes(&config) {
+        diagnostics::warn(
+            "lint-config",
+            format!(
+                "Unknown lint code '{}' in [lint] disable. Known codes: {}",
+                code,
+                CODES.join(", ")
+            ),
+        );
+    }
+
+    let lints = check(&config, project.root(), home.as_deref());
+    if lints.is_empty() {
+        println!("✓ No suggestions");
+        return Ok(());
+    }
+
+    for lint in &lints {
+        diagnostics::warn(
+            lint.code,
+            format!("{} [{}]\n  → {}", lint.message, lint.code, lint.hint),
+        );
+    }
+    println!(
+        "{} suggestion{}. Silence one with [lint] disable = [\"{}\"] in .claude-vm.toml",
+        lints.len(),
+        if lints.len() == 1 { "" } else { "s" },
+        lints[0].code
+    );
+    Ok(())
+}
+
+/// Lints for `config`, without the ones disabled in `[lint]`
+pub fn check(config: &Config, project_root: &Path, home: Option<&Path>) -> Vec<Lint> {
+    let network = config.security.network.enabled;
+    let mut lints = Vec::new();
+
+    if !network {
+        if let Some(reason) = secrets_reason(config, project_root) {
+            lints.push(Lint {
+                code: NETWORK_WITH_SECRETS,
+                message: format!(
+                    "Network isolation is disabled but the project handles secrets ({})",
+                    reason
+                ),
+                hint: "Enable [security.network] so the agent cannot send them to arbitrary hosts"
+                    .to_string(),
+            });
+        }
+
+        if config
+            .defaults
+            .claude_args()
+            .iter()
+            .any(|arg| arg == "--dangerously-skip-permissions")
+        {
+            lints.push(Lint {
+                code: SKIP_PERMISSIONS_OPEN_NETWORK,
+                message: "The agent runs with --dangerously-skip-permissions and unrestricted network access".to_string(),
+                hint: "Enable [security.network] with an allowlist, or remove the flag from defaults.claude_args".to_string(),
+            });
+        }
+    }
+
+    let locations = config
+        .mounts
+        .iter()
+        .map(|m| &m.location)
+        .chain(config.shared_mounts.iter().map(|m| &m.location));
+    for location in locations {
+        if let Some(what) = home.and_then(|home| sensitive_location(location, home)) {
+            lints.push(Lint {
+                code: SENSITIVE_MOUNT,
+                message: format!("Mount '{}' exposes {} to the VM", location, what),
+                hint: "Mount only the files the agent needs, or forward the SSH agent with --forward-ssh-agent".to_string(),
+            });
+        }
+    }
+
+    if config.vm.disk > LARGE_DISK_GB {
+        lints.push(Lint {
+            code: LARGE_DISK,
+            message: format!("vm.disk is {} GB", config.vm.disk),
+            hint: format!(
+                "Every template and session clone can grow to this size; {} GB or less is usually enough",
+                LARGE_DISK_GB
+            ),
+        });
+    }
+    if config.vm.memory > LARGE_MEMORY_GB {
+        lints.push(Lint {
+            code: LARGE_MEMORY,
+            message: format!("vm.memory is {} GB", config.vm.memory),
+            hint: format!(
+                "Each running VM reserves this memory on the host; {} GB or less is usually enough",
+                LARGE_MEMORY_GB
+            ),
+        });
+    }
+
+    lints.retain(|lint| !config.lint.disable.iter().any(|code| code == lint.code));
+    lints
+}
+
+/// Codes in `[lint] disable` that no lint uses
+fn unknown_disabled_codes(config: &Config) -> Vec<&str> {
+    config
+        .lint
+        .disable
+        .iter()
+        .map(String::as_str)
+        .filter(|code| !CODES.contains(code))
+        .collect()
+}
+
+/// Why the project looks like it handles secrets, if it does
+fn secrets_reason(config: &Config, project_root: &Path) -> Option<String> {
+    if config.git.forwards_credentials() {
+        return Some("git.forward_credentials is enabled".to_string());
+    }
+
+    let phases = config
+        .phase
+        .before_all
+        .iter()
+        .chain(&config.phase.setup)
+        .chain(&config.phase.runtime);
+    let mut names: Vec<&String> = config
+        .phase
+        .env
+        .keys()
+        .chain(phases.flat_map(|phase| phase.env.keys()))
+        .filter(|name| {
+            let upper = name.to_uppercase();
+            SECRET_ENV_MARKERS
+                .iter()
+                .any(|marker| upper.contains(marker))
+        })
+        .collect();
+    names.sort();
+    if let Some(name) = names.first() {
+        return Some(format!("{} is passed to a phase", name));
+    }
+
+    if project_root.join(".env").is_file() {
+        return Some("the project has a .env file".to_string());
+    }
+    None
+}
+
+/// What a mount location exposes, if it is the home directory or holds credentials
+fn sensitive_location(location: &str, home: &Path) -> Option<String> {
+    let path = if location == "home" {
+        home.to_path_buf()
+    } else if location == "~" || location.starts_with("~/") {
+        home.join(location.trim_start_matches('~').trim_start_matches('/'))
+    } else {
+        PathBuf::from(location)
+    };
+    let path = PathBuf::from(path.to_string_lossy().trim_end_matches('/'));
+
+    if path == home {
+        return Some("the whole home directory".to_string());
+    }
+    SENSITIVE_HOME_DIRS
+        .iter()
+        .find(|dir| path.starts_with(home.join(dir)))
+        .map(|dir| format!("~/{}", dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MountEntry;
+    use tempfile::TempDir;
+
+    fn codes(lints: &[Lint]) -> Vec<&'static str> {
+        lints.iter().map(|lint| lint.code).collect()
+    }
+
+    fn mount(location: &str) -> MountEntry {
+        MountEntry {
+            location: location.to_string(),
+            writable: false,
+            mount_point: None,
+            allow_outside: false,
+        }
+    }
+
+    #[test]
+    fn test_lint_network_and_secrets() {
+        let project = TempDir::new().unwrap();
+        let home = Path::new("/home/dev");
+
+        // The default agent arguments skip permissions
+        let config = Config::default();
+        assert_eq!(
+            codes(&check(&config, project.path(), Some(home))),
+            vec![SKIP_PERMISSIONS_OPEN_NETWORK]
+        );
+
+        let mut config = Config::default();
+        config.defaults.claude_args = Some(vec![]);
+        config
+            .phase
+            .env
+            .insert("GITHUB_TOKEN".to_string(), "x".to_string());
+        let lints = check(&config, project.path(), Some(home));
+        assert_eq!(codes(&lints), vec![NETWORK_WITH_SECRETS]);
+        assert!(lints[0].message.contains("GITHUB_TOKEN"));
+
+        config.security.network.enabled = true;
+        assert!(check(&config, project.path(), Some(home)).is_empty());
+
+        let mut config = Config::default();
+        config.defaults.claude_args = Some(vec![]);
+        std::fs::write(project.path().join(".env"), "KEY=value\n").unwrap();
+        let lints = check(&config, project.path(), Some(home));
+        assert!(lints[0].message.contains(".env file"));
+    }
+
+    #[test]
+    fn test_lint_sensitive_mounts_and_sizes() {
+        let project = TempDir::new().unwrap();
+        let home = Path::new("/home/dev");
+        let mut config = Config::default();
+        config.defaults.claude_args = Some(vec![]);
+        config.mounts = vec![
+            mount("~/.ssh"),
+            mount("/home/dev/.config/gcloud/"),
+            mount("~"),
+            mount("home"),
+            mount("~/projects/shared"),
+        ];
+        config.vm.disk = 500;
+        config.vm.memory = 64;
+
+        let lints = check(&config, project.path(), Some(home));
+        assert_eq!(
+            codes(&lints),
+            vec![
+                SENSITIVE_MOUNT,
+                SENSITIVE_MOUNT,
+                SENSITIVE_MOUNT,
+                SENSITIVE_MOUNT,
+                LARGE_DISK,
+                LARGE_MEMORY
+            ]
+        );
+        assert!(lints[0].message.contains("~/.ssh"));
+        assert!(lints[1].message.contains("~/.config/gcloud"));
+        assert!(lints[2].message.contains("whole home directory"));
+        assert!(lints[3].message.contains("whole home directory"));
+    }
+
+    #[test]
+    fn test_lint_disable_codes() {
+        let project = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.vm.memory = 48;
+        config.lint.disable = vec![
+            SKIP_PERMISSIONS_OPEN_NETWORK.to_string(),
+            "CVML999".to_string(),
+        ];
+
+        assert_eq!(
+            codes(&check(&config, project.path(), None)),
+            vec![LARGE_MEMORY]
+        );
+        assert_eq!(unknown_disabled_codes(&config), vec!["CVML999"]);
+    }
+}
@@ -0,0 +1,329 @@
+//! `config set`: change one key of the project `.claude-vm.toml` from the command line.
+//!
+//! Keys are dotted paths into the config (`vm.disk`, `security.network.enabled`).
+//! The value is parsed with the type of the field's default value, and the
+//! updated file must still load as a valid configuration before it is written.
+//! Only that key is rewritten: comments and formatting are kept.
+
+use crate::config::{Config, ToolsConfig};
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use toml::{Table, Value};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Project config file name
+const PROJECT_CONFIG: &str = ".claude-vm.toml";
+
+/// Set `key` to `value` in the project config, creating the file if needed
+pub fn set(key: &str, value: &str) -> Result<()> {
+    let project = Project::detect()?;
+    let path = project.root().join(PROJECT_CONFIG);
+    let content = if path.exists() {
+        std::fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+
+    let (updated, value) = set_value(&content, key, value)?;
+    std::fs::write(&path, updated)?;
+    println!("✓ Set {} = {} in {}", key, value, path.display());
+    Ok(())
+}
+
+/// Return `content` with `key` set to `raw`, and the value as written
+pub fn set_value(content: &str, key: &str, raw: &str) -> Result<(String, Value)> {
+    let table: Table = toml::from_str(content)?;
+    let path: Vec<&str> = key.split('.').collect();
+    if path.iter().any(|segment| segment.is_empty()) {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Invalid key '{}': expected a dotted path such as vm.disk",
+            key
+        )));
+    }
+
+    let defaults = Value::try_from(Config::default()).map_err(encode_error)?;
+    let mut path: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+    let default = lookup(&defaults, &path);
+    let value = if path.len() == 2 && path[0] == "tools" {
+        // Tools are named by capability id (network-isolation), fields use underscores
+        path[1] = path[1].replace('-', "_");
+        tool_value(&path[1], raw)?
+    } else {
+        parse_value(key, default, raw)?
+    };
+
+    let (value, config) = match with_value(table.clone(), &path, value, key) {
+        // Without a default to follow, a value that looked like a number or a
+        // boolean may be meant as a string
+        Err(_) if default.is_none() && !raw.starts_with(['"', '[']) => {
+            let value = Value::String(raw.to_string());
+            with_value(table, &path, value, key)?
+        }
+        result => result?,
+    };
+
+    // The field must exist: serde ignores unknown keys, so check the key survives a round trip
+    let written = Value::try_from(&config).map_err(encode_error)?;
+    if lookup(&written, &path).is_none() {
+        return Err(unknown_key(key, &defaults));
+    }
+
+    let mut document: DocumentMut = content.parse().map_err(|e: toml_edit::TomlError| {
+        ClaudeVmError::InvalidConfig(format!("Failed to parse the config: {}", e))
+    })?;
+    edit(&mut document, &path, &value, key)?;
+    Ok((document.to_string(), value))
+}
+
+/// Set `path` in the document, creating the sections on the way and leaving
+/// the rest of the file untouched
+fn edit(document: &mut DocumentMut, path: &[String], value: &Value, key: &str) -> Result<()> {
+    let (last, sections) = path.split_last().expect("key has at least one segment");
+    let mut current: &mut dyn TableLike = document.as_table_mut();
+    for segment in sections {
+        current = current
+            .entry(segment)
+            .or_insert_with(|| {
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            })
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                ClaudeVmError::InvalidConfig(format!(
+                    "Cannot set {}: {} is not a section",
+                    key, segment
+                ))
+            })?;
+    }
+    let value: toml_edit::Value = value.to_string().parse().map_err(|e| {
+        ClaudeVmError::InvalidConfig(format!("Failed to encode the value of {}: {}", key, e))
+    })?;
+    match current.get_mut(last).and_then(Item::as_value_mut) {
+        // Keep the comments around the old value
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            current.insert(last, toml_edit::value(value));
+        }
+    }
+    Ok(())
+}
+
+/// The configuration `table` loads as once `path` is set to `value`
+fn with_value(
+    mut table: Table,
+    path: &[String],
+    value: Value,
+    key: &str,
+) -> Result<(Value, Config)> {
+    insert(&mut table, path, value.clone(), key)?;
+    let config = Value::Table(table)
+        .try_into::<Config>()
+        .map_err(|e| invalid_value(key, e))?;
+    Ok((value, config))
+}
+
+fn invalid_value(key: &str, e: toml::de::Error) -> ClaudeVmError {
+    ClaudeVmError::InvalidConfig(format!("Invalid value for {}: {}", key, e.message()))
+}
+
+/// `tools.<id>`: a boolean, for a tool `ToolsConfig::enable` knows
+fn tool_value(field: &str, raw: &str) -> Result<Value> {
+    let id = field.replace('_', "-");
+    let mut tools = ToolsConfig::default();
+    tools.enable(&id);
+    if !tools.is_enabled(&id) {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Unknown tool '{}'. Valid tools: docker, node, python, rust, chromium, gpg, gh, git, network_isolation",
+            field
+        )));
+    }
+    parse_value(
+        &format!("tools.{}", field),
+        Some(&Value::Boolean(false)),
+        raw,
+    )
+}
+
+/// Parse `raw` with the type of the field's default value.
+/// Fields without a default (unset options) take any TOML value, or a string.
+fn parse_value(key: &str, default: Option<&Value>, raw: &str) -> Result<Value> {
+    let invalid = |expected: &str| {
+        ClaudeVmError::InvalidConfig(format!(
+            "Invalid value for {}: expected {}, got '{}'",
+            key, expected, raw
+        ))
+    };
+    match default {
+        Some(Value::Integer(_)) => raw
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| invalid("an integer")),
+        Some(Value::Float(_)) => raw
+            .parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| invalid("a number")),
+        Some(Value::Boolean(_)) => raw
+            .parse::<bool>()
+            .map(Value::Boolean)
+            .map_err(|_| invalid("true or false")),
+        Some(Value::String(_)) => Ok(Value::String(raw.to_string())),
+        Some(Value::Table(_)) => Err(ClaudeVmError::InvalidConfig(format!(
+            "{} is a section: set one of its keys, e.g. {}.<key>",
+            key, key
+        ))),
+        Some(Value::Array(_)) => parse_literal(raw)
+            .filter(Value::is_array)
+            .ok_or_else(|| invalid("an array, e.g. '[\"a\", \"b\"]'")),
+        Some(Value::Datetime(_)) | None => {
+            Ok(parse_literal(raw).unwrap_or_else(|| Value::String(raw.to_string())))
+        }
+    }
+}
+
+/// `raw` as a TOML value, if it is one
+fn parse_literal(raw: &str) -> Option<Value> {
+    toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |value, segment| value.as_table()?.get(segment))
+}
+
+/// Set `path` in `table`, creating the sections on the way
+fn insert(table: &mut Table, path: &[String], value: Value, key: &str) -> Result<()> {
+    let (last, sections) = path.split_last().expect("key has at least one segment");
+    let mut current = table;
+    for segment in sections {
+        current = current
+            .entry(segment.as_str())
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| {
+                ClaudeVmError::InvalidConfig(format!(
+                    "Cannot set {}: {} is not a section",
+                    key, segment
+                ))
+            })?;
+    }
+    current.insert(last.clone(), value);
+    Ok(())
+}
+
+fn unknown_key(key: &str, defaults: &Value) -> ClaudeVmError {
+    let sections: Vec<&str> = defaults
+        .as_table()
+        .map(|table| {
+            table
+                .iter()
+                .filter(|(_, value)| value.is_table())
+                .map(|(name, _)| name.as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    ClaudeVmError::InvalidConfig(format!(
+        "Unknown config key '{}'. Valid sections: {}",
+        key,
+        sections.join(", ")
+    ))
+}
+
+fn encode_error(e: toml::ser::Error) -> ClaudeVmError {
+    ClaudeVmError::InvalidConfig(format!("Failed to encode the config: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_in(content: &str, key: &str, value: &str) -> Result<Config> {
+        let (updated, _) = set_value(content, key, value)?;
+        Ok(toml::from_str(&updated).unwrap())
+    }
+
+    #[test]
+    fn test_set_value_keeps_unrelated_keys() {
+        let content = "[vm]\ndisk = 40\n\n[context]\ninstructions = \"Be brief\"\n";
+
+        let config = set_in(content, "vm.memory", "16").unwrap();
+        assert_eq!(config.vm.memory, 16);
+        assert_eq!(config.vm.disk, 40);
+        assert_eq!(config.context.instructions, "Be brief");
+
+        let config = set_in(content, "security.network.enabled", "true").unwrap();
+        assert!(config.security.network.enabled);
+
+        let config = set_in("", "security.network.allowed_domains", "[\"github.com\"]").unwrap();
+        assert_eq!(config.security.network.allowed_domains, vec!["github.com"]);
+
+        // Unset options take their type from the field
+        let config = set_in("", "vm.swap_gb", "4").unwrap();
+        assert_eq!(config.vm.swap_gb, Some(4));
+        let config = set_in("", "setup.build_log", "42").unwrap();
+        assert_eq!(config.setup.build_log.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn test_set_value_tools() {
+        assert!(set_in("", "tools.docker", "true").unwrap().tools.docker);
+        assert!(
+            !set_in("tools.docker = true", "tools.docker", "false")
+                .unwrap()
+                .tools
+                .docker
+        );
+        // By capability id or by field name
+        let config = set_in("", "tools.network-isolation", "true").unwrap();
+        assert!(config.tools.network_isolation);
+        let config = set_in("", "tools.network_isolation", "true").unwrap();
+        assert!(config.tools.network_isolation);
+
+        let err = set_in("", "tools.docker", "yes").unwrap_err();
+        assert!(err.to_string().contains("expected true or false"));
+        let err = set_in("", "tools.cobol", "true").unwrap_err();
+        assert!(err.to_string().contains("Unknown tool 'cobol'"));
+    }
+
+    #[test]
+    fn test_set_value_rejects_invalid_input() {
+        let err = set_in("", "vm.disk", "abc").unwrap_err();
+        assert!(err.to_string().contains("expected an integer"));
+
+        let err = set_in("", "security.network.mode", "open").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid value for security.network.mode"));
+
+        let err = set_in("", "vm.sizee", "4").unwrap_err();
+        assert!(err.to_string().contains("Unknown config key 'vm.sizee'"));
+        assert!(err.to_string().contains("vm, "));
+
+        let err = set_in("", "nosuch.key", "1").unwrap_err();
+        assert!(err.to_string().contains("Valid sections"));
+
+        assert!(set_in("", "vm", "4").is_err());
+        assert!(set_in("", "vm..disk", "4").is_err());
+    }
+
+    #[test]
+    fn test_set_value_keeps_comments() {
+        let content = "# Project VM\n[vm]\ndisk = 40 # room for images\nmemory = 8\n\n# Tools\n[tools]\ndocker = true\n";
+
+        let (updated, _) = set_value(content, "vm.disk", "60").unwrap();
+        assert_eq!(
+            updated,
+            "# Project VM\n[vm]\ndisk = 60 # room for images\nmemory = 8\n\n# Tools\n[tools]\ndocker = true\n"
+        );
+
+        let (updated, _) = set_value(content, "security.network.enabled", "true").unwrap();
+        assert!(updated.starts_with(content));
+        assert!(updated.ends_with("[security.network]\nenabled = true\n"));
+    }
+}
@@ -0,0 +1,268 @@
+//! `conversations prune`: remove stale Claude conversation folders on the host.
+//!
+//! Claude keeps one folder per project in `~/.claude/projects`, named after the
+//! project path with every non-alphanumeric character replaced by `-`. The
+//! encoding is lossy, so a folder is mapped back to its project by walking the
+//! filesystem from `/` and following the entries whose encoded path is a
+//! prefix of the folder name.
+
+use crate::cli::ConversationsCommands;
+use crate::config::Config;
+use crate::error::Result;
+use crate::status;
+use crate::vm::mount::encode_project_path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Why a conversation folder can be removed
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleReason {
+    /// The project directory no longer exists
+    ProjectMissing,
+    /// No conversation activity for this many days
+    Inactive(u64),
+}
+
+impl std::fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StaleReason::ProjectMissing => write!(f, "project no longer exists"),
+            StaleReason::Inactive(days) => write!(f, "inactive for {} days", days),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct StaleFolder {
+    pub path: PathBuf,
+    pub reason: StaleReason,
+}
+
+pub fn execute(command: &ConversationsCommands) -> Result<()> {
+    match command {
+        ConversationsCommands::Prune {
+            older_than,
+            dry_run,
+            yes,
+        } => prune(*older_than, *dry_run, *yes),
+    }
+}
+
+/// Claude's conversation folders directory on the host
+fn projects_dir() -> Option<PathBuf> {
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".claude").join("projects"))
+}
+
+fn prune(older_than: Option<u64>, dry_run: bool, yes: bool) -> Result<()> {
+    let Some(dir) = projects_dir() else {
+        return Ok(());
+    };
+    let stale = stale_folders(&dir, older_than, true)?;
+    if stale.is_empty() {
+        println!("No stale conversation folders in {}", dir.display());
+        return Ok(());
+    }
+
+    println!("Stale conversation folders in {}:", dir.display());
+    for folder in &stale {
+        let name = folder
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        println!("  {} ({})", name, folder.reason);
+    }
+    if dry_run {
+        println!("\nDry run: nothing removed.");
+        return Ok(());
+    }
+
+    if !yes {
+        print!("\nRemove {} folder(s)? [y/N] ", stale.len());
+        let _ = io::stdout().flush();
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).ok();
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    for folder in &stale {
+        std::fs::remove_dir_all(&folder.path)?;
+    }
+    println!("Removed {} conversation folder(s).", stale.len());
+    Ok(())
+}
+
+/// Prune conversation folders inactive for `[conversations] retention_days` before
+/// a session when `auto_prune` is set. Folders of missing projects are only removed
+/// by `conversations prune`, which confirms first: a project on an unmounted drive
+/// looks missing. The current project's folder is always kept. Never fails the session.
+pub fn auto_prune(config: &Config, project_root: &Path) {
+    if !config.conversations.auto_prune {
+        return;
+    }
+    let Some(retention_days) = config.conversations.retention_days else {
+        crate::diagnostics::warn(
+            "conversations-prune",
+            "[conversations] auto_prune needs retention_days: nothing pruned",
+        );
+        return;
+    };
+    let Some(dir) = projects_dir() else {
+        return;
+    };
+    let current = encode_project_path(project_root);
+    let result = stale_folders(&dir, Some(retention_days), false).and_then(|stale| {
+        let mut removed = 0;
+        for folder in stale
+            .iter()
+            .filter(|f| f.path.file_name() != Some(current.as_ref()))
+        {
+            std::fs::remove_dir_all(&folder.path)?;
+            removed += 1;
+        }
+        Ok(removed)
+    });
+    match result {
+        Ok(0) => {}
+        Ok(removed) => status!("Pruned {} stale conversation folder(s)", removed),
+        Err(e) => crate::diagnostics::warn(
+            "conversations-prune",
+            format!("Failed to prune conversation folders: {}", e),
+        ),
+    }
+}
+
+/// Conversation folders in `dir` whose project is gone (with `missing_projects`),
+/// or (with `older_than`) that saw no activity for that many days
+pub fn stale_folders(
+    dir: &Path,
+    older_than: Option<u64>,
+    missing_projects: bool,
+) -> Result<Vec<StaleFolder>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut stale = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        let reason = if missing_projects && !project_exists(Path::new("/"), &name) {
+            Some(StaleReason::ProjectMissing)
+        } else {
+            let inactive_days = last_activity(&path)
+                .and_then(|t| SystemTime::now().duration_since(t).ok())
+                .map(|age| age.as_secs() / DAY.as_secs());
+            match (older_than, inactive_days) {
+                (Some(limit), Some(days)) if days >= limit => Some(StaleReason::Inactive(days)),
+                _ => None,
+            }
+        };
+        if let Some(reason) = reason {
+            stale.push(StaleFolder { path, reason });
+        }
+    }
+    stale.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(stale)
+}
+
+/// Whether a directory under `dir` encodes to `encoded`.
+/// Unreadable directories count as a match: a folder is never pruned on doubt.
+fn project_exists(dir: &Path, encoded: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return true;
+    };
+    entries.flatten().any(|entry| {
+        let path = entry.path();
+        if !path.is_dir() {
+            return false;
+        }
+        let candidate = encode_project_path(&path);
+        if candidate == encoded {
+            return true;
+        }
+        // Only descend into directories on the path: the encoding must continue with '-'
+        encoded
+            .strip_prefix(candidate.as_str())
+            .is_some_and(|rest| rest.starts_with('-'))
+            && project_exists(&path, encoded)
+    })
+}
+
+/// Most recent modification of the folder or the files directly in it
+fn last_activity(folder: &Path) -> Option<SystemTime> {
+    let own = std::fs::metadata(folder).and_then(|m| m.modified()).ok();
+    std::fs::read_dir(folder)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .chain(own)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_stale_folders_detects_missing_projects() {
+        let home = tempfile::TempDir::new().unwrap();
+        let projects = home.path().join(".claude").join("projects");
+
+        let existing = home.path().join("work").join("my-app");
+        fs::create_dir_all(&existing).unwrap();
+        let removed = home.path().join("work").join("old.app");
+        fs::create_dir_all(&removed).unwrap();
+
+        let existing_folder = projects.join(encode_project_path(&existing));
+        let removed_folder = projects.join(encode_project_path(&removed));
+        fs::create_dir_all(&existing_folder).unwrap();
+        fs::create_dir_all(&removed_folder).unwrap();
+        fs::write(removed_folder.join("session.jsonl"), "{}").unwrap();
+        fs::remove_dir_all(&removed).unwrap();
+
+        let stale = stale_folders(&projects, None, true).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].path, removed_folder);
+        assert_eq!(stale[0].reason, StaleReason::ProjectMissing);
+
+        // Recent activity is not stale, whatever the retention
+        assert_eq!(stale_folders(&projects, Some(30), true).unwrap().len(), 1);
+        // With a zero-day retention, every folder is inactive
+        let stale = stale_folders(&projects, Some(0), true).unwrap();
+        assert_eq!(stale.len(), 2);
+        assert!(stale
+            .iter()
+            .any(|f| f.path == existing_folder && f.reason == StaleReason::Inactive(0)));
+
+        // Without missing_projects (auto-prune), only the retention applies
+        assert!(stale_folders(&projects, None, false).unwrap().is_empty());
+        assert!(stale_folders(&projects, Some(30), false)
+            .unwrap()
+            .is_empty());
+        let stale = stale_folders(&projects, Some(0), false).unwrap();
+        assert_eq!(stale.len(), 2);
+        assert!(stale.iter().all(|f| f.reason == StaleReason::Inactive(0)));
+    }
+
+    #[test]
+    fn test_stale_folders_without_projects_dir() {
+        let home = tempfile::TempDir::new().unwrap();
+        let missing = home.path().join(".claude").join("projects");
+        assert!(stale_folders(&missing, None, true).unwrap().is_empty());
+    }
+}
@@ -1,6 +1,8 @@
-use crate::config::Config;
+use crate::config::{Config, ProjectRootFallback};
 use crate::error::Result;
 use crate::project::Project;
+use crate::status;
+use crate::vm::session::VmSession;
 use crate::vm::template;
 use crate::worktree::{operations, validation};
 use std::io::{self, Write};
@@ -16,33 +18,42 @@ use std::path::PathBuf;
 pub fn ensure_template_exists(project: &Project, config: &Config) -> Result<()> {
     // Check if template exists
     if template::exists(project.template_name())? {
+        // A template whose setup failed and was kept for resuming is not usable
+        if crate::vm::checkpoint::is_incomplete(project.template_name()) {
+            return Err(crate::error::ClaudeVmError::CommandFailed(format!(
+                "The setup of template '{}' did not finish.\n\
+                 Run 'claude-vm setup --resume-on-failure' to complete it, or 'claude-vm setup' to rebuild it.",
+                project.template_name()
+            )));
+        }
         return Ok(());
     }
 
     // Template doesn't exist
     if config.auto_setup {
         // Auto-create template without prompting
-        println!("Template not found. Auto-creating template...");
+        status!("Template not found. Auto-creating template...");
         create_template(project, config)?;
         return Ok(());
     }
 
     // Prompt user
-    println!(
+    status!(
         "No template found for project: {}",
         project.root().display()
     );
-    println!("Template name: {}", project.template_name());
-    println!();
-    print!("Would you like to create it now? [Y/n]: ");
-    io::stdout().flush()?;
+    status!("Template name: {}", project.template_name());
+    status!();
+    // On stderr: with --events-json, stdout only carries events
+    eprint!("Would you like to create it now? [Y/n]: ");
+    io::stderr().flush()?;
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
     let input = input.trim().to_lowercase();
 
     if input.is_empty() || input == "y" || input == "yes" {
-        println!();
+        status!();
         create_template(project, config)?;
         Ok(())
     } else {
@@ -58,6 +69,119 @@ fn create_template(project: &Project, config: &Config) -> Result<()> {
     crate::commands::setup::execute(project, config, false)
 }
 
+/// Open the VM session for agent and shell commands
+///
+/// With `use_vm`, attaches to an existing running VM that is left untouched
+/// on exit. Otherwise clones an ephemeral VM from the project template.
+pub fn open_session(project: &Project, config: &Config, use_vm: Option<&str>) -> Result<VmSession> {
+    match use_vm {
+        Some(name) => {
+            if !config.mounts.is_empty() || !config.shared_mounts.is_empty() {
+                crate::diagnostics::warn(
+                    "mounts-ignored",
+                    format!(
+                        "Custom mounts are ignored with --use-vm (VM '{}' is not reconfigured)",
+                        name
+                    ),
+                );
+            }
+            eprintln!("Using existing VM: {}", name);
+            let session = VmSession::attach(name, config.verbose)?;
+            prepare_session(project, config, &session);
+            Ok(session)
+        }
+        None => {
+            if let Some(limit) = config.vm.ephemeral_limit() {
+                let running = crate::commands::network::find_running_vms(project)?;
+                check_ephemeral_limit(&running, limit)?;
+            }
+            crate::vm::preflight::check_memory(&config.vm)?;
+            confirm_project_root_fallback(config)?;
+            let session = VmSession::new(project, config)?;
+            prepare_session(project, config, &session);
+            Ok(session)
+        }
+    }
+}
+
+/// Outside a git repository, apply `project_root_fallback` before mounting anything:
+/// ask for confirmation with `prompt`, point out that nothing is mounted with `none`
+fn confirm_project_root_fallback(config: &Config) -> Result<()> {
+    if matches!(crate::utils::git::get_git_root(), Ok(Some(_))) {
+        return Ok(());
+    }
+    let cwd = std::env::current_dir()?;
+    match config.project_root_fallback() {
+        ProjectRootFallback::Cwd => Ok(()),
+        ProjectRootFallback::None => {
+            crate::diagnostics::warn(
+                "no-project-mount",
+                format!(
+                    "Not in a git repository: {} is not mounted (project_root_fallback = \"none\")",
+                    cwd.display()
+                ),
+            );
+            Ok(())
+        }
+        ProjectRootFallback::Prompt => {
+            eprint!(
+                "Not in a git repository. Mount {} in the VM? [y/N]: ",
+                cwd.display()
+            );
+            io::stderr().flush()?;
+
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            let input = input.trim().to_lowercase();
+            if input == "y" || input == "yes" {
+                Ok(())
+            } else {
+                Err(crate::error::ClaudeVmError::CommandFailed(format!(
+                    "Aborted: {} was not mounted",
+                    cwd.display()
+                )))
+            }
+        }
+    }
+}
+
+/// Best-effort adjustments to a session VM before the command runs
+fn prepare_session(project: &Project, config: &Config, session: &VmSession) {
+    crate::vm::clock::check(session.name(), config.vm.sync_clock());
+    if config.git.forwards_credentials() {
+        crate::vm::git_credentials::forward(
+            project.root(),
+            session.name(),
+            &config.security.network,
+        );
+    }
+}
+
+/// Refuse to start another ephemeral VM once `limit` of them are running
+fn check_ephemeral_limit(running: &[String], limit: u32) -> Result<()> {
+    if running.len() < limit as usize {
+        return Ok(());
+    }
+
+    eprintln!(
+        "{} ephemeral VMs are already running for this project (limit: {}):",
+        running.len(),
+        limit
+    );
+    for vm in running {
+        eprintln!("  {}", vm);
+    }
+    eprintln!();
+    eprintln!("Reuse one of them:   claude-vm --use-vm <name> ...");
+    eprintln!("Or stop one:         limactl delete --force <name>");
+    eprintln!("Or raise the limit:  [vm] max_ephemeral in .claude-vm.toml (0 = unlimited)");
+    Err(crate::error::ClaudeVmError::CommandFailed(format!(
+        "Ephemeral VM limit reached ({} running, max_ephemeral = {})",
+        running.len(),
+        limit
+    )))
+}
+
 /// Resolve worktree from command-line arguments
 ///
 /// This function handles the --worktree flag for agent and shell commands.
@@ -112,6 +236,20 @@ mod tests {
         let _fn: fn(&Project, &Config) -> Result<()> = create_template;
     }
 
+    #[test]
+    fn test_check_ephemeral_limit() {
+        let running: Vec<String> = (1..=3)
+            .map(|i| format!("claude-tpl_demo_12345678-{}", i))
+            .collect();
+
+        assert!(check_ephemeral_limit(&[], 1).is_ok());
+        assert!(check_ephemeral_limit(&running, 4).is_ok());
+
+        let err = check_ephemeral_limit(&running, 3).unwrap_err().to_string();
+        assert!(err.contains("3 running, max_ephemeral = 3"));
+        assert!(check_ephemeral_limit(&running, 2).is_err());
+    }
+
     #[test]
     fn test_module_exports() {
         // Ensure the public API is accessible
@@ -1,19 +1,167 @@
-use crate::config::Config;
-use crate::error::Result;
+use crate::capabilities::registry::CapabilityRegistry;
+use crate::config::{Config, PolicyMode, VmConfig};
+use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
 use crate::vm::limactl::LimaCtl;
-use crate::vm::template;
+use crate::vm::{mount, template};
+use serde::Serialize;
 
-pub fn execute() -> Result<()> {
+/// How a capability's configured state compares with the built template
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Drift {
+    /// Enabled in the config, missing from the template: a rebuild adds it
+    NotBuilt,
+    /// In the template, disabled in the config: a rebuild removes it
+    Disabled,
+}
+
+/// One row of the capability audit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapabilityStatus {
+    pub id: String,
+    /// Enabled in the current configuration
+    pub configured: bool,
+    /// Installed in the built template (None when unknown: no template or no metadata)
+    pub built: Option<bool>,
+    pub drift: Option<Drift>,
+}
+
+/// Compare the configured capabilities with the ones recorded in the template metadata
+pub fn capability_audit(
+    all: &[String],
+    configured: &[String],
+    built: Option<&[String]>,
+) -> Vec<CapabilityStatus> {
+    all.iter()
+        .map(|id| {
+            let configured = configured.contains(id);
+            let built = built.map(|built| built.contains(id));
+            let drift = match (configured, built) {
+                (true, Some(false)) => Some(Drift::NotBuilt),
+                (false, Some(true)) => Some(Drift::Disabled),
+                _ => None,
+            };
+            CapabilityStatus {
+                id: id.clone(),
+                configured,
+                built,
+                drift,
+            }
+        })
+        .collect()
+}
+
+/// `info --json` output. Configuration fields use the TOML key names.
+#[derive(Debug, Serialize)]
+struct InfoReport {
+    project_root: String,
+    template: String,
+    template_exists: bool,
+    vm: VmConfig,
+    /// Enabled capability ids, in install order
+    capabilities: Vec<String>,
+    /// Mounts of a session, project first
+    mounts: Vec<MountReport>,
+    security: SecurityReport,
+    capability_audit: Vec<CapabilityStatus>,
+}
+
+#[derive(Debug, Serialize)]
+struct MountReport {
+    location: String,
+    /// Path in the VM (the host path when not remapped)
+    mount_point: String,
+    writable: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct SecurityReport {
+    network: NetworkReport,
+}
+
+#[derive(Debug, Serialize)]
+struct NetworkReport {
+    enabled: bool,
+    mode: PolicyMode,
+}
+
+impl InfoReport {
+    fn new(
+        project: &Project,
+        config: &Config,
+        exists: bool,
+        capability_audit: Vec<CapabilityStatus>,
+    ) -> Result<Self> {
+        let capabilities = CapabilityRegistry::load()?
+            .get_enabled_capabilities(config)?
+            .iter()
+            .map(|c| c.capability.id.clone())
+            .collect();
+        Ok(Self {
+            project_root: project.root().to_string_lossy().to_string(),
+            template: project.template_name().to_string(),
+            template_exists: exists,
+            vm: config.vm.clone(),
+            capabilities,
+            mounts: session_mounts(config)?,
+            security: SecurityReport {
+                network: NetworkReport {
+                    enabled: config.security.network.enabled,
+                    mode: config.security.network.mode.clone(),
+                },
+            },
+            capability_audit,
+        })
+    }
+}
+
+/// Mounts a session would get, as computed when it starts
+fn session_mounts(config: &Config) -> Result<Vec<MountReport>> {
+    let mounts = mount::compute_mounts(&mount::MountOptions {
+        strict: false,
+        ..mount::MountOptions::from_config(config)
+    })?;
+    Ok(mounts
+        .into_iter()
+        .map(|m| MountReport {
+            location: m.location.to_string_lossy().to_string(),
+            mount_point: m
+                .mount_point
+                .unwrap_or_else(|| m.location.clone())
+                .to_string_lossy()
+                .to_string(),
+            writable: m.writable,
+        })
+        .collect())
+}
+
+pub fn execute(metadata: bool, json: bool) -> Result<()> {
     let project = Project::detect()?;
+    LimaCtl::ensure_available()?;
+
+    if metadata {
+        return print_metadata(&project);
+    }
     let config = Config::load_with_main_repo(project.root(), project.main_repo_root())?;
+    let exists = template::exists(project.template_name())?;
+    let audit = audit_project(&project, &config, exists)?;
+
+    if json {
+        let report = InfoReport::new(&project, &config, exists, audit)?;
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to encode info: {}", e)))?;
+        println!("{}", json);
+        return Ok(());
+    }
 
     println!("Project Information:");
     println!("  Path: {}", project.root().display());
     println!("  Template: {}", project.template_name());
+    if let Some(flavor) = project.flavor() {
+        println!("  Flavor: {}", flavor);
+    }
 
-    // Check if template exists
-    let exists = template::exists(project.template_name())?;
     if !exists {
         println!("  Status: Not created");
         println!("\nRun 'claude-vm setup' to create the template.");
@@ -35,29 +183,7 @@ pub fn execute() -> Result<()> {
     println!("  Disk: {}GB", config.vm.disk);
     println!("  Memory: {}GB", config.vm.memory);
 
-    // Show enabled capabilities
-    let enabled_capabilities: Vec<String> = vec![
-        ("docker", config.tools.docker),
-        ("node", config.tools.node),
-        ("python", config.tools.python),
-        ("chromium", config.tools.chromium),
-        ("gpg", config.tools.gpg),
-        ("gh", config.tools.gh),
-        ("git", config.tools.git),
-    ]
-    .into_iter()
-    .filter_map(|(name, enabled)| {
-        if enabled {
-            Some(name.to_string())
-        } else {
-            None
-        }
-    })
-    .collect();
-
-    if !enabled_capabilities.is_empty() {
-        println!("  Capabilities: {}", enabled_capabilities.join(", "));
-    }
+    print_capability_audit(&audit);
 
     // Show mounts
     if !config.mounts.is_empty() {
@@ -72,6 +198,18 @@ pub fn execute() -> Result<()> {
         }
     }
 
+    if !config.shared_mounts.is_empty() {
+        println!("\nShared Dependency Mounts:");
+        for mount in &config.shared_mounts {
+            let mode = if mount.writable { "rw" } else { "ro" };
+            if let Some(ref mount_point) = mount.mount_point {
+                println!("  - {} -> {} ({})", mount.location, mount_point, mode);
+            } else {
+                println!("  - {} ({})", mount.location, mode);
+            }
+        }
+    }
+
     // Show runtime scripts
     if !config.runtime.scripts.is_empty() {
         println!("\nRuntime Scripts:");
@@ -83,6 +221,87 @@ pub fn execute() -> Result<()> {
     Ok(())
 }
 
+/// Capability audit of the project: configured capabilities against the template's metadata
+fn audit_project(
+    project: &Project,
+    config: &Config,
+    exists: bool,
+) -> Result<Vec<CapabilityStatus>> {
+    let registry = CapabilityRegistry::load()?;
+    let all: Vec<String> = registry
+        .all()
+        .iter()
+        .map(|c| c.capability.id.clone())
+        .collect();
+    let configured: Vec<String> = registry
+        .get_enabled_capabilities(config)?
+        .iter()
+        .map(|c| c.capability.id.clone())
+        .collect();
+    let built = if exists {
+        template::try_read_metadata(project.template_name())?.map(|m| m.capabilities)
+    } else {
+        None
+    };
+    Ok(capability_audit(&all, &configured, built.as_deref()))
+}
+
+fn print_capability_audit(audit: &[CapabilityStatus]) {
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+    println!("\nCapabilities:");
+    println!("  {:<20} {:<12} BUILT", "CAPABILITY", "CONFIGURED");
+    for status in audit {
+        let built = status.built.map(yes_no).unwrap_or("unknown");
+        let note = match status.drift {
+            Some(Drift::NotBuilt) => "  <- not in template",
+            Some(Drift::Disabled) => "  <- disabled since build",
+            None => "",
+        };
+        println!(
+            "  {:<20} {:<12} {:<7}{}",
+            status.id,
+            yes_no(status.configured),
+            built,
+            note
+        );
+    }
+
+    let ids = |drift: Drift| -> Vec<&str> {
+        audit
+            .iter()
+            .filter(|s| s.drift == Some(drift))
+            .map(|s| s.id.as_str())
+            .collect()
+    };
+    let (added, removed) = (ids(Drift::NotBuilt), ids(Drift::Disabled));
+    if !added.is_empty() || !removed.is_empty() {
+        let mut changes = Vec::new();
+        if !added.is_empty() {
+            changes.push(format!("add {}", added.join(", ")));
+        }
+        if !removed.is_empty() {
+            changes.push(format!("remove {}", removed.join(", ")));
+        }
+        println!(
+            "\nThe template is out of date. 'claude-vm setup' would {}.",
+            changes.join(" and ")
+        );
+    } else if audit.iter().any(|s| s.built.is_none()) {
+        println!("\nThe template has no build metadata; rebuild it to compare capabilities.");
+    }
+}
+
+/// Print the stored build metadata of the project's template
+fn print_metadata(project: &Project) -> Result<()> {
+    let metadata = template::read_metadata(project.template_name())?;
+    let json = serde_json::to_string_pretty(&metadata).map_err(|e| {
+        crate::error::ClaudeVmError::CommandFailed(format!("Failed to encode metadata: {}", e))
+    })?;
+    println!("{}", json);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,7 +310,58 @@ mod tests {
     fn test_info_function_signature() {
         // Verify the execute function has the correct signature
         // This ensures the public API is stable
-        let _execute_fn: fn() -> Result<()> = execute;
+        let _execute_fn: fn(bool, bool) -> Result<()> = execute;
+    }
+
+    #[test]
+    fn test_info_report_json_shape() {
+        let project = Project::detect().unwrap();
+        let config = Config::default();
+        let report = InfoReport::new(&project, &config, false, Vec::new()).unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["template_exists"], false);
+        assert_eq!(json["vm"]["disk"], 20);
+        assert_eq!(json["vm"]["memory"], 8);
+        assert_eq!(json["vm"]["cpus"], 4);
+        assert!(json["capabilities"].is_array());
+        assert_eq!(json["security"]["network"]["enabled"], false);
+        assert_eq!(json["security"]["network"]["mode"], "denylist");
+
+        // The project is mounted writable at the same path
+        let project_mount = &json["mounts"][0];
+        assert_eq!(project_mount["location"], project_mount["mount_point"]);
+        assert_eq!(project_mount["writable"], true);
+    }
+
+    #[test]
+    fn test_capability_audit_drift() {
+        let ids = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let all = ids(&["docker", "gpg", "node", "rust"]);
+        let configured = ids(&["docker", "node"]);
+        let built = ids(&["docker", "gpg"]);
+
+        let audit = capability_audit(&all, &configured, Some(&built));
+        let drift: Vec<_> = audit.iter().map(|s| (s.id.as_str(), s.drift)).collect();
+        assert_eq!(
+            drift,
+            vec![
+                ("docker", None),
+                ("gpg", Some(Drift::Disabled)),
+                ("node", Some(Drift::NotBuilt)),
+                ("rust", None),
+            ]
+        );
+
+        // Without metadata, nothing can be compared
+        let audit = capability_audit(&all, &configured, None);
+        assert!(audit.iter().all(|s| s.built.is_none() && s.drift.is_none()));
+
+        let value =
+            serde_json::to_value(capability_audit(&all, &configured, Some(&built))).unwrap();
+        assert_eq!(value[1]["drift"], "disabled");
+        assert_eq!(value[2]["drift"], "not-built");
+        assert_eq!(value[2]["built"], false);
     }
 
     #[test]
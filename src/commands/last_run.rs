@@ -0,0 +1,147 @@
+//! VM sizing of the last successful agent run, reused as defaults when
+//! `defaults.remember_runtime` is enabled.
+//!
+//! Stored in `.claude-vm/last-run.toml` under the project root. Remembered
+//! values sit above the built-in defaults: they only fill in the sizing no
+//! config file sets, and the command-line flags still win.
+
+use crate::config::VmConfig;
+use crate::error::{ClaudeVmError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Effective sizing of a successful agent run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastRun {
+    pub disk: u32,
+    pub memory: u32,
+    pub cpus: u32,
+}
+
+impl LastRun {
+    /// The sizing a run used
+    pub fn from_vm(vm: &VmConfig) -> Self {
+        Self {
+            disk: vm.disk,
+            memory: vm.memory,
+            cpus: vm.cpus,
+        }
+    }
+
+    /// Read the remembered sizing of a project, if any
+    pub fn load(project_root: &Path) -> Result<Option<Self>> {
+        let path = path(project_root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        toml::from_str(&content).map(Some).map_err(|e| {
+            ClaudeVmError::InvalidConfig(format!(
+                "Invalid last run file {}: {}",
+                path.display(),
+                e.message()
+            ))
+        })
+    }
+
+    /// Remember this sizing for the project
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let path = path(project_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(|e| {
+            ClaudeVmError::InvalidConfig(format!("Failed to encode the last run: {}", e))
+        })?;
+        fs::write(
+            path,
+            format!(
+                "# Written by claude-vm after a successful agent run (defaults.remember_runtime)\n{}",
+                content
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Use the remembered sizing for the fields still at their default
+    pub fn apply(&self, vm: &mut VmConfig) {
+        let defaults = VmConfig::default();
+        if vm.disk == defaults.disk {
+            vm.disk = self.disk;
+        }
+        if vm.memory == defaults.memory {
+            vm.memory = self.memory;
+        }
+        if vm.cpus == defaults.cpus {
+            vm.cpus = self.cpus;
+        }
+    }
+}
+
+/// `.claude-vm/last-run.toml` under the project root
+pub fn path(project_root: &Path) -> PathBuf {
+    project_root.join(".claude-vm").join("last-run.toml")
+}
+
+/// Remember the sizing of a successful run, when enabled (failures are only warned about)
+pub fn remember(project_root: &Path, vm: &VmConfig, enabled: bool) {
+    if !enabled {
+        return;
+    }
+    if let Err(e) = LastRun::from_vm(vm).save(project_root) {
+        crate::diagnostics::warn(
+            "last-run",
+            format!("Could not remember the VM sizing of this run: {}", e),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_last_run_round_trip() {
+        let project = TempDir::new().unwrap();
+        assert_eq!(LastRun::load(project.path()).unwrap(), None);
+
+        let vm = VmConfig {
+            disk: 50,
+            memory: 16,
+            cpus: 6,
+            ..VmConfig::default()
+        };
+        remember(project.path(), &vm, true);
+        let last = LastRun::load(project.path()).unwrap().unwrap();
+        assert_eq!(last, LastRun::from_vm(&vm));
+
+        let mut applied = VmConfig::default();
+        last.apply(&mut applied);
+        assert_eq!((applied.disk, applied.memory, applied.cpus), (50, 16, 6));
+
+        // Sizing set by a config file is kept
+        let mut configured = VmConfig {
+            memory: 32,
+            ..VmConfig::default()
+        };
+        last.apply(&mut configured);
+        assert_eq!(
+            (configured.disk, configured.memory, configured.cpus),
+            (50, 32, 6)
+        );
+    }
+
+    #[test]
+    fn test_last_run_disabled_or_invalid() {
+        let project = TempDir::new().unwrap();
+        remember(project.path(), &VmConfig::default(), false);
+        assert!(!path(project.path()).exists());
+
+        fs::create_dir_all(project.path().join(".claude-vm")).unwrap();
+        fs::write(path(project.path()), "disk = \"big\"\n").unwrap();
+        let err = LastRun::load(project.path()).unwrap_err();
+        assert!(err.to_string().contains("Invalid last run file"));
+    }
+}
@@ -1,26 +1,83 @@
-use crate::error::Result;
-use crate::vm::template;
+use crate::error::{ClaudeVmError, Result};
+use crate::vm::limactl::VmInfo;
+use crate::vm::{limactl::LimaCtl, template};
+use serde::Serialize;
 
-pub fn execute(unused: bool, disk_usage: bool) -> Result<()> {
-    let templates = template::list_all()?;
+/// A template in `list --json` output
+#[derive(Debug, Serialize)]
+struct TemplateEntry {
+    name: String,
+    /// Project root recorded when the template was built
+    project_path: Option<String>,
+    /// Lima status (Running, Stopped, ...)
+    status: String,
+    /// Last use (RFC 3339), from the template directory's modification time
+    last_used: Option<String>,
+    /// Disk usage as reported by `du -sh`, with `--disk-usage`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disk_usage: Option<String>,
+}
 
-    if templates.is_empty() {
-        println!("No claude-vm templates found.");
-        return Ok(());
+impl TemplateEntry {
+    fn new(vm: VmInfo, disk_usage: bool) -> Self {
+        let project_path = template::try_read_metadata(&vm.name)
+            .ok()
+            .flatten()
+            .map(|metadata| metadata.project_root);
+        let last_used = template::get_last_access_time(&vm.name)
+            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
+        Self {
+            disk_usage: disk_usage.then(|| template::get_disk_usage(&vm.name)),
+            name: vm.name,
+            project_path,
+            status: vm.status,
+            last_used,
+        }
     }
+}
+
+pub fn execute(unused: bool, disk_usage: bool, json: bool) -> Result<()> {
+    LimaCtl::ensure_available()?;
+    let templates = template::list_all_vms()?;
 
     // Filter unused templates if requested
-    let templates: Vec<String> = if unused {
+    let templates: Vec<VmInfo> = if unused {
         templates
             .into_iter()
-            .filter(|name| template::is_unused(name))
+            .filter(|vm| template::is_unused(&vm.name))
             .collect()
     } else {
         templates
     };
 
-    if unused && templates.is_empty() {
-        println!("No unused templates found.");
+    // Only show templates built for the selected flavor
+    let flavor = crate::project::selected_flavor();
+    let templates: Vec<VmInfo> = match &flavor {
+        Some(flavor) => templates
+            .into_iter()
+            .filter(|vm| built_for_flavor(&vm.name, flavor))
+            .collect(),
+        None => templates,
+    };
+
+    if json {
+        let entries: Vec<TemplateEntry> = templates
+            .into_iter()
+            .map(|vm| TemplateEntry::new(vm, disk_usage))
+            .collect();
+        let json = serde_json::to_string_pretty(&entries).map_err(|e| {
+            ClaudeVmError::CommandFailed(format!("Failed to encode templates: {}", e))
+        })?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    if templates.is_empty() {
+        match &flavor {
+            Some(flavor) => println!("No templates found for flavor '{}'.", flavor),
+            None if unused => println!("No unused templates found."),
+            None => println!("No claude-vm templates found."),
+        }
         return Ok(());
     }
 
@@ -28,21 +85,29 @@ pub fn execute(unused: bool, disk_usage: bool) -> Result<()> {
     if disk_usage {
         println!("{:<50} {:>10} {:>15}", "TEMPLATE", "SIZE", "LAST USED");
         println!("{}", "-".repeat(77));
-        for name in templates {
-            let size = template::get_disk_usage(&name);
-            let last_used = template::format_last_used(&name);
-            println!("{:<50} {:>10} {:>15}", name, size, last_used);
+        for vm in templates {
+            let size = template::get_disk_usage(&vm.name);
+            let last_used = template::format_last_used(&vm.name);
+            println!("{:<50} {:>10} {:>15}", vm.name, size, last_used);
         }
     } else {
         println!("Claude VM templates:");
-        for name in templates {
-            println!("  {}", name);
+        for vm in templates {
+            println!("  {}", vm.name);
         }
     }
 
     Ok(())
 }
 
+/// Whether the template's metadata records the given flavor
+fn built_for_flavor(name: &str, flavor: &str) -> bool {
+    matches!(
+        template::try_read_metadata(name),
+        Ok(Some(metadata)) if metadata.flavor.as_deref() == Some(flavor)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,7 +115,7 @@ mod tests {
     #[test]
     fn test_list_function_signature() {
         // Verify the execute function has the correct signature
-        let _execute_fn: fn(bool, bool) -> Result<()> = execute;
+        let _execute_fn: fn(bool, bool, bool) -> Result<()> = execute;
     }
 
     #[test]
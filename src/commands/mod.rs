@@ -1,13 +1,26 @@
 pub mod agent;
+pub mod agent_logs;
+pub mod auth;
+pub mod batch;
+pub mod batch_state;
+pub mod capabilities;
 pub mod clean;
 pub mod clean_all;
 pub mod config;
+pub mod config_bundle;
+pub mod config_init;
+pub mod config_lint;
+pub mod config_set;
+pub mod conversations;
 pub mod helpers;
 pub mod info;
+pub mod last_run;
 pub mod list;
 pub mod network;
 pub mod setup;
 pub mod shell;
+pub mod shell_all;
+pub mod template;
 pub mod update;
 pub mod version;
 pub mod worktree;
@@ -1,8 +1,12 @@
 use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
-use crate::utils::shell::escape as shell_escape;
+use crate::utils::shell::{self as shell_utils, escape as shell_escape};
+use crate::vm::limactl::LimaCtl;
 use std::process::Command;
 
+/// Proxy log inside the VM; rotated copies are `<LOG_FILE>.1` (newest) to `.N` (oldest)
+const LOG_FILE: &str = "/tmp/mitmproxy.log";
+
 pub fn execute(
     project: &Project,
     lines: usize,
@@ -27,12 +31,17 @@ pub fn execute(
     let instance_name = super::select_vm(&running_vms)?;
 
     // Check if network isolation is enabled by checking if the log file exists
-    let check_log = Command::new("limactl")
-        .args(["shell", &instance_name, "test", "-f", "/tmp/mitmproxy.log"])
-        .output()
-        .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to check log file: {}", e)))?;
-
-    if !check_log.status.success() {
+    let list_logs = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "sh",
+        "-c",
+        &format!("ls -1 {}*", LOG_FILE),
+    ]))
+    .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to check log file: {}", e)))?;
+    let log_files = log_files_in_order(&String::from_utf8_lossy(&list_logs.stdout));
+
+    if !log_files.iter().any(|f| f == LOG_FILE) {
         eprintln!("Network isolation logs not found.");
         eprintln!();
         eprintln!("Network isolation may not be enabled for this VM.");
@@ -46,8 +55,8 @@ pub fn execute(
 
     // Build the command to read logs
     let read_cmd = if follow {
-        // Follow mode: use tail -f for real-time streaming
-        let mut cmd = "tail -f /tmp/mitmproxy.log".to_string();
+        // Follow mode: tail -F keeps following the log across rotations
+        let mut cmd = format!("tail -F {} 2>/dev/null", LOG_FILE);
 
         // Add grep filter if specified
         if let Some(pattern) = filter {
@@ -59,17 +68,12 @@ pub fn execute(
 
         cmd
     } else {
-        // Static read mode
-        let mut cmd = String::new();
+        // Static read mode: rotated logs (oldest first), then the current log
+        let mut cmd = format!("cat {}", shell_utils::join_args(&log_files));
 
         if let Some(pattern) = filter {
             // Use grep to filter (pattern is shell-escaped to prevent injection)
-            cmd.push_str(&format!(
-                "grep -i {} /tmp/mitmproxy.log",
-                shell_escape(pattern)
-            ));
-        } else {
-            cmd.push_str("cat /tmp/mitmproxy.log");
+            cmd.push_str(&format!(" | grep -i {}", shell_escape(pattern)));
         }
 
         // Apply line limit
@@ -93,10 +97,14 @@ pub fn execute(
         println!("═════════════════════════════════════════════════════════════");
         println!();
 
-        let status = Command::new("limactl")
-            .args(["shell", &instance_name, "sh", "-c", &read_cmd])
-            .status()
-            .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to follow logs: {}", e)))?;
+        let status = LimaCtl::run_status(Command::new("limactl").args([
+            "shell",
+            &instance_name,
+            "sh",
+            "-c",
+            &read_cmd,
+        ]))
+        .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to follow logs: {}", e)))?;
 
         if !status.success() {
             return Err(ClaudeVmError::CommandFailed(
@@ -105,10 +113,14 @@ pub fn execute(
         }
     } else {
         // Static mode: read all at once
-        let output = Command::new("limactl")
-            .args(["shell", &instance_name, "sh", "-c", &read_cmd])
-            .output()
-            .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to read logs: {}", e)))?;
+        let output = LimaCtl::run_output(Command::new("limactl").args([
+            "shell",
+            &instance_name,
+            "sh",
+            "-c",
+            &read_cmd,
+        ]))
+        .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to read logs: {}", e)))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -157,9 +169,80 @@ pub fn execute(
             println!("  -f <pattern>   Filter logs by domain pattern");
             println!("  --follow       Follow log output in real-time");
             println!();
-            println!("Log file: /tmp/mitmproxy.log (inside VM)");
+            println!("Log file: {} (inside VM)", LOG_FILE);
+            if log_files.len() > 1 {
+                println!(
+                    "Rotated logs: {} (read oldest first)",
+                    log_files[..log_files.len() - 1].join(", ")
+                );
+            }
         }
     }
 
     Ok(())
 }
+
+/// Order proxy log files from an `ls` listing chronologically: rotated logs from
+/// the oldest (highest suffix) to the newest, then the current log.
+/// Unrelated files are ignored.
+fn log_files_in_order(listing: &str) -> Vec<String> {
+    let mut rotated: Vec<(u32, &str)> = Vec::new();
+    let mut current = None;
+
+    for file in listing.lines().map(str::trim) {
+        if file == LOG_FILE {
+            current = Some(file);
+        } else if let Some(n) = file
+            .strip_prefix(LOG_FILE)
+            .and_then(|suffix| suffix.strip_prefix('.'))
+            .and_then(|n| n.parse::<u32>().ok())
+        {
+            rotated.push((n, file));
+        }
+    }
+
+    rotated.sort_by_key(|(n, _)| std::cmp::Reverse(*n));
+    rotated
+        .into_iter()
+        .map(|(_, file)| file)
+        .chain(current)
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_files_in_chronological_order() {
+        let listing = "/tmp/mitmproxy.log\n\
+                       /tmp/mitmproxy.log.1\n\
+                       /tmp/mitmproxy.log.10\n\
+                       /tmp/mitmproxy.log.2\n";
+        assert_eq!(
+            log_files_in_order(listing),
+            vec![
+                "/tmp/mitmproxy.log.10",
+                "/tmp/mitmproxy.log.2",
+                "/tmp/mitmproxy.log.1",
+                "/tmp/mitmproxy.log",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_files_ignores_unrelated_files() {
+        let listing = "/tmp/mitmproxy.log\n/tmp/mitmproxy.log.old\n/tmp/mitmproxy.log.pid\n";
+        assert_eq!(log_files_in_order(listing), vec!["/tmp/mitmproxy.log"]);
+    }
+
+    #[test]
+    fn test_log_files_without_current_log() {
+        assert!(log_files_in_order("").is_empty());
+        assert_eq!(
+            log_files_in_order("/tmp/mitmproxy.log.1\n"),
+            vec!["/tmp/mitmproxy.log.1"]
+        );
+    }
+}
@@ -9,6 +9,7 @@ use std::io::{self, Write};
 
 /// Find running ephemeral VMs for a project
 pub fn find_running_vms(project: &Project) -> Result<Vec<String>> {
+    LimaCtl::ensure_available()?;
     let template_prefix = format!("{}-", project.template_name());
     let all_vms = LimaCtl::list()?;
 
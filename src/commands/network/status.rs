@@ -1,6 +1,7 @@
 use crate::config::Config;
 use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
+use crate::vm::limactl::LimaCtl;
 use std::process::Command;
 
 pub fn execute(project: &Project, config: &Config) -> Result<()> {
@@ -15,6 +16,7 @@ pub fn execute(project: &Project, config: &Config) -> Result<()> {
         println!();
         println!("No ephemeral VMs are currently running for this project.");
         println!("Network security status is only available while a VM is running.");
+        println!("Skipping the live firewall check.");
         println!();
         println!("Start a VM with:");
         println!("  claude-vm        # Run Claude");
@@ -50,12 +52,14 @@ pub fn execute(project: &Project, config: &Config) -> Result<()> {
     }
 
     // Check if proxy process is running
-    let check_pid = Command::new("limactl")
-        .args(["shell", &instance_name, "test", "-f", "/tmp/mitmproxy.pid"])
-        .output()
-        .map_err(|e| {
-            ClaudeVmError::CommandFailed(format!("Failed to check proxy status: {}", e))
-        })?;
+    let check_pid = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "test",
+        "-f",
+        "/tmp/mitmproxy.pid",
+    ]))
+    .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to check proxy status: {}", e)))?;
 
     if !check_pid.status.success() {
         println!("Status: INACTIVE (Proxy not started)");
@@ -68,22 +72,27 @@ pub fn execute(project: &Project, config: &Config) -> Result<()> {
     }
 
     // Read proxy PID
-    let pid_output = Command::new("limactl")
-        .args(["shell", &instance_name, "cat", "/tmp/mitmproxy.pid"])
-        .output()
-        .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to read proxy PID: {}", e)))?;
+    let pid_output = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "cat",
+        "/tmp/mitmproxy.pid",
+    ]))
+    .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to read proxy PID: {}", e)))?;
 
     let proxy_pid = String::from_utf8_lossy(&pid_output.stdout)
         .trim()
         .to_string();
 
     // Check if proxy process is actually running
-    let check_running = Command::new("limactl")
-        .args(["shell", &instance_name, "kill", "-0", &proxy_pid])
-        .output()
-        .map_err(|e| {
-            ClaudeVmError::CommandFailed(format!("Failed to check proxy process: {}", e))
-        })?;
+    let check_running = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "kill",
+        "-0",
+        &proxy_pid,
+    ]))
+    .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to check proxy process: {}", e)))?;
 
     if !check_running.status.success() {
         println!("Status: INACTIVE (Proxy stopped)");
@@ -105,17 +114,15 @@ pub fn execute(project: &Project, config: &Config) -> Result<()> {
     println!("  Listening: localhost:8080");
 
     // Get uptime if available
-    let uptime_output = Command::new("limactl")
-        .args([
-            "shell",
-            &instance_name,
-            "ps",
-            "-p",
-            &proxy_pid,
-            "-o",
-            "etime=",
-        ])
-        .output();
+    let uptime_output = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "ps",
+        "-p",
+        &proxy_pid,
+        "-o",
+        "etime=",
+    ]));
 
     if let Ok(output) = uptime_output {
         if output.status.success() {
@@ -183,10 +190,15 @@ pub fn execute(project: &Project, config: &Config) -> Result<()> {
 
     println!();
 
+    report_firewall(&instance_name, config);
+
     // Try to read statistics if available
-    let stats_output = Command::new("limactl")
-        .args(["shell", &instance_name, "cat", "/tmp/mitmproxy_stats.json"])
-        .output();
+    let stats_output = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        &instance_name,
+        "cat",
+        "/tmp/mitmproxy_stats.json",
+    ]));
 
     if let Ok(output) = stats_output {
         if output.status.success() {
@@ -211,3 +223,208 @@ pub fn execute(project: &Project, config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+/// Protections found in the guest's live firewall rules
+#[derive(Debug, Default, PartialEq)]
+pub struct FirewallState {
+    /// Outgoing raw TCP and UDP are rejected
+    pub tcp_udp: bool,
+    /// Every private IPv4 range is rejected
+    pub private_networks: bool,
+    /// The cloud metadata address is rejected
+    pub metadata: bool,
+}
+
+/// Private IPv4 ranges the runtime script rejects
+const PRIVATE_RANGES: [&str; 3] = ["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16"];
+
+/// Cloud metadata service address the runtime script rejects
+const METADATA_ADDRESS: &str = "169.254.169.254";
+
+/// Find the protections in `nft list ruleset` output.
+/// Only reject rules of output chains count.
+pub fn parse_ruleset(ruleset: &str) -> FirewallState {
+    let mut in_output = false;
+    let mut rejects = Vec::new();
+    for line in ruleset.lines().map(str::trim) {
+        if let Some(chain) = line.strip_prefix("chain ") {
+            in_output = chain
+                .trim_end_matches('{')
+                .trim()
+                .eq_ignore_ascii_case("output");
+        } else if line == "}" {
+            in_output = false;
+        } else if in_output && (line.contains(" reject") || line.contains("\"REJECT\"")) {
+            rejects.push(line);
+        }
+    }
+
+    let rejects_protocol = |proto: &str| {
+        rejects.iter().any(|rule| {
+            (rule.contains(&format!("l4proto {}", proto))
+                || rule.contains(&format!("protocol {}", proto)))
+                && !rule.contains("daddr")
+                && !rule.contains("dport")
+        })
+    };
+    let rejects_address = |address: &str| {
+        rejects
+            .iter()
+            .any(|rule| rule.contains(&format!("ip daddr {} ", address)))
+    };
+
+    FirewallState {
+        tcp_udp: rejects_protocol("tcp") && rejects_protocol("udp"),
+        private_networks: PRIVATE_RANGES.iter().all(|range| rejects_address(range)),
+        metadata: rejects_address(METADATA_ADDRESS),
+    }
+}
+
+/// Differences between the configured protections and the live rules, as
+/// (protection, expected blocked)
+pub fn firewall_mismatches(config: &Config, live: &FirewallState) -> Vec<(&'static str, bool)> {
+    let network = &config.security.network;
+    // Private network and metadata rules are only installed along with the TCP/UDP block
+    let expected = [
+        ("Raw TCP/UDP", network.block_tcp_udp, live.tcp_udp),
+        (
+            "Private networks",
+            network.block_tcp_udp && network.block_private_networks,
+            live.private_networks,
+        ),
+        (
+            "Cloud metadata",
+            network.block_tcp_udp && network.block_metadata_services,
+            live.metadata,
+        ),
+    ];
+    expected
+        .into_iter()
+        .filter(|(_, expected, live)| expected != live)
+        .map(|(name, expected, _)| (name, expected))
+        .collect()
+}
+
+/// Compare the guest firewall with the configured protocol blocks
+fn report_firewall(instance_name: &str, config: &Config) {
+    println!("Firewall (live check):");
+    let output = LimaCtl::run_output(Command::new("limactl").args([
+        "shell",
+        instance_name,
+        "sudo",
+        "nft",
+        "list",
+        "ruleset",
+    ]));
+    let ruleset = match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        }
+        _ => {
+            println!("  Could not read the firewall rules (sudo nft list ruleset failed)");
+            println!();
+            return;
+        }
+    };
+
+    let mismatches = firewall_mismatches(config, &parse_ruleset(&ruleset));
+    if mismatches.is_empty() {
+        println!("  Rules match the configuration ✓");
+    } else {
+        for (name, expected) in &mismatches {
+            if *expected {
+                println!("  {}: configured as blocked, but no rule found ✗", name);
+            } else {
+                println!("  {}: configured as allowed, but blocked by a rule ✗", name);
+            }
+        }
+        println!("  Start a new session to apply the configuration.");
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `nft list ruleset` after the runtime script with every protection enabled
+    const FULL_RULESET: &str = r#"table ip filter {
+	chain OUTPUT {
+		type filter hook output priority filter; policy accept;
+		meta skuid 501 counter packets 12 bytes 720 accept
+		ip daddr 10.0.0.0/8 counter packets 0 bytes 0 reject
+		ip daddr 172.16.0.0/12 counter packets 0 bytes 0 reject
+		ip daddr 192.168.0.0/16 counter packets 0 bytes 0 reject
+		ip daddr 169.254.169.254 counter packets 0 bytes 0 reject
+		ct state related,established counter packets 40 bytes 2400 accept
+		meta l4proto udp udp dport 53 counter packets 3 bytes 180 accept
+		meta l4proto tcp tcp dport 53 counter packets 0 bytes 0 accept
+		oifname "lo" counter packets 8 bytes 480 accept
+		meta l4proto tcp counter packets 1 bytes 60 reject with tcp reset
+		meta l4proto udp counter packets 0 bytes 0 reject
+	}
+}
+table ip6 filter {
+	chain OUTPUT {
+		type filter hook output priority filter; policy accept;
+		ip6 daddr fc00::/7 counter packets 0 bytes 0 reject
+		meta l4proto tcp counter packets 0 bytes 0 reject with tcp reset
+	}
+}
+"#;
+
+    #[test]
+    fn test_parse_ruleset() {
+        assert_eq!(
+            parse_ruleset(FULL_RULESET),
+            FirewallState {
+                tcp_udp: true,
+                private_networks: true,
+                metadata: true,
+            }
+        );
+        assert_eq!(parse_ruleset(""), FirewallState::default());
+
+        // Only the TCP/UDP block, without private network and metadata rules
+        let ruleset: String = FULL_RULESET
+            .lines()
+            .filter(|line| !line.contains("ip daddr"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert_eq!(
+            parse_ruleset(&ruleset),
+            FirewallState {
+                tcp_udp: true,
+                ..FirewallState::default()
+            }
+        );
+
+        // Reject rules of other chains do not count
+        let input_chain = FULL_RULESET.replace("chain OUTPUT", "chain INPUT");
+        assert_eq!(parse_ruleset(&input_chain), FirewallState::default());
+    }
+
+    #[test]
+    fn test_firewall_mismatches() {
+        let mut config = Config::default();
+        config.security.network.enabled = true;
+        let live = parse_ruleset(FULL_RULESET);
+        assert!(firewall_mismatches(&config, &live).is_empty());
+
+        config.security.network.block_metadata_services = false;
+        assert_eq!(
+            firewall_mismatches(&config, &live),
+            vec![("Cloud metadata", false)]
+        );
+
+        config.security.network.block_metadata_services = true;
+        assert_eq!(
+            firewall_mismatches(&config, &FirewallState::default()),
+            vec![
+                ("Raw TCP/UDP", true),
+                ("Private networks", true),
+                ("Cloud metadata", true)
+            ]
+        );
+    }
+}
@@ -1,8 +1,36 @@
 use crate::config::{Config, PolicyMode};
 use crate::error::Result;
+use crate::utils::cidr::{is_cidr, Cidr};
+use crate::utils::domain::{matches_any, matches_pattern};
+use std::net::IpAddr;
 
-/// Test if a domain would be allowed or blocked by network isolation policies
-pub fn execute(config: &Config, domain: &str) -> Result<()> {
+/// Private ranges rejected by the firewall when `block_private_networks` is set
+const PRIVATE_RANGES: [&str; 5] = [
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "fc00::/7",
+    "fe80::/10",
+];
+
+/// Cloud metadata endpoints rejected when `block_metadata_services` is set
+const METADATA_ADDRESSES: [&str; 2] = ["169.254.169.254/32", "fe80::a9fe:a9fe/128"];
+
+/// Test if a domain would be allowed or blocked by network isolation policies.
+/// With `explain`, also print how the decision was reached.
+pub fn execute(config: &Config, domain: &str, explain: bool) -> Result<()> {
+    report(config, domain);
+    if explain {
+        println!();
+        println!("Evaluation trace:");
+        for (i, step) in trace(config, domain).iter().enumerate() {
+            println!("  {}. {}", i + 1, step);
+        }
+    }
+    Ok(())
+}
+
+fn report(config: &Config, domain: &str) {
     println!("Testing domain: {}", domain);
     println!("════════════════════════════════════════════════════════════");
     println!();
@@ -19,7 +47,7 @@ pub fn execute(config: &Config, domain: &str) -> Result<()> {
         println!("     [security.network]");
         println!("     enabled = true");
         println!("  2. Recreate the VM: claude-vm clean && claude-vm setup");
-        return Ok(());
+        return;
     }
 
     // Check bypass domains first
@@ -37,7 +65,7 @@ pub fn execute(config: &Config, domain: &str) -> Result<()> {
         println!("  - Pass through proxy without TLS interception");
         println!("  - Useful for certificate pinning");
         println!("  - Always allowed regardless of policy mode");
-        return Ok(());
+        return;
     }
 
     // Check policy mode
@@ -116,50 +144,216 @@ pub fn execute(config: &Config, domain: &str) -> Result<()> {
             }
         }
     }
+}
 
-    Ok(())
+/// The steps `report` goes through for `domain`, naming the config entries that matched
+fn trace(config: &Config, domain: &str) -> Vec<String> {
+    let network = &config.security.network;
+    if !network.enabled {
+        return vec![
+            "security.network.enabled = false: no rules evaluated".to_string(),
+            "Decision: ALLOWED (network isolation disabled)".to_string(),
+        ];
+    }
+
+    let mode = match network.mode {
+        PolicyMode::Allowlist => "allowlist (block all except allowed_domains)",
+        PolicyMode::Denylist => "denylist (allow all except blocked_domains)",
+    };
+    let mut steps = vec![format!("Policy mode: {}", mode)];
+
+    let bypass = first_match(domain, &network.bypass_domains);
+    steps.push(list_step("bypass_domains", domain, bypass));
+
+    let ip = domain
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .ok();
+    steps.push(firewall_step(
+        "Private network blocking",
+        network.block_private_networks,
+        ip,
+        &PRIVATE_RANGES,
+    ));
+    steps.push(firewall_step(
+        "Metadata service blocking",
+        network.block_metadata_services,
+        ip,
+        &METADATA_ADDRESSES,
+    ));
+
+    if let Some(entry) = bypass {
+        steps.push(format!(
+            "Decision: ALLOWED by bypass_domains entry \"{}\" (checked before the policy mode)",
+            entry
+        ));
+        return steps;
+    }
+
+    let decision = match network.mode {
+        PolicyMode::Allowlist => {
+            let allowed = first_match(domain, &network.allowed_domains);
+            steps.push(list_step("allowed_domains", domain, allowed));
+            match allowed {
+                Some(entry) => format!("Decision: ALLOWED by allowed_domains entry \"{}\"", entry),
+                None => "Decision: BLOCKED (no allowed_domains entry matched)".to_string(),
+            }
+        }
+        PolicyMode::Denylist => {
+            let blocked = first_match(domain, &network.blocked_domains);
+            steps.push(list_step("blocked_domains", domain, blocked));
+            match blocked {
+                Some(entry) => format!("Decision: BLOCKED by blocked_domains entry \"{}\"", entry),
+                None => "Decision: ALLOWED (no blocked_domains entry matched)".to_string(),
+            }
+        }
+    };
+    steps.push(decision);
+    steps
+}
+
+/// The first entry of `patterns` matching `host`
+fn first_match<'a>(host: &str, patterns: &'a [String]) -> Option<&'a str> {
+    patterns
+        .iter()
+        .find(|p| matches_pattern(host, p))
+        .map(String::as_str)
 }
 
-/// Check if host matches a pattern (with wildcard support)
-fn matches_pattern(host: &str, pattern: &str) -> bool {
-    if pattern.is_empty() {
-        return false;
+fn list_step(list: &str, host: &str, matched: Option<&str>) -> String {
+    match matched {
+        Some(entry) => format!(
+            "{}: \"{}\" matched ({})",
+            list,
+            entry,
+            match_kind(host, entry)
+        ),
+        None => format!("{}: no entry matched", list),
     }
-    if let Some(domain) = pattern.strip_prefix("*.") {
-        // *.example.com matches api.example.com and example.com
-        host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// How a config entry matched the host
+fn match_kind(host: &str, entry: &str) -> &'static str {
+    if is_cidr(entry) {
+        "CIDR range"
+    } else if entry.starts_with("*.") {
+        if entry.strip_prefix("*.") == Some(host) {
+            "wildcard, base domain"
+        } else {
+            "wildcard"
+        }
     } else {
-        host == pattern
+        "exact match"
     }
 }
 
-/// Check if host matches any pattern in the list
-fn matches_any(host: &str, patterns: &[String]) -> bool {
-    patterns.iter().any(|p| matches_pattern(host, p))
+/// Firewall rules apply to the address of direct connections, not to domain names
+fn firewall_step(name: &str, enabled: bool, ip: Option<IpAddr>, ranges: &[&str]) -> String {
+    if !enabled {
+        return format!("{}: disabled", name);
+    }
+    let Some(ip) = ip else {
+        return format!(
+            "{}: enabled, not evaluated for a domain name (applies to the resolved address of direct connections)",
+            name
+        );
+    };
+    match ranges
+        .iter()
+        .find(|range| Cidr::parse(range).is_ok_and(|r| r.contains(ip)))
+    {
+        Some(range) => format!(
+            "{}: {} is in {} (direct connections are rejected by the firewall)",
+            name, ip, range
+        ),
+        None => format!("{}: {} is not in a blocked range", name, ip),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn network_config(mode: PolicyMode) -> Config {
+        let mut config = Config::default();
+        config.security.network.enabled = true;
+        config.security.network.mode = mode;
+        config
+    }
+
     #[test]
-    fn test_matches_pattern_exact() {
-        assert!(matches_pattern("example.com", "example.com"));
-        assert!(!matches_pattern("example.com", "other.com"));
+    fn test_trace_names_matching_entry() {
+        let mut config = network_config(PolicyMode::Allowlist);
+        config.security.network.allowed_domains =
+            vec!["github.com".to_string(), "*.example.com".to_string()];
+
+        let steps = trace(&config, "api.example.com");
+        assert!(steps[0].starts_with("Policy mode: allowlist"));
+        assert!(steps.contains(&"bypass_domains: no entry matched".to_string()));
+        assert!(
+            steps.contains(&"allowed_domains: \"*.example.com\" matched (wildcard)".to_string())
+        );
+        assert_eq!(
+            steps.last().unwrap(),
+            "Decision: ALLOWED by allowed_domains entry \"*.example.com\""
+        );
+
+        let steps = trace(&config, "example.org");
+        assert_eq!(
+            steps.last().unwrap(),
+            "Decision: BLOCKED (no allowed_domains entry matched)"
+        );
     }
 
     #[test]
-    fn test_matches_pattern_wildcard() {
-        assert!(matches_pattern("api.example.com", "*.example.com"));
-        assert!(matches_pattern("example.com", "*.example.com"));
-        assert!(!matches_pattern("example.org", "*.example.com"));
+    fn test_trace_bypass_and_denylist() {
+        let mut config = network_config(PolicyMode::Denylist);
+        config.security.network.blocked_domains = vec!["10.0.0.0/8".to_string()];
+        config.security.network.bypass_domains = vec!["pinned.example.com".to_string()];
+
+        let steps = trace(&config, "pinned.example.com");
+        assert!(steps
+            .contains(&"bypass_domains: \"pinned.example.com\" matched (exact match)".to_string()));
+        assert!(steps
+            .last()
+            .unwrap()
+            .starts_with("Decision: ALLOWED by bypass_domains"));
+
+        let steps = trace(&config, "10.1.2.3");
+        assert!(steps.contains(&"blocked_domains: \"10.0.0.0/8\" matched (CIDR range)".to_string()));
+        assert!(steps
+            .iter()
+            .any(|s| s.starts_with("Private network blocking: 10.1.2.3 is in 10.0.0.0/8")));
+        assert_eq!(
+            steps.last().unwrap(),
+            "Decision: BLOCKED by blocked_domains entry \"10.0.0.0/8\""
+        );
     }
 
     #[test]
-    fn test_matches_any() {
-        let patterns = vec!["example.com".to_string(), "*.test.com".to_string()];
-        assert!(matches_any("example.com", &patterns));
-        assert!(matches_any("api.test.com", &patterns));
-        assert!(!matches_any("other.com", &patterns));
+    fn test_trace_firewall_blocks() {
+        let mut config = network_config(PolicyMode::Denylist);
+        let steps = trace(&config, "169.254.169.254");
+        assert!(steps
+            .iter()
+            .any(|s| s.starts_with("Metadata service blocking: 169.254.169.254 is in")));
+        assert!(steps.contains(
+            &"Private network blocking: 169.254.169.254 is not in a blocked range".to_string()
+        ));
+
+        config.security.network.block_private_networks = false;
+        let steps = trace(&config, "example.com");
+        assert!(steps.contains(&"Private network blocking: disabled".to_string()));
+        assert!(steps
+            .iter()
+            .any(|s| s.starts_with("Metadata service blocking: enabled, not evaluated")));
+
+        config.security.network.enabled = false;
+        let steps = trace(&config, "example.com");
+        assert_eq!(
+            steps.last().unwrap(),
+            "Decision: ALLOWED (network isolation disabled)"
+        );
     }
 }
@@ -1,51 +1,286 @@
+use crate::build_log;
 use crate::capabilities;
-use crate::config::Config;
+use crate::cli::SummaryFormat;
+use crate::config::{Config, PhaseScript, ScriptPhase};
+use crate::diagnostics;
 use crate::error::{ClaudeVmError, Result};
+use crate::events::{self, Event};
 use crate::project::Project;
-use crate::scripts::runner;
-use crate::vm::{limactl::LimaCtl, mount, template};
-use std::path::Path;
+use crate::scripts::{remote::RepoSource, runner};
+use crate::status;
+use crate::vm::checkpoint::{self, Checkpointer, SetupCheckpoint};
+use crate::vm::{limactl::LimaCtl, mount, preflight, restricted_user, template};
+use std::path::{Path, PathBuf};
 
 pub fn execute(project: &Project, config: &Config, no_agent_install: bool) -> Result<()> {
-    // Check if Lima is installed
-    if !LimaCtl::is_installed() {
-        return Err(ClaudeVmError::LimaNotInstalled);
+    let Some(log_path) = config.setup.build_log_path(project.root()) else {
+        return build(project, config, no_agent_install);
+    };
+
+    build_log::start(&log_path, config.setup.build_log_mode.unwrap_or_default())?;
+    status!("Build log: {}", log_path.display());
+    let result = build(project, config, no_agent_install);
+    if let Err(e) = &result {
+        build_log::record(&format!("Setup failed: {}", e));
     }
+    build_log::stop();
+    result
+}
+
+/// Print what `setup` would do, without calling limactl
+pub fn dry_run(project: &Project, config: &Config) -> Result<()> {
+    config.packages.check_tools(&config.tools)?;
+    let registry = capabilities::registry::CapabilityRegistry::load()?;
+    let minimums = registry.resource_minimums(config)?;
+    let config = &capabilities::size_vm(config)?;
+
+    println!("Setup plan for project: {}", project.root().display());
+    println!("Template name: {}", project.template_name());
 
+    println!();
+    println!("VM sizing:");
     println!(
+        "  disk: {} GB, memory: {} GB, cpus: {}",
+        config.vm.disk, config.vm.memory, config.vm.cpus
+    );
+    if let Some(gb) = config.vm.swap() {
+        println!("  swap: {} GB", gb);
+    }
+    for (resource, minimum) in [("disk", &minimums.disk), ("memory", &minimums.memory)] {
+        if let Some(minimum) = minimum {
+            println!(
+                "  minimum {}: {} GB (for {})",
+                resource, minimum.gb, minimum.capability
+            );
+        }
+    }
+
+    println!();
+    println!("Capabilities (install order):");
+    let capabilities = enabled_capability_ids(config)?;
+    if capabilities.is_empty() {
+        println!("  (none)");
+    }
+    for (i, id) in capabilities.iter().enumerate() {
+        println!("  {}. {}", i + 1, id);
+    }
+
+    println!();
+    println!("Packages:");
+    let system = registry.collect_system_packages(config)?;
+    let mut lists = vec![("system", system.as_slice())];
+    lists.extend(
+        config
+            .packages
+            .language_packages()
+            .into_iter()
+            .map(|(list, _, packages)| (list, packages)),
+    );
+    let lists: Vec<_> = lists.into_iter().filter(|(_, p)| !p.is_empty()).collect();
+    if lists.is_empty() {
+        println!("  (none)");
+    }
+    for (list, packages) in lists {
+        println!("  {}: {}", list, packages.join(", "));
+    }
+
+    println!();
+    println!("Setup phases (in order):");
+    let scripts: Vec<String> = standard_setup_scripts(project)
+        .into_iter()
+        .filter(|path| Path::new(path).exists())
+        .chain(config.setup.scripts.iter().cloned())
+        .collect();
+    let phases: Vec<String> = config
+        .phase
+        .before_all
+        .iter()
+        .map(|phase| format!("before_all: {}", describe_phase(phase)))
+        .chain(scripts.iter().map(|path| format!("setup script: {}", path)))
+        .chain(
+            config
+                .phase
+                .setup
+                .iter()
+                .map(|phase| format!("setup: {}", describe_phase(phase))),
+        )
+        .collect();
+    if phases.is_empty() {
+        println!("  (none)");
+    }
+    for phase in phases {
+        println!("  - {}", phase);
+    }
+    if !config.setup.checks.is_empty() {
+        println!();
+        println!("Checks: {}", config.setup.checks.len());
+    }
+
+    println!();
+    println!("Dry run: no VM was created");
+    Ok(())
+}
+
+/// Name of a phase and where its scripts come from
+fn describe_phase(phase: &ScriptPhase) -> String {
+    let mut sources = Vec::new();
+    if phase.script.is_some() {
+        sources.push("inline script".to_string());
+    }
+    if !phase.script_files.is_empty() {
+        sources.push(format!("files: {}", phase.script_files.join(", ")));
+    }
+    if let Some(repo) = &phase.repo {
+        sources.push(format!("repo: {}", repo));
+    }
+    if sources.is_empty() {
+        sources.push("no scripts".to_string());
+    }
+    let mut description = format!("{} ({})", phase.name, sources.join("; "));
+    if let Some(when) = &phase.when {
+        description.push_str(&format!(", when: {}", when));
+    }
+    description
+}
+
+fn build(project: &Project, config: &Config, no_agent_install: bool) -> Result<()> {
+    // Check if Lima is installed
+    LimaCtl::ensure_available()?;
+
+    status!(
         "Setting up template for project: {}",
         project.root().display()
     );
-    println!("Template name: {}", project.template_name());
+    status!("Template name: {}", project.template_name());
 
-    // Clean old template if it exists
-    if template::exists(project.template_name())? {
-        println!("Removing existing template...");
-        template::delete(project.template_name())?;
+    let (_, dotfile_warnings) = config.runtime.dotfiles(project.root());
+    for warning in config.vm.validate() {
+        diagnostics::warn("vm-config", warning);
+    }
+    for warning in dotfile_warnings {
+        diagnostics::warn("dotfiles", warning);
+    }
+    for (code, warning) in config.security.network.diagnostics() {
+        diagnostics::warn(code, warning);
     }
 
-    // Create base template
-    create_base_template(project, config)?;
+    // Language packages need the capability providing their package manager
+    config.packages.check_tools(&config.tools)?;
+
+    // Check the VM is large enough for the enabled capabilities
+    let config = &capabilities::size_vm(config)?;
+
+    // Continue an unfinished setup of the same VM, or start over
+    let base_hash = base_hash(config);
+    let resume_from = if config.resume_on_failure {
+        resumable_checkpoint(project, &base_hash)?
+    } else {
+        None
+    };
+
+    let started = std::time::Instant::now();
+    if let Some(checkpoint) = &resume_from {
+        status!(
+            "Resuming setup ({} step(s) completed in a previous run)...",
+            checkpoint.completed.len()
+        );
+    } else {
+        // Check the host can fit the new template before removing the old one,
+        // whose disk will be freed
+        let existing = template::exists(project.template_name())?;
+        let reclaimable = if existing {
+            template::get_disk_bytes(project.template_name()).unwrap_or(0)
+        } else {
+            0
+        };
+        preflight::check(&config.vm, reclaimable)?;
+
+        // Clean old template if it exists
+        if existing {
+            status!("Removing existing template...");
+            template::delete(project.template_name())?;
+        }
+
+        // Create base template
+        events::emit(Event::VmCreating {
+            vm: project.template_name().to_string(),
+        });
+        create_base_template(project, config)?;
+    }
+
+    let mut checkpoints = Checkpointer::new(
+        checkpoint::path(project.template_name()),
+        base_hash,
+        resume_from,
+    )?;
 
     // Run the setup process and clean up on failure
-    match run_setup_process(project, config, no_agent_install) {
-        Ok(()) => {
-            println!("\nTemplate ready for project: {}", project.root().display());
-            println!("Run 'claude-vm' in this project directory to use it.");
+    match run_setup_process(project, config, no_agent_install, started, &mut checkpoints) {
+        Ok(checks) => {
+            let capabilities = enabled_capability_ids(config)?;
+            if let Err(e) = record_metadata(project, config, &capabilities) {
+                diagnostics::warn(
+                    "template-metadata",
+                    format!("Failed to record template metadata: {}", e),
+                );
+            }
+            if let Err(e) = checks {
+                eprintln!("\nSetup checks failed: {}", e);
+                eprintln!("Template kept: all steps but the checks completed.");
+                eprintln!(
+                    "Fix the problem, then run 'claude-vm setup --resume-on-failure' to run the checks again."
+                );
+                return Err(e);
+            }
+            checkpoint::clear(project.template_name())?;
+
+            let summary = SetupSummary::new(project, config, capabilities, started.elapsed());
+            match config.summary_format {
+                SummaryFormat::Human => {
+                    status!("\n{}", summary.human());
+                    status!("Run 'claude-vm' in this project directory to use it.");
+                }
+                SummaryFormat::Json => println!("{}", summary.json()),
+            }
             Ok(())
         }
         Err(e) => {
             eprintln!("\nSetup failed: {}", e);
-            eprintln!("Cleaning up template...");
 
             // Try to stop the VM if it's running
             if let Err(stop_err) = LimaCtl::stop(project.template_name(), false) {
-                eprintln!("Warning: Failed to stop template VM: {}", stop_err);
+                diagnostics::warn(
+                    "template-cleanup",
+                    format!("Failed to stop template VM: {}", stop_err),
+                );
+            }
+
+            if config.resume_on_failure {
+                eprintln!(
+                    "Template kept with {} completed step(s){}.",
+                    checkpoints.completed(),
+                    checkpoints
+                        .pending()
+                        .map(|step| format!("; failed at '{}'", step))
+                        .unwrap_or_default()
+                );
+                eprintln!(
+                    "Fix the problem, then run 'claude-vm setup --resume-on-failure' to continue."
+                );
+                return Err(e);
             }
 
+            eprintln!("Cleaning up template...");
+            events::emit(Event::Teardown {
+                vm: project.template_name().to_string(),
+            });
+
             // Delete the template
             if let Err(del_err) = template::delete(project.template_name()) {
-                eprintln!("Warning: Failed to delete template: {}", del_err);
+                diagnostics::warn(
+                    "template-cleanup",
+                    format!("Failed to delete template: {}", del_err),
+                );
             } else {
                 eprintln!("Template cleaned up successfully.");
             }
@@ -55,83 +290,445 @@ pub fn execute(project: &Project, config: &Config, no_agent_install: bool) -> Re
     }
 }
 
-fn run_setup_process(project: &Project, config: &Config, no_agent_install: bool) -> Result<()> {
+/// Checkpoint of an unfinished setup that can be resumed: the template still
+/// exists and was created with the same VM settings
+fn resumable_checkpoint(project: &Project, base_hash: &str) -> Result<Option<SetupCheckpoint>> {
+    if !template::exists(project.template_name())? {
+        return Ok(None);
+    }
+    match checkpoint::read(project.template_name())? {
+        Some(checkpoint) if checkpoint.base_hash == base_hash => Ok(Some(checkpoint)),
+        Some(_) => {
+            status!("VM settings changed since the failed setup, rebuilding from scratch.");
+            Ok(None)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Serialized step input for checkpoint hashes.
+/// Goes through `serde_json::Value` so map keys are sorted and hashes are stable across runs.
+fn step_input<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_value(value)
+        .map(|value| value.to_string())
+        .unwrap_or_default()
+}
+
+/// What the template VM is created from: a change requires a new VM
+fn base_hash(config: &Config) -> String {
+    let input = step_input(&(
+        crate::version::VERSION,
+        BASE_IMAGE,
+        (config.vm.disk, config.vm.memory, config.vm.cpus),
+        // Port forwards come from the enabled capabilities
+        &config.tools,
+        &config.setup.mounts,
+        config.setup.apt_cache,
+    ));
+    checkpoint::chain("", "vm", &input)
+}
+
+/// A user setup phase, its environment and the contents of its local script files
+fn phase_input(project: &Project, config: &Config, phase: &ScriptPhase) -> String {
+    step_input(&(
+        phase,
+        config.phase.env_for(phase),
+        phase.get_scripts(project.root()).unwrap_or_default(),
+    ))
+}
+
+/// Contents of the file-based setup scripts, missing ones included as empty
+fn script_files_input(paths: &[String]) -> String {
+    let contents: Vec<(&String, String)> = paths
+        .iter()
+        .map(|path| (path, std::fs::read_to_string(path).unwrap_or_default()))
+        .collect();
+    step_input(&contents)
+}
+
+/// Build the template. The inner result is the outcome of `[[setup.checks]]`:
+/// a failed check leaves a complete template, which is kept for inspection.
+fn run_setup_process(
+    project: &Project,
+    config: &Config,
+    no_agent_install: bool,
+    started: std::time::Instant,
+    checkpoints: &mut Checkpointer,
+) -> Result<Result<()>> {
     // Start the VM
-    println!("Starting template VM...");
+    status!("Starting template VM...");
     LimaCtl::start(project.template_name(), true)?; // Always verbose for setup
+    events::emit(Event::VmReady {
+        vm: project.template_name().to_string(),
+        duration_ms: events::millis(started.elapsed()),
+    });
+
+    // Capability steps depend on the enabled tools and their settings
+    // Capability options are sorted so the input does not depend on map order
+    let capability_options: std::collections::BTreeMap<_, _> =
+        config.capability_config.iter().collect();
+    let capability_input = step_input(&(&config.tools, &config.security, &capability_options));
+    // Capability hooks also depend on which capabilities skip them
+    let hook_input = step_input(&(&capability_input, &config.setup.skip_capability_phases));
+    capabilities::warn_skipped_phases(config)?;
+
+    // Run user phases that must precede everything else (e.g. DNS fixes)
+    for phase in &config.phase.before_all {
+        checkpoints.run(
+            &format!("before_all: {}", phase.name),
+            &phase_input(project, config, phase),
+            || run_setup_phases(project, config, std::slice::from_ref(phase)),
+        )?;
+    }
 
     // Run host setup hooks for capabilities
-    capabilities::execute_host_setup(project, config)?;
+    checkpoints.run("host setup", &hook_input, || {
+        capabilities::execute_host_setup(project, config)
+    })?;
 
     // Store project metadata
-    store_project_metadata(project)?;
+    checkpoints.run("project metadata", &step_input(project.root()), || {
+        store_project_metadata(project)
+    })?;
 
     // Disable needrestart interactive prompts
-    disable_needrestart(project)?;
+    checkpoints.run("needrestart", "", || disable_needrestart(project))?;
+
+    // Keep downloaded packages in the mounted host cache
+    checkpoints.run("apt cache", &step_input(&config.setup.apt_cache), || {
+        if config.setup.apt_cache {
+            set_keep_downloaded_packages(project, true)?;
+        }
+        Ok(())
+    })?;
 
     // Install base packages
-    install_base_packages(project)?;
+    checkpoints.run("base packages", "", || install_base_packages(project))?;
+
+    // Provision guest swap before heavy installs
+    checkpoints.run("swap", &step_input(&config.vm.swap()), || {
+        if let Some(gb) = config.vm.swap() {
+            configure_swap(project, gb)?;
+        }
+        Ok(())
+    })?;
+
+    // Copy host dotfiles before capability and agent installs append their
+    // PATH entries to ~/.bashrc, so a replaced ~/.bashrc keeps them
+    checkpoints.run("dotfiles", &step_input(&config.runtime), || {
+        install_dotfiles(project, config)
+    })?;
 
     // === THREE-PHASE PACKAGE MANAGEMENT ===
 
     // Phase 1: Setup custom repositories (Docker, Node, gh, etc.)
-    capabilities::setup_repositories(project, config)?;
+    checkpoints.run(
+        "repositories",
+        &step_input(&(&capability_input, &config.packages.setup_script)),
+        || capabilities::setup_repositories(project, config),
+    )?;
 
     // Phase 2: Batch install all packages in SINGLE apt-get call
-    capabilities::install_system_packages(project, config)?;
+    checkpoints.run(
+        "system packages",
+        &step_input(&(&capability_input, &config.packages.system)),
+        || capabilities::install_system_packages(project, config),
+    )?;
+
+    // Phase 3: npm, pip and cargo packages, with the package managers just installed
+    checkpoints.run(
+        "language packages",
+        &step_input(&(
+            &capability_input,
+            &config.packages.npm,
+            &config.packages.pip,
+            &config.packages.cargo,
+        )),
+        || capabilities::install_language_packages(project, config),
+    )?;
 
     // === END PACKAGE MANAGEMENT ===
 
     // Execute vm_setup hooks (now primarily for post-install configuration)
-    capabilities::execute_vm_setup(project, config)?;
+    checkpoints.run("vm setup", &hook_input, || {
+        capabilities::execute_vm_setup(project, config)
+    })?;
+
+    // Check that installed capabilities are usable
+    checkpoints.run("verify", &hook_input, || {
+        capabilities::verify_capabilities(project, config)
+    })?;
 
     // Install vm_runtime scripts into template
-    capabilities::install_vm_runtime_scripts(project, config)?;
+    checkpoints.run("runtime scripts", &hook_input, || {
+        capabilities::install_vm_runtime_scripts(project, config)
+    })?;
+
+    // Install the idle shutdown watchdog
+    checkpoints.run(
+        "idle watchdog",
+        &step_input(&config.vm.idle_shutdown()),
+        || {
+            if let Some(mins) = config.vm.idle_shutdown() {
+                install_idle_watchdog(project, mins)?;
+            }
+            Ok(())
+        },
+    )?;
 
-    // Install Claude Code (skip if --no-agent-install flag is set)
-    if !no_agent_install {
-        install_claude(project)?;
+    // Unprivileged user for --drop-privileges sessions
+    checkpoints.run("restricted user", "bindfs", || {
+        restricted_user::provision(project.template_name())
+    })?;
 
-        // Authenticate Claude
-        authenticate_claude(project)?;
+    // Install Claude Code (skip if --no-agent-install flag is set)
+    checkpoints.run(
+        "agent",
+        &step_input(&(no_agent_install, &capability_input)),
+        || {
+            if !no_agent_install {
+                install_claude(project)?;
+
+                // Authenticate Claude
+                authenticate_claude(project)?;
+
+                // Configure all MCP servers from capabilities
+                capabilities::configure_mcp_servers(project, config)?;
+            } else {
+                status!("Skipping Claude Code installation (--no-agent-install flag set)");
+            }
+            Ok(())
+        },
+    )?;
 
-        // Configure all MCP servers from capabilities
-        capabilities::configure_mcp_servers(project, config)?;
-    } else {
-        println!("Skipping Claude Code installation (--no-agent-install flag set)");
+    // Run user-defined setup scripts
+    let script_files: Vec<String> = standard_setup_scripts(project)
+        .into_iter()
+        .chain(config.setup.scripts.iter().cloned())
+        .collect();
+    checkpoints.run("setup scripts", &script_files_input(&script_files), || {
+        run_setup_scripts(project, config)
+    })?;
+    for phase in &config.phase.setup {
+        checkpoints.run(
+            &format!("setup: {}", phase.name),
+            &phase_input(project, config, phase),
+            || run_setup_phases(project, config, std::slice::from_ref(phase)),
+        )?;
     }
 
-    // Run user-defined setup scripts
-    run_setup_scripts(project, config)?;
+    // Check the template is what the user expects
+    let checks = checkpoints.run("checks", &step_input(&config.setup.checks), || {
+        run_setup_checks(project, config)
+    });
+
+    // Sessions don't mount the cache, so stop keeping packages in the template
+    if config.setup.apt_cache {
+        set_keep_downloaded_packages(project, false)?;
+    }
 
     // Stop template
-    println!("Stopping template VM...");
+    status!("Stopping template VM...");
     LimaCtl::stop(project.template_name(), true)?; // Always verbose for setup
 
-    Ok(())
+    Ok(checks)
+}
+
+/// Outcome of a `[[setup.checks]]` command
+#[derive(Debug)]
+struct CheckResult {
+    name: String,
+    /// Output of the failed command, None when it passed
+    failure: Option<String>,
+}
+
+/// Run the user's `[[setup.checks]]` in the template and print a pass/fail table
+fn run_setup_checks(project: &Project, config: &Config) -> Result<()> {
+    if config.setup.checks.is_empty() {
+        return Ok(());
+    }
+
+    let mut results = Vec::new();
+    for (index, check) in config.setup.checks.iter().enumerate() {
+        status!("Checking {}...", check.name);
+        // A login shell, so tools added to PATH by the profile are found
+        let script = format!(
+            "#!/bin/bash\nexec bash -lc {}\n",
+            crate::utils::shell::escape(&check.command)
+        );
+        let captured = runner::execute_script_captured(
+            project.template_name(),
+            &script,
+            &format!("setup_check_{}.sh", index),
+        )?;
+        results.push(CheckResult {
+            name: check.name.clone(),
+            failure: (!captured.success()).then_some(captured.output),
+        });
+    }
+
+    status!("\nSetup checks:");
+    for result in &results {
+        match result.failure {
+            None => status!("  ✓ {}", result.name),
+            Some(_) => status!("  ✗ {}", result.name),
+        }
+    }
+
+    check_results(&results, config.soft_checks)
+}
+
+/// Fail on failed checks (or only warn with `--soft-checks`), naming each with its output
+fn check_results(results: &[CheckResult], soft: bool) -> Result<()> {
+    let failed: Vec<&CheckResult> = results.iter().filter(|r| r.failure.is_some()).collect();
+    if failed.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = format!("{} of {} setup checks failed:", failed.len(), results.len());
+    for result in failed {
+        message.push_str(&format!("\n  {}", result.name));
+        let output = result.failure.as_deref().unwrap_or_default();
+        let (lines, _) = runner::tail_lines(output, 5);
+        for line in lines {
+            message.push_str(&format!("\n    {}", line));
+        }
+    }
+
+    if soft {
+        diagnostics::warn("setup-checks", message);
+        return Ok(());
+    }
+    Err(ClaudeVmError::CommandFailed(message))
+}
+
+/// Lima template the VM is created from
+const BASE_IMAGE: &str = "debian-13";
+
+/// Ids of the enabled capabilities, in install order
+fn enabled_capability_ids(config: &Config) -> Result<Vec<String>> {
+    Ok(capabilities::registry::CapabilityRegistry::load()?
+        .get_enabled_capabilities(config)?
+        .iter()
+        .map(|c| c.capability.id.clone())
+        .collect())
+}
+
+/// Record what the template was built from (read back with `info --metadata`)
+fn record_metadata(project: &Project, config: &Config, capabilities: &[String]) -> Result<()> {
+    let metadata = template::TemplateMetadata {
+        claude_vm_version: crate::version::VERSION.to_string(),
+        built_at: chrono::Utc::now().to_rfc3339(),
+        project_root: project.root().to_string_lossy().to_string(),
+        capabilities: capabilities.to_vec(),
+        config_hash: config_hash(config),
+        disk: config.vm.disk,
+        memory: config.vm.memory,
+        cpus: config.vm.cpus,
+        base_image: BASE_IMAGE.to_string(),
+        packages: config.packages.system.clone(),
+        flavor: project.flavor().map(String::from),
+    };
+
+    template::write_metadata(project.template_name(), &metadata)
+}
+
+/// End-of-run report of a successful setup (`--summary-format`)
+#[derive(Debug, serde::Serialize)]
+struct SetupSummary {
+    template: String,
+    project_root: String,
+    capabilities: Vec<String>,
+    disk: u32,
+    memory: u32,
+    cpus: u32,
+    duration_ms: u64,
+}
+
+impl SetupSummary {
+    fn new(
+        project: &Project,
+        config: &Config,
+        capabilities: Vec<String>,
+        duration: std::time::Duration,
+    ) -> Self {
+        Self {
+            template: project.template_name().to_string(),
+            project_root: project.root().to_string_lossy().to_string(),
+            capabilities,
+            disk: config.vm.disk,
+            memory: config.vm.memory,
+            cpus: config.vm.cpus,
+            duration_ms: events::millis(duration),
+        }
+    }
+
+    fn human(&self) -> String {
+        let capabilities = if self.capabilities.is_empty() {
+            "none".to_string()
+        } else {
+            self.capabilities.join(", ")
+        };
+        let secs = self.duration_ms / 1000;
+        format!(
+            "Template ready for project: {}\n  \
+             Template:     {}\n  \
+             Capabilities: {}\n  \
+             Resources:    {}GB disk, {}GB memory, {} CPUs\n  \
+             Build time:   {}m {:02}s",
+            self.project_root,
+            self.template,
+            capabilities,
+            self.disk,
+            self.memory,
+            self.cpus,
+            secs / 60,
+            secs % 60
+        )
+    }
+
+    fn json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Hash of the effective configuration, to spot drift between builds.
+/// Stable across runs and Rust releases: map keys are sorted by `step_input`.
+fn config_hash(config: &Config) -> String {
+    format!("{:x}", md5::compute(step_input(config).as_bytes()))
 }
 
 fn create_base_template(project: &Project, config: &Config) -> Result<()> {
-    println!("Creating base template VM...");
+    status!("Creating base template VM...");
 
     // Collect port forwards from enabled capabilities
     let port_forwards = capabilities::get_port_forwards(config)?;
 
     if !port_forwards.is_empty() {
-        println!("Configuring {} port forward(s)...", port_forwards.len());
+        status!("Configuring {} port forward(s)...", port_forwards.len());
     }
 
     // Convert setup mounts from config using shared helper
-    let setup_mounts = mount::convert_mount_entries(&config.setup.mounts)?;
+    let mut setup_mounts =
+        mount::convert_mount_entries(&config.setup.mounts, project.root(), config.strict_mounts)?;
+
+    if config.setup.apt_cache {
+        let cache_dir = apt_cache_dir()?;
+        prepare_apt_cache_dir(&cache_dir)?;
+        status!("Using apt package cache: {}", cache_dir.display());
+        setup_mounts.push(
+            mount::Mount::new(cache_dir, true).with_mount_point(PathBuf::from(APT_ARCHIVES_DIR)),
+        );
+    }
 
     if !setup_mounts.is_empty() {
-        println!("Configuring {} setup mount(s)...", setup_mounts.len());
+        status!("Configuring {} setup mount(s)...", setup_mounts.len());
     }
 
     // Use Debian 13 template with setup mounts
     LimaCtl::create(
         project.template_name(),
-        "debian-13",
+        BASE_IMAGE,
         config.vm.disk,
         config.vm.memory,
         config.vm.cpus,
@@ -143,8 +740,74 @@ fn create_base_template(project: &Project, config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Where apt keeps downloaded .deb files in the VM
+const APT_ARCHIVES_DIR: &str = "/var/cache/apt/archives";
+
+/// apt config file that keeps downloaded packages while the cache is mounted
+const APT_KEEP_CONF: &str = "/etc/apt/apt.conf.d/99claude-vm-keep-debs";
+
+/// Host directory shared by all templates for downloaded apt packages
+fn apt_cache_dir() -> Result<PathBuf> {
+    std::env::var("HOME")
+        .map(|home| PathBuf::from(home).join(".claude-vm").join("apt-cache"))
+        .map_err(|_| ClaudeVmError::InvalidConfig("HOME environment variable not set".to_string()))
+}
+
+/// Create the cache directory (with apt's `partial/` subdirectory), refusing
+/// anything that is not a directory rather than mounting it
+fn prepare_apt_cache_dir(dir: &Path) -> Result<()> {
+    for path in [dir.to_path_buf(), dir.join("partial")] {
+        if path.exists() && !path.is_dir() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "apt cache path is not a directory: {}\nRun 'claude-vm setup --clear-apt-cache' to reset it.",
+                path.display()
+            )));
+        }
+    }
+    std::fs::create_dir_all(dir.join("partial"))?;
+    Ok(())
+}
+
+/// Delete the host apt package cache
+pub fn clear_apt_cache() -> Result<()> {
+    let dir = apt_cache_dir()?;
+    remove_apt_cache(&dir)?;
+    status!("Cleared apt package cache: {}", dir.display());
+    Ok(())
+}
+
+fn remove_apt_cache(dir: &Path) -> Result<()> {
+    if dir.is_dir() {
+        std::fs::remove_dir_all(dir)?;
+    } else if dir.exists() {
+        std::fs::remove_file(dir)?;
+    }
+    Ok(())
+}
+
+fn set_keep_downloaded_packages(project: &Project, keep: bool) -> Result<()> {
+    let cmd = if keep {
+        format!(
+            "echo 'Binary::apt::APT::Keep-Downloaded-Packages \"true\";' > {}",
+            APT_KEEP_CONF
+        )
+    } else {
+        format!("rm -f {}", APT_KEEP_CONF)
+    };
+
+    LimaCtl::shell(
+        project.template_name(),
+        None,
+        "sudo",
+        &["bash", "-c", &cmd],
+        false,
+    )?;
+
+    Ok(())
+}
+
 fn store_project_metadata(project: &Project) -> Result<()> {
-    println!("Storing project metadata...");
+    status!("Storing project metadata...");
 
     let project_root = project.root().to_string_lossy();
     let cmd = format!(
@@ -158,7 +821,7 @@ fn store_project_metadata(project: &Project) -> Result<()> {
 }
 
 fn disable_needrestart(project: &Project) -> Result<()> {
-    println!("Configuring system...");
+    status!("Configuring system...");
 
     let cmd = r#"mkdir -p /etc/needrestart/conf.d && echo '$nrconf{restart} = '"'"'a'"'"';' > /etc/needrestart/conf.d/no-prompt.conf"#;
 
@@ -173,8 +836,84 @@ fn disable_needrestart(project: &Project) -> Result<()> {
     Ok(())
 }
 
+/// Guest-side watchdog: powers the VM off once no agent process and no terminal
+/// session has been seen for the configured number of minutes.
+const IDLE_WATCHDOG_SCRIPT: &str = r#"#!/bin/bash
+limit="${1:?minutes required}"
+idle=0
+while sleep 60; do
+    if pgrep -x claude >/dev/null || ls /dev/pts | grep -qv '^ptmx$'; then
+        idle=0
+    else
+        idle=$((idle + 1))
+    fi
+    if [ "$idle" -ge "$limit" ]; then
+        logger -t claude-vm-idle "No activity for ${limit} minutes, shutting down"
+        systemctl poweroff
+    fi
+done
+"#;
+
+/// Install the idle watchdog as a systemd service so it runs in every VM cloned from the template.
+///
+/// Guest-side rather than a host-side reaper: the watchdog keeps working when the
+/// host process that started the VM is gone, at the cost of a change in the template.
+fn install_idle_watchdog(project: &Project, mins: u32) -> Result<()> {
+    status!("Installing idle shutdown watchdog ({} min)...", mins);
+
+    let unit = format!(
+        "[Unit]\nDescription=claude-vm idle shutdown\n\n[Service]\nExecStart=/usr/local/bin/claude-vm-idle-watchdog {}\nRestart=on-failure\n\n[Install]\nWantedBy=multi-user.target\n",
+        mins
+    );
+    let cmd = format!(
+        "cat > /usr/local/bin/claude-vm-idle-watchdog <<'WATCHDOG'\n{}WATCHDOG\n\
+         chmod +x /usr/local/bin/claude-vm-idle-watchdog && \
+         printf '{}' > /etc/systemd/system/claude-vm-idle.service && \
+         systemctl daemon-reload && systemctl enable claude-vm-idle.service",
+        IDLE_WATCHDOG_SCRIPT, unit
+    );
+
+    LimaCtl::shell(
+        project.template_name(),
+        None,
+        "sudo",
+        &["bash", "-c", &cmd],
+        false,
+    )?;
+
+    Ok(())
+}
+
+/// Create and enable a swap file in the template, persisted through /etc/fstab
+fn configure_swap(project: &Project, gb: u32) -> Result<()> {
+    status!("Configuring {} GB swap file...", gb);
+
+    LimaCtl::shell(
+        project.template_name(),
+        None,
+        "sudo",
+        &["bash", "-c", &swap_script(gb)],
+        false,
+    )?;
+
+    Ok(())
+}
+
+fn swap_script(gb: u32) -> String {
+    format!(
+        "set -e\n\
+         swapoff /swapfile 2>/dev/null || true\n\
+         fallocate -l {gb}G /swapfile || dd if=/dev/zero of=/swapfile bs=1M count=$(({gb} * 1024))\n\
+         chmod 600 /swapfile\n\
+         mkswap /swapfile\n\
+         swapon /swapfile\n\
+         grep -q '^/swapfile ' /etc/fstab || echo '/swapfile none swap sw 0 0' >> /etc/fstab\n",
+        gb = gb
+    )
+}
+
 fn install_base_packages(project: &Project) -> Result<()> {
-    println!("Installing base packages...");
+    status!("Installing base packages...");
 
     // Note: No apt-get update needed here. Base packages are in default Debian repos
     // and Lima templates come with current package lists. We do a single apt-get update
@@ -199,6 +938,7 @@ fn install_base_packages(project: &Project) -> Result<()> {
             "unzip",
             "zip",
             "ca-certificates",
+            "gnupg",
         ],
         false,
     )?;
@@ -206,10 +946,45 @@ fn install_base_packages(project: &Project) -> Result<()> {
     Ok(())
 }
 
+/// Copy `[runtime] dotfiles` from the host into the template user's home
+fn install_dotfiles(project: &Project, config: &Config) -> Result<()> {
+    let (dotfiles, _) = config.runtime.dotfiles(project.root());
+    if dotfiles.is_empty() {
+        return Ok(());
+    }
+
+    status!("Installing {} dotfile(s)...", dotfiles.len());
+    let vm_name = project.template_name();
+    for (i, dotfile) in dotfiles.iter().enumerate() {
+        status!("  {} -> ~/{}", dotfile.source.display(), dotfile.target);
+        let staged = format!("/tmp/claude-vm-dotfile-{}", i);
+        LimaCtl::copy(&dotfile.source, vm_name, &staged)?;
+        LimaCtl::shell(
+            vm_name,
+            None,
+            "bash",
+            &["-c", &dotfile_install_script(&staged, &dotfile.target)],
+            false,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Move a staged dotfile to its place in the VM home, creating parent directories
+fn dotfile_install_script(staged: &str, target: &str) -> String {
+    let dest = format!("\"$HOME\"/{}", crate::utils::shell::escape(target));
+    format!(
+        "mkdir -p \"$(dirname {dest})\" && mv -f {staged} {dest}",
+        dest = dest,
+        staged = crate::utils::shell::escape(staged),
+    )
+}
+
 // Removed: install_optional_tools - now handled by capability system
 
 fn install_claude(project: &Project) -> Result<()> {
-    println!("Installing Claude Code...");
+    status!("Installing Claude Code...");
 
     LimaCtl::shell(
         project.template_name(),
@@ -226,9 +1001,10 @@ fn install_claude(project: &Project) -> Result<()> {
     Ok(())
 }
 
-fn authenticate_claude(project: &Project) -> Result<()> {
-    println!("Setting up Claude authentication...");
-    println!("(This will open a browser window for authentication)");
+/// Run Claude once in the template so the user can log in
+pub fn authenticate_claude(project: &Project) -> Result<()> {
+    status!("Setting up Claude authentication...");
+    status!("(This will open a browser window for authentication)");
 
     LimaCtl::shell(
         project.template_name(),
@@ -243,139 +1019,463 @@ fn authenticate_claude(project: &Project) -> Result<()> {
 
 // Removed: configure_chrome_mcp - now handled by capability system
 
-fn run_setup_scripts(project: &Project, config: &Config) -> Result<()> {
-    let vm_name = project.template_name();
-
-    // 1. Auto-detected file-based scripts (unchanged)
-    let standard_scripts = vec![
+/// Setup scripts picked up automatically from the home directory and the project root
+fn standard_setup_scripts(project: &Project) -> Vec<String> {
+    vec![
         format!(
             "{}/.claude-vm.setup.sh",
             std::env::var("HOME").unwrap_or_default()
         ),
         format!("{}/.claude-vm.setup.sh", project.root().display()),
-    ];
+    ]
+}
+
+/// Run the file-based setup scripts ([[phase.setup]] phases are checkpointed one by one)
+fn run_setup_scripts(project: &Project, config: &Config) -> Result<()> {
+    let vm_name = project.template_name();
 
-    for script_path_str in standard_scripts {
+    // 1. Auto-detected file-based scripts (unchanged)
+    for script_path_str in standard_setup_scripts(project) {
         let script_path = Path::new(&script_path_str);
         if script_path.exists() {
-            println!("Running setup script: {}", script_path.display());
+            status!("Running setup script: {}", script_path.display());
             runner::execute_script_file(vm_name, script_path)?;
         }
     }
 
     // 2. Legacy scripts (with deprecation warning)
     if !config.setup.scripts.is_empty() {
-        eprintln!(
-            "⚠ Warning: [setup] scripts array is deprecated. Please migrate to [[phase.setup]]"
+        diagnostics::warn(
+            "deprecated-setup-scripts",
+            "[setup] scripts array is deprecated. Please migrate to [[phase.setup]]\n   \
+             See: docs/configuration.md",
         );
-        eprintln!("   See: docs/configuration.md");
 
         for script_path_str in &config.setup.scripts {
             let script_path = Path::new(script_path_str);
             if !script_path.exists() {
-                eprintln!("⚠ Warning: Setup script not found: {}", script_path_str);
+                diagnostics::warn(
+                    "script-not-found",
+                    format!("Setup script not found: {}", script_path_str),
+                );
                 continue;
             }
-            println!("Running custom setup script: {}", script_path.display());
+            status!("Running custom setup script: {}", script_path.display());
             runner::execute_script_file(vm_name, script_path)?;
         }
     }
 
-    // 3. New phase-based scripts
-    for phase in &config.phase.setup {
-        println!("\n━━━ Setup Phase: {} ━━━", phase.name);
+    Ok(())
+}
 
-        // Validate phase and emit warnings for potential issues
-        phase.validate_and_warn();
+/// Run setup phases in order, stopping at the first failure
+fn run_setup_phases(project: &Project, config: &Config, phases: &[ScriptPhase]) -> Result<()> {
+    for phase in phases {
+        let started = std::time::Instant::now();
+        events::emit(Event::PhaseStart {
+            phase: phase.name.clone(),
+        });
+
+        let result = run_setup_phase(project, config, phase);
+
+        events::emit(Event::PhaseEnd {
+            phase: phase.name.clone(),
+            duration_ms: events::millis(started.elapsed()),
+            success: matches!(result, Ok(true)),
+        });
+        result?;
+    }
 
-        // Check conditional execution
-        if !phase.should_execute(vm_name)? {
-            println!("⊘ Skipped (condition not met: {:?})", phase.when);
-            continue;
+    Ok(())
+}
+
+/// Scripts of a setup phase: inline script, script files, then files fetched from its repo
+fn load_phase_scripts(
+    project: &Project,
+    config: &Config,
+    phase: &ScriptPhase,
+) -> Result<Vec<PhaseScript>> {
+    let mut scripts = phase.get_scripts(project.root())?;
+
+    if let Some(repo) = &phase.repo {
+        let source = RepoSource::parse(repo)?;
+        status!("  Fetching scripts from {}#{}", source.url, source.git_ref);
+        for path in source.fetch_scripts(config.allow_unpinned)? {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| source.path.clone());
+            scripts.push((name, std::fs::read_to_string(&path)?));
         }
+    }
 
-        // Get all scripts for this phase
-        let scripts = match phase.get_scripts(project.root()) {
-            Ok(s) => s,
+    Ok(scripts)
+}
+
+/// Run a single setup phase.
+/// Returns whether every script succeeded (failures are tolerated with continue_on_error).
+fn run_setup_phase(project: &Project, config: &Config, phase: &ScriptPhase) -> Result<bool> {
+    let vm_name = project.template_name();
+
+    status!("\n━━━ Setup Phase: {} ━━━", phase.name);
+
+    // Validate phase and emit warnings for potential issues
+    phase.validate_and_warn();
+
+    // Check conditional execution
+    if !phase.should_execute(vm_name)? {
+        status!("⊘ Skipped (condition not met: {:?})", phase.when);
+        return Ok(true);
+    }
+
+    // Get all scripts for this phase
+    let scripts = match load_phase_scripts(project, config, phase) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("\n❌ Failed to load scripts for phase '{}'", phase.name);
+            eprintln!("   Error: {}", e);
+            if !phase.script_files.is_empty() {
+                eprintln!("   Script files:");
+                for file in &phase.script_files {
+                    eprintln!("   - {}", file);
+                }
+                eprintln!("\n   Hint: Check that script files exist and are readable");
+            }
+            if let Some(repo) = &phase.repo {
+                eprintln!("   Repo: {}", repo);
+            }
+
+            if phase.continue_on_error {
+                eprintln!("   ℹ Continuing due to continue_on_error=true");
+                return Ok(false);
+            } else {
+                return Err(e);
+            }
+        }
+    };
+
+    // Execute scripts in this phase
+    let mut success = true;
+    for (script_name, content) in scripts {
+        status!("  Running: {}", script_name);
+
+        // Create environment with phase-specific vars
+        let env_setup = config
+            .phase
+            .env_for(phase)
+            .iter()
+            .map(|(k, v)| format!("export {}='{}'", k, v.replace('\'', "'\\''")))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let script = phase.bash_script(&content);
+        let full_script = if env_setup.is_empty() {
+            script
+        } else {
+            format!("{}\n\n{}", env_setup, script)
+        };
+
+        // Capture output of non-interactive phases so only the tail is shown on failure.
+        // Verbose mode streams output prefixed with the phase name; interactive phases
+        // stream output directly since they need the terminal.
+        let capture = !config.verbose && !phase.interactive;
+        let mut captured_output = None;
+        let result = if capture {
+            runner::execute_script_captured(vm_name, &full_script, &script_name).and_then(
+                |captured| {
+                    if captured.success() {
+                        Ok(())
+                    } else {
+                        let err = match captured.exit_code {
+                            Some(code) => ClaudeVmError::CommandExitCode(code),
+                            None => ClaudeVmError::LimaExecution(
+                                "Script terminated by signal".to_string(),
+                            ),
+                        };
+                        captured_output = Some(captured.output);
+                        Err(err)
+                    }
+                },
+            )
+        } else if !phase.interactive && !config.no_prefix {
+            runner::execute_script_prefixed(vm_name, &full_script, &script_name, &phase.name)
+        } else {
+            runner::execute_script(vm_name, &full_script, &script_name)
+        };
+
+        match result {
+            Ok(_) => status!("  ✓ Completed: {}", script_name),
             Err(e) => {
-                eprintln!("\n❌ Failed to load scripts for phase '{}'", phase.name);
+                // Enhanced error message with context
+                eprintln!("\n❌ Setup phase '{}' failed", phase.name);
+                eprintln!("   Script: {}", script_name);
                 eprintln!("   Error: {}", e);
-                if !phase.script_files.is_empty() {
-                    eprintln!("   Script files:");
-                    for file in &phase.script_files {
-                        eprintln!("   - {}", file);
+
+                // Show the tail of the captured output
+                if let Some(ref output) = captured_output {
+                    print_failure_output(output, config.setup.failure_log_lines());
+                }
+
+                // Show condition if present
+                if let Some(ref condition) = phase.when {
+                    eprintln!("   Condition: {}", condition);
+                }
+
+                // Show script preview for inline scripts
+                if script_name.contains("-inline") {
+                    let preview = content.lines().take(3).collect::<Vec<_>>().join("\n");
+                    let lines = content.lines().count();
+                    eprintln!("   Script preview:");
+                    eprintln!("   {}", preview.replace('\n', "\n   "));
+                    if lines > 3 {
+                        eprintln!("   ... ({} more lines)", lines - 3);
                     }
-                    eprintln!("\n   Hint: Check that script files exist and are readable");
                 }
 
+                // Provide helpful hints
                 if phase.continue_on_error {
                     eprintln!("   ℹ Continuing due to continue_on_error=true");
-                    continue;
+                    success = false;
                 } else {
+                    eprintln!("\n   Hints:");
+                    eprintln!("   - Check if all required tools are available in the VM");
+                    eprintln!("   - Verify script syntax with: bash -n <script>");
+                    eprintln!("   - Add 'continue_on_error = true' to make this phase optional");
+                    eprintln!("   - Run 'claude-vm shell' to debug interactively");
                     return Err(e);
                 }
             }
+        }
+    }
+
+    Ok(success)
+}
+
+/// Print the trailing lines of a failed phase's captured output
+fn print_failure_output(output: &str, max_lines: usize) {
+    let (lines, omitted) = runner::tail_lines(output, max_lines);
+    if lines.is_empty() {
+        return;
+    }
+
+    eprintln!("   Output (last {} lines):", lines.len());
+    if omitted > 0 {
+        eprintln!(
+            "   ... ({} earlier lines omitted, use --verbose for full output)",
+            omitted
+        );
+    }
+    for line in lines {
+        eprintln!("   | {}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_describe_phase() {
+        let phase = ScriptPhase {
+            name: "tools".to_string(),
+            script: Some("echo hi".to_string()),
+            script_files: vec!["./setup.sh".to_string()],
+            when: Some("command -v docker".to_string()),
+            ..Default::default()
         };
+        assert_eq!(
+            describe_phase(&phase),
+            "tools (inline script; files: ./setup.sh), when: command -v docker"
+        );
 
-        // Execute scripts in this phase
-        for (script_name, content) in scripts {
-            println!("  Running: {}", script_name);
+        let phase = ScriptPhase {
+            name: "empty".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(describe_phase(&phase), "empty (no scripts)");
+    }
 
-            // Create environment with phase-specific vars
-            let env_setup = phase
-                .env
-                .iter()
-                .map(|(k, v)| format!("export {}='{}'", k, v.replace('\'', "'\\''")))
-                .collect::<Vec<_>>()
-                .join("\n");
+    #[test]
+    fn test_check_results_exit_behavior() {
+        let results = vec![
+            CheckResult {
+                name: "node is v20".to_string(),
+                failure: None,
+            },
+            CheckResult {
+                name: "docker works".to_string(),
+                failure: Some("Cannot connect to the Docker daemon\n".to_string()),
+            },
+        ];
+
+        // Passing checks never fail setup
+        assert!(check_results(&results[..1], false).is_ok());
+
+        let message = check_results(&results, false).unwrap_err().to_string();
+        assert!(message.contains("1 of 2 setup checks failed"));
+        assert!(message.contains("docker works"));
+        assert!(message.contains("Cannot connect to the Docker daemon"));
+        assert!(!message.contains("node is v20"));
+
+        // --soft-checks only warns
+        assert!(check_results(&results, true).is_ok());
+    }
 
-            let full_script = if env_setup.is_empty() {
-                content.clone()
-            } else {
-                format!("{}\n\n{}", env_setup, content)
+    #[test]
+    fn test_base_hash_tracks_vm_settings_only() {
+        let config = Config::default();
+        let mut resized = Config::default();
+        resized.vm.disk += 10;
+        let mut with_packages = Config::default();
+        with_packages.packages.system.push("jq".to_string());
+
+        assert_eq!(base_hash(&config), base_hash(&Config::default()));
+        assert_ne!(base_hash(&config), base_hash(&resized));
+        // Package changes are resumable: only the package steps rerun
+        assert_eq!(base_hash(&config), base_hash(&with_packages));
+    }
+
+    #[test]
+    fn test_setup_summary_formats() {
+        let summary = SetupSummary {
+            template: "claude-tpl_app_1234".to_string(),
+            project_root: "/work/app".to_string(),
+            capabilities: vec!["docker".to_string(), "node".to_string()],
+            disk: 20,
+            memory: 8,
+            cpus: 4,
+            duration_ms: 125_400,
+        };
+
+        let human = summary.human();
+        assert!(human.starts_with("Template ready for project: /work/app"));
+        assert!(human.contains("Capabilities: docker, node"));
+        assert!(human.contains("20GB disk, 8GB memory, 4 CPUs"));
+        assert!(human.contains("Build time:   2m 05s"));
+
+        let value: serde_json::Value = serde_json::from_str(&summary.json()).unwrap();
+        assert_eq!(value["template"], "claude-tpl_app_1234");
+        assert_eq!(value["capabilities"], serde_json::json!(["docker", "node"]));
+        assert_eq!(value["duration_ms"], 125_400);
+    }
+
+    #[test]
+    fn test_phase_input_is_stable() {
+        let project = Project::detect().unwrap();
+        let with_env = |keys: &mut dyn Iterator<Item = usize>| {
+            let mut config = Config::default();
+            let mut phase = ScriptPhase {
+                name: "deps".to_string(),
+                script: Some("make deps".to_string()),
+                ..Default::default()
             };
+            for i in keys {
+                config
+                    .phase
+                    .env
+                    .insert(format!("GLOBAL_{}", i), i.to_string());
+                phase.env.insert(format!("PHASE_{}", i), i.to_string());
+            }
+            phase_input(&project, &config, &phase)
+        };
 
-            match runner::execute_script(vm_name, &full_script, &script_name) {
-                Ok(_) => println!("  ✓ Completed: {}", script_name),
-                Err(e) => {
-                    // Enhanced error message with context
-                    eprintln!("\n❌ Setup phase '{}' failed", phase.name);
-                    eprintln!("   Script: {}", script_name);
-                    eprintln!("   Error: {}", e);
-
-                    // Show condition if present
-                    if let Some(ref condition) = phase.when {
-                        eprintln!("   Condition: {}", condition);
-                    }
+        // Env maps are hashed in key order, whatever their insertion order
+        assert_eq!(with_env(&mut (0..32)), with_env(&mut (0..32).rev()));
+        assert_ne!(with_env(&mut (0..32)), with_env(&mut (0..31)));
+    }
 
-                    // Show script preview for inline scripts
-                    if script_name.contains("-inline") {
-                        let preview = content.lines().take(3).collect::<Vec<_>>().join("\n");
-                        let lines = content.lines().count();
-                        eprintln!("   Script preview:");
-                        eprintln!("   {}", preview.replace('\n', "\n   "));
-                        if lines > 3 {
-                            eprintln!("   ... ({} more lines)", lines - 3);
-                        }
-                    }
+    #[test]
+    fn test_script_files_input_tracks_contents() {
+        let dir = TempDir::new().unwrap();
+        let script = dir.path().join("setup.sh");
+        let paths = vec![script.to_string_lossy().to_string()];
 
-                    // Provide helpful hints
-                    if phase.continue_on_error {
-                        eprintln!("   ℹ Continuing due to continue_on_error=true");
-                    } else {
-                        eprintln!("\n   Hints:");
-                        eprintln!("   - Check if all required tools are available in the VM");
-                        eprintln!("   - Verify script syntax with: bash -n <script>");
-                        eprintln!(
-                            "   - Add 'continue_on_error = true' to make this phase optional"
-                        );
-                        eprintln!("   - Run 'claude-vm shell' to debug interactively");
-                        return Err(e);
-                    }
-                }
+        let missing = script_files_input(&paths);
+        std::fs::write(&script, "echo one").unwrap();
+        let one = script_files_input(&paths);
+        std::fs::write(&script, "echo two").unwrap();
+
+        assert_ne!(missing, one);
+        assert_ne!(one, script_files_input(&paths));
+    }
+
+    #[test]
+    fn test_swap_script() {
+        let script = swap_script(4);
+        assert!(script.contains("fallocate -l 4G /swapfile"));
+        assert!(script.contains("count=$((4 * 1024))"));
+        assert!(script.contains("swapon /swapfile"));
+        assert!(script.contains("grep -q '^/swapfile ' /etc/fstab"));
+    }
+
+    #[test]
+    fn test_config_hash_tracks_config() {
+        let config = Config::default();
+        assert_eq!(config_hash(&config), config_hash(&Config::default()));
+        assert_eq!(config_hash(&config).len(), 32);
+
+        let mut changed = Config::default();
+        changed.tools.docker = true;
+        assert_ne!(config_hash(&config), config_hash(&changed));
+
+        // HashMap fields iterate in a random order per map
+        let with_env = |keys: &mut dyn Iterator<Item = usize>| {
+            let mut config = Config::default();
+            for i in keys {
+                config.phase.env.insert(format!("VAR_{}", i), i.to_string());
             }
-        }
+            config_hash(&config)
+        };
+        assert_eq!(with_env(&mut (0..32)), with_env(&mut (0..32).rev()));
     }
 
-    Ok(())
+    #[test]
+    fn test_dotfile_install_script() {
+        assert_eq!(
+            dotfile_install_script("/tmp/claude-vm-dotfile-0", ".config/git/config"),
+            "mkdir -p \"$(dirname \"$HOME\"/'.config/git/config')\" && \
+             mv -f '/tmp/claude-vm-dotfile-0' \"$HOME\"/'.config/git/config'"
+        );
+    }
+
+    #[test]
+    fn test_prepare_apt_cache_dir_creates_partial() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("apt-cache");
+
+        prepare_apt_cache_dir(&dir).unwrap();
+        assert!(dir.join("partial").is_dir());
+
+        // Idempotent on an existing cache
+        prepare_apt_cache_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prepare_apt_cache_dir_rejects_file() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("apt-cache");
+        std::fs::write(&dir, "not a directory").unwrap();
+
+        let err = prepare_apt_cache_dir(&dir).unwrap_err();
+        assert!(err.to_string().contains("not a directory"));
+
+        remove_apt_cache(&dir).unwrap();
+        prepare_apt_cache_dir(&dir).unwrap();
+        assert!(dir.is_dir());
+    }
+
+    #[test]
+    fn test_remove_apt_cache() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("apt-cache");
+        prepare_apt_cache_dir(&dir).unwrap();
+        std::fs::write(dir.join("pkg.deb"), "deb").unwrap();
+
+        remove_apt_cache(&dir).unwrap();
+        assert!(!dir.exists());
+
+        // Missing cache is not an error
+        remove_apt_cache(&dir).unwrap();
+    }
 }
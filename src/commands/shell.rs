@@ -1,16 +1,46 @@
 use crate::cli::ShellCmd;
-use crate::commands::helpers;
+use crate::commands::{agent_logs, helpers, shell_all};
 use crate::config::Config;
 use crate::error::{ClaudeVmError, Result};
 use crate::project::Project;
 use crate::scripts::runner;
+use crate::status;
 use crate::utils::env as env_utils;
 use crate::utils::shell as shell_utils;
-use crate::vm::session::VmSession;
+use crate::vm::limactl::LimaCtl;
+use crate::vm::mount::{self, GuestMountState, MountCheck};
+use std::collections::BTreeMap;
 
 pub fn execute(project: &Project, config: &Config, cmd: &ShellCmd) -> Result<()> {
+    // Reads from an already running VM: no session is created
+    if cmd.agent_logs {
+        return agent_logs::execute(project, cmd.lines, cmd.follow);
+    }
+    if cmd.all_running {
+        return shell_all::execute(project, &cmd.command, cmd.all_templates);
+    }
+    // Only assembles the environment: no VM is needed
+    if cmd.dump_env {
+        let cli_vars = env_utils::collect_env_sources(
+            &cmd.runtime.env,
+            &cmd.runtime.env_file,
+            &cmd.runtime.inherit_env,
+        )?;
+        for line in format_env(&session_env(config, cli_vars), cmd.show_values) {
+            println!("{}", line);
+        }
+        status!("Capability runtime scripts may export more variables when the session starts");
+        return Ok(());
+    }
+
+    LimaCtl::ensure_available()?;
+    let use_vm = cmd.runtime.use_vm.as_deref();
+
     // Ensure template exists (create if missing and user confirms)
-    helpers::ensure_template_exists(project, config)?;
+    // An existing VM does not need the project template
+    if use_vm.is_none() {
+        helpers::ensure_template_exists(project, config)?;
+    }
 
     // Resolve worktree if --worktree flag present
     if !cmd.runtime.worktree.is_empty() {
@@ -18,9 +48,9 @@ pub fn execute(project: &Project, config: &Config, cmd: &ShellCmd) -> Result<()>
         std::env::set_current_dir(&worktree_path)?;
     }
 
-    let is_interactive = cmd.command.is_empty();
+    let is_interactive = cmd.command.is_empty() && !cmd.show_mounts;
 
-    if !config.verbose {
+    if !config.verbose && use_vm.is_none() {
         if is_interactive {
             eprintln!("Starting ephemeral VM session for shell...");
         } else {
@@ -29,12 +59,7 @@ pub fn execute(project: &Project, config: &Config, cmd: &ShellCmd) -> Result<()>
     }
 
     // Create ephemeral session
-    let session = VmSession::new(
-        project,
-        config.verbose,
-        config.mount_conversations,
-        &config.mounts,
-    )?;
+    let session = helpers::open_session(project, config, use_vm)?;
     let _cleanup = session.ensure_cleanup();
 
     // Use current directory for workdir (not project root)
@@ -50,15 +75,26 @@ pub fn execute(project: &Project, config: &Config, cmd: &ShellCmd) -> Result<()>
 
     let workdir = Some(current_dir.as_path());
 
-    if is_interactive {
+    for copy in &cmd.copy {
+        status!("Copying {} to {}", copy.host.display(), copy.guest);
+        LimaCtl::copy(&copy.host, session.name(), &copy.guest)?;
+    }
+
+    if cmd.show_mounts {
+        show_mounts(session.name(), config)?;
+    } else if is_interactive {
         // Interactive shell mode
-        println!(
+        status!(
             "VM: {} | Dir: {} | Project: {}",
             session.name(),
             current_dir.display(),
             project.template_name()
         );
-        println!("Type 'exit' to stop and delete the VM");
+        if use_vm.is_some() {
+            status!("Type 'exit' to leave the shell (the VM keeps running)");
+        } else {
+            status!("Type 'exit' to stop and delete the VM");
+        }
 
         runner::execute_command_with_runtime_scripts(
             session.name(),
@@ -96,3 +132,259 @@ pub fn execute(project: &Project, config: &Config, cmd: &ShellCmd) -> Result<()>
 
     Ok(())
 }
+
+/// Print the expected mounts next to the guest's view of them
+fn show_mounts(vm_name: &str, config: &Config) -> Result<()> {
+    let expected = mount::compute_mounts(&mount::MountOptions::from_config(config))?;
+
+    let output = LimaCtl::shell_output(vm_name, "findmnt", &["-rn", "-o", "TARGET,OPTIONS"])?;
+    if !output.status.success() {
+        return Err(ClaudeVmError::LimaExecution(format!(
+            "Failed to read guest mounts: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let guest = mount::parse_findmnt(&String::from_utf8_lossy(&output.stdout));
+    let checks = mount::check_guest_mounts(&expected, &guest);
+
+    println!("Mounts in VM {}:", vm_name);
+    for check in checks.iter().filter(|c| !c.mount.shared) {
+        print_mount_check(check);
+    }
+    for tmpfs in &config.tmpfs_mounts {
+        println!(
+            "  - tmpfs -> {} (mounted at session start)",
+            tmpfs.path.display()
+        );
+    }
+    if checks.iter().any(|c| c.mount.shared) {
+        println!("Shared dependency mounts:");
+        for check in checks.iter().filter(|c| c.mount.shared) {
+            print_mount_check(check);
+        }
+    }
+
+    let problems = checks
+        .iter()
+        .filter(|c| c.state != GuestMountState::Ok)
+        .count();
+    if problems > 0 {
+        return Err(ClaudeVmError::CommandFailed(format!(
+            "{} mount(s) differ from the expected configuration",
+            problems
+        )));
+    }
+
+    Ok(())
+}
+
+fn print_mount_check(check: &MountCheck) {
+    let mode = if check.mount.writable { "rw" } else { "ro" };
+    let (mark, note) = match check.state {
+        GuestMountState::Ok => ("✓", ""),
+        GuestMountState::Missing => ("✗", "  (missing in guest)"),
+        GuestMountState::ReadOnly => ("✗", "  (read-only in guest)"),
+        GuestMountState::Writable => ("⚠", "  (writable in guest)"),
+    };
+    println!(
+        "  {} {} -> {} ({}){}",
+        mark,
+        check.mount.location.display(),
+        check.guest_path().display(),
+        mode,
+        note
+    );
+}
+
+/// Environment exported by the session entrypoint, as `(name, value, source)`.
+/// The entrypoint exports its own variables after the CLI ones, so they win;
+/// variables only set for a runtime script are listed after the session ones.
+fn session_env(
+    config: &Config,
+    cli_vars: BTreeMap<String, (String, String)>,
+) -> Vec<(String, String, String)> {
+    let mut session = cli_vars;
+    let mut script_only = Vec::new();
+    for var in runner::entrypoint_env(config) {
+        if var.script_only {
+            let source = format!("{}, runtime script only", var.source);
+            script_only.push((var.name, var.value, source));
+        } else {
+            session.insert(var.name, (var.value, var.source));
+        }
+    }
+    session
+        .into_iter()
+        .map(|(key, (value, source))| (key, value, source))
+        .chain(script_only)
+        .collect()
+}
+
+/// One `NAME=value  (source)` line per variable, values redacted unless `show_values`
+fn format_env(vars: &[(String, String, String)], show_values: bool) -> Vec<String> {
+    let entries: Vec<(String, &String)> = vars
+        .iter()
+        .map(|(key, value, source)| {
+            let value = if show_values {
+                value.as_str()
+            } else {
+                "<redacted>"
+            };
+            (format!("{}={}", key, value), source)
+        })
+        .collect();
+    let width = entries
+        .iter()
+        .map(|(entry, _)| entry.len())
+        .max()
+        .unwrap_or(0);
+    entries
+        .into_iter()
+        .map(|(entry, source)| format!("{:<width$}  ({})", entry, source, width = width))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_shell_copy_flag() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let fixture = dir.path().join("fixture.json");
+        let seed = dir.path().join("seed.sql");
+        std::fs::write(&fixture, "{}").unwrap();
+        std::fs::write(&seed, "select 1;").unwrap();
+
+        let cmd = ShellCmd::try_parse_from([
+            "shell".to_string(),
+            "--copy".to_string(),
+            format!("{}:/tmp/fixture.json", fixture.display()),
+            "--copy".to_string(),
+            format!("{}:/home/user/seed.sql", seed.display()),
+        ])
+        .unwrap();
+        let copies: Vec<(&std::path::Path, &str)> = cmd
+            .copy
+            .iter()
+            .map(|c| (c.host.as_path(), c.guest.as_str()))
+            .collect();
+        assert_eq!(
+            copies,
+            vec![
+                (fixture.as_path(), "/tmp/fixture.json"),
+                (seed.as_path(), "/home/user/seed.sql")
+            ]
+        );
+
+        let err = ShellCmd::try_parse_from([
+            "shell".to_string(),
+            "--copy".to_string(),
+            format!("{}:tmp/fixture.json", fixture.display()),
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("must be an absolute path"));
+
+        // The overlay would be layered over the mounts of a VM claude-vm does not manage
+        assert!(ShellCmd::try_parse_from(["shell", "--scratch", "--use-vm", "dev"]).is_err());
+
+        let err = ShellCmd::try_parse_from([
+            "shell".to_string(),
+            "--copy".to_string(),
+            format!("{}:/tmp/x", dir.path().join("missing").display()),
+        ])
+        .unwrap_err();
+        assert!(err.to_string().contains("not a file"));
+    }
+
+    #[test]
+    fn test_session_env_layers_network_vars() {
+        let mut config = Config::default();
+        config.security.network.enabled = true;
+        config.security.network.allowed_domains = vec!["github.com".to_string()];
+
+        let mut cli_vars = BTreeMap::new();
+        cli_vars.insert(
+            "API_TOKEN".to_string(),
+            ("s3cret".to_string(), "--env".to_string()),
+        );
+        cli_vars.insert(
+            "POLICY_MODE".to_string(),
+            ("denylist".to_string(), "--env".to_string()),
+        );
+
+        let vars = session_env(&config, cli_vars);
+        let source = |name: &str| {
+            vars.iter()
+                .find(|(key, _, _)| key == name)
+                .map(|(_, value, source)| (value.as_str(), source.as_str()))
+        };
+        assert_eq!(
+            source("ALLOWED_DOMAINS"),
+            Some(("github.com", "network isolation"))
+        );
+        // The entrypoint exports network variables last
+        assert_eq!(source("POLICY_MODE").unwrap().1, "network isolation");
+
+        let redacted = format_env(&vars, false);
+        assert!(redacted
+            .iter()
+            .any(|line| line.starts_with("API_TOKEN=<redacted>") && line.ends_with("(--env)")));
+        assert!(!redacted.iter().any(|line| line.contains("s3cret")));
+        assert!(format_env(&vars, true)
+            .iter()
+            .any(|line| line.starts_with("API_TOKEN=s3cret ")));
+    }
+
+    #[test]
+    fn test_session_env_without_network_isolation() {
+        let mut config = Config::default();
+        config.security.network.enabled = false;
+        assert!(session_env(&config, BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_session_env_includes_entrypoint_exports() {
+        let config: Config = toml::from_str(
+            r#"
+            [capabilities.node]
+            version = "20"
+
+            [phase.env]
+            SHARED = "all"
+
+            [[phase.runtime]]
+            name = "services"
+            script = "true"
+            source = true
+            env = { DB_URL = "postgres://db" }
+
+            [[phase.runtime]]
+            name = "seed"
+            script = "true"
+            "#,
+        )
+        .unwrap();
+        let config = config.with_fresh_home(true);
+
+        let vars = session_env(&config, BTreeMap::new());
+        let lines = format_env(&vars, true);
+        let line = |prefix: &str| lines.iter().find(|l| l.starts_with(prefix)).cloned();
+
+        assert!(line("DB_URL=postgres://db")
+            .unwrap()
+            .ends_with("(runtime phase 'services')"));
+        assert!(line("CLAUDE_CONFIG_DIR=")
+            .unwrap()
+            .ends_with("(--fresh-home)"));
+        assert!(line("CAPABILITY_CONFIG_VERSION=20")
+            .unwrap()
+            .ends_with("([capabilities.node], runtime script only)"));
+        // Only the unsourced phase's copy of the shared env is script-only
+        let shared: Vec<&String> = lines.iter().filter(|l| l.starts_with("SHARED=")).collect();
+        assert_eq!(shared.len(), 2);
+        assert!(shared[0].ends_with("(runtime phase 'services')"));
+        assert!(shared[1].ends_with("(runtime phase 'seed', runtime script only)"));
+    }
+}
@@ -0,0 +1,172 @@
+//! `shell --all-running`: run a one-off command in every running session VM.
+
+use crate::commands::batch::run_bounded;
+use crate::error::{ClaudeVmError, Result};
+use crate::project::Project;
+use crate::status;
+use crate::utils::shell as shell_utils;
+use crate::vm::limactl::{LimaCtl, VmInfo};
+
+/// VMs the command runs in at the same time
+const MAX_PARALLEL: usize = 4;
+
+/// Outcome of the command in one VM
+#[derive(Debug)]
+pub struct VmResult {
+    pub vm: String,
+    /// Exit code of the command, None when it could not be run
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr of the command, or the error running it
+    pub output: String,
+}
+
+impl VmResult {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Whether `name` is an ephemeral session VM (`<template>-<pid>`), not a template
+fn is_session_vm(name: &str) -> bool {
+    name.starts_with("claude-tpl_")
+        && name
+            .rsplit_once('-')
+            .is_some_and(|(_, pid)| !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Running session VMs, of one template or of all of them, in name order
+pub fn running_sessions(vms: Vec<VmInfo>, template: Option<&str>) -> Vec<String> {
+    let prefix = template.map(|t| format!("{}-", t));
+    let mut names: Vec<String> = vms
+        .into_iter()
+        .filter(|vm| vm.status == "Running" && is_session_vm(&vm.name))
+        .filter(|vm| prefix.as_ref().is_none_or(|p| vm.name.starts_with(p)))
+        .map(|vm| vm.name)
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn execute(project: &Project, command: &[String], all_templates: bool) -> Result<()> {
+    LimaCtl::ensure_available()?;
+    let template = (!all_templates).then(|| project.template_name());
+    let vms = running_sessions(LimaCtl::list()?, template);
+    if vms.is_empty() {
+        let scope = if all_templates {
+            "No claude-vm session VMs are running"
+        } else {
+            "No session VMs are running for this project (use --all-templates for every project)"
+        };
+        return Err(ClaudeVmError::CommandFailed(scope.to_string()));
+    }
+
+    let cmd_str = shell_utils::join_args(command);
+    status!("Running in {} VM(s): {}", vms.len(), cmd_str);
+    let results = run_bounded(vms, MAX_PARALLEL, |vm| run_in_vm(vm, &cmd_str));
+
+    for result in &results {
+        println!("==> {} <==", result.vm);
+        print!("{}", result.output);
+        if !result.output.is_empty() && !result.output.ends_with('\n') {
+            println!();
+        }
+    }
+    print_summary(&results);
+
+    let failed = results.iter().filter(|r| !r.success()).count();
+    if failed > 0 {
+        return Err(ClaudeVmError::CommandFailed(format!(
+            "Command failed in {} of {} VM(s)",
+            failed,
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+fn run_in_vm(vm: String, cmd_str: &str) -> VmResult {
+    match LimaCtl::shell_output(&vm, "bash", &["-lc", cmd_str]) {
+        Ok(output) => VmResult {
+            vm,
+            exit_code: output.status.code(),
+            output: format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        },
+        Err(e) => VmResult {
+            vm,
+            exit_code: None,
+            output: e.to_string(),
+        },
+    }
+}
+
+fn print_summary(results: &[VmResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.vm.len())
+        .max()
+        .unwrap_or(0)
+        .max("VM".len());
+
+    status!("\nSummary:");
+    status!("  {:<width$}  {:<6}  EXIT", "VM", "STATUS", width = width);
+    for result in results {
+        let status = if result.success() { "ok" } else { "failed" };
+        let exit = result
+            .exit_code
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        status!(
+            "  {:<width$}  {:<6}  {}",
+            result.vm,
+            status,
+            exit,
+            width = width
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm(name: &str, status: &str) -> VmInfo {
+        VmInfo {
+            name: name.to_string(),
+            status: status.to_string(),
+            memory: None,
+        }
+    }
+
+    #[test]
+    fn test_running_sessions() {
+        let vms = || {
+            vec![
+                vm("claude-tpl_app_1a2b3c4d-4242", "Running"),
+                vm("claude-tpl_app_1a2b3c4d-17", "Running"),
+                vm("claude-tpl_app_1a2b3c4d-99", "Stopped"),
+                // The template itself, running during setup
+                vm("claude-tpl_app_1a2b3c4d", "Running"),
+                vm("claude-tpl_my-api_5e6f7a8b-300", "Running"),
+                vm("default", "Running"),
+            ]
+        };
+
+        assert_eq!(
+            running_sessions(vms(), Some("claude-tpl_app_1a2b3c4d")),
+            vec!["claude-tpl_app_1a2b3c4d-17", "claude-tpl_app_1a2b3c4d-4242"]
+        );
+        assert_eq!(
+            running_sessions(vms(), None),
+            vec![
+                "claude-tpl_app_1a2b3c4d-17",
+                "claude-tpl_app_1a2b3c4d-4242",
+                "claude-tpl_my-api_5e6f7a8b-300"
+            ]
+        );
+        assert!(running_sessions(vms(), Some("claude-tpl_other_00000000")).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::cli::TemplateCommands;
+use crate::commands::config::{flatten_value, format_value};
+use crate::error::{ClaudeVmError, Result};
+use crate::vm::limactl::LimaCtl;
+use crate::vm::template::{self, TemplateMetadata};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Metadata fields that always differ between builds, shown in the header instead
+const BUILD_FIELDS: [&str; 2] = ["built_at", "project_root"];
+
+pub fn execute(command: &TemplateCommands) -> Result<()> {
+    match command {
+        TemplateCommands::Diff { left, right } => diff(left, right),
+    }
+}
+
+fn diff(left: &str, right: &str) -> Result<()> {
+    LimaCtl::ensure_available()?;
+    let left_metadata = template::try_read_metadata(left)?;
+    let right_metadata = template::try_read_metadata(right)?;
+
+    let (left_metadata, right_metadata) = match (left_metadata, right_metadata) {
+        (Some(l), Some(r)) => (l, r),
+        (l, r) => {
+            for (name, metadata) in [(left, &l), (right, &r)] {
+                if metadata.is_none() {
+                    eprintln!(
+                        "⚠ Template '{}' has no metadata (it was built by an older claude-vm version)",
+                        name
+                    );
+                }
+            }
+            eprintln!("   Rebuild it with 'claude-vm setup' from its project to record metadata.");
+            return Err(ClaudeVmError::CommandFailed(
+                "Cannot compare templates without metadata".to_string(),
+            ));
+        }
+    };
+
+    println!("Comparing templates:");
+    print_header("A", left, &left_metadata);
+    print_header("B", right, &right_metadata);
+    println!();
+
+    let diffs = diff_metadata(&left_metadata, &right_metadata);
+    if diffs.is_empty() {
+        println!("✓ No differences in template metadata");
+        return Ok(());
+    }
+
+    for field in &diffs {
+        println!("{}", field.path);
+        match (&field.left, &field.right) {
+            (Some(Value::Array(l)), Some(Value::Array(r))) => {
+                print_only_in("A", l, r);
+                print_only_in("B", r, l);
+                if l.iter().all(|v| r.contains(v)) && r.iter().all(|v| l.contains(v)) {
+                    println!("  same entries, different order:");
+                    println!("  A: {}", format_value(field.left.as_ref()));
+                    println!("  B: {}", format_value(field.right.as_ref()));
+                }
+            }
+            _ => {
+                println!("  A: {}", format_value(field.left.as_ref()));
+                println!("  B: {}", format_value(field.right.as_ref()));
+            }
+        }
+    }
+
+    println!(
+        "\n{} field{} differ",
+        diffs.len(),
+        if diffs.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(())
+}
+
+fn print_header(label: &str, name: &str, metadata: &TemplateMetadata) {
+    println!("  {}: {}", label, name);
+    println!(
+        "     built {} for {}",
+        metadata.built_at, metadata.project_root
+    );
+}
+
+/// Print entries of `list` missing from `other`
+fn print_only_in(label: &str, list: &[Value], other: &[Value]) {
+    let only: Vec<String> = list
+        .iter()
+        .filter(|v| !other.contains(v))
+        .map(|v| {
+            v.as_str()
+                .map(String::from)
+                .unwrap_or_else(|| v.to_string())
+        })
+        .collect();
+    if !only.is_empty() {
+        println!("  only in {}: {}", label, only.join(", "));
+    }
+}
+
+#[derive(Debug)]
+struct FieldDiff {
+    path: String,
+    left: Option<Value>,
+    right: Option<Value>,
+}
+
+/// Compare two templates' metadata field by field, ignoring build-specific fields
+fn diff_metadata(left: &TemplateMetadata, right: &TemplateMetadata) -> Vec<FieldDiff> {
+    let left = flatten_metadata(left);
+    let right = flatten_metadata(right);
+
+    let mut paths: Vec<&String> = left.keys().chain(right.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter(|path| !BUILD_FIELDS.contains(&path.as_str()))
+        .filter(|path| !is_unset(left.get(*path)) && !is_unset(right.get(*path)))
+        .filter(|path| left.get(*path) != right.get(*path))
+        .map(|path| FieldDiff {
+            path: path.clone(),
+            left: left.get(path).cloned(),
+            right: right.get(path).cloned(),
+        })
+        .collect()
+}
+
+/// Empty strings are fields older versions did not record (e.g. `base_image`):
+/// unknown rather than different
+fn is_unset(value: Option<&Value>) -> bool {
+    matches!(value, Some(Value::String(s)) if s.is_empty())
+}
+
+fn flatten_metadata(metadata: &TemplateMetadata) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    let value = serde_json::to_value(metadata).unwrap_or(Value::Null);
+    flatten_value("", &value, &mut fields);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> TemplateMetadata {
+        TemplateMetadata {
+            claude_vm_version: "0.7.0".to_string(),
+            built_at: "2026-01-01T00:00:00+00:00".to_string(),
+            project_root: "/work/a".to_string(),
+            capabilities: vec!["node".to_string()],
+            config_hash: "aaaa".to_string(),
+            disk: 20,
+            memory: 8,
+            cpus: 4,
+            base_image: "debian-13".to_string(),
+            packages: vec![],
+            flavor: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_ignores_build_fields() {
+        let left = metadata();
+        let mut right = metadata();
+        right.built_at = "2026-02-01T00:00:00+00:00".to_string();
+        right.project_root = "/work/b".to_string();
+
+        assert!(diff_metadata(&left, &right).is_empty());
+    }
+
+    #[test]
+    fn test_diff_lists_changed_fields() {
+        let left = metadata();
+        let mut right = metadata();
+        right.capabilities.push("docker".to_string());
+        right.config_hash = "bbbb".to_string();
+        right.packages = vec!["jq".to_string()];
+        right.memory = 16;
+
+        let paths: Vec<String> = diff_metadata(&left, &right)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["capabilities", "config_hash", "memory", "packages"]
+        );
+    }
+
+    #[test]
+    fn test_diff_with_older_metadata() {
+        // Metadata from older versions has no base image or packages recorded
+        let mut left = metadata();
+        left.base_image = String::new();
+        let mut right = metadata();
+        assert!(diff_metadata(&left, &right).is_empty());
+
+        right.memory = 16;
+        let paths: Vec<String> = diff_metadata(&left, &right)
+            .into_iter()
+            .map(|d| d.path)
+            .collect();
+        assert_eq!(paths, vec!["memory"]);
+    }
+}
@@ -1,13 +1,26 @@
+use crate::cli::AgentCmd;
+use crate::commands::agent;
 use crate::config::Config;
 use crate::error::Result;
 use crate::project::Project;
+use crate::vm::{limactl::LimaCtl, template};
 use crate::worktree::operations;
 use crate::worktree::validation::{check_git_version, check_submodules_and_warn};
+use clap::Parser;
 
 /// Execute the create worktree command
 ///
-/// Creates a new worktree for the specified branch, or resumes an existing one
-pub fn execute(config: &Config, project: &Project, branch: &str, base: Option<&str>) -> Result<()> {
+/// Creates a new worktree for the specified branch, or resumes an existing one.
+/// This only manages git: no VM or template is built unless `setup` is set.
+/// With `agent` (the Claude arguments), the agent then runs in the worktree.
+pub fn execute(
+    config: &Config,
+    project: &Project,
+    branch: &str,
+    base: Option<&str>,
+    setup: bool,
+    agent: Option<&[String]>,
+) -> Result<()> {
     let repo_root = project.root();
 
     // Validate git version supports worktrees
@@ -22,5 +35,113 @@ pub fn execute(config: &Config, project: &Project, branch: &str, base: Option<&s
     // Print user-facing message
     println!("{}", result.message(branch));
 
-    Ok(())
+    // Worktree management works without Lima; only report template state when we can
+    let template_exists =
+        LimaCtl::is_installed() && template::exists(project.template_name()).unwrap_or(false);
+
+    if setup && !template_exists {
+        println!("\nBuilding template for the worktree...");
+        std::env::set_current_dir(result.path())?;
+        let worktree_project = Project::detect()?;
+        let worktree_config = Config::load_with_main_repo(
+            worktree_project.root(),
+            worktree_project.main_repo_root(),
+        )?;
+        crate::commands::setup::execute(&worktree_project, &worktree_config, false)?;
+    } else if setup {
+        println!("\nTemplate already exists: {}", project.template_name());
+    }
+
+    let Some(claude_args) = agent else {
+        println!("\n{}", next_steps(branch, setup || template_exists));
+        return Ok(());
+    };
+
+    // Run the agent as if started from the worktree
+    std::env::set_current_dir(result.path())?;
+    let cmd = agent_cmd(claude_args);
+    let worktree_project = Project::detect()?;
+    let worktree_config = agent::load_config(&worktree_project, &cmd, config.verbose)?;
+    agent::execute(&worktree_project, &worktree_config, &cmd)
+}
+
+/// Agent command running Claude with `claude_args` and no other option
+fn agent_cmd(claude_args: &[String]) -> AgentCmd {
+    let args = ["agent", "--"]
+        .into_iter()
+        .map(String::from)
+        .chain(claude_args.iter().cloned());
+    AgentCmd::parse_from(args)
+}
+
+/// How to start working in the new worktree
+fn next_steps(branch: &str, template_ready: bool) -> String {
+    let mut steps = format!(
+        "To run Claude in this worktree:\n  claude-vm agent --worktree {}",
+        branch
+    );
+    if !template_ready {
+        steps.push_str(
+            "\n\nNo template exists for this project yet. It will be created on the first run,\n\
+             or build it now with 'claude-vm setup' (or 'worktree create --setup').",
+        );
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands, WorktreeCommands};
+
+    #[test]
+    fn test_next_steps() {
+        let ready = next_steps("feature", true);
+        assert!(ready.contains("claude-vm agent --worktree feature"));
+        assert!(!ready.contains("No template"));
+
+        let missing = next_steps("feature", false);
+        assert!(missing.contains("No template exists"));
+        assert!(missing.contains("--setup"));
+    }
+
+    #[test]
+    fn test_create_agent_flag() {
+        let cli = Cli::try_parse_from([
+            "claude-vm",
+            "worktree",
+            "create",
+            "feature",
+            "main",
+            "--agent",
+            "--",
+            "-p",
+            "fix the tests",
+        ])
+        .unwrap();
+        let Some(Commands::Worktree {
+            command:
+                WorktreeCommands::Create {
+                    branch,
+                    base,
+                    agent,
+                    claude_args,
+                    ..
+                },
+        }) = cli.command
+        else {
+            panic!("expected worktree create");
+        };
+        assert_eq!(branch, "feature");
+        assert_eq!(base.as_deref(), Some("main"));
+        assert!(agent);
+        assert_eq!(claude_args, vec!["-p", "fix the tests"]);
+        assert_eq!(agent_cmd(&claude_args).claude_args, claude_args);
+
+        // Claude arguments need --agent
+        assert!(
+            Cli::try_parse_from(["claude-vm", "worktree", "create", "feature", "--", "-p"])
+                .is_err()
+        );
+    }
 }
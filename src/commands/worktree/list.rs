@@ -1,7 +1,12 @@
 use crate::error::Result;
 use crate::worktree::{filter, operations, recovery, state, validation};
 
-pub fn execute(merged_base: Option<&str>, locked: bool, detached: bool) -> Result<()> {
+pub fn execute(
+    merged_base: Option<&str>,
+    default_base: Option<&str>,
+    locked: bool,
+    detached: bool,
+) -> Result<()> {
     // Validate git version
     validation::check_git_version()?;
 
@@ -12,8 +17,9 @@ pub fn execute(merged_base: Option<&str>, locked: bool, detached: bool) -> Resul
     // where skip(1) removes the wrong entry when main doesn't match filters
 
     // Get merged branches if needed (must live long enough for the iterator)
-    let merged_branches = if let Some(base) = merged_base {
-        Some(operations::list_merged_branches(base)?)
+    let merged_branches = if merged_base.is_some() {
+        let base = operations::resolve_merged_base(merged_base, default_base)?;
+        Some(operations::list_merged_branches(&base)?)
     } else {
         None
     };
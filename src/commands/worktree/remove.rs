@@ -6,6 +6,7 @@ use std::io::{self, Write};
 pub fn execute(
     branches: Option<&[String]>,
     merged_base: Option<&str>,
+    default_base: Option<&str>,
     yes: bool,
     dry_run: bool,
     locked: bool,
@@ -44,7 +45,7 @@ pub fn execute(
         select_by_explicit_branches(&worktrees, branch_names)?
     } else {
         // Merged branch mode (from clean.rs)
-        select_by_merged_status(&worktrees, merged_base, locked)?
+        select_by_merged_status(&worktrees, merged_base, default_base, locked)?
     };
 
     // If no worktrees to remove, exit early
@@ -98,10 +99,13 @@ fn select_by_explicit_branches(
 
     // Report missing branches
     if !missing.is_empty() {
-        eprintln!("Warning: The following branches have no worktree:");
-        for branch in &missing {
-            eprintln!("  {}", branch);
-        }
+        crate::diagnostics::warn(
+            "worktree-missing",
+            format!(
+                "The following branches have no worktree:\n  {}",
+                missing.join("\n  ")
+            ),
+        );
         if to_remove.is_empty() {
             return Err(ClaudeVmError::Worktree(
                 "No valid worktrees found to remove".to_string(),
@@ -117,18 +121,11 @@ fn select_by_explicit_branches(
 fn select_by_merged_status(
     worktrees: &[WorktreeEntry],
     merged_base: Option<&str>,
+    default_base: Option<&str>,
     locked: bool,
 ) -> Result<Vec<(String, std::path::PathBuf)>> {
-    // Resolve the actual base branch
-    let merged_base = match merged_base {
-        Some(base) if !base.is_empty() => base.to_string(),
-        _ => {
-            // None or empty string - use current branch
-            let branch = crate::utils::git::get_current_branch()?;
-            println!("Using current branch: {}", branch);
-            branch
-        }
-    };
+    // Resolve the actual base branch (CLI, then [worktree] default_base, then current branch)
+    let merged_base = operations::resolve_merged_base(merged_base, default_base)?;
 
     // Get merged branches (this validates base branch exists)
     let merged_branches = operations::list_merged_branches(&merged_base)?;
@@ -238,7 +235,10 @@ fn execute_deletion(
                 if multi_worktree {
                     println!(" failed");
                 }
-                eprintln!("Warning: Failed to remove worktree '{}': {}", branch, e);
+                crate::diagnostics::warn(
+                    "worktree-remove",
+                    format!("Failed to remove worktree '{}': {}", branch, e),
+                );
             }
         }
     }
@@ -2,7 +2,7 @@ use crate::cli::flags::RuntimeFlags;
 use crate::cli::SetupCmd;
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -32,22 +32,70 @@ pub struct Config {
     #[serde(default)]
     pub context: ContextConfig,
 
+    /// Retention of Claude conversation folders on the host
+    #[serde(default)]
+    pub conversations: ConversationsConfig,
+
     #[serde(default)]
     pub security: SecurityConfig,
 
     #[serde(default)]
     pub mounts: Vec<MountEntry>,
 
+    /// What to mount as the project outside a git repository (default: cwd)
+    #[serde(default)]
+    pub project_root_fallback: Option<ProjectRootFallback>,
+
+    /// Large shared dependency caches mounted at stable VM paths, read-only by default
+    #[serde(default)]
+    pub shared_mounts: Vec<SharedMountEntry>,
+
+    /// Fail instead of warning when a mount's host path does not exist
+    #[serde(default)]
+    pub strict_mounts: bool,
+
     #[serde(default)]
     pub update_check: UpdateCheckSettings,
 
     #[serde(default)]
     pub worktree: crate::worktree::config::WorktreeConfig,
 
+    /// Git identity to use inside the VM (overrides host detection)
+    #[serde(default)]
+    pub git: GitIdentityConfig,
+
     /// Automatically create template if missing (default: false)
     #[serde(default)]
     pub auto_setup: bool,
 
+    /// Another config file merged before this one (relative to this file's directory)
+    #[serde(default)]
+    pub extends: Option<String>,
+
+    /// Command aliases: name -> arguments it expands to (e.g. `review = "agent --audit"`)
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// `config lint` settings
+    #[serde(default)]
+    pub lint: LintConfig,
+
+    /// Per-capability options (`[capabilities.<id>]`), passed to the capability's
+    /// scripts as `CAPABILITY_CONFIG_<KEY>` variables
+    #[serde(default, rename = "capabilities")]
+    pub capability_config: HashMap<String, toml::Value>,
+
+    /// Named config variants, selected with `--flavor <name>`.
+    /// Each flavor is merged on top of the project config and gets its own template.
+    #[serde(default)]
+    pub flavors: BTreeMap<String, Config>,
+
+    /// Named config overrides, selected with `--profile <name>`.
+    /// Unlike flavors, profiles share the project template, so they cannot
+    /// change the settings the template is built with (see `TEMPLATE_VM_KEYS`).
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Config>,
+
     /// Verbose mode - show verbose output including Lima logs (not stored in config file)
     #[serde(skip)]
     pub verbose: bool,
@@ -59,6 +107,55 @@ pub struct Config {
     /// Mount Claude conversation folder in VM (not stored in config file)
     #[serde(skip)]
     pub mount_conversations: bool,
+
+    /// Project whose conversation folder is mounted instead of the current one's
+    /// (not stored in config file)
+    #[serde(skip)]
+    pub conversation_project: Option<PathBuf>,
+
+    /// Mount the project read-only with a discardable overlay (not stored in config file)
+    #[serde(skip)]
+    pub scratch: bool,
+
+    /// Don't prefix setup script output with its capability or phase (not stored in config file)
+    #[serde(skip)]
+    pub no_prefix: bool,
+
+    /// Run the agent with a fresh, temporary config directory (not stored in config file)
+    #[serde(skip)]
+    pub fresh_home: bool,
+
+    /// Agent restarts allowed after a crash, 0 for the shell (not stored in config file)
+    #[serde(skip)]
+    pub agent_retries: u32,
+
+    /// Run the agent as the unprivileged guest user, without sudo (not stored in config file)
+    #[serde(skip)]
+    pub drop_privileges: bool,
+
+    /// Allow setup phase repos with a ref other than a commit hash (not stored in config file)
+    #[serde(skip)]
+    pub allow_unpinned: bool,
+
+    /// Keep a failed template and resume its setup from the checkpoints (not stored in config file)
+    #[serde(skip)]
+    pub resume_on_failure: bool,
+
+    /// Format of the report printed after setup (not stored in config file)
+    #[serde(skip)]
+    pub summary_format: crate::cli::SummaryFormat,
+
+    /// Raise the VM sizing to what enabled capabilities need (not stored in config file)
+    #[serde(skip)]
+    pub auto_size: bool,
+
+    /// Report failed `[[setup.checks]]` as warnings instead of failing (not stored in config file)
+    #[serde(skip)]
+    pub soft_checks: bool,
+
+    /// tmpfs mounts inside the VM (not stored in config file)
+    #[serde(skip)]
+    pub tmpfs_mounts: Vec<crate::vm::mount::TmpfsMount>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +168,22 @@ pub struct VmConfig {
 
     #[serde(default = "default_cpus")]
     pub cpus: u32,
+
+    /// Power the VM off after this many minutes without an agent process or terminal session
+    #[serde(default)]
+    pub idle_shutdown_mins: Option<u32>,
+
+    /// Size of a swap file provisioned in the guest during setup (GB)
+    #[serde(default)]
+    pub swap_gb: Option<u32>,
+
+    /// Maximum number of ephemeral VMs running at once for the project (0 = unlimited)
+    #[serde(default)]
+    pub max_ephemeral: Option<u32>,
+
+    /// Set the guest clock from the host's at session start when they drift apart
+    #[serde(default)]
+    pub sync_clock: Option<bool>,
 }
 
 impl Default for VmConfig {
@@ -79,10 +192,31 @@ impl Default for VmConfig {
             disk: default_disk(),
             memory: default_memory(),
             cpus: default_cpus(),
+            idle_shutdown_mins: None,
+            swap_gb: None,
+            max_ephemeral: None,
+            sync_clock: None,
         }
     }
 }
 
+/// VM settings applied when the template is built, which sessions cannot change
+pub const TEMPLATE_VM_KEYS: &[&str] = &["vm.disk", "vm.swap_gb"];
+
+impl VmConfig {
+    /// The `TEMPLATE_VM_KEYS` set to something other than their default
+    pub fn template_keys(&self) -> Vec<&'static str> {
+        let defaults = VmConfig::default();
+        let set = [self.disk != defaults.disk, self.swap_gb.is_some()];
+        TEMPLATE_VM_KEYS
+            .iter()
+            .zip(set)
+            .filter(|(_, set)| *set)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
+
 fn default_disk() -> u32 {
     20
 }
@@ -91,6 +225,9 @@ fn default_memory() -> u32 {
     8
 }
 
+/// Ephemeral VM limit when `vm.max_ephemeral` is not set
+pub const DEFAULT_MAX_EPHEMERAL: u32 = 5;
+
 fn default_cpus() -> u32 {
     4
 }
@@ -111,6 +248,43 @@ impl VmConfig {
             self.memory = 1;
         }
     }
+
+    /// Idle shutdown delay in minutes, if enabled (0 disables it)
+    pub fn idle_shutdown(&self) -> Option<u32> {
+        self.idle_shutdown_mins.filter(|mins| *mins > 0)
+    }
+
+    /// Guest swap size in GB, if enabled (0 disables it)
+    pub fn swap(&self) -> Option<u32> {
+        self.swap_gb.filter(|gb| *gb > 0)
+    }
+
+    /// Ephemeral VM limit, if any (0 disables it)
+    pub fn ephemeral_limit(&self) -> Option<u32> {
+        Some(self.max_ephemeral.unwrap_or(DEFAULT_MAX_EPHEMERAL)).filter(|max| *max > 0)
+    }
+
+    /// Whether a skewed guest clock is corrected at session start
+    pub fn sync_clock(&self) -> bool {
+        self.sync_clock.unwrap_or(false)
+    }
+
+    /// Validate configuration and return warnings (not errors - config is still usable)
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let Some(swap) = self.swap() {
+            if swap.saturating_mul(2) > self.disk {
+                warnings.push(format!(
+                    "vm.swap_gb ({} GB) uses more than half of the VM disk ({} GB). \
+                    Consider raising vm.disk or lowering vm.swap_gb.",
+                    swap, self.disk
+                ));
+            }
+        }
+
+        warnings
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -242,7 +416,52 @@ pub struct PackagesConfig {
     /// ```
     #[serde(default)]
     pub setup_script: Option<String>,
-    // Future extensions: npm, pip, cargo, etc.
+
+    /// Global npm packages, e.g. "typescript" or "lodash@4.17.21" (needs the node capability)
+    #[serde(default)]
+    pub npm: Vec<String>,
+
+    /// Python packages installed for the VM user, e.g. "requests==2.31.0" (needs the python capability)
+    #[serde(default)]
+    pub pip: Vec<String>,
+
+    /// Crates installed with `cargo install`, e.g. "ripgrep" or "cargo-nextest@0.9.70" (needs the rust capability)
+    #[serde(default)]
+    pub cargo: Vec<String>,
+}
+
+impl PackagesConfig {
+    /// Language package lists: (list name, capability installing its package manager, packages)
+    pub fn language_packages(&self) -> [(&'static str, &'static str, &[String]); 3] {
+        [
+            ("npm", "node", &self.npm),
+            ("pip", "python", &self.pip),
+            ("cargo", "rust", &self.cargo),
+        ]
+    }
+
+    /// Fail when language packages are listed without the capability installing their package manager
+    pub fn check_tools(&self, tools: &ToolsConfig) -> Result<()> {
+        let missing: Vec<String> = self
+            .language_packages()
+            .into_iter()
+            .filter(|(_, tool, packages)| !packages.is_empty() && !tools.is_enabled(tool))
+            .map(|(list, tool, _)| {
+                format!(
+                    "packages.{} needs the {} capability: add '{} = true' to [tools] or pass --{}",
+                    list, tool, tool, tool
+                )
+            })
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::error::ClaudeVmError::InvalidConfig(
+                missing.join("\n"),
+            ))
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -251,12 +470,220 @@ pub struct SetupConfig {
     pub scripts: Vec<String>,
     #[serde(default)]
     pub mounts: Vec<MountEntry>,
+
+    /// Trailing output lines shown when a setup phase fails (--verbose shows everything)
+    #[serde(default)]
+    pub failure_log_lines: Option<usize>,
+
+    /// Keep downloaded .deb files in a host cache (~/.claude-vm/apt-cache) across rebuilds
+    #[serde(default)]
+    pub apt_cache: bool,
+
+    /// Capabilities whose setup hooks (host_setup, vm_setup, verify, vm_runtime)
+    /// are skipped; their packages and forwards are still installed. "*" skips all
+    /// but network-isolation, whose hooks enforce the network policy.
+    #[serde(default)]
+    pub skip_capability_phases: Vec<String>,
+
+    /// Commands run in the template once setup completes, each expected to exit 0
+    #[serde(default)]
+    pub checks: Vec<SetupCheck>,
+
+    /// Host file receiving the full setup output, timestamped (~ and ./ are expanded)
+    #[serde(default)]
+    pub build_log: Option<String>,
+
+    /// What happens to the previous build log: overwritten (default) or rotated
+    #[serde(default)]
+    pub build_log_mode: Option<BuildLogMode>,
+}
+
+/// A `[[setup.checks]]` entry: a named assertion about the built template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetupCheck {
+    pub name: String,
+    pub command: String,
+}
+
+/// What a new build does with the previous build log
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildLogMode {
+    /// Replace the previous log
+    #[default]
+    Overwrite,
+    /// Keep the previous logs as `<path>.1`, `<path>.2`, ...
+    Rotate,
+}
+
+/// Default number of trailing output lines shown for a failed setup phase
+pub const DEFAULT_FAILURE_LOG_LINES: usize = 50;
+
+impl SetupConfig {
+    /// Number of trailing lines to show for a failed setup phase
+    pub fn failure_log_lines(&self) -> usize {
+        self.failure_log_lines.unwrap_or(DEFAULT_FAILURE_LOG_LINES)
+    }
+
+    /// Build log file, relative paths resolved from the project root
+    pub fn build_log_path(&self, project_root: &Path) -> Option<PathBuf> {
+        self.build_log.as_ref().map(|path| {
+            let path =
+                crate::utils::path::expand_tilde(path).unwrap_or_else(|| PathBuf::from(path));
+            project_root.join(path)
+        })
+    }
+
+    /// Whether the setup hooks of capability `id` are skipped
+    pub fn skips_capability_phases(&self, id: &str) -> bool {
+        self.skip_capability_phases
+            .iter()
+            .any(|skipped| skipped == id || (skipped == "*" && id != "network-isolation"))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RuntimeConfig {
     #[serde(default)]
     pub scripts: Vec<String>,
+
+    /// What to do when a runtime script file is missing.
+    /// Unset keeps the defaults: warn for `scripts`, error for `[[phase.runtime]]`.
+    #[serde(default)]
+    pub on_missing_script: Option<MissingScriptPolicy>,
+
+    /// Host dotfiles copied into the VM user's home during setup.
+    /// Format: "host_path" or "host_path:vm_path" (vm_path relative to the VM home)
+    /// Example: "~/.bashrc.vm:~/.bashrc"
+    #[serde(default)]
+    pub dotfiles: Vec<String>,
+}
+
+impl RuntimeConfig {
+    /// Parse the configured dotfiles, returning the ones ready to copy and warnings
+    /// for invalid or missing entries (which are skipped)
+    pub fn dotfiles(&self, base_path: &Path) -> (Vec<Dotfile>, Vec<String>) {
+        let mut dotfiles = Vec::new();
+        let mut warnings = Vec::new();
+
+        for spec in &self.dotfiles {
+            match Dotfile::parse(spec, base_path) {
+                Ok(dotfile) if !dotfile.source.exists() => warnings.push(format!(
+                    "Dotfile not found, skipping: {}",
+                    dotfile.source.display()
+                )),
+                Ok(dotfile) if !dotfile.source.is_file() => warnings.push(format!(
+                    "Dotfile is not a regular file, skipping: {}",
+                    dotfile.source.display()
+                )),
+                Ok(dotfile) => dotfiles.push(dotfile),
+                Err(e) => warnings.push(format!("Invalid dotfile '{}', skipping: {}", spec, e)),
+            }
+        }
+
+        (dotfiles, warnings)
+    }
+}
+
+/// Home entries used by the agent and claude-vm, never overwritten by dotfiles
+const PROTECTED_HOME_ENTRIES: &[&str] = &[".claude", ".claude.json", ".claude-vm"];
+
+/// A host file copied into the VM user's home
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dotfile {
+    /// File on the host
+    pub source: PathBuf,
+    /// Destination relative to the VM user's home
+    pub target: String,
+}
+
+impl Dotfile {
+    /// Parse "host_path[:vm_path]". The VM path defaults to the host file name.
+    pub fn parse(spec: &str, base_path: &Path) -> std::result::Result<Self, String> {
+        let (source, target) = match spec.split_once(':') {
+            Some((source, target)) => (source, Some(target)),
+            None => (spec, None),
+        };
+        if source.is_empty() {
+            return Err("missing host path".to_string());
+        }
+
+        let source = ScriptPhase::resolve_path(source, base_path).map_err(|e| e.to_string())?;
+        let target = match target {
+            Some(target) => target.to_string(),
+            None => source
+                .file_name()
+                .and_then(|n| n.to_str())
+                .ok_or_else(|| "host path has no file name".to_string())?
+                .to_string(),
+        };
+
+        let relative = target
+            .strip_prefix("~/")
+            .unwrap_or(&target)
+            .trim_start_matches("./");
+        let path = Path::new(relative);
+        if relative.is_empty()
+            || target.starts_with('/')
+            || target.starts_with('~') && !target.starts_with("~/")
+            || !path
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            return Err(format!(
+                "VM path '{}' must be a file inside the VM home (e.g. ~/.bashrc)",
+                target
+            ));
+        }
+
+        let first = path
+            .components()
+            .next()
+            .and_then(|c| c.as_os_str().to_str())
+            .unwrap_or_default();
+        if PROTECTED_HOME_ENTRIES.contains(&first) {
+            return Err(format!(
+                "VM path '{}' would overwrite ~/{}, which claude-vm manages",
+                target, first
+            ));
+        }
+
+        Ok(Self {
+            source,
+            target: relative.to_string(),
+        })
+    }
+}
+
+/// What to mount as the project when not in a git repository
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectRootFallback {
+    /// Mount the current directory
+    #[default]
+    Cwd,
+    /// Mount nothing automatically: only explicit mounts are available
+    None,
+    /// Ask before mounting the current directory
+    Prompt,
+}
+
+impl ProjectRootFallback {
+    /// Whether the current directory is mounted outside a git repository
+    /// (with `Prompt`, the user confirmed before the session started)
+    pub fn mounts_cwd(self) -> bool {
+        self != ProjectRootFallback::None
+    }
+}
+
+/// How to handle a runtime script file that does not exist
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingScriptPolicy {
+    /// Print a warning and skip the script
+    Warn,
+    /// Fail before the session starts
+    Error,
 }
 
 /// A phase of script execution with metadata and control options
@@ -292,12 +719,50 @@ pub struct ScriptPhase {
     /// When false (default), the script runs with 'bash' in a subprocess (isolated)
     #[serde(default)]
     pub source: bool,
+
+    /// Phase needs a terminal (e.g. prompts for input)
+    /// Interactive setup phases stream output directly instead of capturing it
+    #[serde(default)]
+    pub interactive: bool,
+
+    /// Program running the phase scripts, with optional arguments (default: bash)
+    /// Example: "sh" or "python3 -u"
+    #[serde(default)]
+    pub interpreter: Option<String>,
+
+    /// Scripts fetched from a remote git repository (setup phases only)
+    /// Format: "<url>#<ref>:<path>", where path is a script or a directory of scripts
+    #[serde(default)]
+    pub repo: Option<String>,
+
+    /// Kill each script of the phase after this many seconds (runtime phases only)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
 }
 
+/// Heredoc delimiter wrapping scripts run with a non-bash interpreter
+const INTERPRETER_HEREDOC: &str = "CLAUDE_VM_PHASE_SCRIPT";
+
+/// A phase script as (name, content)
+pub type PhaseScript = (String, String);
+
 impl ScriptPhase {
     /// Get all script contents for this phase (inline + files)
     pub fn get_scripts(&self, base_path: &Path) -> Result<Vec<(String, String)>> {
+        let (scripts, missing) = self.get_available_scripts(base_path)?;
+        match missing.into_iter().next() {
+            Some(path) => Err(crate::error::ClaudeVmError::ScriptNotFound(path)),
+            None => Ok(scripts),
+        }
+    }
+
+    /// Get the scripts whose files exist, along with the paths of missing script files
+    pub fn get_available_scripts(
+        &self,
+        base_path: &Path,
+    ) -> Result<(Vec<PhaseScript>, Vec<PathBuf>)> {
         let mut scripts = Vec::new();
+        let mut missing = Vec::new();
 
         // Inline script first (if present)
         if let Some(content) = &self.script {
@@ -309,7 +774,8 @@ impl ScriptPhase {
         for (i, file_path) in self.script_files.iter().enumerate() {
             let path = Self::resolve_path(file_path, base_path)?;
             if !path.exists() {
-                return Err(crate::error::ClaudeVmError::ScriptNotFound(path));
+                missing.push(path);
+                continue;
             }
             let content = std::fs::read_to_string(&path)?;
             let name = path
@@ -320,11 +786,11 @@ impl ScriptPhase {
             scripts.push((name, content));
         }
 
-        Ok(scripts)
+        Ok((scripts, missing))
     }
 
     /// Resolve file path (handle ~, relative paths)
-    fn resolve_path(file: &str, base_path: &Path) -> Result<PathBuf> {
+    pub(crate) fn resolve_path(file: &str, base_path: &Path) -> Result<PathBuf> {
         let path = if file.starts_with('~') {
             crate::utils::path::expand_tilde(file).unwrap_or_else(|| PathBuf::from(file))
         } else if file.starts_with("./") || file.starts_with("../") {
@@ -354,6 +820,86 @@ impl ScriptPhase {
         }
     }
 
+    /// Interpreter command for this phase
+    pub fn interpreter(&self) -> &str {
+        self.interpreter.as_deref().unwrap_or("bash").trim()
+    }
+
+    fn uses_bash(&self) -> bool {
+        self.interpreter() == "bash"
+    }
+
+    /// Validate settings that make the phase unusable
+    pub fn validate(&self) -> Result<()> {
+        if self.interpreter().is_empty() {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Phase '{}' has an empty interpreter",
+                self.name
+            )));
+        }
+        if self.source && !self.uses_bash() {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Phase '{}' uses source=true with interpreter '{}'. \
+                Sourcing is only supported with bash.",
+                self.name,
+                self.interpreter()
+            )));
+        }
+        if let Some(repo) = &self.repo {
+            crate::scripts::remote::RepoSource::parse(repo)?;
+        }
+        if self.timeout_secs == Some(0) {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Phase '{}' has timeout_secs = 0. Use a positive number of seconds, \
+                or remove it for no limit.",
+                self.name
+            )));
+        }
+        if self.source && self.timeout_secs.is_some() {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Phase '{}' uses source=true with timeout_secs. \
+                A sourced script runs in the session shell and cannot be killed on its own.",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Script content to run with bash for this phase.
+    ///
+    /// Scripts for other interpreters are wrapped: the content is written to a temp
+    /// file through a heredoc and run with the interpreter, after checking it exists.
+    pub fn bash_script(&self, content: &str) -> String {
+        if self.uses_bash() {
+            return content.to_string();
+        }
+
+        let words: Vec<&str> = self.interpreter().split_whitespace().collect();
+        let program = crate::utils::shell::escape(words[0]);
+        let command = crate::utils::shell::join_args(&words);
+        let newline = if content.ends_with('\n') { "" } else { "\n" };
+
+        format!(
+            "if ! command -v {program} >/dev/null 2>&1; then\n\
+             \x20 echo \"Interpreter {program} not found in VM (phase '{name}')\" >&2\n\
+             \x20 exit 127\n\
+             fi\n\
+             script=$(mktemp)\n\
+             cat > \"$script\" <<'{delim}'\n\
+             {content}{newline}{delim}\n\
+             {command} \"$script\"\n\
+             status=$?\n\
+             rm -f \"$script\"\n\
+             exit $status\n",
+            program = program,
+            name = self.name.replace(['\'', '"', '$', '`', '\\'], ""),
+            delim = INTERPRETER_HEREDOC,
+            content = content,
+            newline = newline,
+            command = command,
+        )
+    }
+
     /// Check if a script has a shebang line
     fn has_shebang(content: &str) -> bool {
         content.trim_start().starts_with("#!")
@@ -366,29 +912,39 @@ impl ScriptPhase {
             // Check inline script
             if let Some(content) = &self.script {
                 if Self::has_shebang(content) {
-                    eprintln!(
-                        "⚠ Warning: Phase '{}' uses source=true with a shebang line",
-                        self.name
+                    crate::diagnostics::warn(
+                        "phase-source-shebang",
+                        format!(
+                            "Phase '{}' uses source=true with a shebang line\n   \
+                             When sourcing, the shebang is ignored and the script runs in the current bash shell\n   \
+                             Consider removing the shebang or setting source=false",
+                            self.name
+                        ),
                     );
-                    eprintln!("   When sourcing, the shebang is ignored and the script runs in the current bash shell");
-                    eprintln!("   Consider removing the shebang or setting source=false");
                 }
             }
         }
 
         // Warn if phase has no scripts at all
-        if self.script.is_none() && self.script_files.is_empty() {
-            eprintln!(
-                "⚠ Warning: Phase '{}' has no script or script_files defined",
-                self.name
+        if self.script.is_none() && self.script_files.is_empty() && self.repo.is_none() {
+            crate::diagnostics::warn(
+                "phase-empty",
+                format!(
+                    "Phase '{}' has no script or script_files defined\n   This phase will do nothing",
+                    self.name
+                ),
             );
-            eprintln!("   This phase will do nothing");
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PhaseConfig {
+    /// Setup phases run first, right after the template VM starts
+    /// (before base packages and capabilities are installed)
+    #[serde(default)]
+    pub before_all: Vec<ScriptPhase>,
+
     /// Setup phases (run during template creation)
     #[serde(default)]
     pub setup: Vec<ScriptPhase>,
@@ -396,6 +952,37 @@ pub struct PhaseConfig {
     /// Runtime phases (run before each session)
     #[serde(default)]
     pub runtime: Vec<ScriptPhase>,
+
+    /// Environment variables shared by every phase (a phase's own `env` wins)
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl PhaseConfig {
+    /// Environment of a phase: the shared `[phase.env]` overridden by the phase's `env`
+    pub fn env_for(&self, phase: &ScriptPhase) -> HashMap<String, String> {
+        let mut env = self.env.clone();
+        env.extend(phase.env.clone());
+        env
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LintConfig {
+    /// Lint codes not reported by `config lint` (e.g. "CVML010")
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ConversationsConfig {
+    /// Prune inactive conversation folders before each agent session
+    #[serde(default)]
+    pub auto_prune: bool,
+
+    /// With auto_prune, prune folders without activity for this many days (required)
+    #[serde(default)]
+    pub retention_days: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -407,19 +994,142 @@ pub struct ContextConfig {
     /// Path to a file containing instructions for Claude
     #[serde(default)]
     pub instructions_file: String,
+
+    /// Files containing instructions for Claude, concatenated in order after
+    /// `instructions_file` (separated by a blank line)
+    #[serde(default)]
+    pub instructions_files: Vec<String>,
+
+    /// Maximum size of the generated context in bytes.
+    /// Runtime script results are truncated first, then user instructions.
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+
+    /// Append the project's README.md to the context
+    #[serde(default)]
+    pub include_readme: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DefaultsConfig {
-    #[serde(default = "default_claude_args")]
-    pub claude_args: Vec<String>,
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GitIdentityConfig {
+    /// Value for `git config user.name` in the VM
+    #[serde(default)]
+    pub user_name: Option<String>,
+
+    /// Value for `git config user.email` in the VM
+    #[serde(default)]
+    pub user_email: Option<String>,
+
+    /// Value for `git config user.signingkey` in the VM (enables commit signing)
+    #[serde(default)]
+    pub signing_key: Option<String>,
+
+    /// Forward the host's HTTPS credentials for the project's remotes to each session
+    #[serde(default)]
+    pub forward_credentials: Option<bool>,
 }
 
-impl Default for DefaultsConfig {
-    fn default() -> Self {
-        Self {
-            claude_args: default_claude_args(),
+impl GitIdentityConfig {
+    /// Whether any identity field is set
+    pub fn is_configured(&self) -> bool {
+        self.user_name.is_some() || self.user_email.is_some() || self.signing_key.is_some()
+    }
+
+    /// Whether host git credentials are forwarded to sessions
+    pub fn forwards_credentials(&self) -> bool {
+        self.forward_credentials.unwrap_or(false)
+    }
+
+    /// Validate configuration and return warnings (not errors - config is still usable)
+    pub fn validate(&self, tools: &ToolsConfig) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if let Some(email) = &self.user_email {
+            if !is_valid_email(email) {
+                warnings.push(format!(
+                    "git.user_email '{}' does not look like an email address (expected name@domain)",
+                    email
+                ));
+            }
+        }
+
+        if self.signing_key.is_some() && !tools.gpg {
+            warnings.push(
+                "git.signing_key is set but the gpg capability is not enabled. \
+                Commit signing will fail in the VM. Enable it with 'gpg = true' in [tools]."
+                    .to_string(),
+            );
         }
+
+        warnings
+    }
+
+    /// Shell commands applying the configured identity to the VM's global git config
+    pub fn to_shell_commands(&self) -> String {
+        use crate::utils::shell::escape;
+
+        let mut commands = String::new();
+        if let Some(name) = &self.user_name {
+            commands.push_str(&format!("git config --global user.name {}\n", escape(name)));
+        }
+        if let Some(email) = &self.user_email {
+            commands.push_str(&format!(
+                "git config --global user.email {}\n",
+                escape(email)
+            ));
+        }
+        if let Some(key) = &self.signing_key {
+            commands.push_str(&format!(
+                "git config --global user.signingkey {}\n",
+                escape(key)
+            ));
+            commands.push_str("git config --global commit.gpgsign true\n");
+        }
+        commands
+    }
+}
+
+/// Minimal email shape check: exactly one '@', non-empty local part, dotted domain
+fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && !email.contains(char::is_whitespace)
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DefaultsConfig {
+    /// Arguments passed to the agent on every run (`agent_args` is accepted as an alias).
+    /// `None` means not set; an explicit empty list clears the built-in default.
+    #[serde(default, alias = "agent_args")]
+    pub claude_args: Option<Vec<String>>,
+
+    /// Restart the agent in the same VM this many times when it crashes
+    #[serde(default)]
+    pub retry_on_crash: Option<u32>,
+
+    /// Reuse the VM sizing of the last successful agent run as defaults
+    /// for `agent` and `shell` (command-line flags still win)
+    #[serde(default)]
+    pub remember_runtime: Option<bool>,
+}
+
+impl DefaultsConfig {
+    /// Effective arguments, falling back to the built-in default when unset
+    pub fn claude_args(&self) -> Vec<String> {
+        self.claude_args.clone().unwrap_or_else(default_claude_args)
+    }
+
+    /// Whether the sizing of the last successful agent run is remembered
+    pub fn remembers_runtime(&self) -> bool {
+        self.remember_runtime.unwrap_or(false)
     }
 }
 
@@ -463,6 +1173,22 @@ pub struct NetworkIsolationConfig {
     #[serde(default)]
     pub bypass_domains: Vec<String>,
 
+    /// File with additional allowed domains, one per line (`#` starts a comment)
+    #[serde(default)]
+    pub allowed_domains_file: Option<String>,
+
+    /// File with additional blocked domains, one per line (`#` starts a comment)
+    #[serde(default)]
+    pub blocked_domains_file: Option<String>,
+
+    /// File with additional bypass domains, one per line (`#` starts a comment)
+    #[serde(default)]
+    pub bypass_domains_file: Option<String>,
+
+    /// Size in MB at which the proxy log is rotated inside the VM (default: 10, 0 disables rotation)
+    #[serde(default)]
+    pub max_log_mb: Option<u32>,
+
     /// Enable network filtering
     #[serde(default)]
     pub enabled: bool,
@@ -478,14 +1204,34 @@ impl Default for NetworkIsolationConfig {
             allowed_domains: vec![],
             blocked_domains: vec![],
             bypass_domains: vec![],
+            allowed_domains_file: None,
+            blocked_domains_file: None,
+            bypass_domains_file: None,
+            max_log_mb: None,
             enabled: false, // Opt-in for backward compatibility
         }
     }
 }
 
+/// Default size at which the proxy log is rotated
+const DEFAULT_MAX_LOG_MB: u32 = 10;
+
 impl NetworkIsolationConfig {
+    /// Proxy log rotation size in MB (0 means the log is never rotated)
+    pub fn max_log_mb(&self) -> u32 {
+        self.max_log_mb.unwrap_or(DEFAULT_MAX_LOG_MB)
+    }
+
     /// Validate configuration and return warnings (not errors - config is still usable)
     pub fn validate(&self) -> Vec<String> {
+        self.diagnostics()
+            .into_iter()
+            .map(|(_, warning)| warning)
+            .collect()
+    }
+
+    /// Warnings with their diagnostic code (see [`crate::diagnostics`])
+    pub fn diagnostics(&self) -> Vec<(&'static str, String)> {
         let mut warnings = Vec::new();
 
         // Skip validation if network isolation is disabled
@@ -495,11 +1241,12 @@ impl NetworkIsolationConfig {
 
         // 1. Check for empty allowlist in allowlist mode
         if self.mode == PolicyMode::Allowlist && self.allowed_domains.is_empty() {
-            warnings.push(
+            warnings.push((
+                "empty-allowlist",
                 "Network isolation is in 'allowlist' mode but no domains are allowed. \
                 This will block ALL network access (only DNS and localhost allowed)."
                     .to_string(),
-            );
+            ));
         }
 
         // 2. Validate domain patterns
@@ -520,10 +1267,24 @@ impl NetworkIsolationConfig {
             .collect();
 
         for (domain, list_name) in all_domains {
+            if list_name == "bypass_domains" && crate::utils::cidr::is_cidr(domain) {
+                warnings.push((
+                    "invalid-domain",
+                    format!(
+                        "Invalid domain in bypass_domains: '{}' - CIDR ranges are only supported \
+                        in allowed_domains and blocked_domains",
+                        domain
+                    ),
+                ));
+                continue;
+            }
             if let Some(warning) = Self::validate_domain_pattern(domain) {
-                warnings.push(format!(
-                    "Invalid domain in {}: '{}' - {}",
-                    list_name, domain, warning
+                warnings.push((
+                    "invalid-domain",
+                    format!(
+                        "Invalid domain in {}: '{}' - {}",
+                        list_name, domain, warning
+                    ),
                 ));
             }
         }
@@ -531,10 +1292,13 @@ impl NetworkIsolationConfig {
         // 3. Check for conflicting domains
         for allowed in &self.allowed_domains {
             if self.blocked_domains.contains(allowed) {
-                warnings.push(format!(
-                    "Domain '{}' appears in both allowed_domains and blocked_domains. \
-                    It will be treated as ALLOWED (allowed_domains takes precedence).",
-                    allowed
+                warnings.push((
+                    "conflicting-domain",
+                    format!(
+                        "Domain '{}' appears in both allowed_domains and blocked_domains. \
+                        It will be treated as ALLOWED (allowed_domains takes precedence).",
+                        allowed
+                    ),
                 ));
             }
         }
@@ -542,12 +1306,18 @@ impl NetworkIsolationConfig {
         warnings
     }
 
-    /// Validate a single domain pattern
+    /// Validate a single domain pattern, or CIDR range (`10.1.0.0/16`)
     fn validate_domain_pattern(domain: &str) -> Option<String> {
         if domain.is_empty() {
             return Some("domain cannot be empty".to_string());
         }
 
+        if crate::utils::cidr::is_cidr(domain) {
+            return crate::utils::cidr::Cidr::parse(domain)
+                .err()
+                .map(|e| format!("invalid CIDR range: {}", e));
+        }
+
         // Check for invalid characters (only alphanumeric, dots, hyphens, underscores, asterisk allowed)
         if domain
             .chars()
@@ -606,7 +1376,73 @@ impl NetworkIsolationConfig {
             return Some("domain cannot start or end with a hyphen".to_string());
         }
 
-        None
+        None
+    }
+
+    /// Load the `*_domains_file` lists and append them to the inline lists.
+    /// Relative paths are resolved against `base_dir` (the config file's directory).
+    pub fn load_domain_files(&mut self, base_dir: &Path) -> Result<()> {
+        if let Some(file) = &self.allowed_domains_file {
+            let domains = Self::read_domain_file(&resolve_config_path(file, base_dir))?;
+            self.allowed_domains.extend(domains);
+        }
+        if let Some(file) = &self.blocked_domains_file {
+            let domains = Self::read_domain_file(&resolve_config_path(file, base_dir))?;
+            self.blocked_domains.extend(domains);
+        }
+        if let Some(file) = &self.bypass_domains_file {
+            let domains = Self::read_domain_file(&resolve_config_path(file, base_dir))?;
+            self.bypass_domains.extend(domains);
+        }
+        Ok(())
+    }
+
+    /// Read newline-delimited domains, skipping blank lines, `#` comments and invalid entries
+    fn read_domain_file(path: &Path) -> Result<Vec<String>> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Failed to read domain list {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let mut domains = Vec::new();
+        for (line_num, line) in contents.lines().enumerate() {
+            let domain = line.split('#').next().unwrap_or("").trim();
+            if domain.is_empty() {
+                continue;
+            }
+
+            if let Some(warning) = Self::validate_domain_pattern(domain) {
+                crate::diagnostics::warn(
+                    "invalid-domain",
+                    format!(
+                        "Skipping invalid domain '{}' at {}:{}: {}",
+                        domain,
+                        path.display(),
+                        line_num + 1,
+                        warning
+                    ),
+                );
+                continue;
+            }
+
+            domains.push(domain.to_string());
+        }
+
+        Ok(domains)
+    }
+}
+
+/// Resolve a path referenced from a config file: expands `~`, and resolves
+/// relative paths against the directory containing the config file
+fn resolve_config_path(path: &str, base_dir: &Path) -> PathBuf {
+    let expanded = crate::utils::path::expand_tilde(path).unwrap_or_else(|| PathBuf::from(path));
+    if expanded.is_absolute() {
+        expanded
+    } else {
+        base_dir.join(expanded)
     }
 }
 
@@ -629,10 +1465,83 @@ impl PolicyMode {
     }
 }
 
+/// Environment variable selecting the config profile (set by `--profile`)
+pub const PROFILE_ENV: &str = "CLAUDE_VM_PROFILE";
+
+/// Profile selected for this process (`--profile` or CLAUDE_VM_PROFILE), if any
+pub fn selected_profile() -> Option<String> {
+    std::env::var(PROFILE_ENV)
+        .ok()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+}
+
+/// Environment variable setting how often transient limactl failures are retried
+pub const LIMA_RETRIES_ENV: &str = "CLAUDE_VM_LIMA_RETRIES";
+
+/// Retries used when CLAUDE_VM_LIMA_RETRIES is not set
+const DEFAULT_LIMA_RETRIES: u32 = 3;
+
+/// How often a limactl command failing with a transient error is retried
+pub fn lima_retries() -> u32 {
+    std::env::var(LIMA_RETRIES_ENV)
+        .ok()
+        .and_then(|r| r.trim().parse().ok())
+        .unwrap_or(DEFAULT_LIMA_RETRIES)
+}
+
 fn default_policy_mode() -> PolicyMode {
     PolicyMode::Denylist
 }
 
+/// Report an unreadable context file: an error in tests, otherwise a warning
+/// asking whether to continue without it
+fn context_file_error(file_path: &Path, e: std::io::Error) -> Result<()> {
+    // In test mode, fail immediately without prompting
+    #[cfg(test)]
+    {
+        Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+            "Failed to read context file '{}': {}",
+            file_path.display(),
+            e
+        )))
+    }
+
+    #[cfg(not(test))]
+    {
+        use std::io::{self, Write};
+
+        // Print highly visible warning
+        eprintln!();
+        eprintln!("╔═══════════════════════════════════════════════════════╗");
+        eprintln!("║ ⚠️  WARNING: Failed to load context file            ║");
+        eprintln!("╚═══════════════════════════════════════════════════════╝");
+        eprintln!("  File: {}", file_path.display());
+        eprintln!("  Error: {}", e);
+        eprintln!();
+        eprintln!("  Claude will start WITHOUT the instructions in this file.");
+        eprintln!();
+
+        // Prompt user to continue
+        eprint!("Continue anyway? [y/N]: ");
+        io::stderr().flush().ok();
+
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(_) if input.trim().eq_ignore_ascii_case("y") => Ok(()),
+            Ok(_) => Err(crate::error::ClaudeVmError::InvalidConfig(
+                "Context file load failed and user chose to abort".to_string(),
+            )),
+            // If stdin is not available (non-interactive), abort
+            Err(_) => Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Failed to read context file '{}': {}",
+                file_path.display(),
+                e
+            ))),
+        }
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -644,12 +1553,36 @@ pub struct MountEntry {
     pub writable: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mount_point: Option<String>,
+    /// Allow a `./` location to resolve outside the project root
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_outside: bool,
 }
 
 fn default_writable() -> bool {
     true
 }
 
+/// A `[[shared_mounts]]` entry: like a mount, but read-only unless stated otherwise
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedMountEntry {
+    pub location: String,
+    #[serde(default)]
+    pub writable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mount_point: Option<String>,
+}
+
+impl SharedMountEntry {
+    pub fn to_mount_entry(&self) -> MountEntry {
+        MountEntry {
+            location: self.location.clone(),
+            writable: self.writable,
+            mount_point: self.mount_point.clone(),
+            allow_outside: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateCheckSettings {
     #[serde(default = "default_update_check_enabled")]
@@ -677,12 +1610,26 @@ fn default_update_check_interval() -> u64 {
 }
 
 impl Config {
+    /// What to mount as the project outside a git repository
+    pub fn project_root_fallback(&self) -> ProjectRootFallback {
+        self.project_root_fallback.unwrap_or_default()
+    }
+
+    /// Shared dependency mounts as mount entries, ready for `compute_mounts`
+    pub fn shared_mount_entries(&self) -> Vec<MountEntry> {
+        self.shared_mounts
+            .iter()
+            .map(SharedMountEntry::to_mount_entry)
+            .collect()
+    }
+
     /// Load configuration with precedence:
     /// 1. CLI flags (applied later via with_runtime_overrides or with_setup_overrides)
-    /// 2. Environment variables
-    /// 3. Project config (.claude-vm.toml in project root)
-    /// 4. Global config (~/.claude-vm.toml)
-    /// 5. Built-in defaults
+    /// 2. Selected profile (`--profile`)
+    /// 3. Environment variables
+    /// 4. Project config (.claude-vm.toml in project root)
+    /// 5. Global config (~/.claude-vm.toml)
+    /// 6. Built-in defaults
     ///
     /// For worktrees, this method checks both the worktree and main repo.
     /// Pass the worktree root as project_root and main repo root as main_repo_root.
@@ -718,27 +1665,288 @@ impl Config {
             config = config.merge(Self::from_file(&project_config)?);
         }
 
-        // 4. Apply environment variables
+        // 4. Apply the selected flavor
+        config = config.apply_flavor(crate::project::selected_flavor().as_deref())?;
+
+        // 5. Apply environment variables
         config = config.merge_env();
 
-        // 5. Apply CI-specific resource constraints
+        // 6. Apply the selected profile (only CLI flags take precedence)
+        config = config.apply_profile(selected_profile().as_deref())?;
+
+        // 7. Apply CI-specific resource constraints
         config.vm.apply_ci_constraints();
 
-        // 6. Resolve context file if needed
+        // 8. Resolve context file if needed
         config = config.resolve_context_file()?;
 
         Ok(config)
     }
 
-    /// Load configuration from a TOML file
+    /// `[aliases]` of the global, main repo and project configs (and the files they
+    /// `extends`), for expanding the command line before it is parsed. Unreadable
+    /// files are skipped here: loading the full configuration reports them.
+    pub fn load_aliases(
+        project_root: Option<&Path>,
+        main_repo_root: Option<&Path>,
+    ) -> BTreeMap<String, String> {
+        let files = [
+            home_dir().map(|home| home.join(".claude-vm.toml")),
+            main_repo_root
+                .filter(|root| Some(*root) != project_root)
+                .map(|root| root.join(".claude-vm.toml")),
+            project_root.map(|root| root.join(".claude-vm.toml")),
+        ];
+
+        let mut aliases = BTreeMap::new();
+        for path in files.into_iter().flatten() {
+            aliases.extend(Self::file_aliases(&path, &mut Vec::new()));
+        }
+        aliases
+    }
+
+    /// `[aliases]` of a config file over those of its `extends` chain.
+    /// `chain` holds the files being read, to stop at cycles.
+    fn file_aliases(path: &Path, chain: &mut Vec<PathBuf>) -> BTreeMap<String, String> {
+        #[derive(Deserialize)]
+        struct AliasesOnly {
+            #[serde(default)]
+            aliases: BTreeMap<String, String>,
+            #[serde(default)]
+            extends: Option<String>,
+        }
+
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if chain.contains(&canonical) {
+            return BTreeMap::new();
+        }
+        chain.push(canonical);
+
+        let Some(file) = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| toml::from_str::<AliasesOnly>(&contents).ok())
+        else {
+            return BTreeMap::new();
+        };
+        let mut aliases = match &file.extends {
+            Some(extends) => {
+                let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+                Self::file_aliases(&resolve_config_path(extends, base_dir), chain)
+            }
+            None => BTreeMap::new(),
+        };
+        aliases.extend(file.aliases);
+        aliases
+    }
+
+    /// Load configuration from a TOML file, on top of the files it `extends`
     pub fn from_file(path: &Path) -> Result<Self> {
+        Self::from_file_extending(path, &mut Vec::new())
+    }
+
+    /// Load a config file, then merge it over its `extends` chain.
+    /// `chain` holds the files being loaded, to detect cycles.
+    fn from_file_extending(path: &Path, chain: &mut Vec<PathBuf>) -> Result<Self> {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if chain.contains(&canonical) {
+            let cycle: Vec<String> = chain
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Config files extend each other in a cycle: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        chain.push(canonical);
+
         let contents = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&contents)?;
-        Ok(config)
+        let mut config: Config = toml::from_str(&contents)?;
+
+        config.validate_phases()?;
+        config.validate_capability_config()?;
+
+        // Load domain lists referenced from this file
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        config.security.network.load_domain_files(base_dir)?;
+
+        for (name, flavor) in config.flavors.iter_mut() {
+            crate::project::validate_flavor_name(name)?;
+            if !flavor.flavors.is_empty() {
+                return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "Flavor '{}' defines nested flavors, which are not supported",
+                    name
+                )));
+            }
+            flavor.validate_phases()?;
+            flavor.validate_capability_config()?;
+            flavor.security.network.load_domain_files(base_dir)?;
+        }
+
+        for (name, profile) in config.profiles.iter_mut() {
+            if !profile.profiles.is_empty() || !profile.flavors.is_empty() {
+                return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "Profile '{}' defines nested profiles or flavors, which are not supported",
+                    name
+                )));
+            }
+            if let Some(key) = profile.vm.template_keys().first() {
+                return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "Profile '{}' sets {}, which is fixed when the template is built: \
+                     use a flavor ([flavors.{}]) to get a template with its own sizing",
+                    name, key, name
+                )));
+            }
+            profile.validate_phases()?;
+            profile.validate_capability_config()?;
+            profile.security.network.load_domain_files(base_dir)?;
+        }
+
+        let mut nested = config.flavors.iter().chain(&config.profiles);
+        if let Some((name, _)) = nested.find(|(_, c)| c.extends.is_some()) {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "'{}' uses extends, which is only supported at the top level of a config file",
+                name
+            )));
+        }
+
+        let Some(extends) = config.extends.clone() else {
+            return Ok(config);
+        };
+        let parent_path = resolve_config_path(&extends, base_dir);
+        if !parent_path.is_file() {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "{} extends '{}', but {} does not exist",
+                path.display(),
+                extends,
+                parent_path.display()
+            )));
+        }
+        let parent = Self::from_file_extending(&parent_path, chain)?;
+        Ok(parent.merge(config))
+    }
+
+    /// Reject `[capabilities]` entries that are not tables of options
+    fn validate_capability_config(&self) -> Result<()> {
+        for (id, options) in &self.capability_config {
+            if !options.is_table() {
+                return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "capabilities.{} must be a table of options, e.g. [capabilities.{}]",
+                    id, id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Environment variables carrying the `[capabilities.<id>]` options of a capability.
+    /// `version = "20"` becomes `CAPABILITY_CONFIG_VERSION=20`; strings are passed
+    /// as-is and other values in their TOML form.
+    pub fn capability_env(&self, capability_id: &str) -> HashMap<String, String> {
+        let Some(toml::Value::Table(options)) = self.capability_config.get(capability_id) else {
+            return HashMap::new();
+        };
+        options
+            .iter()
+            .map(|(key, value)| {
+                let name = format!(
+                    "CAPABILITY_CONFIG_{}",
+                    key.to_uppercase().replace(['-', '.'], "_")
+                );
+                let value = match value {
+                    toml::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (name, value)
+            })
+            .collect()
+    }
+
+    /// Reject phases that cannot run
+    fn validate_phases(&self) -> Result<()> {
+        for phase in self
+            .phase
+            .before_all
+            .iter()
+            .chain(&self.phase.setup)
+            .chain(&self.phase.runtime)
+        {
+            phase.validate()?;
+        }
+        if let Some(phase) = self.phase.runtime.iter().find(|p| p.repo.is_some()) {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Runtime phase '{}' uses repo, which is only supported in [[phase.setup]]",
+                phase.name
+            )));
+        }
+        if let Some(phase) = self
+            .phase
+            .before_all
+            .iter()
+            .chain(&self.phase.setup)
+            .find(|p| p.timeout_secs.is_some())
+        {
+            return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                "Setup phase '{}' uses timeout_secs, which is only supported in [[phase.runtime]]",
+                phase.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Merge the named flavor on top of this config.
+    /// Fails for flavors that are not defined in any config file.
+    pub fn apply_flavor(mut self, flavor: Option<&str>) -> Result<Self> {
+        let Some(name) = flavor else {
+            return Ok(self);
+        };
+
+        match self.flavors.remove(name) {
+            Some(overlay) => Ok(self.merge(overlay)),
+            None => {
+                let available: Vec<&str> = self.flavors.keys().map(String::as_str).collect();
+                Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "Unknown flavor '{}'. {}",
+                    name,
+                    if available.is_empty() {
+                        "No flavors are defined: add a [flavors.<name>] section to .claude-vm.toml"
+                            .to_string()
+                    } else {
+                        format!("Available flavors: {}", available.join(", "))
+                    }
+                )))
+            }
+        }
+    }
+
+    /// Merge the named profile on top of this config.
+    /// Fails for profiles that are not defined in any config file.
+    pub fn apply_profile(mut self, profile: Option<&str>) -> Result<Self> {
+        let Some(name) = profile else {
+            return Ok(self);
+        };
+
+        match self.profiles.remove(name) {
+            Some(overlay) => Ok(self.merge(overlay)),
+            None => {
+                let available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                Err(crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "Unknown profile '{}'. {}",
+                    name,
+                    if available.is_empty() {
+                        "No profiles are defined: add a [profiles.<name>] section to .claude-vm.toml"
+                            .to_string()
+                    } else {
+                        format!("Available profiles: {}", available.join(", "))
+                    }
+                )))
+            }
+        }
     }
 
     /// Merge another config into this one (other takes precedence)
-    fn merge(mut self, other: Self) -> Self {
+    pub(crate) fn merge(mut self, other: Self) -> Self {
         // VM settings
         if other.vm.disk != default_disk() {
             self.vm.disk = other.vm.disk;
@@ -749,6 +1957,18 @@ impl Config {
         if other.vm.cpus != default_cpus() {
             self.vm.cpus = other.vm.cpus;
         }
+        if other.vm.idle_shutdown_mins.is_some() {
+            self.vm.idle_shutdown_mins = other.vm.idle_shutdown_mins;
+        }
+        if other.vm.swap_gb.is_some() {
+            self.vm.swap_gb = other.vm.swap_gb;
+        }
+        if other.vm.max_ephemeral.is_some() {
+            self.vm.max_ephemeral = other.vm.max_ephemeral;
+        }
+        if other.vm.sync_clock.is_some() {
+            self.vm.sync_clock = other.vm.sync_clock;
+        }
 
         // Tools
         self.tools.docker = self.tools.docker || other.tools.docker;
@@ -764,6 +1984,9 @@ impl Config {
 
         // Packages (extend/append)
         self.packages.system.extend(other.packages.system);
+        self.packages.npm.extend(other.packages.npm);
+        self.packages.pip.extend(other.packages.pip);
+        self.packages.cargo.extend(other.packages.cargo);
         // Merge setup_script (other takes precedence if present)
         if other.packages.setup_script.is_some() {
             self.packages.setup_script = other.packages.setup_script;
@@ -772,17 +1995,99 @@ impl Config {
         // Scripts (append)
         self.setup.scripts.extend(other.setup.scripts);
         self.runtime.scripts.extend(other.runtime.scripts);
+        self.runtime.dotfiles.extend(other.runtime.dotfiles);
+        if other.runtime.on_missing_script.is_some() {
+            self.runtime.on_missing_script = other.runtime.on_missing_script;
+        }
 
         // New phases: append (preserves order)
+        self.phase.before_all.extend(other.phase.before_all);
         self.phase.setup.extend(other.phase.setup);
         self.phase.runtime.extend(other.phase.runtime);
+        self.phase.env.extend(other.phase.env);
+
+        // Worktree settings
+        self.worktree.merge(other.worktree);
+
+        // Flavors: same-name flavors from both sources are merged
+        for (name, flavor) in other.flavors {
+            let merged = match self.flavors.remove(&name) {
+                Some(existing) => existing.merge(flavor),
+                None => flavor,
+            };
+            self.flavors.insert(name, merged);
+        }
+
+        // Profiles: same-name profiles from both sources are merged
+        for (name, profile) in other.profiles {
+            let merged = match self.profiles.remove(&name) {
+                Some(existing) => existing.merge(profile),
+                None => profile,
+            };
+            self.profiles.insert(name, merged);
+        }
 
         // Mounts (append)
         self.mounts.extend(other.mounts);
+        self.shared_mounts.extend(other.shared_mounts);
+        self.strict_mounts = self.strict_mounts || other.strict_mounts;
+        self.aliases.extend(other.aliases);
+        if other.extends.is_some() {
+            self.extends = other.extends;
+        }
+        self.lint.disable.extend(other.lint.disable);
+        // Capability options: merged key by key, a key set by `other` replaces
+        // the whole value (nested tables and arrays are not merged)
+        for (id, options) in other.capability_config {
+            match (self.capability_config.get_mut(&id), options) {
+                (Some(toml::Value::Table(existing)), toml::Value::Table(options)) => {
+                    existing.extend(options);
+                }
+                (_, options) => {
+                    self.capability_config.insert(id, options);
+                }
+            }
+        }
+        if other.project_root_fallback.is_some() {
+            self.project_root_fallback = other.project_root_fallback;
+        }
+        self.conversations.auto_prune =
+            self.conversations.auto_prune || other.conversations.auto_prune;
+        if other.conversations.retention_days.is_some() {
+            self.conversations.retention_days = other.conversations.retention_days;
+        }
         self.setup.mounts.extend(other.setup.mounts);
+        self.setup.apt_cache = self.setup.apt_cache || other.setup.apt_cache;
+        self.setup
+            .skip_capability_phases
+            .extend(other.setup.skip_capability_phases);
+        self.setup.checks.extend(other.setup.checks);
+        if other.setup.failure_log_lines.is_some() {
+            self.setup.failure_log_lines = other.setup.failure_log_lines;
+        }
+        if other.setup.build_log.is_some() {
+            self.setup.build_log = other.setup.build_log;
+        }
+        if other.setup.build_log_mode.is_some() {
+            self.setup.build_log_mode = other.setup.build_log_mode;
+        }
 
-        // Default Claude args (append)
-        self.defaults.claude_args.extend(other.defaults.claude_args);
+        // Default Claude args: append, but an explicit empty list clears everything before it
+        match other.defaults.claude_args {
+            Some(args) if args.is_empty() => self.defaults.claude_args = Some(args),
+            Some(args) => {
+                let mut merged = self.defaults.claude_args();
+                merged.extend(args);
+                self.defaults.claude_args = Some(merged);
+            }
+            None => {}
+        }
+        if other.defaults.retry_on_crash.is_some() {
+            self.defaults.retry_on_crash = other.defaults.retry_on_crash;
+        }
+        if other.defaults.remember_runtime.is_some() {
+            self.defaults.remember_runtime = other.defaults.remember_runtime;
+        }
 
         // Context (replace if not empty)
         if !other.context.instructions.is_empty() {
@@ -791,6 +2096,13 @@ impl Config {
         if !other.context.instructions_file.is_empty() {
             self.context.instructions_file = other.context.instructions_file;
         }
+        self.context
+            .instructions_files
+            .extend(other.context.instructions_files);
+        if other.context.max_bytes.is_some() {
+            self.context.max_bytes = other.context.max_bytes;
+        }
+        self.context.include_readme = self.context.include_readme || other.context.include_readme;
 
         // Security config
         // Enable if other enables it
@@ -820,84 +2132,62 @@ impl Config {
             .network
             .bypass_domains
             .extend(other.security.network.bypass_domains);
+        if other.security.network.max_log_mb.is_some() {
+            self.security.network.max_log_mb = other.security.network.max_log_mb;
+        }
 
         // Update check settings (other takes precedence)
         self.update_check = other.update_check;
 
+        // Git identity (other takes precedence per field)
+        if other.git.user_name.is_some() {
+            self.git.user_name = other.git.user_name;
+        }
+        if other.git.user_email.is_some() {
+            self.git.user_email = other.git.user_email;
+        }
+        if other.git.signing_key.is_some() {
+            self.git.signing_key = other.git.signing_key;
+        }
+        if other.git.forward_credentials.is_some() {
+            self.git.forward_credentials = other.git.forward_credentials;
+        }
+
         self
     }
 
-    /// Load context from file if instructions_file is set and instructions is empty
+    /// Load context from the instruction files if instructions is empty:
+    /// `instructions_file`, then `instructions_files`, separated by a blank line
     fn resolve_context_file(mut self) -> Result<Self> {
         // If instructions is already set, don't load from file
         if !self.context.instructions.is_empty() {
             return Ok(self);
         }
 
-        // If instructions_file is set, load from file
-        if !self.context.instructions_file.is_empty() {
+        let files = std::iter::once(&self.context.instructions_file)
+            .chain(&self.context.instructions_files)
+            .filter(|file| !file.is_empty());
+        let mut instructions = String::new();
+        for file in files {
             // Expand ~ in the path (supports both ~ and ~user syntax)
-            let file_path = crate::utils::path::expand_tilde(&self.context.instructions_file)
-                .unwrap_or_else(|| PathBuf::from(&self.context.instructions_file));
+            let file_path =
+                crate::utils::path::expand_tilde(file).unwrap_or_else(|| PathBuf::from(file));
 
             // Read file content
             match std::fs::read_to_string(&file_path) {
                 Ok(content) => {
-                    self.context.instructions = content;
-                }
-                Err(e) => {
-                    // In test mode, fail immediately without prompting
-                    #[cfg(test)]
-                    {
-                        return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
-                            "Failed to read context file '{}': {}",
-                            file_path.display(),
-                            e
-                        )));
-                    }
-
-                    #[cfg(not(test))]
-                    {
-                        use std::io::{self, Write};
-
-                        // Print highly visible warning
-                        eprintln!();
-                        eprintln!("╔═══════════════════════════════════════════════════════╗");
-                        eprintln!("║ ⚠️  WARNING: Failed to load context file            ║");
-                        eprintln!("╚═══════════════════════════════════════════════════════╝");
-                        eprintln!("  File: {}", file_path.display());
-                        eprintln!("  Error: {}", e);
-                        eprintln!();
-                        eprintln!("  Claude will start WITHOUT your custom instructions.");
-                        eprintln!();
-
-                        // Prompt user to continue
-                        eprint!("Continue anyway? [y/N]: ");
-                        io::stderr().flush().ok();
-
-                        let mut input = String::new();
-                        match io::stdin().read_line(&mut input) {
-                            Ok(_) => {
-                                if !input.trim().eq_ignore_ascii_case("y") {
-                                    return Err(crate::error::ClaudeVmError::InvalidConfig(
-                                        "Context file load failed and user chose to abort"
-                                            .to_string(),
-                                    ));
-                                }
-                            }
-                            Err(_) => {
-                                // If stdin is not available (non-interactive), abort
-                                return Err(crate::error::ClaudeVmError::InvalidConfig(format!(
-                                    "Failed to read context file '{}': {}",
-                                    file_path.display(),
-                                    e
-                                )));
-                            }
+                    if !instructions.is_empty() {
+                        if !instructions.ends_with('\n') {
+                            instructions.push('\n');
                         }
+                        instructions.push('\n');
                     }
+                    instructions.push_str(&content);
                 }
+                Err(e) => context_file_error(&file_path, e)?,
             }
         }
+        self.context.instructions = instructions;
 
         Ok(self)
     }
@@ -1001,6 +2291,14 @@ impl Config {
     pub fn with_runtime_overrides(mut self, runtime: &RuntimeFlags, verbose: bool) -> Self {
         self.verbose = verbose;
         self.forward_ssh_agent = runtime.forward_ssh_agent;
+        if runtime.git_credentials {
+            self.git.forward_credentials = Some(true);
+        }
+        self.scratch = runtime.scratch;
+        self.tmpfs_mounts = runtime.mount_tmpfs.clone();
+        if runtime.strict_mounts {
+            self.strict_mounts = true;
+        }
 
         if runtime.auto_setup {
             self.auto_setup = true;
@@ -1014,10 +2312,14 @@ impl Config {
                         location: mount.location.to_string_lossy().to_string(),
                         writable: mount.writable,
                         mount_point: mount.mount_point.map(|p| p.to_string_lossy().to_string()),
+                        allow_outside: false,
                     });
                 }
                 Err(e) => {
-                    eprintln!("Warning: Invalid mount spec '{}': {}", mount_spec, e);
+                    crate::diagnostics::warn(
+                        "invalid-mount",
+                        format!("Invalid mount spec '{}': {}", mount_spec, e),
+                    );
                 }
             }
         }
@@ -1049,6 +2351,77 @@ impl Config {
         self
     }
 
+    /// Mount the conversation folder of another project (agent command only)
+    pub fn with_conversation_project(mut self, path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(self);
+        };
+        let project = path
+            .canonicalize()
+            .ok()
+            .filter(|p| p.is_dir())
+            .ok_or_else(|| {
+                crate::error::ClaudeVmError::InvalidConfig(format!(
+                    "--conversation-project: {} is not a directory",
+                    path.display()
+                ))
+            })?;
+        self.conversation_project = Some(project);
+        Ok(self)
+    }
+
+    /// Which conversation folder sessions mount
+    pub fn conversation_mount(&self) -> crate::vm::mount::ConversationMount<'_> {
+        use crate::vm::mount::ConversationMount;
+        match &self.conversation_project {
+            _ if !self.mount_conversations => ConversationMount::None,
+            Some(project) => ConversationMount::Project(project),
+            None => ConversationMount::CurrentProject,
+        }
+    }
+
+    /// Run the agent as the restricted guest user (agent command only).
+    /// Its history stays in that user's home, so the conversation folder is not mounted.
+    pub fn with_drop_privileges(mut self, drop: bool) -> Self {
+        self.drop_privileges = drop;
+        if drop {
+            self.mount_conversations = false;
+        }
+        self
+    }
+
+    /// Use the VM sizing remembered from the last successful agent run, when
+    /// `defaults.remember_runtime` is set, for the sizing no config file sets.
+    /// Apply before the runtime overrides so flags win.
+    pub fn with_last_run(mut self, project_root: &Path) -> Self {
+        if !self.defaults.remembers_runtime() {
+            return self;
+        }
+        match crate::commands::last_run::LastRun::load(project_root) {
+            Ok(Some(last)) => last.apply(&mut self.vm),
+            Ok(None) => {}
+            Err(e) => crate::diagnostics::warn("last-run", format!("{}, ignoring it", e)),
+        }
+        self
+    }
+
+    /// Restart the agent after a crash (agent command only):
+    /// `--retry-on-crash`, else `[defaults] retry_on_crash`
+    pub fn with_retry_on_crash(mut self, retries: Option<u32>) -> Self {
+        self.agent_retries = retries.or(self.defaults.retry_on_crash).unwrap_or(0);
+        self
+    }
+
+    /// Run the agent in a clean home (agent command only).
+    /// A fresh home never mounts the conversation folder.
+    pub fn with_fresh_home(mut self, fresh: bool) -> Self {
+        self.fresh_home = fresh;
+        if fresh {
+            self.mount_conversations = false;
+        }
+        self
+    }
+
     /// Apply setup command overrides (tools, VM sizing, setup scripts/mounts)
     pub fn with_setup_overrides(mut self, cmd: &SetupCmd, verbose: bool) -> Self {
         self.verbose = verbose;
@@ -1106,6 +2479,32 @@ impl Config {
             }
         }
 
+        self.no_prefix = cmd.no_prefix;
+        self.allow_unpinned = cmd.allow_unpinned;
+        self.resume_on_failure = cmd.resume_on_failure;
+        self.summary_format = cmd.summary_format;
+        self.soft_checks = cmd.soft_checks;
+        self.auto_size = cmd.auto_size;
+        if cmd.apt_cache {
+            self.setup.apt_cache = true;
+        }
+        if let Some(path) = &cmd.build_log {
+            // Relative to where the command runs, not to the project
+            let path = std::env::current_dir()
+                .map(|dir| dir.join(path))
+                .unwrap_or_else(|_| path.clone());
+            self.setup.build_log = Some(path.to_string_lossy().to_string());
+        }
+        if let Some(ids) = &cmd.no_capability_phases {
+            if ids.is_empty() {
+                self.setup.skip_capability_phases.push("*".to_string());
+            } else {
+                self.setup
+                    .skip_capability_phases
+                    .extend(ids.iter().cloned());
+            }
+        }
+
         // Setup scripts
         for script in &cmd.setup_scripts {
             if let Some(script_str) = script.to_str() {
@@ -1121,10 +2520,14 @@ impl Config {
                         location: mount.location.to_string_lossy().to_string(),
                         writable: mount.writable,
                         mount_point: mount.mount_point.map(|p| p.to_string_lossy().to_string()),
+                        allow_outside: false,
                     });
                 }
                 Err(e) => {
-                    eprintln!("Warning: Invalid setup mount spec '{}': {}", mount_spec, e);
+                    crate::diagnostics::warn(
+                        "invalid-mount",
+                        format!("Invalid setup mount spec '{}': {}", mount_spec, e),
+                    );
                 }
             }
         }
@@ -1138,34 +2541,397 @@ fn home_dir() -> Option<PathBuf> {
     std::env::var("HOME").ok().map(PathBuf::from)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = Config::default();
+        assert_eq!(config.vm.disk, 20);
+        assert_eq!(config.vm.memory, 8);
+        assert_eq!(config.vm.cpus, 4);
+        assert!(!config.tools.docker);
+    }
+
+    #[test]
+    fn test_merge_config() {
+        let mut base = Config::default();
+        base.vm.disk = 30;
+
+        let mut override_cfg = Config::default();
+        override_cfg.vm.memory = 16;
+        override_cfg.vm.cpus = 2;
+        override_cfg.tools.docker = true;
+
+        let merged = base.merge(override_cfg);
+        assert_eq!(merged.vm.disk, 30); // Kept from base
+        assert_eq!(merged.vm.memory, 16); // From override
+        assert_eq!(merged.vm.cpus, 2); // From override
+        assert!(merged.tools.docker); // From override
+    }
+
+    #[test]
+    fn test_last_run_precedence() {
+        let project = tempfile::TempDir::new().unwrap();
+        crate::commands::last_run::LastRun {
+            disk: 50,
+            memory: 16,
+            cpus: 6,
+        }
+        .save(project.path())
+        .unwrap();
+
+        let mut config = Config::default();
+        config.vm.disk = 30;
+
+        // Not remembered unless enabled
+        assert_eq!(config.clone().with_last_run(project.path()).vm.disk, 30);
+
+        // Below the config files and the flags, above the defaults
+        config.defaults.remember_runtime = Some(true);
+        let runtime = RuntimeFlags {
+            memory: Some(12),
+            ..Default::default()
+        };
+        let config = config
+            .with_last_run(project.path())
+            .with_runtime_overrides(&runtime, false);
+        assert_eq!(
+            (config.vm.disk, config.vm.memory, config.vm.cpus),
+            (30, 12, 6)
+        );
+    }
+
+    #[test]
+    fn test_cpus_precedence() {
+        use clap::Parser;
+
+        // Project config over global config
+        let mut global = Config::default();
+        global.vm.cpus = 2;
+        let mut project = Config::default();
+        project.vm.cpus = 6;
+        let merged = global.clone().merge(project);
+        assert_eq!(merged.vm.cpus, 6);
+
+        // A project config left at the default keeps the global value
+        let merged = global.merge(Config::default());
+        assert_eq!(merged.vm.cpus, 2);
+
+        // CLI flags over config, for sessions and for setup
+        let runtime = RuntimeFlags {
+            cpus: Some(8),
+            ..Default::default()
+        };
+        assert_eq!(
+            merged
+                .clone()
+                .with_runtime_overrides(&runtime, false)
+                .vm
+                .cpus,
+            8
+        );
+        assert_eq!(
+            merged
+                .clone()
+                .with_runtime_overrides(&RuntimeFlags::default(), false)
+                .vm
+                .cpus,
+            2
+        );
+        let setup = SetupCmd::try_parse_from(["setup", "--cpus", "12"]).unwrap();
+        assert_eq!(merged.with_setup_overrides(&setup, false).vm.cpus, 12);
+    }
+
+    #[test]
+    fn test_idle_shutdown_merge() {
+        let mut base = Config::default();
+        base.vm.idle_shutdown_mins = Some(30);
+
+        let merged = base.clone().merge(Config::default());
+        assert_eq!(merged.vm.idle_shutdown(), Some(30));
+
+        let mut override_cfg = Config::default();
+        override_cfg.vm.idle_shutdown_mins = Some(0);
+        let merged = base.merge(override_cfg);
+        assert_eq!(merged.vm.idle_shutdown(), None); // 0 disables
+    }
+
+    #[test]
+    fn test_max_ephemeral_merge() {
+        assert_eq!(
+            Config::default().vm.ephemeral_limit(),
+            Some(DEFAULT_MAX_EPHEMERAL)
+        );
+
+        let mut base = Config::default();
+        base.vm.max_ephemeral = Some(2);
+        let merged = base.clone().merge(Config::default());
+        assert_eq!(merged.vm.ephemeral_limit(), Some(2));
+
+        let mut override_cfg = Config::default();
+        override_cfg.vm.max_ephemeral = Some(0);
+        assert_eq!(base.merge(override_cfg).vm.ephemeral_limit(), None); // 0 disables
+    }
+
+    #[test]
+    fn test_sync_clock_merge() {
+        assert!(!Config::default().vm.sync_clock());
+
+        let base: Config = toml::from_str("[vm]\nsync_clock = true").unwrap();
+        assert!(base.clone().merge(Config::default()).vm.sync_clock());
+
+        let mut override_cfg = Config::default();
+        override_cfg.vm.sync_clock = Some(false);
+        assert!(!base.merge(override_cfg).vm.sync_clock());
+    }
+
+    #[test]
+    fn test_swap_merge_and_validate() {
+        let mut base = Config::default();
+        base.vm.swap_gb = Some(4);
+
+        let merged = base.clone().merge(Config::default());
+        assert_eq!(merged.vm.swap(), Some(4));
+        assert!(merged.vm.validate().is_empty());
+
+        let mut override_cfg = Config::default();
+        override_cfg.vm.swap_gb = Some(0);
+        assert_eq!(base.clone().merge(override_cfg).vm.swap(), None); // 0 disables
+
+        base.vm.swap_gb = Some(15);
+        let warnings = base.vm.validate();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("vm.swap_gb"));
+
+        // Does not overflow
+        base.vm.swap_gb = Some(u32::MAX);
+        assert_eq!(base.vm.validate().len(), 1);
+
+        assert!(toml::from_str::<Config>("[vm]\nswap_gb = -1").is_err());
+    }
+
+    #[test]
+    fn test_apply_flavor() {
+        let config: Config = toml::from_str(
+            r#"
+            [vm]
+            memory = 8
+
+            [tools]
+            node = true
+
+            [flavors.backend.vm]
+            memory = 16
+
+            [flavors.backend.tools]
+            docker = true
+            "#,
+        )
+        .unwrap();
+
+        let unchanged = config.clone().apply_flavor(None).unwrap();
+        assert_eq!(unchanged.vm.memory, 8);
+        assert!(!unchanged.tools.docker);
+
+        let backend = config.apply_flavor(Some("backend")).unwrap();
+        assert_eq!(backend.vm.memory, 16);
+        assert!(backend.tools.docker);
+        assert!(backend.tools.node);
+        assert!(backend.flavors.is_empty());
+    }
+
+    #[test]
+    fn test_apply_unknown_flavor() {
+        let config: Config = toml::from_str(
+            r#"
+            [flavors.backend.tools]
+            docker = true
+
+            [flavors.frontend.tools]
+            chromium = true
+            "#,
+        )
+        .unwrap();
+
+        let err = config.apply_flavor(Some("mobile")).unwrap_err().to_string();
+        assert!(err.contains("Unknown flavor 'mobile'"));
+        assert!(err.contains("backend, frontend"));
+
+        let err = Config::default()
+            .apply_flavor(Some("backend"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("No flavors are defined"));
+    }
+
+    #[test]
+    fn test_merge_flavors_by_name() {
+        let global: Config = toml::from_str(
+            r#"
+            [flavors.backend.tools]
+            docker = true
+            "#,
+        )
+        .unwrap();
+        let project: Config = toml::from_str(
+            r#"
+            [flavors.backend.vm]
+            memory = 16
+
+            [flavors.frontend.tools]
+            chromium = true
+            "#,
+        )
+        .unwrap();
+
+        let merged = global.merge(project);
+        assert_eq!(merged.flavors.len(), 2);
+        let backend = &merged.flavors["backend"];
+        assert!(backend.tools.docker);
+        assert_eq!(backend.vm.memory, 16);
+    }
+
+    #[test]
+    fn test_nested_flavors_rejected() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(".claude-vm.toml");
+        std::fs::write(
+            &path,
+            r#"
+            [flavors.backend.flavors.inner.tools]
+            docker = true
+            "#,
+        )
+        .unwrap();
+
+        let err = Config::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("nested flavors"));
+    }
 
     #[test]
-    fn test_default_config() {
-        let config = Config::default();
-        assert_eq!(config.vm.disk, 20);
-        assert_eq!(config.vm.memory, 8);
-        assert_eq!(config.vm.cpus, 4);
-        assert!(!config.tools.docker);
+    fn test_profile_rejects_template_settings() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".claude-vm.toml");
+        std::fs::write(&path, "[profiles.heavy.vm]\ncpus = 8\ndisk = 60\n").unwrap();
+
+        let err = Config::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains("Profile 'heavy' sets vm.disk"));
+        assert!(err.contains("[flavors.heavy]"));
     }
 
     #[test]
-    fn test_merge_config() {
-        let mut base = Config::default();
-        base.vm.disk = 30;
+    #[serial_test::serial]
+    fn test_load_with_profile() {
+        let home = tempfile::TempDir::new().unwrap();
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            project.path().join(".claude-vm.toml"),
+            r#"
+            [vm]
+            cpus = 2
+
+            [profiles.heavy.vm]
+            cpus = 8
+            memory = 32
+            idle_shutdown_mins = 120
+
+            [profiles.light.vm]
+            cpus = 1
+            "#,
+        )
+        .unwrap();
+        let original_home = std::env::var("HOME").ok();
+        std::env::set_var("HOME", home.path());
+
+        std::env::set_var(PROFILE_ENV, "heavy");
+        let heavy = Config::load(project.path());
+        std::env::set_var(PROFILE_ENV, "huge");
+        let unknown = Config::load(project.path());
+        std::env::remove_var(PROFILE_ENV);
+        let base = Config::load(project.path());
+
+        match original_home {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
 
-        let mut override_cfg = Config::default();
-        override_cfg.vm.memory = 16;
-        override_cfg.vm.cpus = 2;
-        override_cfg.tools.docker = true;
+        let heavy = heavy.unwrap();
+        assert_eq!(heavy.vm.cpus, 8);
+        assert_eq!(heavy.vm.memory, 32);
+        assert_eq!(heavy.vm.idle_shutdown_mins, Some(120));
+        let base = base.unwrap();
+        assert_eq!(base.vm.cpus, 2);
+        assert_eq!(base.vm.memory, VmConfig::default().memory);
+        let err = unknown.unwrap_err().to_string();
+        assert!(err.contains("Unknown profile 'huge'"));
+        assert!(err.contains("Available profiles: heavy, light"));
+    }
 
-        let merged = base.merge(override_cfg);
-        assert_eq!(merged.vm.disk, 30); // Kept from base
-        assert_eq!(merged.vm.memory, 16); // From override
-        assert_eq!(merged.vm.cpus, 2); // From override
-        assert!(merged.tools.docker); // From override
+    #[test]
+    fn test_config_extends() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let shared = dir.path().join("shared");
+        let app = dir.path().join("apps").join("web");
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(
+            shared.join("root.toml"),
+            "[vm]\ndisk = 40\nmemory = 4\n\n[packages]\nsystem = [\"jq\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            shared.join("base.toml"),
+            "extends = \"root.toml\"\n\n[vm]\nmemory = 12\n\n[tools]\nnode = true\n",
+        )
+        .unwrap();
+        let child = app.join(".claude-vm.toml");
+        std::fs::write(
+            &child,
+            "extends = \"../../shared/base.toml\"\n\n[vm]\ncpus = 6\n\n[packages]\nsystem = [\"ripgrep\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::from_file(&child).unwrap();
+        assert_eq!(
+            (config.vm.disk, config.vm.memory, config.vm.cpus),
+            (40, 12, 6)
+        );
+        assert!(config.tools.node);
+        assert_eq!(config.packages.system, vec!["jq", "ripgrep"]);
+
+        // Cycles are reported with the chain of files
+        std::fs::write(shared.join("root.toml"), "extends = \"base.toml\"\n").unwrap();
+        let err = Config::from_file(&child).unwrap_err().to_string();
+        assert!(err.contains("cycle"));
+        assert!(err.contains("base.toml -> "));
+        assert!(err.contains("root.toml -> "));
+
+        std::fs::write(&child, "extends = \"./missing.toml\"\n").unwrap();
+        let err = Config::from_file(&child).unwrap_err().to_string();
+        assert!(err.contains("does not exist"));
+    }
+
+    #[test]
+    fn test_aliases_follow_extends() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("base.toml"),
+            "extends = \".claude-vm.toml\"\n\n[aliases]\nreview = \"agent --read-only-project\"\nfix = \"agent -p fix\"\n",
+        )
+        .unwrap();
+        let child = dir.path().join(".claude-vm.toml");
+        std::fs::write(
+            &child,
+            "extends = \"base.toml\"\n\n[aliases]\nfix = \"agent -p 'fix the build'\"\n",
+        )
+        .unwrap();
+
+        // The child's aliases win, and the cycle back to it stops the chain
+        let aliases = Config::file_aliases(&child, &mut Vec::new());
+        assert_eq!(aliases["review"], "agent --read-only-project");
+        assert_eq!(aliases["fix"], "agent -p 'fix the build'");
     }
 
     #[test]
@@ -1297,6 +3063,84 @@ mod tests {
         std::fs::remove_file(&context_file).unwrap();
     }
 
+    #[test]
+    fn test_context_multiple_instruction_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let standards = dir.path().join("standards.md");
+        let architecture = dir.path().join("architecture.md");
+        std::fs::write(&standards, "# Standards\nUse Result.\n").unwrap();
+        std::fs::write(&architecture, "# Architecture\nOne crate.").unwrap();
+
+        let mut config = Config::default();
+        config.context.instructions_files = vec![
+            standards.to_string_lossy().to_string(),
+            architecture.to_string_lossy().to_string(),
+        ];
+        let config = config.resolve_context_file().unwrap();
+        assert_eq!(
+            config.context.instructions,
+            "# Standards\nUse Result.\n\n# Architecture\nOne crate."
+        );
+
+        // The single file comes first; a missing file in the list fails like it
+        let mut config = Config::default();
+        config.context.instructions_file = architecture.to_string_lossy().to_string();
+        config.context.instructions_files = vec![standards.to_string_lossy().to_string()];
+        let config = config.resolve_context_file().unwrap();
+        assert!(config.context.instructions.starts_with("# Architecture"));
+        assert!(config.context.instructions.ends_with("Use Result.\n"));
+
+        let mut config = Config::default();
+        config.context.instructions_files = vec![
+            standards.to_string_lossy().to_string(),
+            "/nonexistent/notes.md".to_string(),
+        ];
+        let err = config.resolve_context_file().unwrap_err();
+        assert!(err.to_string().contains("/nonexistent/notes.md"));
+    }
+
+    #[test]
+    fn test_capability_config_env() {
+        let global: Config = toml::from_str(
+            r#"
+            [capabilities.node]
+            version = "18"
+            corepack = true
+
+            [capabilities.python]
+            version = "3.12"
+            "#,
+        )
+        .unwrap();
+        let project: Config = toml::from_str(
+            r#"
+            [capabilities.node]
+            version = "20"
+            "#,
+        )
+        .unwrap();
+        let config = global.merge(project);
+
+        let env = config.capability_env("node");
+        assert_eq!(env.get("CAPABILITY_CONFIG_VERSION").unwrap(), "20");
+        assert_eq!(env.get("CAPABILITY_CONFIG_COREPACK").unwrap(), "true");
+        assert_eq!(
+            config
+                .capability_env("python")
+                .get("CAPABILITY_CONFIG_VERSION"),
+            Some(&"3.12".to_string())
+        );
+        assert!(config.capability_env("rust").is_empty());
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".claude-vm.toml");
+        std::fs::write(&path, "capabilities.node = \"20\"\n").unwrap();
+        let err = Config::from_file(&path).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("capabilities.node must be a table"));
+    }
+
     #[test]
     fn test_context_instructions_precedence() {
         use std::io::Write;
@@ -1405,6 +3249,31 @@ mod tests {
         assert_eq!(merged.update_check.interval_hours, 168);
     }
 
+    #[test]
+    fn test_shared_mounts_read_only_by_default() {
+        let config: Config = toml::from_str(
+            r#"
+            [[shared_mounts]]
+            location = "/srv/registry"
+            mount_point = "/deps/registry"
+
+            [[shared_mounts]]
+            location = "/srv/build-cache"
+            writable = true
+            "#,
+        )
+        .unwrap();
+
+        let entries = config.shared_mount_entries();
+        assert_eq!(entries.len(), 2);
+        assert!(!entries[0].writable);
+        assert_eq!(entries[0].mount_point.as_deref(), Some("/deps/registry"));
+        assert!(entries[1].writable);
+        // Regular mounts keep their writable default
+        let regular: MountEntry = toml::from_str("location = \"/data\"").unwrap();
+        assert!(regular.writable);
+    }
+
     #[test]
     fn test_mounts_merge() {
         // Create base config with one mount
@@ -1413,6 +3282,7 @@ mod tests {
             location: "/host/path1".to_string(),
             writable: true,
             mount_point: None,
+            allow_outside: false,
         });
 
         // Create override config with another mount
@@ -1421,6 +3291,7 @@ mod tests {
             location: "/host/path2".to_string(),
             writable: false,
             mount_point: Some("/vm/path2".to_string()),
+            allow_outside: false,
         });
 
         // Merge configs
@@ -1443,6 +3314,7 @@ mod tests {
             location: "/setup/path1".to_string(),
             writable: true,
             mount_point: None,
+            allow_outside: false,
         });
 
         // Create override config with another setup mount
@@ -1451,6 +3323,7 @@ mod tests {
             location: "/setup/path2".to_string(),
             writable: true,
             mount_point: None,
+            allow_outside: false,
         });
 
         // Merge configs
@@ -1562,6 +3435,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_packages_language_merge() {
+        let mut base = Config::default();
+        base.packages.npm.push("typescript".to_string());
+        base.packages.pip.push("requests==2.31.0".to_string());
+
+        let mut override_cfg = Config::default();
+        override_cfg.packages.npm.push("lodash@4.17.21".to_string());
+        override_cfg.packages.cargo.push("ripgrep".to_string());
+
+        let merged = base.merge(override_cfg);
+
+        // Extended, with version pins kept verbatim
+        assert_eq!(merged.packages.npm, vec!["typescript", "lodash@4.17.21"]);
+        assert_eq!(merged.packages.pip, vec!["requests==2.31.0"]);
+        assert_eq!(merged.packages.cargo, vec!["ripgrep"]);
+    }
+
+    #[test]
+    fn test_packages_language_require_capability() {
+        let mut config = Config::default();
+        assert!(config.packages.check_tools(&config.tools).is_ok());
+
+        config.packages.npm.push("typescript".to_string());
+        config.packages.cargo.push("ripgrep".to_string());
+        let err = config
+            .packages
+            .check_tools(&config.tools)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("packages.npm needs the node capability"));
+        assert!(err.contains("packages.cargo needs the rust capability"));
+
+        config.tools.node = true;
+        config.tools.rust = true;
+        assert!(config.packages.check_tools(&config.tools).is_ok());
+    }
+
     #[test]
     fn test_packages_setup_script_merge_none() {
         // Create base config with setup_script
@@ -1627,20 +3538,64 @@ mod tests {
     fn test_defaults_claude_args_merge() {
         // Create base config with claude args
         let mut base = Config::default();
-        base.defaults.claude_args = vec!["--arg1".to_string()];
+        base.defaults.claude_args = Some(vec!["--arg1".to_string()]);
 
         // Create override config with additional claude args
         let mut override_cfg = Config::default();
-        override_cfg.defaults.claude_args = vec!["--arg2".to_string(), "--arg3".to_string()];
+        override_cfg.defaults.claude_args = Some(vec!["--arg2".to_string(), "--arg3".to_string()]);
 
         // Merge configs
         let merged = base.merge(override_cfg);
 
         // Verify all args are present (extended)
-        assert_eq!(merged.defaults.claude_args.len(), 3);
-        assert_eq!(merged.defaults.claude_args[0], "--arg1");
-        assert_eq!(merged.defaults.claude_args[1], "--arg2");
-        assert_eq!(merged.defaults.claude_args[2], "--arg3");
+        let args = merged.defaults.claude_args();
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[0], "--arg1");
+        assert_eq!(args[1], "--arg2");
+        assert_eq!(args[2], "--arg3");
+    }
+
+    #[test]
+    fn test_defaults_claude_args_builtin() {
+        let config = Config::default();
+        assert_eq!(
+            config.defaults.claude_args(),
+            vec!["--dangerously-skip-permissions".to_string()]
+        );
+
+        // A config file without [defaults] adds nothing (no duplicate built-in)
+        let merged = Config::default().merge(toml::from_str("").unwrap());
+        assert_eq!(merged.defaults.claude_args(), config.defaults.claude_args());
+
+        // Additional args are appended to the built-in default
+        let file: Config = toml::from_str("[defaults]\nclaude_args = [\"--verbose\"]").unwrap();
+        let merged = Config::default().merge(file);
+        assert_eq!(
+            merged.defaults.claude_args(),
+            vec![
+                "--dangerously-skip-permissions".to_string(),
+                "--verbose".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_defaults_claude_args_empty_list_clears() {
+        let mut global = Config::default();
+        global.defaults.claude_args = Some(vec!["--verbose".to_string()]);
+
+        let project: Config = toml::from_str("[defaults]\nclaude_args = []").unwrap();
+        let merged = Config::default().merge(global).merge(project);
+        assert!(merged.defaults.claude_args().is_empty());
+
+        // A later source can add args again after clearing
+        let mut local = Config::default();
+        local.defaults.claude_args = Some(vec!["--model".to_string(), "opus".to_string()]);
+        let merged = merged.merge(local);
+        assert_eq!(
+            merged.defaults.claude_args(),
+            vec!["--model".to_string(), "opus".to_string()]
+        );
     }
 
     #[test]
@@ -1751,6 +3706,27 @@ mod tests {
         assert!(NetworkIsolationConfig::validate_domain_pattern("example.com-").is_some());
     }
 
+    #[test]
+    fn test_network_isolation_cidr_validation() {
+        assert!(NetworkIsolationConfig::validate_domain_pattern("192.168.0.0/16").is_none());
+        assert!(NetworkIsolationConfig::validate_domain_pattern("::1/128").is_none());
+
+        let warning = NetworkIsolationConfig::validate_domain_pattern("10.0.0.0/99").unwrap();
+        assert!(warning.contains("invalid CIDR range"));
+        assert!(NetworkIsolationConfig::validate_domain_pattern("10.0.0/8").is_some());
+
+        let config = NetworkIsolationConfig {
+            enabled: true,
+            allowed_domains: vec!["10.1.0.0/16".to_string(), "10.0.0.0/99".to_string()],
+            bypass_domains: vec!["10.2.0.0/16".to_string()],
+            ..Default::default()
+        };
+        let warnings = config.validate();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("'10.0.0.0/99' - invalid CIDR range"));
+        assert!(warnings[1].contains("bypass_domains: '10.2.0.0/16'"));
+    }
+
     #[test]
     fn test_network_isolation_domain_conflict_warning() {
         let config = NetworkIsolationConfig {
@@ -1901,6 +3877,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_phase_shared_env() {
+        let toml = r#"
+        [phase.env]
+        REGISTRY = "registry.internal"
+        DEBUG = "false"
+
+        [[phase.setup]]
+        name = "install"
+        script = "echo $REGISTRY"
+
+        [[phase.runtime]]
+        name = "login"
+        env = { DEBUG = "true" }
+        script = "echo $REGISTRY $DEBUG"
+        "#;
+
+        let config: Config = toml::from_str(toml).unwrap();
+        let phases = &config.phase;
+
+        // Shared vars reach every phase; the phase's own env wins
+        let setup_env = phases.env_for(&phases.setup[0]);
+        assert_eq!(setup_env["REGISTRY"], "registry.internal");
+        assert_eq!(setup_env["DEBUG"], "false");
+        let runtime_env = phases.env_for(&phases.runtime[0]);
+        assert_eq!(runtime_env["REGISTRY"], "registry.internal");
+        assert_eq!(runtime_env["DEBUG"], "true");
+
+        // Later config sources override shared vars key by key
+        let project: Config = toml::from_str("[phase.env]\nDEBUG = \"1\"").unwrap();
+        let merged = config.merge(project);
+        assert_eq!(merged.phase.env["DEBUG"], "1");
+        assert_eq!(merged.phase.env["REGISTRY"], "registry.internal");
+    }
+
     #[test]
     fn test_phase_conditional() {
         let toml = r#"
@@ -1917,6 +3928,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_phase_timeout_validation() {
+        let config: Config = toml::from_str(
+            r#"
+            [[phase.runtime]]
+            name = "services"
+            script = "start"
+            timeout_secs = 120
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.phase.runtime[0].timeout_secs, Some(120));
+        assert!(config.validate_phases().is_ok());
+
+        let mut sourced = config.clone();
+        sourced.phase.runtime[0].source = true;
+        let err = sourced.validate_phases().unwrap_err();
+        assert!(err.to_string().contains("source=true with timeout_secs"));
+
+        let mut zero = config.clone();
+        zero.phase.runtime[0].timeout_secs = Some(0);
+        assert!(zero.validate_phases().is_err());
+
+        let mut setup = Config::default();
+        setup.phase.setup = config.phase.runtime;
+        let err = setup.validate_phases().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("only supported in [[phase.runtime]]"));
+    }
+
     #[test]
     fn test_phase_continue_on_error() {
         let toml = r#"
@@ -1981,6 +4023,97 @@ mod tests {
         assert_eq!(merged.phase.setup[1].name, "override");
     }
 
+    #[test]
+    fn test_dotfile_parse() {
+        let base = Path::new("/work/project");
+
+        let plain = Dotfile::parse("/host/.gitconfig", base).unwrap();
+        assert_eq!(plain.source, PathBuf::from("/host/.gitconfig"));
+        assert_eq!(plain.target, ".gitconfig");
+
+        let mapped = Dotfile::parse("./vm/bashrc:~/.bashrc", base).unwrap();
+        assert_eq!(mapped.source, PathBuf::from("/work/project/./vm/bashrc"));
+        assert_eq!(mapped.target, ".bashrc");
+
+        let nested = Dotfile::parse("/host/init.lua:.config/nvim/init.lua", base).unwrap();
+        assert_eq!(nested.target, ".config/nvim/init.lua");
+    }
+
+    #[test]
+    fn test_dotfile_parse_rejects_unsafe_targets() {
+        let base = Path::new("/work");
+        for spec in [
+            "/host/x:/etc/profile",
+            "/host/x:~root/.bashrc",
+            "/host/x:../outside",
+            "/host/x:~/",
+            ":~/.bashrc",
+            "/host/settings.json:~/.claude/settings.json",
+            "/host/.claude.json",
+            "/host/x:~/.claude-vm/project-root",
+        ] {
+            assert!(Dotfile::parse(spec, base).is_err(), "accepted {}", spec);
+        }
+    }
+
+    #[test]
+    fn test_runtime_dotfiles_warn_and_skip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("bashrc"), "alias ll='ls -l'\n").unwrap();
+        std::fs::create_dir(dir.path().join("nvim")).unwrap();
+
+        let runtime = RuntimeConfig {
+            dotfiles: vec![
+                "./bashrc:~/.bashrc".to_string(),
+                "./missing".to_string(),
+                "./nvim".to_string(),
+                "./bashrc:~/.claude/CLAUDE.md".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let (dotfiles, warnings) = runtime.dotfiles(dir.path());
+        assert_eq!(dotfiles.len(), 1);
+        assert_eq!(dotfiles[0].target, ".bashrc");
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("not found"));
+        assert!(warnings[1].contains("not a regular file"));
+        assert!(warnings[2].contains("claude-vm manages"));
+    }
+
+    #[test]
+    fn test_before_all_phases() {
+        let toml = r#"
+        [[phase.before_all]]
+        name = "dns"
+        script = "echo 'nameserver 1.1.1.1' | sudo tee /etc/resolv.conf"
+
+        [[phase.setup]]
+        name = "tools"
+        script = "echo tools"
+        "#;
+
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.phase.before_all.len(), 1);
+        assert_eq!(config.phase.before_all[0].name, "dns");
+        assert_eq!(config.phase.setup.len(), 1);
+
+        let mut project = Config::default();
+        project.phase.before_all.push(ScriptPhase {
+            name: "hosts".to_string(),
+            script: Some("echo hosts".to_string()),
+            ..Default::default()
+        });
+        let merged = config.merge(project);
+        let names: Vec<&str> = merged
+            .phase
+            .before_all
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["dns", "hosts"]);
+    }
+
     #[test]
     fn test_phase_if_alias() {
         let toml = r#"
@@ -1996,4 +4129,335 @@ mod tests {
             Some("command -v docker".to_string())
         );
     }
+
+    #[test]
+    fn test_phase_interpreter_default_bash() {
+        let phase = ScriptPhase {
+            name: "tools".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(phase.interpreter(), "bash");
+        assert_eq!(phase.bash_script("echo hi\n"), "echo hi\n");
+        assert!(phase.validate().is_ok());
+    }
+
+    #[test]
+    fn test_phase_interpreter_wraps_script() {
+        let toml = r#"
+        [[phase.runtime]]
+        name = "py"
+        interpreter = "python3 -u"
+        script = "print('hi')"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        let phase = &config.phase.runtime[0];
+
+        let script = phase.bash_script("print('hi')");
+        assert!(script.contains("if ! command -v 'python3' >/dev/null 2>&1; then"));
+        assert!(script.contains("exit 127"));
+        assert!(script.contains(
+            "cat > \"$script\" <<'CLAUDE_VM_PHASE_SCRIPT'\nprint('hi')\nCLAUDE_VM_PHASE_SCRIPT\n"
+        ));
+        assert!(script.contains("'python3' '-u' \"$script\"\n"));
+        assert!(script.ends_with("exit $status\n"));
+    }
+
+    #[test]
+    fn test_phase_interpreter_sh() {
+        let phase = ScriptPhase {
+            name: "posix".to_string(),
+            interpreter: Some("sh".to_string()),
+            ..Default::default()
+        };
+        let script = phase.bash_script("echo hi\n");
+        assert!(script.contains("<<'CLAUDE_VM_PHASE_SCRIPT'\necho hi\nCLAUDE_VM_PHASE_SCRIPT\n"));
+        assert!(script.contains("'sh' \"$script\""));
+    }
+
+    #[test]
+    fn test_phase_source_requires_bash() {
+        let mut phase = ScriptPhase {
+            name: "env".to_string(),
+            source: true,
+            interpreter: Some("sh".to_string()),
+            ..Default::default()
+        };
+        assert!(phase.validate().is_err());
+
+        phase.interpreter = Some("bash".to_string());
+        assert!(phase.validate().is_ok());
+
+        phase.interpreter = Some("  ".to_string());
+        assert!(phase.validate().is_err());
+    }
+
+    #[test]
+    fn test_from_file_validates_phase_repo() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".claude-vm.toml");
+
+        std::fs::write(
+            &path,
+            "[[phase.setup]]\nname = \"shared\"\nrepo = \"https://github.com/org/scripts#main:setup\"\n",
+        )
+        .unwrap();
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(
+            config.phase.setup[0].repo.as_deref(),
+            Some("https://github.com/org/scripts#main:setup")
+        );
+
+        std::fs::write(
+            &path,
+            "[[phase.setup]]\nname = \"shared\"\nrepo = \"/local/scripts#main:setup\"\n",
+        )
+        .unwrap();
+        assert!(Config::from_file(&path).is_err());
+
+        std::fs::write(
+            &path,
+            "[[phase.runtime]]\nname = \"shared\"\nrepo = \"https://github.com/org/scripts#main:setup\"\n",
+        )
+        .unwrap();
+        let err = Config::from_file(&path).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("only supported in [[phase.setup]]"));
+    }
+
+    #[test]
+    fn test_from_file_rejects_sourced_non_bash_phase() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(".claude-vm.toml");
+        std::fs::write(
+            &path,
+            "[[phase.runtime]]\nname = \"env\"\nsource = true\ninterpreter = \"sh\"\nscript = \"x=1\"\n",
+        )
+        .unwrap();
+        assert!(Config::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_git_identity_parse_and_merge() {
+        let toml = r#"
+        [git]
+        user_name = "Build Bot"
+        user_email = "bot@example.com"
+        "#;
+        let base: Config = toml::from_str(toml).unwrap();
+        assert!(base.git.is_configured());
+
+        let mut override_cfg = Config::default();
+        override_cfg.git.user_email = Some("other@example.com".to_string());
+
+        let merged = base.merge(override_cfg);
+        assert_eq!(merged.git.user_name.as_deref(), Some("Build Bot"));
+        assert_eq!(merged.git.user_email.as_deref(), Some("other@example.com"));
+        assert!(merged.git.signing_key.is_none());
+    }
+
+    #[test]
+    fn test_git_identity_validate_email() {
+        let tools = ToolsConfig::default();
+        let mut git = GitIdentityConfig {
+            user_email: Some("bot@example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(git.validate(&tools).is_empty());
+
+        for bad in [
+            "bot",
+            "@example.com",
+            "bot@localhost",
+            "a@b@c.com",
+            "bot @x.com",
+        ] {
+            git.user_email = Some(bad.to_string());
+            assert_eq!(
+                git.validate(&tools).len(),
+                1,
+                "expected warning for {}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_git_identity_signing_key_requires_gpg() {
+        let mut tools = ToolsConfig::default();
+        let git = GitIdentityConfig {
+            signing_key: Some("ABCDEF".to_string()),
+            ..Default::default()
+        };
+        let warnings = git.validate(&tools);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("gpg"));
+
+        tools.gpg = true;
+        assert!(git.validate(&tools).is_empty());
+    }
+
+    #[test]
+    fn test_git_identity_shell_commands() {
+        assert_eq!(GitIdentityConfig::default().to_shell_commands(), "");
+
+        let git = GitIdentityConfig {
+            user_name: Some("O'Brien".to_string()),
+            user_email: None,
+            signing_key: Some("KEY".to_string()),
+            forward_credentials: None,
+        };
+        let commands = git.to_shell_commands();
+        assert!(commands.contains("git config --global user.name 'O'\\''Brien'"));
+        assert!(!commands.contains("user.email"));
+        assert!(commands.contains("git config --global user.signingkey 'KEY'"));
+        assert!(commands.contains("commit.gpgsign true"));
+    }
+
+    #[test]
+    fn test_defaults_agent_args_alias() {
+        let toml = r#"
+        [defaults]
+        agent_args = ["--verbose"]
+        "#;
+
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.defaults.claude_args(), vec!["--verbose".to_string()]);
+    }
+
+    #[test]
+    fn test_setup_failure_log_lines() {
+        let config = Config::default();
+        assert_eq!(config.setup.failure_log_lines(), DEFAULT_FAILURE_LOG_LINES);
+
+        let toml = r#"
+        [setup]
+        failure_log_lines = 10
+        "#;
+        let override_cfg: Config = toml::from_str(toml).unwrap();
+        let merged = config.merge(override_cfg);
+        assert_eq!(merged.setup.failure_log_lines(), 10);
+
+        // Unset in a later source keeps the earlier value
+        let merged = merged.merge(Config::default());
+        assert_eq!(merged.setup.failure_log_lines(), 10);
+    }
+
+    #[test]
+    fn test_conversation_project() {
+        use crate::vm::mount::ConversationMount;
+
+        let config = Config::default().with_conversations(true);
+        assert_eq!(
+            config.conversation_mount(),
+            ConversationMount::CurrentProject
+        );
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = config.with_conversation_project(Some(dir.path())).unwrap();
+        let canonical = dir.path().canonicalize().unwrap();
+        assert_eq!(
+            config.conversation_mount(),
+            ConversationMount::Project(&canonical)
+        );
+
+        // Not mounting conversations wins over the selected project
+        let config = config.with_fresh_home(true);
+        assert_eq!(config.conversation_mount(), ConversationMount::None);
+
+        let missing = dir.path().join("missing");
+        assert!(Config::default()
+            .with_conversation_project(Some(&missing))
+            .is_err());
+    }
+
+    #[test]
+    fn test_setup_build_log() {
+        let root = Path::new("/work/app");
+        assert_eq!(Config::default().setup.build_log_path(root), None);
+
+        let toml = r#"
+        [setup]
+        build_log = "logs/setup.log"
+        build_log_mode = "rotate"
+        "#;
+        let config = Config::default().merge(toml::from_str(toml).unwrap());
+        assert_eq!(
+            config.setup.build_log_path(root),
+            Some(PathBuf::from("/work/app/logs/setup.log"))
+        );
+        assert_eq!(config.setup.build_log_mode, Some(BuildLogMode::Rotate));
+
+        // Unset in a later source keeps the earlier value
+        let config = config.merge(Config::default());
+        assert_eq!(config.setup.build_log_mode, Some(BuildLogMode::Rotate));
+        assert!(config.setup.build_log.is_some());
+
+        let absolute = SetupConfig {
+            build_log: Some("/var/log/setup.log".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            absolute.build_log_path(root),
+            Some(PathBuf::from("/var/log/setup.log"))
+        );
+    }
+
+    #[test]
+    fn test_domain_files_loaded_from_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("allowed.txt"),
+            "# Shared allowlist\napi.example.com\n\n*.github.com  # GitHub\nbad domain\n",
+        )
+        .unwrap();
+        let config_path = temp_dir.path().join(".claude-vm.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+            [security.network]
+            enabled = true
+            allowed_domains = ["inline.com"]
+            allowed_domains_file = "allowed.txt"
+            "#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        assert_eq!(
+            config.security.network.allowed_domains,
+            vec!["inline.com", "api.example.com", "*.github.com"]
+        );
+    }
+
+    #[test]
+    fn test_domain_file_missing_is_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config_path = temp_dir.path().join(".claude-vm.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+            [security.network]
+            blocked_domains_file = "missing.txt"
+            "#,
+        )
+        .unwrap();
+
+        let err = Config::from_file(&config_path).unwrap_err();
+        assert!(err.to_string().contains("missing.txt"));
+    }
+
+    #[test]
+    fn test_resolve_config_path() {
+        let base = Path::new("/project");
+        assert_eq!(
+            resolve_config_path("lists/allowed.txt", base),
+            PathBuf::from("/project/lists/allowed.txt")
+        );
+        assert_eq!(
+            resolve_config_path("/etc/allowed.txt", base),
+            PathBuf::from("/etc/allowed.txt")
+        );
+        assert!(resolve_config_path("~/allowed.txt", base).is_absolute());
+    }
 }
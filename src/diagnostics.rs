@@ -0,0 +1,84 @@
+//! Warnings shown to the user, optionally as JSON lines for wrappers.
+//!
+//! With `--warnings-json`, each warning is written to stderr as one JSON object
+//! (`severity`, `code`, `message`, `timestamp`) instead of the human
+//! `⚠ Warning: ...` line. Codes are stable kebab-case identifiers.
+
+use serde::Serialize;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether warnings are emitted as JSON lines for this process
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Emit warnings as JSON lines on stderr for the rest of the process
+pub fn enable_json() {
+    JSON.store(true, Ordering::SeqCst);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+}
+
+/// A warning with a stable code
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// Report a warning: `⚠ Warning: <message>` on stderr, or a JSON line under `--warnings-json`
+pub fn warn(code: &'static str, message: impl std::fmt::Display) {
+    let diagnostic = Diagnostic {
+        severity: Severity::Warning,
+        code,
+        message: message.to_string(),
+    };
+    crate::build_log::record(&format!("Warning: {}", diagnostic.message));
+
+    if JSON.load(Ordering::SeqCst) {
+        let mut stderr = std::io::stderr().lock();
+        let _ = writeln!(stderr, "{}", to_json_line(&diagnostic));
+    } else {
+        eprintln!("⚠ Warning: {}", diagnostic.message);
+    }
+}
+
+/// Serialize a diagnostic with a timestamp, like lifecycle events
+fn to_json_line(diagnostic: &Diagnostic) -> String {
+    let mut value = serde_json::to_value(diagnostic).unwrap_or(serde_json::Value::Null);
+    if let serde_json::Value::Object(ref mut map) = value {
+        map.insert(
+            "timestamp".to_string(),
+            serde_json::Value::String(chrono::Utc::now().to_rfc3339()),
+        );
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_json_line() {
+        let line = to_json_line(&Diagnostic {
+            severity: Severity::Warning,
+            code: "mount-missing",
+            message: "Mount path does not exist: /data\nsecond line".to_string(),
+        });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(value["severity"], "warning");
+        assert_eq!(value["code"], "mount-missing");
+        assert_eq!(
+            value["message"],
+            "Mount path does not exist: /data\nsecond line"
+        );
+        assert!(value["timestamp"].is_string());
+        assert!(!line.contains('\n'));
+    }
+}
@@ -6,7 +6,10 @@ pub enum ClaudeVmError {
     #[error("Template not found for project: {0}")]
     TemplateNotFound(String),
 
-    #[error("Lima not installed. Install from https://lima-vm.io")]
+    #[error(
+        "Lima is not installed (limactl not found in PATH).\n{}",
+        lima_install_instructions()
+    )]
     LimaNotInstalled,
 
     #[error("Script file not found: {0}")]
@@ -65,6 +68,9 @@ pub enum ClaudeVmError {
 
     #[error("Branch '{branch}' does not exist")]
     BranchNotFound { branch: String },
+
+    #[error("Insufficient host resources: {0}")]
+    InsufficientResources(String),
 }
 
 impl From<self_update::errors::Error> for ClaudeVmError {
@@ -74,3 +80,43 @@ impl From<self_update::errors::Error> for ClaudeVmError {
 }
 
 pub type Result<T> = std::result::Result<T, ClaudeVmError>;
+
+/// Process exit codes, documented in `claude-vm --help`
+pub mod exit_code {
+    /// Any other error
+    pub const FAILURE: i32 = 1;
+    /// Invalid command line (reported by clap)
+    pub const USAGE: i32 = 2;
+    /// Invalid or unreadable configuration
+    pub const CONFIG: i32 = 3;
+    /// The project could not be detected
+    pub const PROJECT: i32 = 4;
+    /// A command run by claude-vm failed
+    pub const COMMAND: i32 = 5;
+}
+
+impl ClaudeVmError {
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ClaudeVmError::InvalidConfig(_) | ClaudeVmError::ConfigParse(_) => exit_code::CONFIG,
+            ClaudeVmError::ProjectDetection(_) => exit_code::PROJECT,
+            ClaudeVmError::CommandFailed(_) => exit_code::COMMAND,
+            _ => exit_code::FAILURE,
+        }
+    }
+}
+
+/// How to install Lima on the current host OS
+fn lima_install_instructions() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Install it with Homebrew:\n  brew install lima\nSee https://lima-vm.io/docs/installation/ for other options."
+    } else if cfg!(target_os = "linux") {
+        "Install it with Homebrew (brew install lima), or download a release from\n\
+         https://github.com/lima-vm/lima/releases and add its bin/ directory to PATH.\n\
+         QEMU is also required (e.g. sudo apt install qemu-system).\n\
+         See https://lima-vm.io/docs/installation/ for details."
+    } else {
+        "See https://lima-vm.io/docs/installation/ for installation instructions."
+    }
+}
@@ -0,0 +1,182 @@
+//! Newline-delimited JSON lifecycle events for editor and CI integrations.
+//!
+//! With `--events-json`, events are written to stdout (one JSON object per line)
+//! and human-readable progress output moves to stderr so stdout stays parseable.
+
+use crate::error::{ClaudeVmError, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Whether JSON events own stdout for this process
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether progress output is suppressed, because a machine-readable report owns stdout
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Lifecycle milestones emitted in `--events-json` mode
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A VM is being created (template build or ephemeral clone)
+    VmCreating { vm: String },
+    /// The VM is started and usable
+    VmReady { vm: String, duration_ms: u64 },
+    /// A setup phase started
+    PhaseStart { phase: String },
+    /// A setup phase finished
+    PhaseEnd {
+        phase: String,
+        duration_ms: u64,
+        success: bool,
+    },
+    /// The main command (agent or shell) is starting in the VM
+    SessionStart { vm: String, command: String },
+    /// The main command exited
+    SessionEnd { vm: String, exit_code: Option<i32> },
+    /// The VM is being stopped and deleted
+    Teardown { vm: String },
+}
+
+/// Route output for the rest of the process: events to stdout, human logs to stderr
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether `--events-json` mode is active
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Suppress progress output for the rest of the process; child output moves to stderr
+pub fn enable_quiet() {
+    QUIET.store(true, Ordering::SeqCst);
+}
+
+/// Whether progress output is suppressed
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::SeqCst)
+}
+
+/// Whether stdout is reserved for machine-readable output
+pub fn stdout_reserved() -> bool {
+    is_enabled() || is_quiet()
+}
+
+/// Emit an event as a single JSON line on stdout (no-op unless enabled)
+pub fn emit(event: Event) {
+    if !is_enabled() {
+        return;
+    }
+
+    let line = to_json_line(&event);
+    let mut stdout = std::io::stdout().lock();
+    // Best effort: a closed stdout must not abort the session
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+/// Serialize an event with a timestamp
+fn to_json_line(event: &Event) -> String {
+    let mut value = serde_json::to_value(event).unwrap_or(serde_json::Value::Null);
+    if let serde_json::Value::Object(ref mut map) = value {
+        map.insert(
+            "timestamp".to_string(),
+            serde_json::Value::String(chrono::Utc::now().to_rfc3339()),
+        );
+    }
+    value.to_string()
+}
+
+/// Stdout for child processes: inherited normally, stderr when stdout is reserved
+pub fn child_stdout() -> Stdio {
+    if stdout_reserved() {
+        Stdio::from(std::io::stderr())
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// Exit code reported in `session_end` for a command result
+pub fn exit_code_of(result: &Result<()>) -> Option<i32> {
+    match result {
+        Ok(()) => Some(0),
+        Err(ClaudeVmError::CommandExitCode(code)) => Some(*code),
+        Err(_) => None,
+    }
+}
+
+/// Duration in whole milliseconds
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// Print human-readable progress output.
+///
+/// Goes to stdout normally, to stderr in `--events-json` mode, and nowhere when quiet.
+/// Always recorded in the build log, when one is being written.
+#[macro_export]
+macro_rules! status {
+    () => {
+        $crate::status!("")
+    };
+    ($($arg:tt)*) => {{
+        if $crate::build_log::is_active() {
+            $crate::build_log::record(&format!($($arg)*));
+        }
+        if $crate::events::is_quiet() {
+        } else if $crate::events::is_enabled() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let line = to_json_line(&Event::PhaseEnd {
+            phase: "install".to_string(),
+            duration_ms: 1200,
+            success: true,
+        });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(value["event"], "phase_end");
+        assert_eq!(value["phase"], "install");
+        assert_eq!(value["duration_ms"], 1200);
+        assert_eq!(value["success"], true);
+        assert!(value["timestamp"].is_string());
+        assert!(!line.contains('\n'));
+    }
+
+    #[test]
+    fn test_session_end_without_exit_code() {
+        let line = to_json_line(&Event::SessionEnd {
+            vm: "vm-1".to_string(),
+            exit_code: None,
+        });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "session_end");
+        assert!(value["exit_code"].is_null());
+    }
+
+    #[test]
+    fn test_exit_code_of() {
+        assert_eq!(exit_code_of(&Ok(())), Some(0));
+        assert_eq!(
+            exit_code_of(&Err(ClaudeVmError::CommandExitCode(3))),
+            Some(3)
+        );
+        assert_eq!(
+            exit_code_of(&Err(ClaudeVmError::LimaExecution("x".to_string()))),
+            None
+        );
+    }
+}
@@ -1,12 +1,16 @@
 #![forbid(unsafe_code)]
 
+pub mod build_log;
 pub mod capabilities;
 pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod diagnostics;
 pub mod error;
+pub mod events;
 pub mod project;
 pub mod scripts;
+pub mod trace;
 pub mod update_check;
 pub mod utils;
 pub mod version;
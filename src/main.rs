@@ -3,17 +3,62 @@
 use anyhow::Result;
 use clap::Parser;
 
-use claude_vm::cli::{router, Cli, Commands, NetworkCommands, WorktreeCommands};
+use claude_vm::cli::{
+    alias, router, Cli, Commands, NetworkCommands, SummaryFormat, WorktreeCommands,
+};
 use claude_vm::config::Config;
 use claude_vm::project::Project;
-use claude_vm::{commands, error::ClaudeVmError};
+use claude_vm::{
+    commands,
+    error::{exit_code, ClaudeVmError},
+};
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(
+            e.downcast_ref::<ClaudeVmError>()
+                .map_or(exit_code::FAILURE, ClaudeVmError::exit_code),
+        );
+    }
+}
+
+fn run() -> Result<()> {
     // Route arguments to default to agent command when appropriate
-    let args = std::env::args_os();
+    let (args, explain_routing) = router::take_explain_routing(std::env::args_os());
+    let args = expand_aliases(args)?;
+    if explain_routing {
+        println!("{}", router::explain(args));
+        return Ok(());
+    }
     let routed_args = router::route_args(args);
     let cli = Cli::parse_from(routed_args);
 
+    if cli.events_json {
+        claude_vm::events::enable();
+    }
+
+    if cli.warnings_json {
+        claude_vm::diagnostics::enable_json();
+    }
+
+    claude_vm::vm::preflight::check_host_user(cli.deny_warnings)?;
+
+    // JSON output is the only output on stdout
+    match &cli.command {
+        Some(Commands::Setup(cmd)) if cmd.summary_format == SummaryFormat::Json => {
+            claude_vm::events::enable_quiet();
+        }
+        Some(Commands::List { json: true, .. }) | Some(Commands::Info { json: true, .. }) => {
+            claude_vm::events::enable_quiet();
+        }
+        _ => {}
+    }
+
+    if let Some(path) = &cli.trace {
+        claude_vm::trace::enable(path)?;
+    }
+
     // Handle commands that truly don't need project or config
     match &cli.command {
         Some(Commands::Version { check }) => {
@@ -31,6 +76,16 @@ fn main() -> Result<()> {
         _ => {}
     }
 
+    // Make the selected flavor visible to project detection, config loading,
+    // and child processes (batch runs)
+    if let Some(flavor) = &cli.flavor {
+        claude_vm::project::validate_flavor_name(flavor)?;
+        std::env::set_var(claude_vm::project::FLAVOR_ENV, flavor);
+    }
+    if let Some(profile) = &cli.profile {
+        std::env::set_var(claude_vm::config::PROFILE_ENV, profile);
+    }
+
     // Try to detect project (most commands need it)
     // If we're in a project, load config to validate it (even if command doesn't use it)
     let project_result = Project::detect();
@@ -40,11 +95,13 @@ fn main() -> Result<()> {
         &cli.command,
         Some(Commands::Agent(..))
             | Some(Commands::Setup(..))
+            | Some(Commands::Auth { .. })
             | Some(Commands::Shell(..))
-            | Some(Commands::Info)
+            | Some(Commands::Info { .. })
             | Some(Commands::Clean { .. })
             | Some(Commands::Network { .. })
             | Some(Commands::Worktree { .. })
+            | Some(Commands::Batch(..))
     );
 
     let (project, config) = if requires_project {
@@ -52,19 +109,20 @@ fn main() -> Result<()> {
         let proj = project_result.map_err(|e| match e {
             ClaudeVmError::ProjectDetection(msg) => {
                 eprintln!("Error: {}", msg);
-                std::process::exit(1);
+                std::process::exit(exit_code::PROJECT);
             }
             _ => e,
         })?;
 
         // Load config and apply command-specific overrides
         let cfg = match &cli.command {
-            Some(Commands::Agent(cmd)) => {
+            Some(Commands::Agent(cmd)) => commands::agent::load_config(&proj, cmd, cli.verbose)?,
+            Some(Commands::Shell(cmd)) => {
                 Config::load_with_main_repo(proj.root(), proj.main_repo_root())?
+                    .with_last_run(proj.root())
                     .with_runtime_overrides(&cmd.runtime, cli.verbose)
-                    .with_conversations(!cmd.no_conversations)
             }
-            Some(Commands::Shell(cmd)) => {
+            Some(Commands::Batch(cmd)) => {
                 Config::load_with_main_repo(proj.root(), proj.main_repo_root())?
                     .with_runtime_overrides(&cmd.runtime, cli.verbose)
             }
@@ -96,16 +154,35 @@ fn main() -> Result<()> {
 
     // Handle commands that don't strictly need project but benefit from config validation
     match &cli.command {
-        Some(Commands::List { unused, disk_usage }) => {
-            commands::list::execute(*unused, *disk_usage)?;
+        Some(Commands::List {
+            unused,
+            disk_usage,
+            json,
+        }) => {
+            commands::list::execute(*unused, *disk_usage, *json)?;
             return Ok(());
         }
         Some(Commands::Config { command }) => {
             commands::config::execute(command)?;
             return Ok(());
         }
-        Some(Commands::CleanAll { yes }) => {
-            commands::clean_all::execute(*yes)?;
+        Some(Commands::Template { command }) => {
+            commands::template::execute(command)?;
+            return Ok(());
+        }
+        Some(Commands::Capabilities { command }) => {
+            commands::capabilities::execute(command)?;
+            return Ok(());
+        }
+        Some(Commands::Conversations { command }) => {
+            commands::conversations::execute(command)?;
+            return Ok(());
+        }
+        Some(Commands::CleanAll {
+            yes,
+            confirm_destroy,
+        }) => {
+            commands::clean_all::execute(*yes, *confirm_destroy)?;
             return Ok(());
         }
         _ => {}
@@ -132,19 +209,43 @@ fn main() -> Result<()> {
         Some(Commands::Shell(cmd)) => {
             commands::shell::execute(&project, &config, cmd)?;
         }
+        Some(Commands::Batch(cmd)) => {
+            commands::batch::execute(&project, &config, cmd, cli.deny_warnings)?;
+        }
         Some(Commands::Setup(_cmd)) => {
             #[cfg(debug_assertions)]
             let skip_install = _cmd.no_agent_install;
             #[cfg(not(debug_assertions))]
             let skip_install = false;
 
+            if _cmd.dry_run {
+                commands::setup::dry_run(&project, &config)?;
+                return Ok(());
+            }
+
+            if _cmd.clear_apt_cache {
+                commands::setup::clear_apt_cache()?;
+            }
+
             commands::setup::execute(&project, &config, skip_install)?;
         }
-        Some(Commands::Info) => {
-            commands::info::execute()?;
+        Some(Commands::Auth { reset, yes }) => {
+            commands::auth::execute(&project, *reset, *yes)?;
         }
-        Some(Commands::Clean { yes }) => {
-            commands::clean::execute(&project, *yes)?;
+        Some(Commands::Info { metadata, json }) => {
+            commands::info::execute(*metadata, *json)?;
+        }
+        Some(Commands::Clean {
+            yes,
+            unused,
+            orphans,
+            all,
+        }) => {
+            if *orphans {
+                commands::clean::orphans(&project, *all)?;
+            } else {
+                commands::clean::execute(&project, *yes, *unused)?;
+            }
         }
         Some(Commands::Network { command }) => match command {
             NetworkCommands::Status => {
@@ -164,20 +265,38 @@ fn main() -> Result<()> {
                     *follow,
                 )?;
             }
-            NetworkCommands::Test { domain } => {
-                commands::network::test::execute(&config, domain)?;
+            NetworkCommands::Test { domain, explain } => {
+                commands::network::test::execute(&config, domain, *explain)?;
             }
         },
         Some(Commands::Worktree { command }) => match command {
-            WorktreeCommands::Create { branch, base } => {
-                commands::worktree::create::execute(&config, &project, branch, base.as_deref())?;
+            WorktreeCommands::Create {
+                branch,
+                base,
+                setup,
+                agent,
+                claude_args,
+            } => {
+                commands::worktree::create::execute(
+                    &config,
+                    &project,
+                    branch,
+                    base.as_deref(),
+                    *setup,
+                    agent.then_some(claude_args.as_slice()),
+                )?;
             }
             WorktreeCommands::List {
                 merged,
                 locked,
                 detached,
             } => {
-                commands::worktree::list::execute(merged.as_deref(), *locked, *detached)?;
+                commands::worktree::list::execute(
+                    merged.as_deref(),
+                    config.worktree.default_base.as_deref(),
+                    *locked,
+                    *detached,
+                )?;
             }
             WorktreeCommands::Remove {
                 branches,
@@ -194,6 +313,7 @@ fn main() -> Result<()> {
                 commands::worktree::remove::execute(
                     branches_opt,
                     merged.as_deref(),
+                    config.worktree.default_base.as_deref(),
                     *yes,
                     *dry_run,
                     *locked,
@@ -212,3 +332,19 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Expand a `[aliases]` entry in the first argument (before routing)
+fn expand_aliases(args: Vec<std::ffi::OsString>) -> Result<Vec<std::ffi::OsString>> {
+    // Only read the config files when the first argument could be an alias
+    let candidate = args.get(1).and_then(|a| a.to_str());
+    if !candidate.is_some_and(|a| !a.starts_with('-') && !router::is_known_subcommand(a)) {
+        return Ok(args);
+    }
+
+    let project = Project::detect().ok();
+    let aliases = Config::load_aliases(
+        project.as_ref().map(|p| p.root()),
+        project.as_ref().map(|p| p.main_repo_root()),
+    );
+    Ok(alias::expand_aliases(args, &aliases)?)
+}
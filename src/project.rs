@@ -10,12 +10,42 @@ use std::process::Command;
 /// template names under 50 chars to stay well under the 104 char limit.
 const MAX_TEMPLATE_NAME_LENGTH: usize = 50;
 
+/// Environment variable selecting the template flavor (set by `--flavor`)
+pub const FLAVOR_ENV: &str = "CLAUDE_VM_FLAVOR";
+
+/// Maximum length of a flavor name, so it fits in template names
+const MAX_FLAVOR_LENGTH: usize = 20;
+
+/// Flavor selected for this process (`--flavor` or CLAUDE_VM_FLAVOR), if any
+pub fn selected_flavor() -> Option<String> {
+    std::env::var(FLAVOR_ENV)
+        .ok()
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+}
+
+/// Check that a flavor name can be used in template names
+pub fn validate_flavor_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if name.is_empty() || name.len() > MAX_FLAVOR_LENGTH || !valid_chars {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Invalid flavor name '{}': use up to {} lowercase letters, digits, '-' or '_'",
+            name, MAX_FLAVOR_LENGTH
+        )));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct Project {
     /// Current working directory (worktree if in worktree, otherwise main repo)
     root: PathBuf,
     /// Main repository root (for template naming)
     main_repo_root: PathBuf,
+    /// Selected flavor (a named config variant with its own template)
+    flavor: Option<String>,
     template_name: String,
 }
 
@@ -23,10 +53,15 @@ impl Project {
     /// Detect the current project and generate its template name
     pub fn detect() -> Result<Self> {
         let (root, main_repo_root) = Self::get_project_roots()?;
-        let template_name = Self::generate_template_name(&main_repo_root);
+        let flavor = selected_flavor();
+        if let Some(name) = &flavor {
+            validate_flavor_name(name)?;
+        }
+        let template_name = Self::generate_template_name(&main_repo_root, flavor.as_deref());
         Ok(Self {
             root,
             main_repo_root,
+            flavor,
             template_name,
         })
     }
@@ -86,9 +121,9 @@ impl Project {
             .map(|p| p.to_path_buf())
     }
 
-    /// Generate template name: claude-tpl_{sanitized-basename}_{8-char-md5-hash}[-dev]
+    /// Generate template name: claude-tpl_{sanitized-basename}[-{flavor}]_{8-char-md5-hash}[-dev]
     /// Enforces MAX_TEMPLATE_NAME_LENGTH to avoid UNIX_PATH_MAX issues with socket paths
-    fn generate_template_name(root: &Path) -> String {
+    fn generate_template_name(root: &Path, flavor: Option<&str>) -> String {
         let basename = root
             .file_name()
             .and_then(|n| n.to_str())
@@ -97,8 +132,12 @@ impl Project {
         // Sanitize: lowercase, alphanumeric + dash, collapse multiple dashes
         let sanitized = Self::sanitize_name(basename);
 
-        // Generate 8-character MD5 hash of the full path
-        let full_path = root.to_string_lossy();
+        // Generate 8-character MD5 hash of the full path (and flavor, so each gets its own template)
+        let mut full_path = root.to_string_lossy().to_string();
+        if let Some(flavor) = flavor {
+            full_path.push('#');
+            full_path.push_str(flavor);
+        }
         let digest = md5::compute(full_path.as_bytes());
         let hash = format!("{:x}", digest);
         let short_hash = &hash[..8];
@@ -109,9 +148,13 @@ impl Project {
         #[cfg(not(debug_assertions))]
         let suffix = "";
 
-        // Calculate fixed overhead: "claude-tpl_" (11) + "_" (1) + hash (8) + suffix (0 or 4)
+        let flavor_part = flavor
+            .map(|f| format!("-{}", Self::sanitize_name(f)))
+            .unwrap_or_default();
+
+        // Calculate fixed overhead: "claude-tpl_" (11) + flavor + "_" (1) + hash (8) + suffix (0 or 4)
         let prefix = "claude-tpl_";
-        let fixed_overhead = prefix.len() + 1 + short_hash.len() + suffix.len();
+        let fixed_overhead = prefix.len() + flavor_part.len() + 1 + short_hash.len() + suffix.len();
 
         // Truncate sanitized name if necessary to stay within max length
         let max_sanitized_len = MAX_TEMPLATE_NAME_LENGTH.saturating_sub(fixed_overhead);
@@ -121,7 +164,10 @@ impl Project {
             &sanitized
         };
 
-        format!("{}{}_{}{}", prefix, truncated, short_hash, suffix)
+        format!(
+            "{}{}{}_{}{}",
+            prefix, truncated, flavor_part, short_hash, suffix
+        )
     }
 
     /// Sanitize name: lowercase, alphanumeric + dash, collapse dashes
@@ -155,6 +201,11 @@ impl Project {
         &self.template_name
     }
 
+    /// Selected flavor, if any
+    pub fn flavor(&self) -> Option<&str> {
+        self.flavor.as_deref()
+    }
+
     /// Check if the project is in a worktree
     pub fn is_worktree(&self) -> bool {
         self.root != self.main_repo_root
@@ -179,7 +230,7 @@ mod tests {
     #[test]
     fn test_generate_template_name() {
         let path = PathBuf::from("/home/user/my-project");
-        let template_name = Project::generate_template_name(&path);
+        let template_name = Project::generate_template_name(&path, None);
 
         assert!(template_name.starts_with("claude-tpl_my-project_"));
 
@@ -204,7 +255,7 @@ mod tests {
     #[test]
     fn test_generate_template_name_dev_suffix() {
         let path = PathBuf::from("/home/user/test-project");
-        let template_name = Project::generate_template_name(&path);
+        let template_name = Project::generate_template_name(&path, None);
 
         // Verify format is correct
         assert!(template_name.starts_with("claude-tpl_test-project_"));
@@ -231,7 +282,7 @@ mod tests {
         // Test that very long project names are truncated to stay within MAX_TEMPLATE_NAME_LENGTH
         let long_name = "a".repeat(100); // 100 'a's
         let path = PathBuf::from(format!("/home/user/{}", long_name));
-        let template_name = Project::generate_template_name(&path);
+        let template_name = Project::generate_template_name(&path, None);
 
         // Template name should not exceed the max length
         assert!(
@@ -266,7 +317,7 @@ mod tests {
         // Test the specific case from the bug report
         let path =
             PathBuf::from("/home/user/claude-orchestrator-themouette-add-user-authentication");
-        let template_name = Project::generate_template_name(&path);
+        let template_name = Project::generate_template_name(&path, None);
 
         // Should be truncated
         assert!(
@@ -287,7 +338,7 @@ mod tests {
     fn test_generate_template_name_short_unchanged() {
         // Test that short names are not affected
         let path = PathBuf::from("/home/user/my-app");
-        let template_name = Project::generate_template_name(&path);
+        let template_name = Project::generate_template_name(&path, None);
 
         // Should contain the full project name (not truncated)
         assert!(template_name.contains("my-app"));
@@ -304,7 +355,7 @@ mod tests {
 
         let long_name = "very-long-project-name-that-should-be-truncated-safely";
         let path = PathBuf::from(format!("/home/user/{}", long_name));
-        let template_name = Project::generate_template_name(&path);
+        let template_name = Project::generate_template_name(&path, None);
 
         // Simulate VM session name (template + "-" + max PID)
         let vm_session_name = format!("{}-9999999999", template_name);
@@ -321,4 +372,34 @@ mod tests {
             SAFE_VM_NAME_LENGTH
         );
     }
+
+    #[test]
+    fn test_generate_template_name_with_flavor() {
+        let path = PathBuf::from("/home/user/my-project");
+        let plain = Project::generate_template_name(&path, None);
+        let backend = Project::generate_template_name(&path, Some("backend"));
+        let frontend = Project::generate_template_name(&path, Some("frontend"));
+
+        assert!(backend.starts_with("claude-tpl_my-project-backend_"));
+        assert!(frontend.starts_with("claude-tpl_my-project-frontend_"));
+        assert_ne!(plain, backend);
+        assert_ne!(backend, frontend);
+
+        // Long project names are truncated, the flavor is kept
+        let long = PathBuf::from("/home/user/very-long-project-name-that-should-be-truncated");
+        let name = Project::generate_template_name(&long, Some("backend"));
+        assert!(name.len() <= MAX_TEMPLATE_NAME_LENGTH);
+        assert!(name.contains("-backend_"));
+    }
+
+    #[test]
+    fn test_validate_flavor_name() {
+        assert!(validate_flavor_name("backend").is_ok());
+        assert!(validate_flavor_name("web_2-ui").is_ok());
+        assert!(validate_flavor_name("").is_err());
+        assert!(validate_flavor_name("Backend").is_err());
+        assert!(validate_flavor_name("back end").is_err());
+        assert!(validate_flavor_name("../x").is_err());
+        assert!(validate_flavor_name(&"a".repeat(21)).is_err());
+    }
 }
@@ -1,18 +1,25 @@
+use crate::build_log;
 use crate::capabilities;
-use crate::config::Config;
+use crate::commands::agent_logs::AgentPaths;
+use crate::config::{Config, MissingScriptPolicy};
+use crate::diagnostics;
 use crate::error::{ClaudeVmError, Result};
+use crate::events::{self, Event};
 use crate::project::Project;
+use crate::status;
+use crate::utils::cidr::is_cidr;
+use crate::utils::domain::matches_any;
 use crate::utils::git;
 use crate::utils::shell::escape as shell_escape;
 use crate::vm::limactl::LimaCtl;
-use crate::vm::{mount, session::VmSession};
-use std::collections::HashMap;
+use crate::vm::{mount, restricted_user, session::VmSession};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 /// Directory where capability runtime scripts are installed in the VM
 const RUNTIME_SCRIPT_DIR: &str = "/usr/local/share/claude-vm/runtime";
 
-/// Type alias for runtime script metadata: (name, content, env_vars, source, when_condition, continue_on_error)
+/// Type alias for runtime script metadata: (name, content, env_vars, source, when_condition, continue_on_error, timeout_secs)
 type RuntimeScriptInfo = (
     String,
     String,
@@ -20,6 +27,7 @@ type RuntimeScriptInfo = (
     bool,
     Option<String>,
     bool,
+    Option<u64>,
 );
 
 /// Sanitize a filename to contain only safe characters
@@ -55,9 +63,12 @@ fn find_runtime_script_path() -> Result<PathBuf> {
 ///
 /// # Note
 /// This is primarily used for embedded scripts (e.g., install_docker.sh).
-/// For user scripts, prefer `execute_script_file`.
+/// For user scripts, prefer `execute_script_file`. Output goes straight to the
+/// terminal, unprefixed, so interactive scripts work. Setup only uses it for
+/// interactive phases and `--no-prefix`; everything else streams through
+/// `execute_script_prefixed` so each line is attributed to its source.
 pub fn execute_script(vm_name: &str, script_content: &str, script_name: &str) -> Result<()> {
-    println!("Running script: {}", script_name);
+    status!("Running script: {}", script_name);
 
     // Write script to temp file
     let temp_path = format!("/tmp/{}", script_name);
@@ -78,11 +89,18 @@ pub fn execute_script(vm_name: &str, script_content: &str, script_name: &str) ->
     Ok(())
 }
 
-/// Execute a script from string content in a VM silently (only show output on error)
+/// Execute a script from string content in a VM, prefixing each output line with `[prefix]`.
 ///
-/// This function is similar to `execute_script` but suppresses output unless there's an error.
-/// Used for runtime scripts that shouldn't clutter the output.
-pub fn execute_script_silent(vm_name: &str, script_content: &str, script_name: &str) -> Result<()> {
+/// Used for setup scripts so output can be attributed to the capability or phase
+/// that produced it.
+pub fn execute_script_prefixed(
+    vm_name: &str,
+    script_content: &str,
+    script_name: &str,
+    prefix: &str,
+) -> Result<()> {
+    status!("Running script: {}", script_name);
+
     // Write script to temp file
     let temp_path = format!("/tmp/{}", script_name);
     let local_temp = std::env::temp_dir().join(script_name);
@@ -94,7 +112,7 @@ pub fn execute_script_silent(vm_name: &str, script_content: &str, script_name: &
 
     // Make executable and run
     LimaCtl::shell(vm_name, None, "chmod", &["+x", &temp_path], false)?;
-    LimaCtl::shell(vm_name, None, "bash", &[&temp_path], false)?;
+    LimaCtl::shell_prefixed(vm_name, "bash", &[&temp_path], prefix)?;
 
     // Cleanup local temp file
     std::fs::remove_file(&local_temp)?;
@@ -102,6 +120,89 @@ pub fn execute_script_silent(vm_name: &str, script_content: &str, script_name: &
     Ok(())
 }
 
+/// Execute a script from string content in a VM silently (only show output on error)
+///
+/// This function is similar to `execute_script` but buffers the output, and only
+/// prints it, prefixed with `[script_name]`, when the script fails.
+/// Used for runtime scripts that shouldn't clutter the output.
+pub fn execute_script_silent(vm_name: &str, script_content: &str, script_name: &str) -> Result<()> {
+    let captured = execute_script_captured(vm_name, script_content, script_name)?;
+    if captured.success() {
+        return Ok(());
+    }
+
+    let _ = flush_captured(&captured.output, script_name, &mut std::io::stderr());
+    Err(match captured.exit_code {
+        Some(code) => ClaudeVmError::CommandExitCode(code),
+        None => ClaudeVmError::LimaExecution("Script terminated by signal".to_string()),
+    })
+}
+
+/// Write buffered script output to `writer`, each line prefixed with `[prefix]`
+fn flush_captured<W: std::io::Write>(
+    output: &str,
+    prefix: &str,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    crate::utils::process::prefix_lines(output.as_bytes(), writer, prefix)
+}
+
+/// Result of a script run with captured output
+pub struct CapturedScript {
+    /// Exit code of the script (None if terminated by a signal)
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr
+    pub output: String,
+}
+
+impl CapturedScript {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Execute a script from string content in a VM, capturing its combined output.
+///
+/// Unlike `execute_script`, a failing script is not an error: the caller decides
+/// how much of the captured output to show.
+pub fn execute_script_captured(
+    vm_name: &str,
+    script_content: &str,
+    script_name: &str,
+) -> Result<CapturedScript> {
+    // Write script to temp file
+    let temp_path = format!("/tmp/{}", script_name);
+    let local_temp = std::env::temp_dir().join(script_name);
+
+    std::fs::write(&local_temp, script_content)?;
+
+    // Copy to VM
+    LimaCtl::copy(&local_temp, vm_name, &temp_path)?;
+
+    // Run with stderr merged into stdout to keep ordering
+    let run = format!("bash {} 2>&1", shell_escape(&temp_path));
+    let output = LimaCtl::shell_output(vm_name, "bash", &["-c", &run])?;
+
+    // Cleanup local temp file
+    std::fs::remove_file(&local_temp)?;
+
+    let captured = String::from_utf8_lossy(&output.stdout);
+    build_log::record(&captured);
+
+    Ok(CapturedScript {
+        exit_code: output.status.code(),
+        output: captured.into_owned(),
+    })
+}
+
+/// Select the last `max_lines` lines of `output`.
+/// Returns the selected lines and the number of earlier lines omitted.
+pub fn tail_lines(output: &str, max_lines: usize) -> (Vec<&str>, usize) {
+    let lines: Vec<&str> = output.lines().collect();
+    let skip = lines.len().saturating_sub(max_lines);
+    (lines[skip..].to_vec(), skip)
+}
+
 /// Execute a script file from the host filesystem in a VM.
 ///
 /// This function copies a script file from the host to the VM,
@@ -123,7 +224,7 @@ pub fn execute_script_file(vm_name: &str, script_path: &Path) -> Result<()> {
         .and_then(|n| n.to_str())
         .unwrap_or("script.sh");
 
-    println!("Running script file: {}", script_path.display());
+    status!("Running script file: {}", script_path.display());
 
     let temp_path = format!("/tmp/{}", script_name);
 
@@ -137,11 +238,14 @@ pub fn execute_script_file(vm_name: &str, script_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Largest README excerpt added with `context.include_readme`
+const README_MAX_BYTES: usize = 16 * 1024;
+
 /// Generate base context markdown for Claude
 ///
 /// Creates a markdown file with VM configuration, enabled capabilities,
-/// mounted directories, and user-provided instructions.
-fn generate_base_context(config: &Config) -> Result<String> {
+/// mounted directories, user-provided instructions, and optionally the project README.
+fn generate_base_context(config: &Config, project_root: &Path) -> Result<String> {
     let mut context = String::new();
 
     // Header
@@ -154,6 +258,9 @@ fn generate_base_context(config: &Config) -> Result<String> {
     context.push_str("## VM Configuration\n");
     context.push_str(&format!("- **Disk**: {} GB\n", config.vm.disk));
     context.push_str(&format!("- **Memory**: {} GB\n", config.vm.memory));
+    if let Some(swap) = config.vm.swap() {
+        context.push_str(&format!("- **Swap**: {} GB\n", swap));
+    }
     context.push('\n');
 
     // Enabled Capabilities
@@ -174,35 +281,255 @@ fn generate_base_context(config: &Config) -> Result<String> {
 
     // Mounted Directories
     context.push_str("## Mounted Directories\n");
-    let mounts = mount::compute_mounts(config.mount_conversations, &config.mounts)?;
-    if mounts.is_empty() {
+    let (shared, mounts): (Vec<_>, Vec<_>) =
+        mount::compute_mounts(&mount::MountOptions::from_config(config))?
+            .into_iter()
+            .partition(|m| m.shared);
+    let project_locations = mount::project_mount_locations(config.project_root_fallback());
+    if mounts.is_empty() && config.tmpfs_mounts.is_empty() {
         context.push_str("None\n");
     } else {
         for m in mounts {
-            let mode = if m.writable { "writable" } else { "read-only" };
+            let mode = if config.scratch && project_locations.contains(&m.location) {
+                "writable scratch overlay, changes are discarded on exit"
+            } else if m.writable {
+                "writable"
+            } else {
+                "read-only"
+            };
             let vm_path = m.mount_point.as_ref().unwrap_or(&m.location);
             context.push_str(&format!("- {} ({})\n", vm_path.display(), mode));
         }
     }
+    for tmpfs in &config.tmpfs_mounts {
+        context.push_str(&format!(
+            "- {} (tmpfs, in memory, contents are discarded on exit)\n",
+            tmpfs.path.display()
+        ));
+    }
     context.push('\n');
 
-    // User Instructions (if provided)
-    if !config.context.instructions.is_empty() {
-        context.push_str("## User Instructions\n");
-        context.push_str(&config.context.instructions);
-        if !config.context.instructions.ends_with('\n') {
-            context.push('\n');
+    // Shared dependency caches
+    if !shared.is_empty() {
+        context.push_str("## Shared Dependency Mounts\n");
+        for m in shared {
+            let mode = if m.writable { "writable" } else { "read-only" };
+            let vm_path = m.mount_point.as_ref().unwrap_or(&m.location);
+            context.push_str(&format!("- {} ({})\n", vm_path.display(), mode));
+        }
+        context.push('\n');
+    }
+
+    if let Some(project) = &config.conversation_project {
+        context.push_str("## Conversation History\n");
+        context.push_str(&format!("- Conversations are shared with the project at {}: earlier sessions there are available, and this session is saved with them.\n", project.display()));
+        context.push('\n');
+    }
+
+    if config.fresh_home {
+        context.push_str("## Session State\n");
+        context.push_str("- Fresh home: Claude runs with a temporary config directory. No conversation history, settings or memory from earlier sessions are available, and nothing written there is kept after this session.\n");
+        context.push('\n');
+    }
+
+    if config.drop_privileges {
+        context.push_str("## Privileges\n");
+        context.push_str(&format!("- Claude runs as the unprivileged user `{}`, without sudo. System packages cannot be installed (no apt), services cannot be managed, and files owned by other users cannot be changed. Ask the user to add missing tools to the template instead.\n", restricted_user::USER));
+        context.push('\n');
+    }
+
+    // Git identity (if configured)
+    if config.git.is_configured() {
+        context.push_str("## Git Identity\n");
+        if let Some(name) = &config.git.user_name {
+            context.push_str(&format!("- **User**: {}\n", name));
+        }
+        if let Some(email) = &config.git.user_email {
+            context.push_str(&format!("- **Email**: {}\n", email));
+        }
+        if let Some(key) = &config.git.signing_key {
+            context.push_str(&format!("- **Signing key**: {}\n", key));
         }
         context.push('\n');
     }
 
+    if config.git.forwards_credentials() {
+        context.push_str("## Git Credentials\n");
+        context.push_str("- HTTPS credentials for this project's remotes are forwarded from the host and kept in memory by git's credential cache: fetch and push over HTTPS work without prompting.\n");
+        context.push('\n');
+    }
+
     // Placeholder for runtime context
-    context.push_str("<!-- claude-vm-context-runtime-placeholder -->\n");
-    context.push_str("<!-- claude-vm-context-end -->\n");
+    let footer = "<!-- claude-vm-context-runtime-placeholder -->\n<!-- claude-vm-context-end -->\n";
+
+    // User Instructions (if provided), truncated when the context exceeds max_bytes
+    if !config.context.instructions.is_empty() {
+        let mut section = String::from("## User Instructions\n");
+        section.push_str(&config.context.instructions);
+        if !config.context.instructions.ends_with('\n') {
+            section.push('\n');
+        }
+        section.push('\n');
+
+        if let Some(max_bytes) = config.context.max_bytes {
+            let budget = max_bytes.saturating_sub(context.len() + footer.len());
+            section = truncate_to_bytes(&section, budget);
+        }
+        context.push_str(&section);
+    }
+
+    // Project README (if enabled and present), cut to its own cap and to what max_bytes leaves
+    if config.context.include_readme {
+        if let Ok(readme) = std::fs::read_to_string(project_root.join("README.md")) {
+            let mut section = String::from("## Project README\n");
+            section.push_str(&truncate_to_bytes(&readme, README_MAX_BYTES));
+            if !section.ends_with('\n') {
+                section.push('\n');
+            }
+            section.push('\n');
+
+            if let Some(max_bytes) = config.context.max_bytes {
+                let budget = max_bytes.saturating_sub(context.len() + footer.len());
+                section = truncate_to_bytes(&section, budget);
+            }
+            context.push_str(&section);
+        }
+    }
+
+    context.push_str(footer);
 
     Ok(context)
 }
 
+/// Marker appended to context sections cut to fit `context.max_bytes`
+const TRUNCATED_MARKER: &str = "\n[truncated]\n";
+
+/// Cut `text` so that, with the truncation marker, it fits in `max_bytes`.
+/// Cuts at the last line break when possible. The marker is always kept,
+/// even if it alone exceeds the budget.
+fn truncate_to_bytes(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+
+    let mut end = max_bytes.saturating_sub(TRUNCATED_MARKER.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let kept = &text[..end];
+    let kept = match kept.rfind('\n') {
+        Some(pos) => &kept[..pos],
+        None => kept,
+    };
+
+    format!("{}{}", kept, TRUNCATED_MARKER)
+}
+
+/// Collect runtime scripts from `[runtime] scripts` (legacy) and `[[phase.runtime]]`.
+///
+/// Missing script files follow `runtime.on_missing_script`. When unset, the legacy
+/// list warns and skips while phases fail unless `continue_on_error` is set.
+fn collect_configured_scripts(config: &Config, base_path: &Path) -> Result<Vec<RuntimeScriptInfo>> {
+    let mut script_contents: Vec<RuntimeScriptInfo> = Vec::new();
+    let policy = config.runtime.on_missing_script;
+
+    // Legacy scripts array (with deprecation warning)
+    if !config.runtime.scripts.is_empty() {
+        diagnostics::warn(
+            "deprecated-runtime-scripts",
+            "[runtime] scripts array is deprecated. Please migrate to [[phase.runtime]]\n   \
+             See: docs/configuration.md",
+        );
+
+        for script_path_str in &config.runtime.scripts {
+            let script_path = PathBuf::from(script_path_str);
+            if !script_path.exists() {
+                if policy == Some(MissingScriptPolicy::Error) {
+                    return Err(ClaudeVmError::ScriptNotFound(script_path));
+                }
+                diagnostics::warn(
+                    "script-not-found",
+                    format!("Runtime script not found: {}", script_path_str),
+                );
+                continue;
+            }
+            let content = std::fs::read_to_string(&script_path)?;
+            let name = script_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("script.sh")
+                .to_string();
+            script_contents.push((name, content, HashMap::new(), false, None, false, None));
+            // Not sourced, no condition, no continue_on_error, no timeout
+        }
+    }
+
+    // New phase-based runtime scripts
+    for phase in &config.phase.runtime {
+        // Validate phase and emit warnings for potential issues
+        phase.validate_and_warn();
+
+        // Get scripts for this phase
+        let scripts = match phase.get_available_scripts(base_path) {
+            Ok((scripts, missing)) if missing.is_empty() => Ok(scripts),
+            Ok((scripts, missing)) if policy == Some(MissingScriptPolicy::Warn) => {
+                for path in &missing {
+                    diagnostics::warn(
+                        "script-not-found",
+                        format!(
+                            "Runtime script not found in phase '{}': {}",
+                            phase.name,
+                            path.display()
+                        ),
+                    );
+                }
+                Ok(scripts)
+            }
+            Ok((_, missing)) => Err(ClaudeVmError::ScriptNotFound(missing[0].clone())),
+            Err(e) => Err(e),
+        };
+
+        let scripts = match scripts {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!(
+                    "\n❌ Failed to load scripts for runtime phase '{}'",
+                    phase.name
+                );
+                eprintln!("   Error: {}", e);
+                if !phase.script_files.is_empty() {
+                    eprintln!("   Script files:");
+                    for file in &phase.script_files {
+                        eprintln!("   - {}", file);
+                    }
+                    eprintln!("\n   Hint: Check that script files exist and are readable");
+                }
+
+                if phase.continue_on_error {
+                    eprintln!("   ℹ Continuing due to continue_on_error=true");
+                    continue;
+                } else {
+                    return Err(e);
+                }
+            }
+        };
+
+        for (name, content) in scripts {
+            script_contents.push((
+                name,
+                phase.bash_script(&content),
+                config.phase.env_for(phase),
+                phase.source,
+                phase.when.clone(), // Store condition for runtime evaluation
+                phase.continue_on_error,
+                phase.timeout_secs,
+            ));
+        }
+    }
+
+    Ok(script_contents)
+}
+
 /// Execute a command with runtime scripts using an entrypoint pattern.
 ///
 /// This function runs all runtime scripts followed by the main command in a single
@@ -256,7 +583,7 @@ pub fn execute_command_with_runtime_scripts(
     args: &[&str],
     env_vars: &HashMap<String, String>,
 ) -> Result<()> {
-    // Collect all runtime scripts as (name, content, env_vars, source, when_condition, continue_on_error) tuples
+    // Collect all runtime scripts as (name, content, env_vars, source, when_condition, continue_on_error, timeout_secs) tuples
     let mut script_contents: Vec<RuntimeScriptInfo> = Vec::new();
 
     // First, check for project-specific runtime script
@@ -268,82 +595,18 @@ pub fn execute_command_with_runtime_scripts(
             .and_then(|n| n.to_str())
             .unwrap_or("runtime.sh")
             .to_string();
-        script_contents.push((name, content, HashMap::new(), false, None, false));
-        // No env, not sourced, no condition, no continue_on_error
+        script_contents.push((name, content, HashMap::new(), false, None, false, None));
+        // No env, not sourced, no condition, no continue_on_error, no timeout
     }
 
-    // Then add custom runtime scripts from config (legacy - with deprecation warning)
-    if !config.runtime.scripts.is_empty() {
-        eprintln!(
-            "⚠ Warning: [runtime] scripts array is deprecated. Please migrate to [[phase.runtime]]"
-        );
-        eprintln!("   See: docs/configuration.md");
-
-        for script_path_str in &config.runtime.scripts {
-            let script_path = PathBuf::from(script_path_str);
-            if !script_path.exists() {
-                eprintln!("⚠ Warning: Runtime script not found: {}", script_path_str);
-                continue;
-            }
-            let content = std::fs::read_to_string(&script_path)?;
-            let name = script_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("script.sh")
-                .to_string();
-            script_contents.push((name, content, HashMap::new(), false, None, false));
-            // Not sourced, no condition, no continue_on_error
-        }
-    }
-
-    // New phase-based runtime scripts
-    for phase in &config.phase.runtime {
-        // Validate phase and emit warnings for potential issues
-        phase.validate_and_warn();
-
-        // Get scripts for this phase
-        let scripts = match phase.get_scripts(project.root()) {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!(
-                    "\n❌ Failed to load scripts for runtime phase '{}'",
-                    phase.name
-                );
-                eprintln!("   Error: {}", e);
-                if !phase.script_files.is_empty() {
-                    eprintln!("   Script files:");
-                    for file in &phase.script_files {
-                        eprintln!("   - {}", file);
-                    }
-                    eprintln!("\n   Hint: Check that script files exist and are readable");
-                }
-
-                if phase.continue_on_error {
-                    eprintln!("   ℹ Continuing due to continue_on_error=true");
-                    continue;
-                } else {
-                    return Err(e);
-                }
-            }
-        };
-
-        for (name, content) in scripts {
-            script_contents.push((
-                name,
-                content,
-                phase.env.clone(),
-                phase.source,
-                phase.when.clone(), // Store condition for runtime evaluation
-                phase.continue_on_error,
-            ));
-        }
-    }
+    // Then custom runtime scripts from config (legacy and phase-based)
+    script_contents.extend(collect_configured_scripts(config, project.root())?);
 
     // Now convert script_contents to files and collect PathBufs for copying
     let mut scripts = Vec::new();
     let temp_dir = std::env::temp_dir();
 
-    for (i, (name, content, _env, _source, _when, _continue_on_error)) in
+    for (i, (name, content, _env, _source, _when, _continue_on_error, _timeout)) in
         script_contents.iter().enumerate()
     {
         // Sanitize filename to prevent issues with special characters
@@ -359,11 +622,11 @@ pub fn execute_command_with_runtime_scripts(
     }
 
     // Generate and copy base context
-    let base_context = generate_base_context(config)?;
+    let base_context = generate_base_context(config, project.root())?;
     let temp_dir = std::env::temp_dir();
     let pid = std::process::id();
     let context_file = temp_dir.join(format!("claude-vm-context-{}.md", pid));
-    std::fs::write(&context_file, base_context)?;
+    std::fs::write(&context_file, &base_context)?;
 
     // Copy context to VM with unique name to avoid race conditions
     let vm_context_path = format!("/tmp/claude-vm-context-base-{}.md", pid);
@@ -425,67 +688,526 @@ pub fn execute_command_with_runtime_scripts(
     entrypoint.push_str("# Create context directory for runtime scripts\n");
     entrypoint.push_str("mkdir -p ~/.claude-vm/context\n\n");
 
+    // Scratch mode: layer a writable overlay over the read-only project mounts
+    if config.scratch {
+        entrypoint.push_str(&build_scratch_overlay(&mount::project_mount_locations(
+            config.project_root_fallback(),
+        )));
+    }
+
+    // Memory-backed scratch directories
+    if !config.tmpfs_mounts.is_empty() {
+        entrypoint.push_str(&build_tmpfs_mounts(&config.tmpfs_mounts));
+    }
+
+    // Apply configured git identity (overrides identity copied from host at setup)
+    if config.git.is_configured() {
+        for warning in config.git.validate(&config.tools) {
+            diagnostics::warn("git-identity", warning);
+        }
+        entrypoint.push_str("# Configured git identity\n");
+        entrypoint.push_str(&config.git.to_shell_commands());
+        entrypoint.push('\n');
+    }
+
     // Export capability-specific environment variables
     entrypoint.push_str("# Export capability environment variables\n");
 
     // Network isolation environment variables
-    if config.security.network.enabled {
-        entrypoint.push_str("export NETWORK_ISOLATION_ENABLED=true\n");
-        let mode = match config.security.network.mode {
-            crate::config::PolicyMode::Allowlist => "allowlist",
-            crate::config::PolicyMode::Denylist => "denylist",
+    let agent_domains = missing_agent_domains(config);
+    if !agent_domains.is_empty() {
+        diagnostics::warn(
+            "agent-domains",
+            format!(
+                "Network isolation is in allowlist mode: also allowing {}, which the agent needs",
+                agent_domains.join(", ")
+            ),
+        );
+    }
+    for (key, value) in network_env(config) {
+        let escaped_value = value.replace('\'', "'\\''");
+        entrypoint.push_str(&format!("export {}='{}'\n", key, escaped_value));
+    }
+    entrypoint.push('\n');
+
+    // Source capability runtime scripts first
+    entrypoint.push_str(&build_capability_runtime_sources(config));
+
+    // Then run user runtime scripts
+    entrypoint.push_str(&build_user_runtime_scripts(
+        &script_contents,
+        &vm_script_paths,
+    ));
+
+    // Generate final CLAUDE.md with runtime context (only if Claude Code is installed)
+    entrypoint.push_str(
+        "# Generate final CLAUDE.md with runtime context (skip if Claude not installed)\n",
+    );
+    entrypoint.push_str("if command -v claude >/dev/null 2>&1; then\n");
+    entrypoint.push_str(&format!(
+        "  cp {} ~/.claude/CLAUDE.md.new\n\n",
+        vm_context_path
+    ));
+
+    entrypoint.push_str("  # Add runtime script results if any exist\n");
+    entrypoint.push_str("  if [ -d ~/.claude-vm/context ] && [ \"$(ls -A ~/.claude-vm/context/*.txt 2>/dev/null)\" ]; then\n");
+    entrypoint.push_str("    results=~/.claude-vm/runtime-context.md\n");
+    entrypoint.push_str("    {\n");
+    entrypoint.push_str("      printf '## Runtime Script Results\\n\\n'\n");
+    entrypoint.push_str("      for context_file in ~/.claude-vm/context/*.txt; do\n");
+    entrypoint.push_str("        if [ -f \"$context_file\" ]; then\n");
+    entrypoint.push_str("          printf '### %s\\n\\n' \"$(basename \"$context_file\" .txt)\"\n");
+    entrypoint.push_str("          cat \"$context_file\"\n");
+    entrypoint.push_str("          printf '\\n'\n");
+    entrypoint.push_str("        fi\n");
+    entrypoint.push_str("      done\n");
+    entrypoint.push_str("    } > \"$results\"\n\n");
+
+    // Runtime results are the first thing cut when the context exceeds max_bytes
+    if let Some(max_bytes) = config.context.max_bytes {
+        let budget = max_bytes.saturating_sub(base_context.len());
+        entrypoint.push_str(&build_runtime_truncation(budget));
+    }
+
+    entrypoint.push_str("    sed -i \"/<!-- claude-vm-context-runtime-placeholder -->/r $results\" ~/.claude/CLAUDE.md.new\n");
+    entrypoint.push_str("    rm -f \"$results\"\n");
+    entrypoint.push_str("  fi\n\n");
+
+    entrypoint.push_str("  # Remove the placeholder marker\n");
+    entrypoint.push_str(
+        "  sed -i '/<!-- claude-vm-context-runtime-placeholder -->/d' ~/.claude/CLAUDE.md.new\n\n",
+    );
+
+    entrypoint.push_str("  # Merge with existing CLAUDE.md if present\n");
+    entrypoint.push_str("  if [ -f ~/.claude/CLAUDE.md ]; then\n");
+    entrypoint
+        .push_str("    if grep -q '<!-- claude-vm-context-start -->' ~/.claude/CLAUDE.md; then\n");
+    entrypoint
+        .push_str("      # Replace content between markers, preserving user content position\n");
+    entrypoint.push_str("      awk '\n");
+    entrypoint.push_str("        /<!-- claude-vm-context-start -->/ { skip=1; next }\n");
+    entrypoint.push_str("        /<!-- claude-vm-context-end -->/ { skip=0; next }\n");
+    entrypoint.push_str("        !skip\n");
+    entrypoint.push_str("      ' ~/.claude/CLAUDE.md > ~/.claude/CLAUDE.md.old\n\n");
+    entrypoint.push_str(
+        "      cat ~/.claude/CLAUDE.md.old ~/.claude/CLAUDE.md.new > ~/.claude/CLAUDE.md\n",
+    );
+    entrypoint.push_str("    else\n");
+    entrypoint.push_str("      # Append our context to existing content\n");
+    entrypoint.push_str(
+        "      cat ~/.claude/CLAUDE.md ~/.claude/CLAUDE.md.new > ~/.claude/CLAUDE.md.tmp\n",
+    );
+    entrypoint.push_str("      mv ~/.claude/CLAUDE.md.tmp ~/.claude/CLAUDE.md\n");
+    entrypoint.push_str("    fi\n");
+    entrypoint.push_str("  else\n");
+    entrypoint.push_str("    # No existing file, use our generated context\n");
+    entrypoint.push_str("    mv ~/.claude/CLAUDE.md.new ~/.claude/CLAUDE.md\n");
+    entrypoint.push_str("  fi\n");
+    entrypoint.push_str("fi\n\n");
+
+    // Point the agent at a clean config directory, seeded with credentials and context only
+    if config.fresh_home {
+        entrypoint.push_str(&build_fresh_home());
+    }
+
+    // Forwarded git credentials only live as long as the session
+    if config.git.forwards_credentials() {
+        entrypoint.push_str("# Clear the forwarded git credentials when the session ends\n");
+        entrypoint.push_str(&on_session_exit("git credential-cache exit"));
+    }
+
+    entrypoint.push_str("# Cleanup temporary files\n");
+    entrypoint.push_str(&format!(
+        "rm -f ~/.claude/CLAUDE.md.new ~/.claude/CLAUDE.md.old {}\n\n",
+        vm_context_path
+    ));
+
+    // Exec main command - $@ contains all positional parameters
+    if config.agent_retries > 0 {
+        // Stay in the shell to restart the command after a crash
+        let command = if config.drop_privileges {
+            entrypoint.push_str(&restricted_user::build_switch_home(
+                &mount::project_mount_locations(config.project_root_fallback()),
+            ));
+            restricted_user::switched_command()
+        } else {
+            "\"$@\"".to_string()
         };
-        entrypoint.push_str(&format!("export POLICY_MODE={}\n", mode));
+        entrypoint.push_str(&build_retry_loop(&command, config.agent_retries));
+    } else if config.drop_privileges {
+        entrypoint.push_str(&restricted_user::build_entrypoint_switch(
+            &mount::project_mount_locations(config.project_root_fallback()),
+        ));
+    } else {
+        entrypoint.push_str("# Execute main command (replaces shell process)\n");
+        entrypoint.push_str("exec \"$@\"\n");
+    }
+
+    // Execute entrypoint with main command as positional parameters
+    // bash -c 'script' -- cmd arg1 arg2
+    // The '--' becomes $0, cmd becomes $1, etc. Then "$@" expands to cmd arg1 arg2
+    let mut shell_args = vec!["-c", entrypoint.as_str(), "--"];
+    shell_args.push(cmd);
+    shell_args.extend(args);
+
+    events::emit(Event::SessionStart {
+        vm: vm_name.to_string(),
+        command: cmd.to_string(),
+    });
 
-        if !config.security.network.allowed_domains.is_empty() {
-            let allowed = config.security.network.allowed_domains.join(",");
-            entrypoint.push_str(&format!("export ALLOWED_DOMAINS='{}'\n", allowed));
+    let result = LimaCtl::shell(
+        vm_name,
+        workdir,
+        "bash",
+        &shell_args,
+        config.forward_ssh_agent,
+    );
+
+    events::emit(Event::SessionEnd {
+        vm: vm_name.to_string(),
+        exit_code: events::exit_code_of(&result),
+    });
+
+    result
+}
+
+/// Exit codes of a process killed by a signal that means it crashed:
+/// SIGILL, SIGABRT, SIGBUS, SIGFPE, SIGKILL (the OOM killer) and SIGSEGV.
+/// Clean exits, error exit codes and interrupts (SIGHUP, SIGINT, SIGTERM) are intentional.
+pub const CRASH_EXIT_CODES: &[i32] = &[132, 134, 135, 136, 137, 139];
+
+/// Build the entrypoint section running `command` and restarting it, up to
+/// `max_restarts` times, when it exits with one of [`CRASH_EXIT_CODES`].
+/// Restarts pass `--continue` to pick up the crashed conversation, unless the
+/// command already continues or resumes one.
+fn build_retry_loop(command: &str, max_restarts: u32) -> String {
+    let codes: Vec<String> = CRASH_EXIT_CODES.iter().map(i32::to_string).collect();
+    format!(
+        "# Execute main command, restarting it after a crash\n\
+         claude_vm_restarts=0\n\
+         while true; do\n\
+         \x20 claude_vm_status=0\n\
+         \x20 {command} || claude_vm_status=$?\n\
+         \x20 case \"$claude_vm_status\" in\n\
+         \x20   {codes})\n\
+         \x20     if [ \"$claude_vm_restarts\" -lt {max} ]; then\n\
+         \x20       if [ \"$claude_vm_restarts\" -eq 0 ]; then\n\
+         \x20         claude_vm_continue=--continue\n\
+         \x20         for claude_vm_arg in \"$@\"; do\n\
+         \x20           case \"$claude_vm_arg\" in\n\
+         \x20             --continue|-c|--resume|--resume=*|-r) claude_vm_continue= ;;\n\
+         \x20           esac\n\
+         \x20         done\n\
+         \x20         set -- \"$@\" $claude_vm_continue\n\
+         \x20       fi\n\
+         \x20       claude_vm_restarts=$((claude_vm_restarts + 1))\n\
+         \x20       echo \"claude-vm: command crashed (exit $claude_vm_status), restarting ($claude_vm_restarts/{max})...\" >&2\n\
+         \x20       sleep 1\n\
+         \x20       continue\n\
+         \x20     fi\n\
+         \x20     ;;\n\
+         \x20 esac\n\
+         \x20 exit \"$claude_vm_status\"\n\
+         done\n",
+        command = command,
+        codes = codes.join("|"),
+        max = max_restarts
+    )
+}
+
+/// Build the entrypoint section giving the agent a fresh config directory.
+///
+/// Only the credentials, the onboarding state and the claude-vm context are carried
+/// over; settings, history, todos and other state written by earlier sessions are not.
+fn build_fresh_home() -> String {
+    let marker = AgentPaths::SESSION_CONFIG_DIR_FILE;
+    let mut script = String::from(
+        "# Fresh home: clean agent config directory (removed when the session ends)\n",
+    );
+    script.push_str("CLAUDE_VM_HOME=$(mktemp -d /tmp/claude-vm-home.XXXXXX)\n");
+    // With --use-vm, the VM outlives the session
+    script.push_str(&on_session_exit(&format!(
+        "rm -rf \"$CLAUDE_VM_HOME\" {}",
+        marker
+    )));
+    script.push_str("mkdir -p \"$CLAUDE_VM_HOME/.claude\"\n");
+    script.push_str("if [ -f ~/.claude/.credentials.json ]; then\n");
+    script.push_str("  cp ~/.claude/.credentials.json \"$CLAUDE_VM_HOME/.claude/\"\n");
+    script.push_str("fi\n");
+    script.push_str("if [ -f ~/.claude.json ]; then\n");
+    script.push_str("  jq 'with_entries(select(.key | IN(\"hasCompletedOnboarding\", \"oauthAccount\", \"userID\", \"mcpServers\")))' \\\n");
+    script.push_str(
+        "    ~/.claude.json > \"$CLAUDE_VM_HOME/.claude/.claude.json\" 2>/dev/null || true\n",
+    );
+    script.push_str("fi\n");
+    script.push_str("if [ -f ~/.claude/CLAUDE.md ]; then\n");
+    script.push_str(
+        "  sed -n '/<!-- claude-vm-context-start -->/,/<!-- claude-vm-context-end -->/p' \\\n",
+    );
+    script.push_str("    ~/.claude/CLAUDE.md > \"$CLAUDE_VM_HOME/.claude/CLAUDE.md\"\n");
+    script.push_str("fi\n");
+    script.push_str("export CLAUDE_CONFIG_DIR=\"$CLAUDE_VM_HOME/.claude\"\n");
+    // Let other shells in the VM (shell --agent-logs) find it
+    script.push_str(&format!("echo \"$CLAUDE_CONFIG_DIR\" > {}\n\n", marker));
+    script
+}
+
+/// Entrypoint line running `command` in the background once the session command
+/// exits. The command replaces the entrypoint shell (exec), so a trap would never
+/// run: a watcher waits for the shell's PID to go away instead.
+fn on_session_exit(command: &str) -> String {
+    format!(
+        "(while kill -0 $$ 2>/dev/null; do sleep 1; done; {}) </dev/null >/dev/null 2>&1 &\n",
+        command
+    )
+}
+
+/// Entrypoint section sourcing the capability runtime scripts (`<id>.sh`), each
+/// with its `[capabilities.<id>]` options exported as `CAPABILITY_CONFIG_*`
+fn build_capability_runtime_sources(config: &Config) -> String {
+    let mut script = String::from("# Source capability runtime scripts\n");
+    script.push_str(&format!("if [ -d {} ]; then\n", RUNTIME_SCRIPT_DIR));
+    script.push_str(&format!(
+        "  for script in {}/*.sh; do\n",
+        RUNTIME_SCRIPT_DIR
+    ));
+    script.push_str("    if [ -f \"$script\" ]; then\n");
+
+    let mut ids: Vec<&String> = config.capability_config.keys().collect();
+    ids.sort();
+    let options: Vec<(&String, BTreeMap<String, String>)> = ids
+        .into_iter()
+        .map(|id| {
+            (
+                id,
+                config
+                    .capability_env(id)
+                    .into_iter()
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        })
+        .filter(|(_, env)| !env.is_empty())
+        .collect();
+    if !options.is_empty() {
+        script.push_str("      case \"$(basename \"$script\" .sh)\" in\n");
+        for (id, env) in &options {
+            let exports: Vec<String> = env
+                .iter()
+                .map(|(key, value)| format!("export {}={}", key, shell_escape(value)))
+                .collect();
+            script.push_str(&format!(
+                "        {}) {} ;;\n",
+                shell_escape(id),
+                exports.join("; ")
+            ));
         }
+        script.push_str("      esac\n");
+    }
+
+    script.push_str("      . \"$script\" 2>&1 || echo \"Warning: Failed to source $script\"\n");
+    if !options.is_empty() {
+        script.push_str("      unset \"${!CAPABILITY_CONFIG_@}\"\n");
+    }
+    script.push_str("    fi\n");
+    script.push_str("  done\n");
+    script.push_str("fi\n\n");
+    script
+}
+
+/// Domains the agent needs to reach (API, and login for OAuth and API keys),
+/// allowed automatically in allowlist mode
+pub const AGENT_NETWORK_DOMAINS: [&str; 3] =
+    ["api.anthropic.com", "console.anthropic.com", "claude.ai"];
+
+/// Agent domains that an allowlist does not cover yet (none outside allowlist mode)
+pub fn missing_agent_domains(config: &Config) -> Vec<&'static str> {
+    let network = &config.security.network;
+    if !network.enabled || network.mode != crate::config::PolicyMode::Allowlist {
+        return Vec::new();
+    }
+    AGENT_NETWORK_DOMAINS
+        .into_iter()
+        .filter(|domain| !matches_any(domain, &network.allowed_domains))
+        .collect()
+}
 
-        if !config.security.network.blocked_domains.is_empty() {
-            let blocked = config.security.network.blocked_domains.join(",");
-            entrypoint.push_str(&format!("export BLOCKED_DOMAINS='{}'\n", blocked));
+/// A variable exported by the session entrypoint
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportedVar {
+    pub name: String,
+    pub value: String,
+    /// Where the value comes from, e.g. "network isolation"
+    pub source: String,
+    /// Only set while a runtime script runs, not for the session command
+    pub script_only: bool,
+}
+
+/// Variables the entrypoint exports on top of the CLI ones, in export order:
+/// network isolation, capability options, runtime phase env, then the fresh home.
+/// Built from the same helpers as the entrypoint, for `shell --dump-env`.
+pub fn entrypoint_env(config: &Config) -> Vec<ExportedVar> {
+    let var = |name: &str, value: String, source: String, script_only: bool| ExportedVar {
+        name: name.to_string(),
+        value,
+        source,
+        script_only,
+    };
+    let mut vars: Vec<ExportedVar> = network_env(config)
+        .into_iter()
+        .map(|(name, value)| var(name, value, "network isolation".to_string(), false))
+        .collect();
+
+    // Exported while the capability's runtime script is sourced, then unset
+    let mut ids: Vec<&String> = config.capability_config.keys().collect();
+    ids.sort();
+    for id in ids {
+        let options: BTreeMap<String, String> = config.capability_env(id).into_iter().collect();
+        for (name, value) in options {
+            vars.push(var(&name, value, format!("[capabilities.{}]", id), true));
         }
+    }
 
-        if !config.security.network.bypass_domains.is_empty() {
-            let bypass = config.security.network.bypass_domains.join(",");
-            entrypoint.push_str(&format!("export BYPASS_DOMAINS='{}'\n", bypass));
+    // Sourced phases export into the session, others run in a subshell
+    for phase in &config.phase.runtime {
+        let env: BTreeMap<String, String> = config.phase.env_for(phase).into_iter().collect();
+        for (name, value) in env {
+            let source = format!("runtime phase '{}'", phase.name);
+            vars.push(var(&name, value, source, !phase.source));
         }
+    }
 
-        entrypoint.push_str(&format!(
-            "export BLOCK_TCP_UDP={}\n",
-            config.security.network.block_tcp_udp
+    if config.fresh_home {
+        vars.push(var(
+            "CLAUDE_CONFIG_DIR",
+            "/tmp/claude-vm-home.XXXXXX/.claude".to_string(),
+            "--fresh-home".to_string(),
+            false,
         ));
-        entrypoint.push_str(&format!(
-            "export BLOCK_PRIVATE_NETWORKS={}\n",
-            config.security.network.block_private_networks
+    }
+    vars
+}
+
+/// Environment variables configuring the network isolation capability,
+/// exported by the entrypoint before runtime scripts are sourced
+pub fn network_env(config: &Config) -> Vec<(&'static str, String)> {
+    let network = &config.security.network;
+    if !network.enabled {
+        return Vec::new();
+    }
+
+    let mode = match network.mode {
+        crate::config::PolicyMode::Allowlist => "allowlist",
+        crate::config::PolicyMode::Denylist => "denylist",
+    };
+    let mut vars = vec![
+        ("NETWORK_ISOLATION_ENABLED", "true".to_string()),
+        ("POLICY_MODE", mode.to_string()),
+    ];
+    // CIDR ranges are matched against IP addresses, separately from domain patterns
+    let (allowed_cidrs, mut allowed_domains): (Vec<&String>, Vec<&String>) =
+        network.allowed_domains.iter().partition(|d| is_cidr(d));
+    let agent_domains: Vec<String> = missing_agent_domains(config)
+        .into_iter()
+        .map(String::from)
+        .collect();
+    allowed_domains.extend(&agent_domains);
+    let (blocked_cidrs, blocked_domains): (Vec<&String>, Vec<&String>) =
+        network.blocked_domains.iter().partition(|d| is_cidr(d));
+    let bypass_domains: Vec<&String> = network.bypass_domains.iter().collect();
+    for (key, entries) in [
+        ("ALLOWED_DOMAINS", &allowed_domains),
+        ("BLOCKED_DOMAINS", &blocked_domains),
+        ("BYPASS_DOMAINS", &bypass_domains),
+        ("ALLOWED_CIDRS", &allowed_cidrs),
+        ("BLOCKED_CIDRS", &blocked_cidrs),
+    ] {
+        if !entries.is_empty() {
+            let entries: Vec<&str> = entries.iter().map(|e| e.as_str()).collect();
+            vars.push((key, entries.join(",")));
+        }
+    }
+    vars.push(("BLOCK_TCP_UDP", network.block_tcp_udp.to_string()));
+    vars.push((
+        "BLOCK_PRIVATE_NETWORKS",
+        network.block_private_networks.to_string(),
+    ));
+    vars.push((
+        "BLOCK_METADATA_SERVICES",
+        network.block_metadata_services.to_string(),
+    ));
+    vars.push(("NETWORK_LOG_MAX_MB", network.max_log_mb().to_string()));
+    vars
+}
+
+/// Build the entrypoint section that mounts a tmpfs-backed overlayfs on top of
+/// each read-only project mount, so writes land in memory and vanish on teardown.
+fn build_scratch_overlay(locations: &[PathBuf]) -> String {
+    let mut script = String::from(
+        "# Scratch mode: writable overlay over read-only project mounts (discarded on exit)\n",
+    );
+    script.push_str("CLAUDE_VM_SCRATCH=$(mktemp -d /tmp/claude-vm-scratch.XXXXXX)\n");
+    script.push_str("sudo mount -t tmpfs tmpfs \"$CLAUDE_VM_SCRATCH\"\n");
+    script.push_str("sudo chown \"$(id -u):$(id -g)\" \"$CLAUDE_VM_SCRATCH\"\n");
+
+    for (i, location) in locations.iter().enumerate() {
+        let path = shell_escape(&location.to_string_lossy());
+        script.push_str(&format!(
+            "mkdir -p \"$CLAUDE_VM_SCRATCH/{i}/upper\" \"$CLAUDE_VM_SCRATCH/{i}/work\"\n"
         ));
-        entrypoint.push_str(&format!(
-            "export BLOCK_METADATA_SERVICES={}\n",
-            config.security.network.block_metadata_services
+        script.push_str(&format!(
+            "sudo mount -t overlay overlay -o lowerdir={path},upperdir=\"$CLAUDE_VM_SCRATCH/{i}/upper\",workdir=\"$CLAUDE_VM_SCRATCH/{i}/work\" {path}\n"
         ));
     }
-    entrypoint.push('\n');
 
-    // Source capability runtime scripts first
-    entrypoint.push_str("# Source capability runtime scripts\n");
-    entrypoint.push_str(&format!("if [ -d {} ]; then\n", RUNTIME_SCRIPT_DIR));
-    entrypoint.push_str(&format!(
-        "  for script in {}/*.sh; do\n",
-        RUNTIME_SCRIPT_DIR
-    ));
-    entrypoint.push_str("    if [ -f \"$script\" ]; then\n");
-    entrypoint.push_str("      . \"$script\" 2>&1 || echo \"Warning: Failed to source $script\"\n");
-    entrypoint.push_str("    fi\n");
-    entrypoint.push_str("  done\n");
-    entrypoint.push_str("fi\n\n");
+    // Re-enter the working directory so the shell sees the overlay, not the base mount
+    script.push_str("cd \"$PWD\"\n");
+    script.push_str(
+        "echo 'Scratch mode: project changes will be discarded when the VM exits' >&2\n\n",
+    );
+    script
+}
 
-    // Then run user runtime scripts
-    entrypoint.push_str("# User runtime scripts - executed in order\n");
+/// Build the entrypoint section that cuts the runtime results file (`$results`)
+/// down to `budget` bytes, ending with the truncation marker
+fn build_runtime_truncation(budget: usize) -> String {
+    let keep = budget.saturating_sub(TRUNCATED_MARKER.len());
+    let marker = TRUNCATED_MARKER.replace('\n', "\\n");
+    format!(
+        "    # Keep the context within context.max_bytes\n\
+         \x20   if [ \"$(wc -c < \"$results\")\" -gt {budget} ]; then\n\
+         \x20     head -c {keep} \"$results\" > \"$results.tmp\"\n\
+         \x20     printf '{marker}' >> \"$results.tmp\"\n\
+         \x20     mv \"$results.tmp\" \"$results\"\n\
+         \x20   fi\n\n"
+    )
+}
+
+/// Build the entrypoint section that mounts each requested tmpfs, writable by the session user
+fn build_tmpfs_mounts(mounts: &[mount::TmpfsMount]) -> String {
+    let mut script = String::from("# tmpfs scratch space (discarded on exit)\n");
+    for tmpfs in mounts {
+        let path = shell_escape(&tmpfs.path.to_string_lossy());
+        let options = match &tmpfs.size {
+            Some(size) => format!("size={},mode=1777", size),
+            None => "mode=1777".to_string(),
+        };
+        script.push_str(&format!("sudo mkdir -p {path}\n"));
+        script.push_str(&format!("sudo mount -t tmpfs -o {options} tmpfs {path}\n"));
+    }
+    script.push('\n');
+    script
+}
+
+/// Entrypoint lines running the user runtime scripts, in order.
+///
+/// A script with a timeout runs under `timeout`: when killed, the session is
+/// aborted, or continues with a warning when the phase sets `continue_on_error`.
+fn build_user_runtime_scripts(scripts: &[RuntimeScriptInfo], vm_script_paths: &[String]) -> String {
+    let mut entrypoint = String::from("# User runtime scripts - executed in order\n");
 
     for (i, vm_path) in vm_script_paths.iter().enumerate() {
-        let (name, _content, script_env, source_script, when_condition, continue_on_error) =
-            &script_contents[i];
+        let (name, _content, script_env, source_script, when_condition, continue_on_error, timeout) =
+            &scripts[i];
 
         // Wrap in conditional block if 'when' is specified
         if let Some(condition) = when_condition {
@@ -502,6 +1224,30 @@ pub fn execute_command_with_runtime_scripts(
         // Determine command: 'source' (or '.') if sourced, 'bash' otherwise
         let run_cmd = if *source_script { "." } else { "bash" };
 
+        // Use shell_escape to prevent injection attacks
+        let invocation = match timeout {
+            Some(secs) => format!("timeout {} {} {}", secs, run_cmd, shell_escape(vm_path)),
+            None => format!("{} {}", run_cmd, shell_escape(vm_path)),
+        };
+        let on_failure = match (timeout, *continue_on_error) {
+            (None, true) => " || true".to_string(),
+            (None, false) => String::new(),
+            (Some(secs), true) => format!(
+                " || {{ rc=$?; if [ $rc -eq 124 ]; then echo {} >&2; fi; }}",
+                shell_escape(&format!(
+                    "Warning: runtime script '{}' timed out after {}s, continuing (continue_on_error)",
+                    name, secs
+                ))
+            ),
+            (Some(secs), false) => format!(
+                " || {{ rc=$?; if [ $rc -eq 124 ]; then echo {} >&2; fi; exit $rc; }}",
+                shell_escape(&format!(
+                    "Runtime script '{}' timed out after {}s",
+                    name, secs
+                ))
+            ),
+        };
+
         // Set phase-specific environment variables if any
         if !script_env.is_empty() {
             entrypoint.push_str("  # Phase-specific environment variables\n");
@@ -511,45 +1257,20 @@ pub fn execute_command_with_runtime_scripts(
                 entrypoint.push_str("  (\n"); // Start subshell to isolate env vars
             }
 
+            let indent = if *source_script { "  " } else { "    " };
             for (key, value) in script_env {
                 let escaped_value = value.replace('\'', "'\\''");
-                let indent = if *source_script { "  " } else { "    " };
                 entrypoint.push_str(&format!("{}export {}='{}'\n", indent, key, escaped_value));
             }
 
-            // Use shell_escape to prevent injection attacks
-            let indent = if *source_script { "  " } else { "    " };
-            if *continue_on_error {
-                entrypoint.push_str(&format!(
-                    "{}{} {} || true\n",
-                    indent,
-                    run_cmd,
-                    shell_escape(vm_path)
-                ));
-            } else {
-                entrypoint.push_str(&format!(
-                    "{}{} {}\n",
-                    indent,
-                    run_cmd,
-                    shell_escape(vm_path)
-                ));
-            }
+            entrypoint.push_str(&format!("{}{}{}\n", indent, invocation, on_failure));
 
             if !*source_script {
                 entrypoint.push_str("  )\n"); // End subshell
             }
             entrypoint.push('\n');
         } else {
-            // Use shell_escape to prevent injection attacks
-            if *continue_on_error {
-                entrypoint.push_str(&format!(
-                    "  {} {} || true\n\n",
-                    run_cmd,
-                    shell_escape(vm_path)
-                ));
-            } else {
-                entrypoint.push_str(&format!("  {} {}\n\n", run_cmd, shell_escape(vm_path)));
-            }
+            entrypoint.push_str(&format!("  {}{}\n\n", invocation, on_failure));
         }
 
         // Close conditional block if 'when' was specified
@@ -558,91 +1279,7 @@ pub fn execute_command_with_runtime_scripts(
         }
     }
 
-    // Generate final CLAUDE.md with runtime context (only if Claude Code is installed)
-    entrypoint.push_str(
-        "# Generate final CLAUDE.md with runtime context (skip if Claude not installed)\n",
-    );
-    entrypoint.push_str("if command -v claude >/dev/null 2>&1; then\n");
-    entrypoint.push_str(&format!(
-        "  cp {} ~/.claude/CLAUDE.md.new\n\n",
-        vm_context_path
-    ));
-
-    entrypoint.push_str("  # Add runtime script results if any exist\n");
-    entrypoint.push_str("  if [ -d ~/.claude-vm/context ] && [ \"$(ls -A ~/.claude-vm/context/*.txt 2>/dev/null)\" ]; then\n");
-    entrypoint.push_str("    # Insert runtime context section header\n");
-    entrypoint.push_str("    sed -i '/<!-- claude-vm-context-runtime-placeholder -->/i ## Runtime Script Results\\n' ~/.claude/CLAUDE.md.new\n\n");
-
-    entrypoint.push_str("    # Add each context file\n");
-    entrypoint.push_str("    for context_file in ~/.claude-vm/context/*.txt; do\n");
-    entrypoint.push_str("      if [ -f \"$context_file\" ]; then\n");
-    entrypoint.push_str("        name=$(basename \"$context_file\" .txt)\n");
-    entrypoint.push_str("        # Insert subsection header\n");
-    entrypoint.push_str("        sed -i \"/<!-- claude-vm-context-runtime-placeholder -->/i ### $name\\n\" ~/.claude/CLAUDE.md.new\n");
-    entrypoint.push_str("        # Insert file contents\n");
-    entrypoint.push_str("        sed -i \"/### $name/r $context_file\" ~/.claude/CLAUDE.md.new\n");
-    entrypoint.push_str("        # Add blank line after content\n");
-    entrypoint.push_str("        sed -i \"/### $name/a \\\\\" ~/.claude/CLAUDE.md.new\n");
-    entrypoint.push_str("      fi\n");
-    entrypoint.push_str("    done\n");
-    entrypoint.push_str("  fi\n\n");
-
-    entrypoint.push_str("  # Remove the placeholder marker\n");
-    entrypoint.push_str(
-        "  sed -i '/<!-- claude-vm-context-runtime-placeholder -->/d' ~/.claude/CLAUDE.md.new\n\n",
-    );
-
-    entrypoint.push_str("  # Merge with existing CLAUDE.md if present\n");
-    entrypoint.push_str("  if [ -f ~/.claude/CLAUDE.md ]; then\n");
     entrypoint
-        .push_str("    if grep -q '<!-- claude-vm-context-start -->' ~/.claude/CLAUDE.md; then\n");
-    entrypoint
-        .push_str("      # Replace content between markers, preserving user content position\n");
-    entrypoint.push_str("      awk '\n");
-    entrypoint.push_str("        /<!-- claude-vm-context-start -->/ { skip=1; next }\n");
-    entrypoint.push_str("        /<!-- claude-vm-context-end -->/ { skip=0; next }\n");
-    entrypoint.push_str("        !skip\n");
-    entrypoint.push_str("      ' ~/.claude/CLAUDE.md > ~/.claude/CLAUDE.md.old\n\n");
-    entrypoint.push_str(
-        "      cat ~/.claude/CLAUDE.md.old ~/.claude/CLAUDE.md.new > ~/.claude/CLAUDE.md\n",
-    );
-    entrypoint.push_str("    else\n");
-    entrypoint.push_str("      # Append our context to existing content\n");
-    entrypoint.push_str(
-        "      cat ~/.claude/CLAUDE.md ~/.claude/CLAUDE.md.new > ~/.claude/CLAUDE.md.tmp\n",
-    );
-    entrypoint.push_str("      mv ~/.claude/CLAUDE.md.tmp ~/.claude/CLAUDE.md\n");
-    entrypoint.push_str("    fi\n");
-    entrypoint.push_str("  else\n");
-    entrypoint.push_str("    # No existing file, use our generated context\n");
-    entrypoint.push_str("    mv ~/.claude/CLAUDE.md.new ~/.claude/CLAUDE.md\n");
-    entrypoint.push_str("  fi\n");
-    entrypoint.push_str("fi\n\n");
-
-    entrypoint.push_str("# Cleanup temporary files\n");
-    entrypoint.push_str(&format!(
-        "rm -f ~/.claude/CLAUDE.md.new ~/.claude/CLAUDE.md.old {}\n\n",
-        vm_context_path
-    ));
-
-    // Exec main command - $@ contains all positional parameters
-    entrypoint.push_str("# Execute main command (replaces shell process)\n");
-    entrypoint.push_str("exec \"$@\"\n");
-
-    // Execute entrypoint with main command as positional parameters
-    // bash -c 'script' -- cmd arg1 arg2
-    // The '--' becomes $0, cmd becomes $1, etc. Then "$@" expands to cmd arg1 arg2
-    let mut shell_args = vec!["-c", entrypoint.as_str(), "--"];
-    shell_args.push(cmd);
-    shell_args.extend(args);
-
-    LimaCtl::shell(
-        vm_name,
-        workdir,
-        "bash",
-        &shell_args,
-        config.forward_ssh_agent,
-    )
 }
 
 /// Build entrypoint script for testing purposes
@@ -685,6 +1322,25 @@ fn build_entrypoint_script(vm_script_paths: &[String], script_names: &[String])
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_flush_captured_multiline() {
+        let mut output = Vec::new();
+        flush_captured(
+            "Starting service\nerror: port 5432 in use\n",
+            "postgres_script.sh",
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[postgres_script.sh] Starting service\n[postgres_script.sh] error: port 5432 in use\n"
+        );
+
+        let mut output = Vec::new();
+        flush_captured("", "empty.sh", &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
     #[test]
     fn test_sanitize_filename_safe() {
         assert_eq!(sanitize_filename("safe-file_123.sh"), "safe-file_123.sh");
@@ -853,7 +1509,7 @@ mod tests {
     #[test]
     fn test_generate_base_context_structure() {
         let config = Config::default();
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Verify HTML markers
         assert!(context.contains("<!-- claude-vm-context-start -->"));
@@ -873,11 +1529,382 @@ mod tests {
         config.vm.disk = 50;
         config.vm.memory = 16;
 
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Verify VM config values
         assert!(context.contains("**Disk**: 50 GB"));
         assert!(context.contains("**Memory**: 16 GB"));
+        assert!(!context.contains("**Swap**"));
+
+        config.vm.swap_gb = Some(4);
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(context.contains("**Swap**: 4 GB"));
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        let output = "one\ntwo\nthree\nfour\n";
+
+        assert_eq!(tail_lines(output, 2), (vec!["three", "four"], 2));
+        assert_eq!(
+            tail_lines(output, 10),
+            (vec!["one", "two", "three", "four"], 0)
+        );
+        assert_eq!(tail_lines(output, 0), (vec![], 4));
+        assert_eq!(tail_lines("", 5), (vec![], 0));
+    }
+
+    #[test]
+    fn test_build_scratch_overlay() {
+        let script = build_scratch_overlay(&[
+            PathBuf::from("/work/project"),
+            PathBuf::from("/work/main repo"),
+        ]);
+
+        assert!(script.contains("sudo mount -t tmpfs"));
+        assert!(script.contains(
+            "lowerdir='/work/project',upperdir=\"$CLAUDE_VM_SCRATCH/0/upper\",workdir=\"$CLAUDE_VM_SCRATCH/0/work\" '/work/project'"
+        ));
+        assert!(script.contains("lowerdir='/work/main repo'"));
+        assert!(script.contains("$CLAUDE_VM_SCRATCH/1/upper"));
+        assert!(script.contains("cd \"$PWD\""));
+    }
+
+    #[test]
+    fn test_build_fresh_home() {
+        let script = build_fresh_home();
+
+        assert!(script.contains("CLAUDE_VM_HOME=$(mktemp -d"));
+        assert!(script.contains("done; rm -rf \"$CLAUDE_VM_HOME\" /tmp/claude-vm-config-dir)"));
+        assert!(script.contains("\"userID\", \"mcpServers\")"));
+        assert!(script.contains("cp ~/.claude/.credentials.json \"$CLAUDE_VM_HOME/.claude/\""));
+        assert!(
+            script.contains("<!-- claude-vm-context-start -->/,/<!-- claude-vm-context-end -->/p")
+        );
+        assert!(script.contains("export CLAUDE_CONFIG_DIR=\"$CLAUDE_VM_HOME/.claude\"\n"));
+        assert!(script.ends_with("echo \"$CLAUDE_CONFIG_DIR\" > /tmp/claude-vm-config-dir\n\n"));
+    }
+
+    #[test]
+    fn test_fresh_home_context_and_mounts() {
+        let config = Config::default().with_fresh_home(true);
+        assert!(!config.mount_conversations);
+
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(context.contains("## Session State"));
+        assert!(context.contains("Fresh home"));
+
+        let context = generate_base_context(&Config::default(), Path::new("/nonexistent")).unwrap();
+        assert!(!context.contains("## Session State"));
+    }
+
+    #[test]
+    fn test_conversation_project_context() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = Config::default()
+            .with_conversations(true)
+            .with_conversation_project(Some(dir.path()))
+            .unwrap();
+
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(context.contains("## Conversation History"));
+        assert!(context.contains(&format!(
+            "shared with the project at {}",
+            dir.path().canonicalize().unwrap().display()
+        )));
+
+        let context = generate_base_context(&Config::default(), Path::new("/nonexistent")).unwrap();
+        assert!(!context.contains("## Conversation History"));
+    }
+
+    #[test]
+    fn test_drop_privileges_context() {
+        let config = Config::default().with_drop_privileges(true);
+        assert!(!config.mount_conversations);
+
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(context.contains("## Privileges"));
+        assert!(context.contains("`claude-agent`, without sudo"));
+
+        let context = generate_base_context(&Config::default(), Path::new("/nonexistent")).unwrap();
+        assert!(!context.contains("## Privileges"));
+    }
+
+    fn missing_script_config(policy: Option<MissingScriptPolicy>) -> Config {
+        let mut config = Config::default();
+        config.runtime.on_missing_script = policy;
+        config
+    }
+
+    fn runtime_phase(name: &str, files: Vec<String>) -> crate::config::ScriptPhase {
+        crate::config::ScriptPhase {
+            name: name.to_string(),
+            script_files: files,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_missing_legacy_script_default_warns() {
+        let mut config = missing_script_config(None);
+        config.runtime.scripts = vec!["/nonexistent/legacy.sh".to_string()];
+
+        let scripts = collect_configured_scripts(&config, Path::new("/")).unwrap();
+        assert!(scripts.is_empty());
+    }
+
+    #[test]
+    fn test_missing_legacy_script_error_policy() {
+        let mut config = missing_script_config(Some(MissingScriptPolicy::Error));
+        config.runtime.scripts = vec!["/nonexistent/legacy.sh".to_string()];
+
+        assert!(matches!(
+            collect_configured_scripts(&config, Path::new("/")),
+            Err(ClaudeVmError::ScriptNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_phase_script_default_errors() {
+        let mut config = missing_script_config(None);
+        config.phase.runtime = vec![runtime_phase(
+            "tools",
+            vec!["/nonexistent/phase.sh".to_string()],
+        )];
+
+        assert!(matches!(
+            collect_configured_scripts(&config, Path::new("/")),
+            Err(ClaudeVmError::ScriptNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_phase_script_warn_policy_keeps_others() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("present.sh"), "echo ok").unwrap();
+
+        let mut config = missing_script_config(Some(MissingScriptPolicy::Warn));
+        config.phase.runtime = vec![runtime_phase(
+            "tools",
+            vec!["./missing.sh".to_string(), "./present.sh".to_string()],
+        )];
+
+        let scripts = collect_configured_scripts(&config, tmp.path()).unwrap();
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(scripts[0].0, "present.sh");
+    }
+
+    #[test]
+    fn test_missing_phase_script_continue_on_error() {
+        let mut config = missing_script_config(Some(MissingScriptPolicy::Error));
+        let mut phase = runtime_phase("tools", vec!["/nonexistent/phase.sh".to_string()]);
+        phase.continue_on_error = true;
+        config.phase.runtime = vec![phase];
+
+        assert!(collect_configured_scripts(&config, Path::new("/"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_user_runtime_scripts_timeout() {
+        let mut phase = runtime_phase("slow", vec![]);
+        phase.script = Some("sleep 600".to_string());
+        let mut config = Config::default();
+        config.phase.runtime = vec![phase.clone()];
+        let paths = vec!["/tmp/claude-vm-1-0-slow-inline".to_string()];
+
+        let scripts = collect_configured_scripts(&config, Path::new("/")).unwrap();
+        let entrypoint = build_user_runtime_scripts(&scripts, &paths);
+        assert!(entrypoint.contains("  bash '/tmp/claude-vm-1-0-slow-inline'\n"));
+        assert!(!entrypoint.contains("timeout"));
+
+        phase.timeout_secs = Some(30);
+        config.phase.runtime = vec![phase.clone()];
+        let scripts = collect_configured_scripts(&config, Path::new("/")).unwrap();
+        let entrypoint = build_user_runtime_scripts(&scripts, &paths);
+        assert!(entrypoint.contains("  timeout 30 bash '/tmp/claude-vm-1-0-slow-inline' || {"));
+        assert!(entrypoint.contains("timed out after 30s'"));
+        assert!(entrypoint.contains("exit $rc; }"));
+
+        // continue_on_error keeps going with a warning
+        phase.continue_on_error = true;
+        config.phase.runtime = vec![phase];
+        let scripts = collect_configured_scripts(&config, Path::new("/")).unwrap();
+        let entrypoint = build_user_runtime_scripts(&scripts, &paths);
+        assert!(entrypoint.contains("timeout 30 bash"));
+        assert!(entrypoint.contains("continuing (continue_on_error)"));
+        assert!(!entrypoint.contains("exit $rc"));
+    }
+
+    #[test]
+    fn test_missing_script_policy_parse() {
+        let config: Config = toml::from_str("[runtime]\non_missing_script = \"error\"").unwrap();
+        assert_eq!(
+            config.runtime.on_missing_script,
+            Some(MissingScriptPolicy::Error)
+        );
+        assert!(toml::from_str::<Config>("[runtime]\non_missing_script = \"ignore\"").is_err());
+    }
+
+    #[test]
+    fn test_truncate_to_bytes() {
+        assert_eq!(truncate_to_bytes("short\n", 100), "short\n");
+
+        let text = "first line\nsecond line\nthird line\n";
+        let truncated = truncate_to_bytes(text, 30);
+        assert_eq!(truncated, "first line\n[truncated]\n");
+        assert!(truncated.len() <= 30);
+
+        // Marker survives even when nothing else fits
+        assert_eq!(truncate_to_bytes(text, 0), TRUNCATED_MARKER);
+
+        // Never splits a multi-byte character
+        let truncated = truncate_to_bytes("ééééééééééééééééééé", 20);
+        assert!(truncated.ends_with(TRUNCATED_MARKER));
+    }
+
+    #[test]
+    fn test_generate_base_context_max_bytes_truncates_instructions() {
+        let mut config = Config::default();
+        config.context.instructions = "Keep this line\n".repeat(200);
+        let full = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+
+        config.context.max_bytes = Some(full.len() - 1000);
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+
+        assert!(context.len() <= full.len() - 1000);
+        assert!(context.contains("[truncated]"));
+        // Critical sections and markers survive
+        assert!(context.contains("## VM Configuration"));
+        assert!(context.contains("## Enabled Capabilities"));
+        assert!(context.contains("## User Instructions"));
+        assert!(context.contains("<!-- claude-vm-context-runtime-placeholder -->"));
+        assert!(context.ends_with("<!-- claude-vm-context-end -->\n"));
+    }
+
+    #[test]
+    fn test_generate_base_context_max_bytes_keeps_critical_sections() {
+        let mut config = Config::default();
+        config.context.instructions = "Some instructions".to_string();
+        config.context.max_bytes = Some(10);
+
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(context.contains("## VM Configuration"));
+        assert!(context.contains("## Mounted Directories"));
+        assert!(!context.contains("Some instructions"));
+        assert!(context.contains("[truncated]"));
+    }
+
+    #[test]
+    fn test_generate_base_context_max_bytes_not_reached() {
+        let mut config = Config::default();
+        config.context.instructions = "Some instructions".to_string();
+        let full = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+
+        config.context.max_bytes = Some(full.len());
+        assert_eq!(
+            generate_base_context(&config, Path::new("/nonexistent")).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_generate_base_context_includes_readme() {
+        let project = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            project.path().join("README.md"),
+            "# My Project\nBuild with make.\n",
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        let context = generate_base_context(&config, project.path()).unwrap();
+        assert!(!context.contains("## Project README"));
+
+        config.context.include_readme = true;
+        let context = generate_base_context(&config, project.path()).unwrap();
+        assert!(context.contains("## Project README\n# My Project\nBuild with make.\n"));
+        assert!(context.ends_with("<!-- claude-vm-context-end -->\n"));
+    }
+
+    #[test]
+    fn test_generate_base_context_readme_missing_or_too_large() {
+        let project = tempfile::TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.context.include_readme = true;
+
+        // No README: skipped silently
+        let context = generate_base_context(&config, project.path()).unwrap();
+        assert!(!context.contains("## Project README"));
+
+        // Large README: capped
+        std::fs::write(
+            project.path().join("README.md"),
+            "A line of the readme\n".repeat(2000),
+        )
+        .unwrap();
+        let context = generate_base_context(&config, project.path()).unwrap();
+        assert!(context.contains("## Project README"));
+        assert!(context.contains("[truncated]"));
+        assert!(context.len() < README_MAX_BYTES + 4096);
+    }
+
+    #[test]
+    fn test_build_runtime_truncation() {
+        let script = build_runtime_truncation(100);
+        assert!(script.contains("-gt 100 ]"));
+        assert!(script.contains(&format!("head -c {} ", 100 - TRUNCATED_MARKER.len())));
+        assert!(script.contains("printf '\\n[truncated]\\n'"));
+
+        // Instructions already used up the budget: runtime results shrink to the marker
+        assert!(build_runtime_truncation(0).contains("head -c 0 "));
+    }
+
+    #[test]
+    fn test_build_tmpfs_mounts() {
+        let script = build_tmpfs_mounts(&[
+            mount::TmpfsMount::from_spec("/tmp/build:2g").unwrap(),
+            mount::TmpfsMount::from_spec("/cache").unwrap(),
+        ]);
+
+        assert!(script.contains("sudo mkdir -p '/tmp/build'\n"));
+        assert!(script.contains("sudo mount -t tmpfs -o size=2g,mode=1777 tmpfs '/tmp/build'\n"));
+        assert!(script.contains("sudo mount -t tmpfs -o mode=1777 tmpfs '/cache'\n"));
+    }
+
+    #[test]
+    fn test_generate_base_context_git_identity() {
+        let mut config = Config::default();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(!context.contains("## Git Identity"));
+
+        config.git.user_name = Some("Build Bot".to_string());
+        config.git.user_email = Some("bot@example.com".to_string());
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
+        assert!(context.contains("## Git Identity"));
+        assert!(context.contains("**User**: Build Bot"));
+        assert!(context.contains("**Email**: bot@example.com"));
+    }
+
+    #[test]
+    fn test_generate_base_context_git_credentials() {
+        let mut config = Config::default();
+        let context = generate_base_context(&config, Path::new(".")).unwrap();
+        assert!(!context.contains("## Git Credentials"));
+
+        config.git.forward_credentials = Some(true);
+        let context = generate_base_context(&config, Path::new(".")).unwrap();
+        assert!(context.contains("## Git Credentials"));
+    }
+
+    #[test]
+    fn test_on_session_exit() {
+        assert_eq!(
+            on_session_exit("git credential-cache exit"),
+            "(while kill -0 $$ 2>/dev/null; do sleep 1; done; git credential-cache exit) \
+             </dev/null >/dev/null 2>&1 &\n"
+        );
     }
 
     #[test]
@@ -885,7 +1912,7 @@ mod tests {
         let mut config = Config::default();
         config.context.instructions = "Test instructions\nMultiple lines".to_string();
 
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Verify user instructions section
         assert!(context.contains("## User Instructions"));
@@ -896,7 +1923,7 @@ mod tests {
     #[test]
     fn test_generate_base_context_no_instructions() {
         let config = Config::default();
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Should not have user instructions section when empty
         assert!(!context.contains("## User Instructions"));
@@ -908,7 +1935,7 @@ mod tests {
         config.tools.docker = true;
         config.tools.node = true;
 
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Verify capabilities are listed
         assert!(context.contains("docker"));
@@ -920,7 +1947,7 @@ mod tests {
     #[test]
     fn test_generate_base_context_no_capabilities() {
         let config = Config::default();
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Should show "None" when no capabilities enabled
         assert!(context.contains("## Enabled Capabilities"));
@@ -933,9 +1960,178 @@ mod tests {
         // Test instructions without trailing newline
         config.context.instructions = "Test without newline".to_string();
 
-        let context = generate_base_context(&config).unwrap();
+        let context = generate_base_context(&config, Path::new("/nonexistent")).unwrap();
 
         // Should add newline after instructions
         assert!(context.contains("Test without newline\n\n"));
     }
+
+    #[test]
+    fn test_network_env_separates_cidr_ranges() {
+        let mut config = Config::default();
+        config.security.network.enabled = true;
+        config.security.network.allowed_domains = vec![
+            "github.com".to_string(),
+            "10.1.0.0/16".to_string(),
+            "::1/128".to_string(),
+        ];
+        config.security.network.blocked_domains = vec!["192.168.0.0/16".to_string()];
+
+        let vars: HashMap<&str, String> = network_env(&config).into_iter().collect();
+        assert_eq!(vars["ALLOWED_DOMAINS"], "github.com");
+        assert_eq!(vars["ALLOWED_CIDRS"], "10.1.0.0/16,::1/128");
+        assert_eq!(vars["BLOCKED_CIDRS"], "192.168.0.0/16");
+        assert!(!vars.contains_key("BLOCKED_DOMAINS"));
+    }
+
+    #[test]
+    fn test_capability_runtime_sources_export_options() {
+        let config = Config::default();
+        let script = build_capability_runtime_sources(&config);
+        assert!(script.contains(". \"$script\" 2>&1"));
+        assert!(!script.contains("CAPABILITY_CONFIG_"));
+
+        let config: Config = toml::from_str(
+            r#"
+            [capabilities.node]
+            version = "20"
+            corepack = true
+
+            [capabilities.python]
+            version = "3.12"
+            "#,
+        )
+        .unwrap();
+        let script = build_capability_runtime_sources(&config);
+        assert!(script.contains(
+            "        'node') export CAPABILITY_CONFIG_COREPACK='true'; export CAPABILITY_CONFIG_VERSION='20' ;;\n"
+        ));
+        assert!(script.contains("        'python') export CAPABILITY_CONFIG_VERSION='3.12' ;;\n"));
+        // Options are exported before the script is sourced, and cleared after it
+        let source = script.find(". \"$script\"").unwrap();
+        assert!(script.find("'node')").unwrap() < source);
+        assert!(script.find("unset \"${!CAPABILITY_CONFIG_@}\"").unwrap() > source);
+    }
+
+    #[test]
+    fn test_network_env_allows_agent_domains() {
+        let mut config = Config::default();
+        config.security.network.enabled = true;
+        config.security.network.mode = crate::config::PolicyMode::Allowlist;
+        config.security.network.allowed_domains = vec!["github.com".to_string()];
+
+        assert_eq!(
+            missing_agent_domains(&config),
+            vec!["api.anthropic.com", "console.anthropic.com", "claude.ai"]
+        );
+        let vars: HashMap<&str, String> = network_env(&config).into_iter().collect();
+        assert_eq!(
+            vars["ALLOWED_DOMAINS"],
+            "github.com,api.anthropic.com,console.anthropic.com,claude.ai"
+        );
+
+        // Partly covered by a wildcard
+        config.security.network.allowed_domains = vec!["*.anthropic.com".to_string()];
+        assert_eq!(missing_agent_domains(&config), vec!["claude.ai"]);
+        config
+            .security
+            .network
+            .allowed_domains
+            .push("claude.ai".to_string());
+        assert!(missing_agent_domains(&config).is_empty());
+        let vars: HashMap<&str, String> = network_env(&config).into_iter().collect();
+        assert_eq!(vars["ALLOWED_DOMAINS"], "*.anthropic.com,claude.ai");
+
+        // Denylist mode allows it anyway
+        config.security.network.mode = crate::config::PolicyMode::Denylist;
+        config.security.network.allowed_domains.clear();
+        assert!(missing_agent_domains(&config).is_empty());
+        let vars: HashMap<&str, String> = network_env(&config).into_iter().collect();
+        assert!(!vars.contains_key("ALLOWED_DOMAINS"));
+    }
+
+    #[test]
+    fn test_retry_loop_only_restarts_crashes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let counter = dir.path().join("runs");
+        let runs = |code: i32| {
+            std::fs::remove_file(&counter).ok();
+            let command = format!("{{ echo run >> {}; (exit {}); }}", counter.display(), code);
+            let status = std::process::Command::new("bash")
+                .args(["-c", &build_retry_loop(&command, 2)])
+                .stderr(std::process::Stdio::null())
+                .status()
+                .unwrap();
+            assert_eq!(status.code(), Some(code));
+            std::fs::read_to_string(&counter).unwrap().lines().count()
+        };
+
+        // Crashes, such as SIGSEGV and the OOM killer's SIGKILL, are restarted
+        for &code in CRASH_EXIT_CODES {
+            assert_eq!(runs(code), 3, "exit {} is a crash", code);
+        }
+        // Clean exits, errors and user interrupts are not
+        for code in [0, 1, 2, 129, 130, 143] {
+            assert_eq!(runs(code), 1, "exit {} is not a crash", code);
+        }
+    }
+
+    #[test]
+    fn test_retry_loop_continues_conversation() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = dir.path().join("args");
+        // Records its arguments, crashes on the first two runs
+        let agent = dir.path().join("agent.sh");
+        std::fs::write(
+            &agent,
+            format!(
+                "echo \"$*\" >> {path}; [ $(wc -l < {path}) -gt 2 ] && exit 0; kill -SEGV $$",
+                path = log.display()
+            ),
+        )
+        .unwrap();
+        let run = |args: &[&str]| {
+            std::fs::remove_file(&log).ok();
+            std::process::Command::new("bash")
+                .args(["-c", &build_retry_loop("\"$@\"", 2), "--", "bash"])
+                .arg(&agent)
+                .args(args)
+                .stderr(std::process::Stdio::null())
+                .status()
+                .unwrap();
+            std::fs::read_to_string(&log).unwrap()
+        };
+
+        assert_eq!(
+            run(&["-p", "task"]),
+            "-p task\n-p task --continue\n-p task --continue\n"
+        );
+        assert_eq!(run(&["--resume", "abc"]), "--resume abc\n".repeat(3));
+    }
+
+    #[test]
+    fn test_retry_loop_restarts_crashed_command() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let counter = dir.path().join("runs");
+        // Crashes (SIGSEGV) on the first two runs, then exits with 3
+        let command = format!(
+            "bash -c 'echo run >> {path}; [ $(wc -l < {path}) -gt 2 ] && exit 3; kill -SEGV $$'",
+            path = counter.display()
+        );
+        let run = |max_restarts| {
+            std::fs::remove_file(&counter).ok();
+            let status = std::process::Command::new("bash")
+                .args(["-c", &build_retry_loop(&command, max_restarts)])
+                .stderr(std::process::Stdio::null())
+                .status()
+                .unwrap();
+            let runs = std::fs::read_to_string(&counter).unwrap().lines().count();
+            (status.code(), runs)
+        };
+
+        // The final exit code is passed through
+        assert_eq!(run(2), (Some(3), 3));
+        // Out of restarts: the crash is reported
+        assert_eq!(run(1), (Some(139), 2));
+    }
 }
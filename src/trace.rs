@@ -0,0 +1,174 @@
+//! Trace of every `limactl` invocation, for bug reports.
+//!
+//! With `--trace <path>`, each `limactl` command run by claude-vm is appended to
+//! the file as one JSON object per line: arguments, exit code, duration, and the
+//! output when it was captured (streamed output goes to the terminal instead).
+//! Values of environment variables passed with `--env`, `--env-file` or
+//! `--inherit-env` are redacted.
+
+use crate::error::{ClaudeVmError, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Open trace file, when `--trace` is set
+static TRACE_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// Values replaced with `REDACTED` in trace entries
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Replacement for secret values
+const REDACTED: &str = "[REDACTED]";
+
+/// Shorter values are not redacted: they would mask unrelated text everywhere
+const MIN_SECRET_LEN: usize = 4;
+
+/// One recorded `limactl` invocation
+#[derive(Debug, Serialize)]
+struct TraceEntry {
+    timestamp: String,
+    args: Vec<String>,
+    /// None when the process was killed by a signal or could not be started
+    exit_code: Option<i32>,
+    duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr: Option<String>,
+}
+
+/// Start recording to `path` for the rest of the process (the file is truncated)
+pub fn enable(path: &Path) -> Result<()> {
+    let file = File::create(path).map_err(|e| {
+        ClaudeVmError::InvalidConfig(format!("Cannot write trace file {}: {}", path.display(), e))
+    })?;
+    *TRACE_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    Ok(())
+}
+
+/// Remember values that must never appear in the trace
+pub fn register_secrets<'a>(values: impl IntoIterator<Item = &'a String>) {
+    let mut secrets = SECRETS.lock().unwrap_or_else(|e| e.into_inner());
+    for value in values {
+        if value.len() >= MIN_SECRET_LEN && !secrets.contains(value) {
+            secrets.push(value.clone());
+        }
+    }
+}
+
+/// Record a finished command (no-op unless enabled).
+/// `exit` is the exit code, or the error that prevented running the command.
+pub fn record(
+    cmd: &Command,
+    started: Instant,
+    exit: std::result::Result<Option<i32>, String>,
+    output: Option<&Output>,
+) {
+    let mut file = TRACE_FILE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = file.as_mut() else {
+        return;
+    };
+
+    let secrets = SECRETS.lock().unwrap_or_else(|e| e.into_inner());
+    let entry = TraceEntry::new(cmd, started, exit, output, &secrets);
+
+    // Best effort: tracing must never break the command being traced
+    if let Ok(line) = serde_json::to_string(&entry) {
+        let _ = writeln!(file, "{}", line);
+        let _ = file.flush();
+    }
+}
+
+impl TraceEntry {
+    fn new(
+        cmd: &Command,
+        started: Instant,
+        exit: std::result::Result<Option<i32>, String>,
+        output: Option<&Output>,
+        secrets: &[String],
+    ) -> Self {
+        let args = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| redact(&arg.to_string_lossy(), secrets))
+            .collect();
+        let captured = |bytes: &[u8]| redact(&String::from_utf8_lossy(bytes), secrets);
+        let (exit_code, error) = match exit {
+            Ok(code) => (code, None),
+            Err(e) => (None, Some(e)),
+        };
+
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            args,
+            exit_code,
+            duration_ms: started.elapsed().as_millis() as u64,
+            error,
+            stdout: output.map(|o| captured(&o.stdout)),
+            stderr: output.map(|o| captured(&o.stderr)),
+        }
+    }
+}
+
+/// Replace every occurrence of a secret value
+fn redact(text: &str, secrets: &[String]) -> String {
+    secrets.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret, REDACTED)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        let secrets = vec!["s3cr3t-token".to_string(), "hunter22".to_string()];
+        assert_eq!(
+            redact("export TOKEN='s3cr3t-token'; echo hunter22", &secrets),
+            "export TOKEN='[REDACTED]'; echo [REDACTED]"
+        );
+        assert_eq!(redact("nothing here", &secrets), "nothing here");
+    }
+
+    #[test]
+    fn test_entry_redacts_args_and_output() {
+        let secrets = vec!["very-secret".to_string()];
+        let mut cmd = Command::new("limactl");
+        cmd.args(["shell", "vm", "bash", "-c", "export A='very-secret'; true"]);
+        let output = Output {
+            status: std::process::ExitStatus::default(),
+            stdout: b"very-secret\n".to_vec(),
+            stderr: Vec::new(),
+        };
+
+        let entry = TraceEntry::new(&cmd, Instant::now(), Ok(Some(0)), Some(&output), &secrets);
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["args"][0], "limactl");
+        assert_eq!(json["args"][5], "export A='[REDACTED]'; true");
+        assert_eq!(json["exit_code"], 0);
+        assert_eq!(json["stdout"], "[REDACTED]\n");
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn test_entry_for_failed_spawn() {
+        let cmd = Command::new("limactl");
+        let entry = TraceEntry::new(
+            &cmd,
+            Instant::now(),
+            Err("not found".to_string()),
+            None,
+            &[],
+        );
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["error"], "not found");
+        assert!(json["exit_code"].is_null());
+        assert!(json.get("stdout").is_none());
+    }
+}
@@ -0,0 +1,99 @@
+//! IPv4/IPv6 CIDR ranges (`10.1.0.0/16`, `fd00::/8`) in network isolation lists.
+
+use std::net::IpAddr;
+
+/// An address range in CIDR notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Parse `address/prefix`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (addr, prefix) = s
+            .split_once('/')
+            .ok_or_else(|| "CIDR range must be in the form address/prefix".to_string())?;
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("'{}' is not an IPv4 or IPv6 address", addr))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix: u8 = prefix
+            .parse()
+            .ok()
+            .filter(|p| *p <= max)
+            .ok_or_else(|| format!("prefix length must be between 0 and {}", max))?;
+        Ok(Self { addr, prefix })
+    }
+
+    /// Whether `ip` is in the range
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether a list entry is meant as a CIDR range rather than a domain
+pub fn is_cidr(entry: &str) -> bool {
+    entry.contains('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_ipv4() {
+        let range = Cidr::parse("192.168.0.0/16").unwrap();
+        assert!(range.contains(ip("192.168.0.1")));
+        assert!(range.contains(ip("192.168.255.254")));
+        assert!(!range.contains(ip("192.169.0.1")));
+        assert!(!range.contains(ip("::1")));
+
+        assert!(Cidr::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+        let host = Cidr::parse("10.0.0.5/32").unwrap();
+        assert!(host.contains(ip("10.0.0.5")));
+        assert!(!host.contains(ip("10.0.0.6")));
+    }
+
+    #[test]
+    fn test_cidr_ipv6() {
+        let loopback = Cidr::parse("::1/128").unwrap();
+        assert!(loopback.contains(ip("::1")));
+        assert!(!loopback.contains(ip("::2")));
+        assert!(!loopback.contains(ip("127.0.0.1")));
+
+        let ula = Cidr::parse("fd00::/8").unwrap();
+        assert!(ula.contains(ip("fd12:3456::1")));
+        assert!(!ula.contains(ip("fe80::1")));
+    }
+
+    #[test]
+    fn test_cidr_invalid() {
+        let err = Cidr::parse("10.0.0.0/99").unwrap_err();
+        assert!(err.contains("between 0 and 32"));
+        assert!(Cidr::parse("::/129").is_err());
+        assert!(Cidr::parse("10.0.0/8").is_err());
+        assert!(Cidr::parse("10.0.0.0/").is_err());
+        assert!(Cidr::parse("example.com/8").is_err());
+        assert!(Cidr::parse("10.0.0.0").is_err());
+    }
+}
@@ -0,0 +1,67 @@
+//! Entries of the network isolation domain lists: exact domains, wildcards
+//! (`*.example.com`) and CIDR ranges.
+
+use crate::utils::cidr::{is_cidr, Cidr};
+
+/// Check if host matches a pattern (with wildcard support).
+/// CIDR patterns match hosts given as an IP address in the range.
+pub fn matches_pattern(host: &str, pattern: &str) -> bool {
+    if pattern.is_empty() {
+        return false;
+    }
+    if is_cidr(pattern) {
+        let ip = host.trim_start_matches('[').trim_end_matches(']').parse();
+        return match (Cidr::parse(pattern), ip) {
+            (Ok(range), Ok(ip)) => range.contains(ip),
+            _ => false,
+        };
+    }
+    if let Some(domain) = pattern.strip_prefix("*.") {
+        // *.example.com matches api.example.com and example.com
+        host == domain || host.ends_with(&format!(".{}", domain))
+    } else {
+        host == pattern
+    }
+}
+
+/// Check if host matches any pattern in the list
+pub fn matches_any(host: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|p| matches_pattern(host, p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern_exact() {
+        assert!(matches_pattern("example.com", "example.com"));
+        assert!(!matches_pattern("example.com", "other.com"));
+    }
+
+    #[test]
+    fn test_matches_pattern_wildcard() {
+        assert!(matches_pattern("api.example.com", "*.example.com"));
+        assert!(matches_pattern("example.com", "*.example.com"));
+        assert!(!matches_pattern("example.org", "*.example.com"));
+    }
+
+    #[test]
+    fn test_matches_pattern_cidr() {
+        assert!(matches_pattern("192.168.1.20", "192.168.0.0/16"));
+        assert!(!matches_pattern("10.0.0.1", "192.168.0.0/16"));
+        assert!(matches_pattern("::1", "::1/128"));
+        assert!(matches_pattern("[::1]", "::1/128"));
+        // Domains are not resolved, and invalid ranges match nothing
+        assert!(!matches_pattern("example.com", "192.168.0.0/16"));
+        assert!(!matches_pattern("10.0.0.1", "10.0.0.0/99"));
+    }
+
+    #[test]
+    fn test_matches_any() {
+        let patterns = vec!["example.com".to_string(), "*.test.com".to_string()];
+        assert!(matches_any("example.com", &patterns));
+        assert!(matches_any("api.test.com", &patterns));
+        assert!(!matches_any("other.com", &patterns));
+    }
+}
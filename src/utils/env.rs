@@ -1,5 +1,5 @@
 use crate::error::{ClaudeVmError, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
@@ -81,18 +81,41 @@ pub fn collect_env_vars(
     env_files: &[std::path::PathBuf],
     inherit_vars: &[String],
 ) -> Result<HashMap<String, String>> {
-    let mut env_vars = HashMap::new();
+    Ok(collect_env_sources(env_args, env_files, inherit_vars)?
+        .into_iter()
+        .map(|(key, (value, _))| (key, value))
+        .collect())
+}
+
+/// Collect all environment variables from CLI flags, each with the flag its value
+/// comes from, sorted by name
+pub fn collect_env_sources(
+    env_args: &[String],
+    env_files: &[std::path::PathBuf],
+    inherit_vars: &[String],
+) -> Result<BTreeMap<String, (String, String)>> {
+    let mut env_vars = BTreeMap::new();
 
     // Load from env files (lowest priority)
     for file in env_files {
-        env_vars.extend(load_env_file(file)?);
+        let source = format!("--env-file {}", file.display());
+        for (key, value) in load_env_file(file)? {
+            env_vars.insert(key, (value, source.clone()));
+        }
     }
 
     // Add --env args (medium priority)
-    env_vars.extend(parse_env_args(env_args)?);
+    for (key, value) in parse_env_args(env_args)? {
+        env_vars.insert(key, (value, "--env".to_string()));
+    }
 
     // Add inherited vars (highest priority)
-    env_vars.extend(get_inherited_vars(inherit_vars));
+    for (key, value) in get_inherited_vars(inherit_vars) {
+        env_vars.insert(key, (value, "--inherit-env".to_string()));
+    }
+
+    // Keep the values out of the --trace file
+    crate::trace::register_secrets(env_vars.values().map(|(value, _)| value));
 
     Ok(env_vars)
 }
@@ -134,4 +157,28 @@ mod tests {
         assert!(exports.contains("export KEY1='value1'"));
         assert!(exports.contains("export KEY2='value'\\''s'"));
     }
+
+    #[test]
+    fn test_collect_env_sources_precedence() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let env_file = dir.path().join(".env");
+        fs::write(&env_file, "FROM_FILE=file\nOVERRIDDEN=file\n").unwrap();
+
+        let vars = collect_env_sources(
+            &["OVERRIDDEN=flag".to_string()],
+            std::slice::from_ref(&env_file),
+            &[],
+        )
+        .unwrap();
+
+        let file_source = format!("--env-file {}", env_file.display());
+        assert_eq!(
+            vars.get("FROM_FILE"),
+            Some(&("file".to_string(), file_source))
+        );
+        assert_eq!(
+            vars.get("OVERRIDDEN"),
+            Some(&("flag".to_string(), "--env".to_string()))
+        );
+    }
 }
@@ -120,7 +120,10 @@ pub fn get_default_branch() -> Result<String> {
         .map_err(|e| ClaudeVmError::Git(format!("Failed to run git: {}", e)))?;
 
     if !output.status.success() {
-        eprintln!("Warning: Could not detect default branch (no remote HEAD ref). Falling back to 'main'.");
+        crate::diagnostics::warn(
+            "default-branch",
+            "Could not detect default branch (no remote HEAD ref). Falling back to 'main'.",
+        );
         return Ok("main".to_string());
     }
 
@@ -1,3 +1,5 @@
+pub mod cidr;
+pub mod domain;
 pub mod env;
 pub mod git;
 pub mod path;
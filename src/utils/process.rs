@@ -1,4 +1,5 @@
 use crate::error::{ClaudeVmError, Result};
+use std::io::{self, BufRead, Write};
 use std::process::{Command, ExitStatus};
 
 /// Execute a command and return its status
@@ -30,3 +31,53 @@ pub fn execute_with_output(command: &str, args: &[&str]) -> Result<String> {
 pub fn command_exists(command: &str) -> bool {
     which::which(command).is_ok()
 }
+
+/// Copy `reader` to `writer` line by line, prefixing each line with `[prefix] `.
+///
+/// Lines are flushed as they arrive so long-running scripts show progress.
+/// Invalid UTF-8 is replaced rather than aborting the stream.
+pub fn prefix_lines<R: BufRead, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    prefix: &str,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        writeln!(writer, "[{}] {}", prefix, text)?;
+        writer.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_lines_multiline() {
+        let input = "first\nsecond\r\n\nlast without newline";
+        let mut output = Vec::new();
+        prefix_lines(input.as_bytes(), &mut output, "docker").unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[docker] first\n[docker] second\n[docker] \n[docker] last without newline\n"
+        );
+    }
+
+    #[test]
+    fn test_prefix_lines_empty_and_invalid_utf8() {
+        let mut output = Vec::new();
+        prefix_lines(&b""[..], &mut output, "node").unwrap();
+        assert!(output.is_empty());
+
+        let mut output = Vec::new();
+        prefix_lines(&b"caf\xff\n"[..], &mut output, "node").unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "[node] caf\u{fffd}\n");
+    }
+}
@@ -0,0 +1,282 @@
+//! Setup checkpoints, so a failed `setup` can continue where it stopped.
+//!
+//! Each setup step is identified by its name and a hash chained over the
+//! inputs of every step before it. Changing an earlier step therefore
+//! invalidates the checkpoints of all the steps after it. The checkpoint file
+//! lives next to the template metadata and only exists while a setup is
+//! unfinished: a template that has one must not be used for sessions.
+
+use crate::build_log;
+use crate::error::{ClaudeVmError, Result};
+use crate::status;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the template's Lima directory holding the setup checkpoints
+const CHECKPOINT_FILE: &str = "claude-vm-checkpoint.json";
+
+/// Progress of an unfinished setup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetupCheckpoint {
+    /// Hash of what the template VM itself was created from (sizing, image, mounts)
+    pub base_hash: String,
+    /// Steps that completed, in order
+    pub completed: Vec<CompletedStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedStep {
+    pub name: String,
+    pub hash: String,
+}
+
+/// Path of a template's checkpoint file
+pub fn path(template_name: &str) -> Option<PathBuf> {
+    super::template::get_path(template_name).map(|dir| dir.join(CHECKPOINT_FILE))
+}
+
+/// Whether the template's setup started but never finished
+pub fn is_incomplete(template_name: &str) -> bool {
+    path(template_name).is_some_and(|p| p.exists())
+}
+
+/// Checkpoints of an unfinished setup, if any
+pub fn read(template_name: &str) -> Result<Option<SetupCheckpoint>> {
+    match path(template_name) {
+        Some(path) if path.exists() => read_from(&path).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn read_from(path: &Path) -> Result<SetupCheckpoint> {
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(|e| {
+        ClaudeVmError::CommandFailed(format!(
+            "Unreadable setup checkpoint ({}): {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Mark the template's setup as finished
+pub fn clear(template_name: &str) -> Result<()> {
+    match path(template_name) {
+        Some(path) if path.exists() => Ok(fs::remove_file(path)?),
+        _ => Ok(()),
+    }
+}
+
+/// Hash identifying a step: its name and input, chained to the previous step
+pub fn chain(previous: &str, name: &str, input: &str) -> String {
+    let data = format!("{}\0{}\0{}", previous, name, input);
+    format!("{:x}", md5::compute(data.as_bytes()))
+}
+
+/// Runs setup steps, skipping those completed by a previous run and recording progress
+pub struct Checkpointer {
+    /// Where progress is saved (None keeps it in memory)
+    path: Option<PathBuf>,
+    /// Steps completed by the run being resumed
+    previous: Vec<CompletedStep>,
+    /// Progress of this run
+    current: SetupCheckpoint,
+    /// Hash of the last step, or the base hash before the first one
+    last_hash: String,
+    /// Cleared once a step runs: everything after it must run too
+    resuming: bool,
+    /// Step being run, if it has not completed
+    pending: Option<String>,
+}
+
+impl Checkpointer {
+    /// Start tracking a setup run.
+    /// With `resume_from`, steps that completed in that run and did not change are skipped.
+    pub fn new(
+        path: Option<PathBuf>,
+        base_hash: String,
+        resume_from: Option<SetupCheckpoint>,
+    ) -> Result<Self> {
+        let previous = resume_from
+            .filter(|checkpoint| checkpoint.base_hash == base_hash)
+            .map(|checkpoint| checkpoint.completed)
+            .unwrap_or_default();
+
+        let checkpointer = Self {
+            path,
+            resuming: !previous.is_empty(),
+            previous,
+            last_hash: base_hash.clone(),
+            current: SetupCheckpoint {
+                base_hash,
+                completed: Vec::new(),
+            },
+            pending: None,
+        };
+        checkpointer.save()?;
+        Ok(checkpointer)
+    }
+
+    /// Run a step unless it already completed with the same inputs
+    pub fn run(
+        &mut self,
+        name: &str,
+        input: &str,
+        step: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let hash = chain(&self.last_hash, name, input);
+        self.last_hash = hash.clone();
+        let completed = CompletedStep {
+            name: name.to_string(),
+            hash,
+        };
+
+        if self.resuming && self.previous.get(self.current.completed.len()) == Some(&completed) {
+            status!("✓ Skipping {} (completed in a previous run)", name);
+            self.current.completed.push(completed);
+            return Ok(());
+        }
+
+        self.resuming = false;
+        self.pending = Some(name.to_string());
+        build_log::phase(name);
+        step()?;
+        self.pending = None;
+        self.current.completed.push(completed);
+        self.save()
+    }
+
+    /// Number of steps done so far, skipped ones included
+    pub fn completed(&self) -> usize {
+        self.current.completed.len()
+    }
+
+    /// Step that was running when setup failed
+    pub fn pending(&self) -> Option<&str> {
+        self.pending.as_deref()
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let json = serde_json::to_string_pretty(&self.current).map_err(|e| {
+            ClaudeVmError::CommandFailed(format!("Failed to encode setup checkpoint: {}", e))
+        })?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Run the steps in order, failing on `fail_at`; returns the steps that actually ran
+    fn run_steps(
+        checkpointer: &mut Checkpointer,
+        inputs: &[(&str, &str)],
+        fail_at: Option<&str>,
+    ) -> Vec<String> {
+        let ran = RefCell::new(Vec::new());
+        for (name, input) in inputs {
+            let result = checkpointer.run(name, input, || {
+                ran.borrow_mut().push(name.to_string());
+                if fail_at == Some(*name) {
+                    return Err(ClaudeVmError::CommandFailed("boom".to_string()));
+                }
+                Ok(())
+            });
+            if result.is_err() {
+                break;
+            }
+        }
+        ran.into_inner()
+    }
+
+    const STEPS: &[(&str, &str)] = &[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4")];
+
+    fn failed_run(dir: &Path) -> (PathBuf, SetupCheckpoint) {
+        let path = dir.join(CHECKPOINT_FILE);
+        let mut checkpointer = Checkpointer::new(Some(path.clone()), "base".into(), None).unwrap();
+        assert_eq!(
+            run_steps(&mut checkpointer, STEPS, Some("c")),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(checkpointer.pending(), Some("c"));
+        (path.clone(), read_from(&path).unwrap())
+    }
+
+    #[test]
+    fn test_records_completed_steps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (_, checkpoint) = failed_run(dir.path());
+
+        assert_eq!(checkpoint.base_hash, "base");
+        let names: Vec<_> = checkpoint
+            .completed
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(checkpoint.completed[0].hash, chain("base", "a", "1"));
+        assert_eq!(
+            checkpoint.completed[1].hash,
+            chain(&checkpoint.completed[0].hash, "b", "2")
+        );
+    }
+
+    #[test]
+    fn test_resume_skips_completed_steps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (path, checkpoint) = failed_run(dir.path());
+
+        let mut checkpointer =
+            Checkpointer::new(Some(path), "base".into(), Some(checkpoint)).unwrap();
+        assert_eq!(run_steps(&mut checkpointer, STEPS, None), vec!["c", "d"]);
+        assert_eq!(checkpointer.completed(), 4);
+    }
+
+    #[test]
+    fn test_changed_step_invalidates_later_checkpoints() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (path, checkpoint) = failed_run(dir.path());
+
+        let changed = [("a", "changed"), ("b", "2"), ("c", "3"), ("d", "4")];
+        let mut checkpointer =
+            Checkpointer::new(Some(path), "base".into(), Some(checkpoint)).unwrap();
+        assert_eq!(
+            run_steps(&mut checkpointer, &changed, None),
+            vec!["a", "b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn test_changed_base_restarts_from_scratch() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (path, checkpoint) = failed_run(dir.path());
+
+        let mut checkpointer =
+            Checkpointer::new(Some(path), "other-base".into(), Some(checkpoint)).unwrap();
+        assert_eq!(run_steps(&mut checkpointer, STEPS, None).len(), 4);
+    }
+
+    #[test]
+    fn test_no_skipping_after_a_step_ran() {
+        // A later step matching by chance must still run once an earlier one ran
+        let previous = SetupCheckpoint {
+            base_hash: "base".into(),
+            completed: vec![CompletedStep {
+                name: "b".into(),
+                hash: chain(&chain("base", "a", "1"), "b", "2"),
+            }],
+        };
+        let mut checkpointer = Checkpointer::new(None, "base".into(), Some(previous)).unwrap();
+        assert_eq!(
+            run_steps(&mut checkpointer, &STEPS[..2], None),
+            vec!["a", "b"]
+        );
+    }
+}
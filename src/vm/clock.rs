@@ -0,0 +1,129 @@
+//! Guest clock checks run when a session starts.
+//!
+//! A VM that was suspended with the host can resume with its clock behind,
+//! which surfaces as confusing TLS errors ("certificate is not yet valid")
+//! and wrong timestamps. The guest clock is compared with the host's and,
+//! with `[vm] sync_clock = true`, set from it.
+
+use crate::error::{ClaudeVmError, Result};
+use crate::status;
+use crate::vm::limactl::LimaCtl;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Skew tolerated before warning, in seconds
+pub const MAX_SKEW_SECS: i64 = 5;
+
+/// Guest clock offset from the host: guest time minus host time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSkew(pub i64);
+
+impl ClockSkew {
+    /// Skew between a guest reading and the host time around it.
+    /// The host time is the middle of the round trip to the guest.
+    pub fn measure(host_before: i64, guest: i64, host_after: i64) -> Self {
+        Self(guest - (host_before + host_after) / 2)
+    }
+
+    pub fn exceeds_threshold(self) -> bool {
+        self.0.abs() > MAX_SKEW_SECS
+    }
+}
+
+impl std::fmt::Display for ClockSkew {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = if self.0 < 0 { "behind" } else { "ahead of" };
+        write!(f, "{}s {} the host", self.0.abs(), direction)
+    }
+}
+
+fn host_epoch() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Read the guest clock and compare it with the host's
+fn measure(vm_name: &str) -> Result<ClockSkew> {
+    let before = host_epoch();
+    let output = LimaCtl::shell_output(vm_name, "date", &["+%s"])?;
+    let after = host_epoch();
+
+    let guest = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| ClaudeVmError::LimaExecution("Could not read the guest clock".to_string()))?;
+    Ok(ClockSkew::measure(before, guest, after))
+}
+
+/// Set the guest clock from the host's
+fn resync(vm_name: &str) -> Result<()> {
+    let now = format!("@{}", host_epoch());
+    let output = LimaCtl::shell_output(vm_name, "sudo", &["date", "-u", "-s", &now])?;
+    if !output.status.success() {
+        return Err(ClaudeVmError::LimaExecution(format!(
+            "Failed to set the guest clock: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// Compare the guest clock with the host's, warning on skew.
+/// With `sync`, a skewed clock is set from the host. Never fails the session:
+/// a clock that cannot be read or fixed only produces a warning.
+pub fn check(vm_name: &str, sync: bool) {
+    let skew = match measure(vm_name) {
+        Ok(skew) => skew,
+        Err(e) => {
+            crate::diagnostics::warn("clock-unreadable", e);
+            return;
+        }
+    };
+    if !skew.exceeds_threshold() {
+        return;
+    }
+
+    if !sync {
+        crate::diagnostics::warn(
+            "clock-skew",
+            format!(
+                "VM clock is {}. TLS connections may fail with certificate errors.\n\
+                 Set 'sync_clock = true' in [vm] to correct it at session start.",
+                skew
+            ),
+        );
+        return;
+    }
+
+    status!("VM clock is {}, syncing it from the host...", skew);
+    match resync(vm_name).and_then(|_| measure(vm_name)) {
+        Ok(after) if !after.exceeds_threshold() => {}
+        Ok(after) => crate::diagnostics::warn(
+            "clock-skew",
+            format!("VM clock is still {} after syncing it", after),
+        ),
+        Err(e) => crate::diagnostics::warn("clock-skew", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_uses_round_trip_midpoint() {
+        assert_eq!(ClockSkew::measure(100, 102, 104), ClockSkew(0));
+        assert_eq!(ClockSkew::measure(1000, 900, 1002), ClockSkew(-101));
+    }
+
+    #[test]
+    fn test_threshold_and_display() {
+        assert!(!ClockSkew(MAX_SKEW_SECS).exceeds_threshold());
+        assert!(!ClockSkew(-MAX_SKEW_SECS).exceeds_threshold());
+        assert!(ClockSkew(-MAX_SKEW_SECS - 1).exceeds_threshold());
+
+        assert_eq!(ClockSkew(-3600).to_string(), "3600s behind the host");
+        assert_eq!(ClockSkew(42).to_string(), "42s ahead of the host");
+    }
+}
@@ -0,0 +1,189 @@
+//! Forward the host's git HTTPS credentials to a session VM.
+//!
+//! For each HTTPS remote of the project, the credential is asked from the
+//! host's configured helper (`git credential fill`, non-interactive) and handed
+//! to the VM's `git credential-cache` through stdin. The cache keeps it in
+//! memory only: nothing is written to the VM disk, and the session entrypoint
+//! clears it when the session command exits.
+
+use crate::config::NetworkIsolationConfig;
+use crate::error::{ClaudeVmError, Result};
+use crate::vm::limactl::LimaCtl;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Credential helper configured in the VM (in-memory cache, cleared when the session ends)
+const VM_HELPER: &str = "cache --timeout=86400";
+
+/// Hosts (with port, as git credential expects) of the HTTPS remotes in `git remote -v` output
+pub fn https_hosts(remotes: &str) -> Vec<String> {
+    let mut hosts: Vec<String> = remotes
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter_map(|url| url.strip_prefix("https://"))
+        .filter_map(|rest| rest.split('/').next())
+        .map(|authority| authority.rsplit('@').next().unwrap_or(authority))
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .collect();
+    hosts.sort();
+    hosts.dedup();
+    hosts
+}
+
+/// Ask the host's credential helper for a host's credential, without prompting.
+/// Returns the credential description to feed to `git credential approve`.
+fn host_credential(project_root: &Path, host: &str) -> Result<Option<String>> {
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(project_root)
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "")
+        .env("SSH_ASKPASS", "")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| ClaudeVmError::Git(format!("Failed to run git credential: {}", e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        write!(stdin, "protocol=https\nhost={}\n\n", host)?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| ClaudeVmError::Git(format!("Failed to run git credential: {}", e)))?;
+
+    let credential = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() || !credential.lines().any(|l| l.starts_with("password=")) {
+        return Ok(None);
+    }
+    Ok(Some(credential))
+}
+
+/// Store a credential in the VM's in-memory credential cache
+fn approve_in_vm(vm_name: &str, credential: &str) -> Result<()> {
+    let script = format!(
+        "git config --global credential.helper '{}' && git credential approve",
+        VM_HELPER
+    );
+    let output =
+        LimaCtl::shell_with_input(vm_name, "bash", &["-c", &script], credential.as_bytes())?;
+    if !output.status.success() {
+        return Err(ClaudeVmError::LimaExecution(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Whether network isolation would block the VM from reaching `host`
+fn blocked_by_network(network: &NetworkIsolationConfig, host: &str) -> bool {
+    use crate::config::PolicyMode;
+    use crate::utils::domain::matches_any;
+
+    let domain = host.split(':').next().unwrap_or(host);
+    if !network.enabled || matches_any(domain, &network.bypass_domains) {
+        return false;
+    }
+    match network.mode {
+        PolicyMode::Allowlist => !matches_any(domain, &network.allowed_domains),
+        PolicyMode::Denylist => matches_any(domain, &network.blocked_domains),
+    }
+}
+
+/// Hand the host's credentials for the project's HTTPS remotes to the VM's git.
+/// Never fails the session: problems are reported as warnings.
+pub fn forward(project_root: &Path, vm_name: &str, network: &NetworkIsolationConfig) {
+    if let Err(e) = try_forward(project_root, vm_name, network) {
+        crate::diagnostics::warn(
+            "git-credentials",
+            format!("Failed to forward git credentials: {}", e),
+        );
+    }
+}
+
+fn try_forward(project_root: &Path, vm_name: &str, network: &NetworkIsolationConfig) -> Result<()> {
+    let dir = crate::utils::git::path_to_str(project_root, "project root")?;
+    let remotes = crate::utils::git::run_git_command(&["-C", dir, "remote", "-v"], "list remotes")?;
+    let hosts = https_hosts(&remotes);
+    if hosts.is_empty() {
+        crate::diagnostics::warn(
+            "git-credentials",
+            "--git-credentials: the project has no HTTPS remotes, nothing to forward",
+        );
+        return Ok(());
+    }
+
+    let mut forwarded = Vec::new();
+    for host in &hosts {
+        if blocked_by_network(network, host) {
+            crate::diagnostics::warn(
+                "git-credentials",
+                format!(
+                    "{} is blocked by network isolation: git over HTTPS will fail. \
+                     Add it to [security.network] allowed_domains.",
+                    host
+                ),
+            );
+        }
+        let Some(credential) = host_credential(project_root, host)? else {
+            crate::diagnostics::warn(
+                "git-credentials",
+                format!(
+                    "No credential for {} in the host's git credential helper",
+                    host
+                ),
+            );
+            continue;
+        };
+        approve_in_vm(vm_name, &credential)?;
+        forwarded.push(host.as_str());
+    }
+    if forwarded.is_empty() {
+        return Ok(());
+    }
+
+    crate::diagnostics::warn(
+        "git-credentials",
+        format!(
+            "Host git credentials for {} are available in the VM: anything running there, \
+             including the agent, can push with them for the rest of the session",
+            forwarded.join(", ")
+        ),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_https_hosts() {
+        let remotes = "origin\thttps://github.com/acme/app.git (fetch)\n\
+                       origin\thttps://github.com/acme/app.git (push)\n\
+                       mirror\thttps://bot@git.example.com:8443/app.git (fetch)\n\
+                       ssh\tgit@github.com:acme/app.git (fetch)\n\
+                       local\t/srv/git/app.git (fetch)\n";
+
+        assert_eq!(
+            https_hosts(remotes),
+            vec!["git.example.com:8443", "github.com"]
+        );
+        assert!(https_hosts("").is_empty());
+    }
+
+    #[test]
+    fn test_blocked_by_network() {
+        let mut network = NetworkIsolationConfig::default();
+        assert!(!blocked_by_network(&network, "github.com"));
+
+        network.enabled = true;
+        network.mode = crate::config::PolicyMode::Allowlist;
+        network.allowed_domains = vec!["*.github.com".to_string()];
+        assert!(!blocked_by_network(&network, "github.com"));
+        assert!(blocked_by_network(&network, "git.example.com:8443"));
+    }
+}
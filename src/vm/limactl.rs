@@ -1,11 +1,97 @@
+use crate::build_log;
 use crate::error::{ClaudeVmError, Result};
+use crate::events;
+use crate::trace;
+use crate::utils::process;
 use crate::vm::mount::Mount;
 use crate::vm::port_forward::PortForward;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
+
+/// Number of attempts for `LimaCtl::copy` before giving up
+const COPY_ATTEMPTS: u32 = 3;
+
+/// Delay between `LimaCtl::copy` attempts
+const COPY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Delay before the first retry of a transient limactl failure, doubled for each retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest delay between retries of a transient limactl failure
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// limactl error messages (lowercase) of failures that usually go away on their own,
+/// e.g. while the instance is still booting
+const TRANSIENT_ERRORS: [&str; 6] = [
+    "is still starting",
+    "connection refused",
+    "connection reset by peer",
+    "kex_exchange_identification",
+    "ssh: handshake failed",
+    "resource temporarily unavailable",
+];
 
 pub struct LimaCtl;
 
+/// A host file to copy into the VM before the session starts (`--copy host:guest`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileCopy {
+    pub host: PathBuf,
+    /// Absolute destination path in the VM
+    pub guest: String,
+}
+
+impl FileCopy {
+    /// Parse a `host:guest` specification. The host file must be readable
+    /// and the guest path absolute.
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let (host, guest) = match spec.rfind(":/") {
+            Some(i) => (&spec[..i], &spec[i + 1..]),
+            None => match spec.rsplit_once(':') {
+                Some((_, guest)) => {
+                    return Err(ClaudeVmError::InvalidConfig(format!(
+                        "Copy destination must be an absolute path in the VM: {}",
+                        guest
+                    )))
+                }
+                None => {
+                    return Err(ClaudeVmError::InvalidConfig(format!(
+                        "Invalid copy '{}': expected host_path:/vm/path",
+                        spec
+                    )))
+                }
+            },
+        };
+
+        if host.is_empty() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Invalid copy '{}': the host path is empty",
+                spec
+            )));
+        }
+        let host = PathBuf::from(host);
+        if !host.is_file() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Copy source is not a file: {}",
+                host.display()
+            )));
+        }
+        std::fs::File::open(&host).map_err(|e| {
+            ClaudeVmError::InvalidConfig(format!(
+                "Copy source {} is not readable: {}",
+                host.display(),
+                e
+            ))
+        })?;
+
+        Ok(Self {
+            host,
+            guest: guest.to_string(),
+        })
+    }
+}
+
 /// VM configuration based on the host operating system
 struct VmConfig {
     vm_type: &'static str,
@@ -67,6 +153,18 @@ impl LimaCtl {
         which::which("limactl").is_ok()
     }
 
+    /// Fail with `LimaNotInstalled` (and install instructions) when limactl is missing.
+    /// Call before any Lima operation so users don't get a raw exec error.
+    pub fn ensure_available() -> Result<()> {
+        Self::ensure_available_in(std::env::var_os("PATH"))
+    }
+
+    fn ensure_available_in(path: Option<std::ffi::OsString>) -> Result<()> {
+        which::which_in("limactl", path, ".")
+            .map(|_| ())
+            .map_err(|_| ClaudeVmError::LimaNotInstalled)
+    }
+
     /// Create a new Lima VM from template
     #[allow(clippy::too_many_arguments)]
     pub fn create(
@@ -140,9 +238,9 @@ impl LimaCtl {
         }
 
         let result = if verbose {
-            cmd.status()
+            Self::run_status(cmd.stdout(events::child_stdout()))
         } else {
-            cmd.stdout(Stdio::null()).stderr(Stdio::null()).status()
+            Self::run_status(cmd.stdout(Stdio::null()).stderr(Stdio::null()))
         };
 
         let status = result
@@ -164,9 +262,9 @@ impl LimaCtl {
         cmd.args(["start", name]);
 
         let result = if verbose {
-            cmd.status()
+            Self::run_status(cmd.stdout(events::child_stdout()))
         } else {
-            cmd.stdout(Stdio::null()).stderr(Stdio::null()).status()
+            Self::run_status(cmd.stdout(Stdio::null()).stderr(Stdio::null()))
         };
 
         let status = result
@@ -188,9 +286,9 @@ impl LimaCtl {
         cmd.args(["stop", name]);
 
         let result = if verbose {
-            cmd.status()
+            Self::run_status(cmd.stdout(events::child_stdout()))
         } else {
-            cmd.stdout(Stdio::null()).stderr(Stdio::null()).status()
+            Self::run_status(cmd.stdout(Stdio::null()).stderr(Stdio::null()))
         };
 
         let status = result
@@ -218,9 +316,9 @@ impl LimaCtl {
         cmd.args(&args);
 
         let result = if verbose {
-            cmd.status()
+            Self::run_status(cmd.stdout(events::child_stdout()))
         } else {
-            cmd.stdout(Stdio::null()).stderr(Stdio::null()).status()
+            Self::run_status(cmd.stdout(Stdio::null()).stderr(Stdio::null()))
         };
 
         let status = result
@@ -236,18 +334,26 @@ impl LimaCtl {
         Ok(())
     }
 
-    /// Clone a Lima VM with additional mounts
-    pub fn clone(source: &str, dest: &str, mounts: &[Mount], verbose: bool) -> Result<()> {
+    /// Clone a Lima VM with additional mounts, and the CPU count and memory (GB)
+    /// of the session
+    pub fn clone(
+        source: &str,
+        dest: &str,
+        mounts: &[Mount],
+        cpus: u32,
+        memory: u32,
+        verbose: bool,
+    ) -> Result<()> {
         // Try "clone" first (older Lima), then "copy" (newer Lima)
         // This ensures compatibility across Lima versions
-        let result = Self::try_clone_command("clone", source, dest, mounts, verbose);
+        let result = Self::try_clone_command("clone", source, dest, mounts, cpus, memory, verbose);
 
         if result.is_ok() {
             return result;
         }
 
         // If clone failed, try copy (Lima >= 0.17)
-        Self::try_clone_command("copy", source, dest, mounts, verbose)
+        Self::try_clone_command("copy", source, dest, mounts, cpus, memory, verbose)
     }
 
     fn try_clone_command(
@@ -255,6 +361,8 @@ impl LimaCtl {
         source: &str,
         dest: &str,
         mounts: &[Mount],
+        cpus: u32,
+        memory: u32,
         verbose: bool,
     ) -> Result<()> {
         // Build mounts JSON array (matches bash format)
@@ -292,12 +400,17 @@ impl LimaCtl {
             cmd.arg("--set").arg(mounts_spec);
         }
 
+        // The template's CPU count and memory are only defaults: --cpus and
+        // --memory apply per session
+        cmd.arg("--set").arg(format!(".cpus={}", cpus));
+        cmd.arg("--set").arg(format!(".memory=\"{}GiB\"", memory));
+
         // Suppress output unless in verbose mode
         if !verbose {
             cmd.stdout(Stdio::null()).stderr(Stdio::null());
         }
 
-        let status = cmd.status().map_err(|e| {
+        let status = Self::run_status(cmd.stdout(events::child_stdout())).map_err(|e| {
             ClaudeVmError::LimaExecution(format!("Failed to {} VM: {}", command, e))
         })?;
 
@@ -337,15 +450,82 @@ impl LimaCtl {
         command.arg(cmd);
         command.args(args);
 
-        let status = command
+        command.stdin(Stdio::inherit());
+        let status = if build_log::is_active() {
+            Self::run_logged(&mut command)
+        } else {
+            Self::run_status(
+                command
+                    .stdout(events::child_stdout())
+                    .stderr(Stdio::inherit()),
+            )
+        }
+        .map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to execute shell: {}", e)))?;
+
+        if !status.success() {
+            // Return exit code if available, otherwise return generic error
+            return Err(match status.code() {
+                Some(code) => ClaudeVmError::CommandExitCode(code),
+                None => ClaudeVmError::LimaExecution("Command terminated by signal".to_string()),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Execute a command in a Lima VM, streaming its output with every line
+    /// prefixed by `[prefix]` so output from several sources can be told apart
+    pub fn shell_prefixed(name: &str, cmd: &str, args: &[&str], prefix: &str) -> Result<()> {
+        let started = Instant::now();
+        let mut command = Command::new("limactl");
+        command
+            .args(["shell", name, cmd])
+            .args(args)
             .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn().map_err(|e| {
+            trace::record(&command, started, Err(e.to_string()), None);
+            ClaudeVmError::LimaExecution(format!("Failed to execute shell: {}", e))
+        })?;
+
+        let stdout = child.stdout.take().map(std::io::BufReader::new);
+        let stderr = child.stderr.take().map(std::io::BufReader::new);
+
+        std::thread::scope(|scope| {
+            if let Some(stderr) = stderr {
+                scope.spawn(move || {
+                    let _ = process::prefix_lines(
+                        stderr,
+                        &mut build_log::tee(std::io::stderr()),
+                        prefix,
+                    );
+                });
+            }
+            if let Some(stdout) = stdout {
+                // Child stdout follows the same routing as `child_stdout()`
+                let _ = if events::stdout_reserved() {
+                    process::prefix_lines(stdout, &mut build_log::tee(std::io::stderr()), prefix)
+                } else {
+                    process::prefix_lines(stdout, &mut build_log::tee(std::io::stdout()), prefix)
+                };
+            }
+        });
+
+        let status = child.wait();
+        trace::record(
+            &command,
+            started,
+            status
+                .as_ref()
+                .map(ExitStatus::code)
+                .map_err(|e| e.to_string()),
+            None,
+        );
+        let status = status
             .map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to execute shell: {}", e)))?;
 
         if !status.success() {
-            // Return exit code if available, otherwise return generic error
             return Err(match status.code() {
                 Some(code) => ClaudeVmError::CommandExitCode(code),
                 None => ClaudeVmError::LimaExecution("Command terminated by signal".to_string()),
@@ -355,29 +535,120 @@ impl LimaCtl {
         Ok(())
     }
 
-    /// Copy a file into a Lima VM
+    /// Execute a command in a Lima VM, capturing its output instead of streaming it
+    pub fn shell_output(name: &str, cmd: &str, args: &[&str]) -> Result<std::process::Output> {
+        Self::run_output(
+            Command::new("limactl")
+                .args(["shell", name, cmd])
+                .args(args)
+                .stdin(Stdio::null()),
+        )
+        .map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to execute shell: {}", e)))
+    }
+
+    /// Execute a command in a Lima VM with `input` on its stdin, capturing its output.
+    /// Used to hand secrets to the VM without putting them in arguments or files.
+    pub fn shell_with_input(
+        name: &str,
+        cmd: &str,
+        args: &[&str],
+        input: &[u8],
+    ) -> Result<std::process::Output> {
+        let mut command = Command::new("limactl");
+        command
+            .args(["shell", name, cmd])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let started = Instant::now();
+        let output = command.spawn().and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                std::io::Write::write_all(&mut stdin, input)?;
+            }
+            child.wait_with_output()
+        });
+        match &output {
+            Ok(o) => trace::record(&command, started, Ok(o.status.code()), Some(o)),
+            Err(e) => trace::record(&command, started, Err(e.to_string()), None),
+        }
+        output.map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to execute shell: {}", e)))
+    }
+
+    /// Copy a file into a Lima VM.
+    ///
+    /// The copy is verified by comparing the remote file size with the local one,
+    /// and retried a few times so a transient failure or truncated copy does not
+    /// leave a broken file in the VM.
     pub fn copy(src: &Path, vm_name: &str, dest: &str) -> Result<()> {
+        let expected_size = std::fs::metadata(src)?.len();
+
+        copy_with_retry(
+            expected_size,
+            COPY_ATTEMPTS,
+            COPY_RETRY_DELAY,
+            || Self::copy_once(src, vm_name, dest),
+            || Self::remote_file_size(vm_name, dest),
+        )
+        .map_err(|e| {
+            ClaudeVmError::LimaExecution(format!(
+                "Failed to copy {} ({} bytes) to VM '{}' after {} attempts: {}",
+                src.display(),
+                expected_size,
+                vm_name,
+                COPY_ATTEMPTS,
+                e
+            ))
+        })
+    }
+
+    /// Run a single `limactl copy` without verification
+    fn copy_once(src: &Path, vm_name: &str, dest: &str) -> std::result::Result<(), String> {
         let dest_path = format!("{}:{}", vm_name, dest);
-        let status = Command::new("limactl")
-            .args(["copy", &src.to_string_lossy(), &dest_path])
-            .status()
-            .map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to copy file: {}", e)))?;
+        let output = Self::run_output(Command::new("limactl").args([
+            "copy",
+            &src.to_string_lossy(),
+            &dest_path,
+        ]))
+        .map_err(|e| format!("failed to run limactl copy: {}", e))?;
 
-        if !status.success() {
-            return Err(ClaudeVmError::LimaExecution(
-                "Failed to copy file".to_string(),
-            ));
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(match stderr.trim() {
+                "" => "limactl copy failed".to_string(),
+                stderr => format!("limactl copy failed: {}", stderr),
+            });
         }
 
         Ok(())
     }
 
+    /// Size in bytes of a file inside the VM
+    fn remote_file_size(vm_name: &str, path: &str) -> std::result::Result<u64, String> {
+        let output = Self::run_output(
+            Command::new("limactl").args(["shell", vm_name, "stat", "-c", "%s", "--", path]),
+        )
+        .map_err(|e| format!("failed to stat copied file: {}", e))?;
+
+        if !output.status.success() {
+            return Err("copied file not found in VM".to_string());
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<u64>()
+            .map_err(|e| format!("unexpected stat output: {}", e))
+    }
+
     /// List all Lima VMs
     pub fn list() -> Result<Vec<VmInfo>> {
-        let output = Command::new("limactl")
-            .args(["list", "--format", "{{.Name}}\t{{.Status}}"])
-            .output()
-            .map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to list VMs: {}", e)))?;
+        let output = Self::run_output_retrying(Command::new("limactl").args([
+            "list",
+            "--format",
+            "{{.Name}}\t{{.Status}}\t{{.Memory}}",
+        ]))
+        .map_err(|e| ClaudeVmError::LimaExecution(format!("Failed to list VMs: {}", e)))?;
 
         if !output.status.success() {
             return Err(ClaudeVmError::LimaExecution(
@@ -394,6 +665,7 @@ impl LimaCtl {
                     Some(VmInfo {
                         name: parts[0].to_string(),
                         status: parts[1].to_string(),
+                        memory: parts.get(2).and_then(|m| m.trim().parse().ok()),
                     })
                 } else {
                     None
@@ -404,6 +676,99 @@ impl LimaCtl {
         Ok(vms)
     }
 
+    /// Run a prepared limactl command, waiting for it to exit.
+    /// Every limactl invocation goes through `run_status` or `run_output`
+    /// (or records itself) so it appears in the `--trace` file.
+    pub fn run_status(cmd: &mut Command) -> std::io::Result<ExitStatus> {
+        let started = Instant::now();
+        let status = cmd.status();
+        trace::record(
+            cmd,
+            started,
+            status
+                .as_ref()
+                .map(ExitStatus::code)
+                .map_err(|e| e.to_string()),
+            None,
+        );
+        status
+    }
+
+    /// Run a prepared limactl command, streaming its output to the terminal
+    /// and to the build log
+    fn run_logged(cmd: &mut Command) -> std::io::Result<ExitStatus> {
+        let started = Instant::now();
+        let mut child = match cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                trace::record(cmd, started, Err(e.to_string()), None);
+                return Err(e);
+            }
+        };
+
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        std::thread::scope(|scope| {
+            if let Some(mut stderr) = stderr {
+                scope.spawn(move || {
+                    let _ = std::io::copy(&mut stderr, &mut build_log::tee(std::io::stderr()));
+                });
+            }
+            if let Some(mut stdout) = stdout {
+                // Child stdout follows the same routing as `child_stdout()`
+                let _ = if events::stdout_reserved() {
+                    std::io::copy(&mut stdout, &mut build_log::tee(std::io::stderr()))
+                } else {
+                    std::io::copy(&mut stdout, &mut build_log::tee(std::io::stdout()))
+                };
+            }
+        });
+
+        let status = child.wait();
+        trace::record(
+            cmd,
+            started,
+            status
+                .as_ref()
+                .map(ExitStatus::code)
+                .map_err(|e| e.to_string()),
+            None,
+        );
+        status
+    }
+
+    /// Run a prepared limactl command, capturing its output
+    pub fn run_output(cmd: &mut Command) -> std::io::Result<Output> {
+        let started = Instant::now();
+        let output = cmd.output();
+        match &output {
+            Ok(o) => trace::record(cmd, started, Ok(o.status.code()), Some(o)),
+            Err(e) => trace::record(cmd, started, Err(e.to_string()), None),
+        }
+        output
+    }
+
+    /// Like `run_output`, retrying failures with a transient error (see
+    /// `TRANSIENT_ERRORS`) up to `CLAUDE_VM_LIMA_RETRIES` times with exponential
+    /// backoff. Only for queries that are safe to repeat, never for user commands
+    /// run in the VM. Copies have their own verified retries (`COPY_ATTEMPTS`).
+    fn run_output_retrying(cmd: &mut Command) -> std::io::Result<Output> {
+        retry_transient(
+            crate::config::lima_retries(),
+            |delay| {
+                crate::status!(
+                    "limactl is not ready yet, retrying in {} ms...",
+                    delay.as_millis()
+                );
+                std::thread::sleep(delay);
+            },
+            || Self::run_output(cmd),
+            |output| {
+                !output.status.success() && is_transient(&String::from_utf8_lossy(&output.stderr))
+            },
+        )
+    }
+
     /// Check if a VM exists
     pub fn vm_exists(name: &str) -> Result<bool> {
         let vms = Self::list()?;
@@ -411,16 +776,244 @@ impl LimaCtl {
     }
 }
 
+/// Whether limactl failed with an error that usually goes away on its own
+fn is_transient(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    TRANSIENT_ERRORS
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+/// Delay before retry number `retry` (starting at 1)
+fn backoff_delay(retry: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// Run `attempt` until its result is not `retryable`, at most `retries` more times,
+/// sleeping with exponential backoff in between. Returns the last result.
+fn retry_transient<T>(
+    retries: u32,
+    mut sleep: impl FnMut(Duration),
+    mut attempt: impl FnMut() -> std::io::Result<T>,
+    retryable: impl Fn(&T) -> bool,
+) -> std::io::Result<T> {
+    let mut result = attempt();
+    for retry in 1..=retries {
+        match &result {
+            Ok(value) if retryable(value) => {}
+            _ => break,
+        }
+        sleep(backoff_delay(retry));
+        result = attempt();
+    }
+    result
+}
+
+/// Copy with bounded retries, verifying the copied size after each attempt.
+/// Returns the last failure reason if every attempt fails.
+fn copy_with_retry<C, S>(
+    expected_size: u64,
+    attempts: u32,
+    delay: Duration,
+    mut copy: C,
+    mut remote_size: S,
+) -> std::result::Result<(), String>
+where
+    C: FnMut() -> std::result::Result<(), String>,
+    S: FnMut() -> std::result::Result<u64, String>,
+{
+    let mut last_error = String::from("no copy attempted");
+
+    for attempt in 1..=attempts {
+        if attempt > 1 {
+            std::thread::sleep(delay);
+        }
+
+        if let Err(e) = copy() {
+            last_error = e;
+            continue;
+        }
+
+        match remote_size() {
+            Ok(size) if size == expected_size => return Ok(()),
+            Ok(size) => {
+                last_error = format!(
+                    "size mismatch after copy (expected {} bytes, got {})",
+                    expected_size, size
+                );
+            }
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
 #[derive(Debug)]
 pub struct VmInfo {
     pub name: String,
     pub status: String,
+    /// Memory allocated to the VM in bytes, when Lima reports it
+    pub memory: Option<u64>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_ensure_available_without_limactl() {
+        let empty = tempfile::TempDir::new().unwrap();
+        let err =
+            LimaCtl::ensure_available_in(Some(empty.path().as_os_str().to_owned())).unwrap_err();
+        assert!(matches!(err, ClaudeVmError::LimaNotInstalled));
+        assert!(err.to_string().contains("lima-vm.io/docs/installation"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ensure_available_finds_limactl() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let limactl = dir.path().join("limactl");
+        std::fs::write(&limactl, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&limactl, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert!(LimaCtl::ensure_available_in(Some(dir.path().as_os_str().to_owned())).is_ok());
+    }
+
+    #[test]
+    fn test_backoff_delay_schedule() {
+        let delays: Vec<u64> = (1..=6)
+            .map(|r| backoff_delay(r).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 8000, 8000]);
+        assert_eq!(backoff_delay(40), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(
+            "FATA[0000] instance \"claude-tpl\" is still starting"
+        ));
+        assert!(is_transient(
+            "ssh: connect to host 127.0.0.1 port 60022: Connection refused"
+        ));
+        assert!(is_transient(
+            "kex_exchange_identification: read: Connection reset by peer"
+        ));
+        assert!(!is_transient("bash: line 1: foo: command not found"));
+        assert!(!is_transient("FATA[0000] instance \"x\" does not exist"));
+        assert!(!is_transient(""));
+    }
+
+    #[test]
+    fn test_retry_transient() {
+        // Retries transient failures with growing delays, then succeeds
+        let mut results = vec!["ok", "connection refused", "connection refused"];
+        let mut delays = Vec::new();
+        let result = retry_transient(
+            3,
+            |d| delays.push(d),
+            || Ok(results.pop().unwrap()),
+            |r| is_transient(r),
+        );
+        assert_eq!(result.unwrap(), "ok");
+        assert_eq!(delays, vec![backoff_delay(1), backoff_delay(2)]);
+
+        // Gives up after the retries and returns the last failure
+        let mut attempts = 0;
+        let result = retry_transient(
+            2,
+            |_| {},
+            || {
+                attempts += 1;
+                Ok("connection refused")
+            },
+            |r| is_transient(r),
+        );
+        assert_eq!(result.unwrap(), "connection refused");
+        assert_eq!(attempts, 3);
+
+        // Other failures are not retried
+        let mut attempts = 0;
+        let result = retry_transient(
+            3,
+            |_| {},
+            || {
+                attempts += 1;
+                Ok("permission denied")
+            },
+            |r| is_transient(r),
+        );
+        assert_eq!(result.unwrap(), "permission denied");
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_copy_with_retry_succeeds_after_failure() {
+        let mut copies = 0;
+        let result = copy_with_retry(
+            42,
+            3,
+            Duration::ZERO,
+            || {
+                copies += 1;
+                if copies == 1 {
+                    Err("transient".to_string())
+                } else {
+                    Ok(())
+                }
+            },
+            || Ok(42),
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(copies, 2);
+    }
+
+    #[test]
+    fn test_copy_with_retry_detects_truncation() {
+        let mut sizes = vec![42, 10].into_iter();
+        let mut copies = 0;
+        let result = copy_with_retry(
+            42,
+            3,
+            Duration::ZERO,
+            || {
+                copies += 1;
+                Ok(())
+            },
+            || Ok(sizes.next_back().unwrap()),
+        );
+
+        // First copy is truncated (10 bytes), second is complete
+        assert!(result.is_ok());
+        assert_eq!(copies, 2);
+    }
+
+    #[test]
+    fn test_copy_with_retry_gives_up() {
+        let mut copies = 0;
+        let result = copy_with_retry(
+            42,
+            3,
+            Duration::ZERO,
+            || {
+                copies += 1;
+                Ok(())
+            },
+            || Ok(7),
+        );
+
+        assert_eq!(copies, 3);
+        let err = result.unwrap_err();
+        assert!(err.contains("expected 42 bytes, got 7"));
+    }
+
     #[test]
     fn test_vm_config_for_current_os() {
         let config = VmConfig::for_current_os();
@@ -1,5 +1,10 @@
+pub mod checkpoint;
+pub mod clock;
+pub mod git_credentials;
 pub mod limactl;
 pub mod mount;
 pub mod port_forward;
+pub mod preflight;
+pub mod restricted_user;
 pub mod session;
 pub mod template;
@@ -1,3 +1,4 @@
+use crate::config::{Config, MountEntry, ProjectRootFallback};
 use crate::error::{ClaudeVmError, Result};
 use crate::utils::git;
 use std::path::{Path, PathBuf};
@@ -7,6 +8,8 @@ pub struct Mount {
     pub location: PathBuf,
     pub mount_point: Option<PathBuf>,
     pub writable: bool,
+    /// Shared dependency mount from `[[shared_mounts]]`
+    pub shared: bool,
 }
 
 impl Mount {
@@ -15,6 +18,7 @@ impl Mount {
             location,
             mount_point: None,
             writable,
+            shared: false,
         }
     }
 
@@ -29,57 +33,165 @@ impl Mount {
     /// - `/host/path:ro` - read-only, same path in VM
     /// - `/host/path:/vm/path` - writable, custom VM path
     /// - `/host/path:/vm/path:ro` - read-only, custom VM path
+    /// - `/host/path=/vm/path[:ro]` - explicit separator, both paths may contain colons
+    ///
+    /// Only a trailing `:ro` or `:rw` is a mode. Without `=`, the VM path starts at
+    /// the first colon followed by `/` or `~`, so `/path:with:colons:/vm/path` works.
+    /// Quoted parts (`"/a:/b":/vm`) are taken literally.
+    ///
+    /// Host paths starting with `./` are kept relative and resolved against
+    /// the project root when the mounts are computed.
     pub fn from_spec(spec: &str) -> Result<Self> {
-        let parts: Vec<&str> = spec.split(':').collect();
-
-        let (host_path, vm_path, writable) = match parts.len() {
-            1 => {
-                // Format: /host/path
-                let path = expand_path(parts[0])?;
-                (path.clone(), None, true)
+        let mut chars = spec_chars(spec)?;
+
+        // Trailing mode
+        let mut writable = true;
+        let n = chars.len();
+        if n > 3 && chars[n - 3] == (':', false) {
+            let mode: String = chars[n - 2..].iter().map(|(c, _)| c).collect();
+            if !chars[n - 2].1 && !chars[n - 1].1 && (mode == "ro" || mode == "rw") {
+                writable = mode == "rw";
+                chars.truncate(n - 3);
             }
-            2 => {
-                // Format: /host/path:ro OR /host/path:/vm/path
-                let host = expand_path(parts[0])?;
-                if parts[1] == "ro" || parts[1] == "rw" {
-                    // Format: /host/path:ro
-                    let writable = parts[1] == "rw";
-                    (host, None, writable)
-                } else {
+        }
+
+        if chars.last() == Some(&('=', false)) {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Invalid mount specification '{}': missing VM path after '='",
+                spec
+            )));
+        }
+
+        // Only an '=' followed by an absolute VM path separates the paths, so
+        // host paths such as /data/year=2024 are kept whole
+        let explicit = chars
+            .windows(2)
+            .position(|pair| pair[0] == ('=', false) && (pair[1].0 == '/' || pair[1].0 == '~'));
+        let (host, vm) = if let Some(i) = explicit {
+            // Format: /host/path=/vm/path
+            (collect(&chars[..i]), Some(collect(&chars[i + 1..])))
+        } else {
+            match vm_path_separator(&chars) {
+                Some(i) => {
                     // Format: /host/path:/vm/path
-                    let vm = expand_path(parts[1])?;
-                    (host, Some(vm), true)
-                }
-            }
-            3 => {
-                // Format: /host/path:/vm/path:ro
-                let host = expand_path(parts[0])?;
-                let vm = expand_path(parts[1])?;
-                let writable = parts[2] == "rw";
-                if parts[2] != "ro" && parts[2] != "rw" {
-                    return Err(ClaudeVmError::InvalidConfig(format!(
-                        "Invalid mount mode '{}': must be 'ro' or 'rw'",
-                        parts[2]
-                    )));
+                    let vm = &chars[i + 1..];
+                    if let Some(j) = vm.iter().position(|&c| c == (':', false)) {
+                        let rest = collect(&vm[j + 1..]);
+                        return Err(ClaudeVmError::InvalidConfig(if rest.contains(':') {
+                            format!(
+                                "Invalid mount specification '{}': too many colons \
+                                (use host=vm or quotes for paths with colons)",
+                                spec
+                            )
+                        } else {
+                            format!("Invalid mount mode '{}': must be 'ro' or 'rw'", rest)
+                        }));
+                    }
+                    (collect(&chars[..i]), Some(collect(vm)))
                 }
-                (host, Some(vm), writable)
-            }
-            _ => {
-                return Err(ClaudeVmError::InvalidConfig(format!(
-                    "Invalid mount specification '{}': too many colons",
-                    spec
-                )));
+                None => (collect(&chars), None),
             }
         };
 
-        let mut mount = Mount::new(host_path, writable);
-        if let Some(vm) = vm_path {
-            mount = mount.with_mount_point(vm);
+        if host.is_empty() {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Invalid mount specification '{}': missing host path",
+                spec
+            )));
+        }
+
+        let mut mount = Mount::new(expand_host_path(&host)?, writable);
+        if let Some(vm) = vm {
+            mount = mount.with_mount_point(expand_path(&vm)?);
         }
         Ok(mount)
     }
 }
 
+/// Characters of a mount spec, with whether they were quoted (quotes removed)
+fn spec_chars(spec: &str) -> Result<Vec<(char, bool)>> {
+    let mut chars = Vec::with_capacity(spec.len());
+    let mut quote: Option<char> = None;
+    for c in spec.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => chars.push((c, true)),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, c) => chars.push((c, false)),
+        }
+    }
+    if let Some(q) = quote {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Invalid mount specification '{}': unterminated {} quote",
+            spec, q
+        )));
+    }
+    Ok(chars)
+}
+
+/// Position of the colon starting the VM path: the first unquoted colon followed
+/// by an absolute (`/`, `~`) or quoted path. A drive letter (`C:/`) is not one.
+fn vm_path_separator(chars: &[(char, bool)]) -> Option<usize> {
+    let drive_letter = chars.first().is_some_and(|(c, _)| c.is_ascii_alphabetic());
+    (0..chars.len().saturating_sub(1)).find(|&i| {
+        let (next, quoted) = chars[i + 1];
+        chars[i] == (':', false)
+            && !(i == 1 && drive_letter)
+            && (quoted || next == '/' || next == '~')
+    })
+}
+
+fn collect(chars: &[(char, bool)]) -> String {
+    chars.iter().map(|(c, _)| c).collect()
+}
+
+/// A memory-backed tmpfs mounted inside the VM (contents vanish on teardown)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmpfsMount {
+    pub path: PathBuf,
+    /// Size limit in tmpfs syntax (e.g. `512m`, `2g`, `50%`); kernel default when unset
+    pub size: Option<String>,
+}
+
+impl TmpfsMount {
+    /// Parse a `/vm/path[:size]` specification
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let (path, size) = match spec.split_once(':') {
+            Some((path, size)) => (path, Some(size)),
+            None => (spec, None),
+        };
+
+        if !path.starts_with('/') {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "tmpfs mount path must be absolute: {}",
+                path
+            )));
+        }
+
+        if let Some(size) = size {
+            if !is_valid_tmpfs_size(size) {
+                return Err(ClaudeVmError::InvalidConfig(format!(
+                    "Invalid tmpfs size '{}': expected a number with optional k/m/g suffix or a percentage (e.g. 512m, 2g, 50%)",
+                    size
+                )));
+            }
+        }
+
+        Ok(Self {
+            path: PathBuf::from(path),
+            size: size.map(|s| s.to_lowercase()),
+        })
+    }
+}
+
+/// Whether a size is accepted by tmpfs: digits with an optional k/m/g or % suffix
+fn is_valid_tmpfs_size(size: &str) -> bool {
+    let digits = size
+        .strip_suffix(['k', 'm', 'g', 'K', 'M', 'G', '%'])
+        .unwrap_or(size);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) && digits != "0"
+}
+
 /// Expand path with ~ support and make it absolute
 /// Supports both ~ (current user) and ~username (other users)
 pub fn expand_path(path: &str) -> Result<PathBuf> {
@@ -104,7 +216,7 @@ pub fn expand_path(path: &str) -> Result<PathBuf> {
     // Ensure path is absolute
     if !expanded.is_absolute() {
         return Err(ClaudeVmError::InvalidConfig(format!(
-            "Mount path must be absolute: {}",
+            "Mount path must be absolute (or start with ./ for a path in the project): {}",
             path
         )));
     }
@@ -112,13 +224,99 @@ pub fn expand_path(path: &str) -> Result<PathBuf> {
     Ok(expanded)
 }
 
+/// Whether a host mount location is relative to the project root (`./data`)
+fn is_project_relative(path: &str) -> bool {
+    path == "." || path.starts_with("./")
+}
+
+/// Like `expand_path`, but keeps project-relative paths as they are
+fn expand_host_path(path: &str) -> Result<PathBuf> {
+    if is_project_relative(path) {
+        Ok(PathBuf::from(path))
+    } else {
+        expand_path(path)
+    }
+}
+
+/// Symbolic mount locations and what they stand for
+pub const SYMBOLIC_LOCATIONS: [&str; 3] = ["git-root", "cwd", "home"];
+
+/// Resolve a symbolic mount location (`git-root`, `cwd` or `home`).
+/// Returns `None` for paths; a bare name that is not symbolic is an error.
+fn resolve_symbolic_location(location: &str) -> Result<Option<PathBuf>> {
+    if location.contains('/') || location.starts_with(['~', '.']) {
+        return Ok(None);
+    }
+    let path = match location {
+        "git-root" => git::get_git_root()?.ok_or_else(|| {
+            ClaudeVmError::InvalidConfig(
+                "Mount location git-root is only available in a git repository".to_string(),
+            )
+        })?,
+        "cwd" => std::env::current_dir()?,
+        "home" => expand_path("~")?,
+        _ => {
+            return Err(ClaudeVmError::InvalidConfig(format!(
+                "Unknown mount location '{}': use an absolute path, ~/ or ./ path, or one of {}",
+                location,
+                SYMBOLIC_LOCATIONS.join(", ")
+            )))
+        }
+    };
+    Ok(Some(path))
+}
+
+/// Resolve a host mount location. `./` paths are joined to the project root and
+/// must stay inside it unless `allow_outside` is set; other paths use `expand_path`.
+pub fn resolve_host_path(path: &str, project_root: &Path, allow_outside: bool) -> Result<PathBuf> {
+    if !is_project_relative(path) {
+        return expand_path(path);
+    }
+
+    // Follow symlinks once the paths exist, so a link cannot escape the project
+    let canonical = |path: PathBuf| path.canonicalize().unwrap_or(path);
+    let root = canonical(normalize(project_root));
+    let resolved = canonical(normalize(&project_root.join(path)));
+    if !resolved.is_absolute() {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Cannot resolve mount path {}: project root is unknown",
+            path
+        )));
+    }
+    if !allow_outside && !resolved.starts_with(&root) {
+        return Err(ClaudeVmError::InvalidConfig(format!(
+            "Mount path {} resolves to {}, outside the project root {}. \
+             Set allow_outside = true on the mount to allow it.",
+            path,
+            resolved.display(),
+            root.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Remove `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
 /// Encode a project path for use as a Claude conversation folder name
 /// Matches Claude Code's encoding logic:
 /// 1. Canonicalize path (resolve symlinks like /tmp -> /private/tmp)
 /// 2. Replace all non-alphanumeric characters with dashes
 ///
 ///    Example: /tmp/project@2024:v1.0 -> -private-tmp-project-2024-v1-0
-fn encode_project_path(path: &Path) -> String {
+pub(crate) fn encode_project_path(path: &Path) -> String {
     // Canonicalize path first (resolve symlinks)
     let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
 
@@ -159,13 +357,26 @@ pub(crate) fn get_claude_conversation_folder(project_path: &Path) -> Option<Path
 }
 
 /// Convert a slice of MountEntry configs to Mount structs with validation
-/// Checks for duplicates, conflicts, and warns about non-existent paths
-pub fn convert_mount_entries(mount_entries: &[crate::config::MountEntry]) -> Result<Vec<Mount>> {
+/// Checks for duplicates, conflicts, and non-existent paths: a missing path is
+/// a warning, or an error when `strict`.
+/// `./` locations are resolved against `project_root`.
+pub fn convert_mount_entries(
+    mount_entries: &[crate::config::MountEntry],
+    project_root: &Path,
+    strict: bool,
+) -> Result<Vec<Mount>> {
     let mut mounts: Vec<Mount> = Vec::new();
 
     for mount_entry in mount_entries {
         // Expand and validate the host path
-        let host_path = expand_path(&mount_entry.location)?;
+        let host_path = match resolve_symbolic_location(&mount_entry.location)? {
+            Some(path) => path,
+            None => resolve_host_path(
+                &mount_entry.location,
+                project_root,
+                mount_entry.allow_outside,
+            )?,
+        };
 
         // Create mount with explicit values from config
         let mut mount = Mount::new(host_path, mount_entry.writable);
@@ -178,9 +389,15 @@ pub fn convert_mount_entries(mount_entries: &[crate::config::MountEntry]) -> Res
 
         // Validate host path exists
         if !mount.location.exists() {
-            eprintln!(
-                "Warning: Mount path does not exist: {}",
-                mount.location.display()
+            if strict {
+                return Err(ClaudeVmError::InvalidConfig(format!(
+                    "Mount path does not exist: {} (strict mounts)",
+                    mount.location.display()
+                )));
+            }
+            crate::diagnostics::warn(
+                "mount-missing",
+                format!("Mount path does not exist: {}", mount.location.display()),
             );
         }
 
@@ -207,29 +424,17 @@ pub fn convert_mount_entries(mount_entries: &[crate::config::MountEntry]) -> Res
     Ok(mounts)
 }
 
-/// Compute the mounts needed for the VM
-/// Mounts the git repository root (if in a git repo), plus main repo if in a worktree,
-/// plus the Claude conversation folder for the current project (if mount_conversations is true),
-/// plus any custom mounts from the configuration
-pub fn compute_mounts(
-    mount_conversations: bool,
-    custom_mounts: &[crate::config::MountEntry],
-) -> Result<Vec<Mount>> {
-    let mut mounts = Vec::new();
-    let mut project_path: Option<PathBuf> = None;
-
+/// Host directories that make up the project: the git repository root
+/// (or, depending on `fallback`, the current directory outside git), plus the
+/// main repo if in a worktree.
+pub fn project_mount_locations(fallback: ProjectRootFallback) -> Vec<PathBuf> {
     // Try to mount the git repository root (so .git is accessible)
     // This ensures git works even when running from subdirectories
-    if let Ok(Some(git_root)) = git::get_git_root() {
-        project_path = Some(git_root.clone());
-        mounts.push(Mount::new(git_root, true));
-    } else {
-        // Fallback: mount current directory if not in a git repo
-        if let Ok(current_dir) = std::env::current_dir() {
-            project_path = Some(current_dir.clone());
-            mounts.push(Mount::new(current_dir, true));
-        }
-    }
+    let git_root = git::get_git_root().ok().flatten();
+    let mut locations: Vec<PathBuf> =
+        root_location(git_root, std::env::current_dir().ok(), fallback)
+            .into_iter()
+            .collect();
 
     // If in a git worktree, also mount the main repo (for git access)
     if git::is_worktree() {
@@ -237,53 +442,140 @@ pub fn compute_mounts(
             if let Some(main_repo) = git_common_dir.parent() {
                 let main_repo = main_repo.to_path_buf();
                 // Only add if different from already mounted directories
-                if !mounts.iter().any(|m| m.location == main_repo) {
-                    // Mount as writable to allow git operations from worktree
-                    mounts.push(Mount::new(main_repo, true));
+                if !locations.contains(&main_repo) {
+                    locations.push(main_repo);
                 }
             }
         }
     }
 
-    // Mount the Claude conversation folder for the current project (if enabled)
-    if mount_conversations {
-        if let Some(project) = project_path {
-            if let Some(conversation_folder) = get_claude_conversation_folder(&project) {
-                // Only add if not already mounted
-                if !mounts.iter().any(|m| m.location == conversation_folder) {
-                    // Extract the folder name (encoded project path)
-                    if let Some(folder_name) = conversation_folder.file_name() {
-                        // Map to VM home directory
-                        // Host: /Users/user/.claude/projects/... -> VM: /home/lima.linux/.claude/projects/...
-                        let vm_mount_point = PathBuf::from("/home/lima.linux")
-                            .join(".claude")
-                            .join("projects")
-                            .join(folder_name);
-
-                        mounts.push(
-                            Mount::new(conversation_folder, true).with_mount_point(vm_mount_point),
-                        );
-                    }
-                }
+    locations
+}
+
+/// The project root to mount: the git root, or the current directory outside git
+/// unless the fallback is disabled
+fn root_location(
+    git_root: Option<PathBuf>,
+    current_dir: Option<PathBuf>,
+    fallback: ProjectRootFallback,
+) -> Option<PathBuf> {
+    git_root.or_else(|| current_dir.filter(|_| fallback.mounts_cwd()))
+}
+
+/// Which Claude conversation folder a session mounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConversationMount<'a> {
+    /// No conversation folder
+    #[default]
+    None,
+    /// The current project's folder
+    CurrentProject,
+    /// Another project's folder (e.g. the main repository's, from a worktree),
+    /// so its history continues in the current project
+    Project(&'a Path),
+}
+
+/// What [`compute_mounts`] mounts besides the project
+#[derive(Debug, Clone, Default)]
+pub struct MountOptions<'a> {
+    /// Claude conversation folder to mount
+    pub conversations: ConversationMount<'a>,
+    /// Mount the project read-only, for a writable overlay in the guest
+    pub scratch: bool,
+    /// Custom mounts from the configuration and the command line
+    pub custom: &'a [MountEntry],
+    /// Shared dependency mounts
+    pub shared: Vec<MountEntry>,
+    /// What to mount as the project outside a git repository
+    pub project_root_fallback: ProjectRootFallback,
+    /// Fail when a custom or shared mount's host path does not exist
+    pub strict: bool,
+}
+
+impl<'a> MountOptions<'a> {
+    /// The mounts a session of `config` gets
+    pub fn from_config(config: &'a Config) -> Self {
+        Self {
+            conversations: config.conversation_mount(),
+            scratch: config.scratch,
+            custom: &config.mounts,
+            shared: config.shared_mount_entries(),
+            project_root_fallback: config.project_root_fallback(),
+            strict: config.strict_mounts,
+        }
+    }
+}
+
+/// Compute the mounts needed for the VM
+/// Mounts the git repository root (if in a git repo), plus main repo if in a worktree,
+/// plus the Claude conversation folder selected by `options.conversations`,
+/// plus any custom mounts from the configuration, then the shared dependency mounts.
+/// With `options.strict`, a custom or shared mount whose host path does not exist is an error.
+///
+/// In scratch mode, project mounts are read-only: a writable overlay is layered on top
+/// inside the guest by the runtime entrypoint.
+pub fn compute_mounts(options: &MountOptions) -> Result<Vec<Mount>> {
+    let locations = project_mount_locations(options.project_root_fallback);
+    let project_path = locations.first().cloned();
+
+    // Project mounts are writable to allow git operations (including from worktrees)
+    let mut mounts: Vec<Mount> = locations
+        .into_iter()
+        .map(|location| Mount::new(location, !options.scratch))
+        .collect();
+
+    // Mount the Claude conversation folder (if enabled). It is mounted where the
+    // agent looks for the current project's history, whichever project it belongs to.
+    let conversation_source = match options.conversations {
+        ConversationMount::None => None,
+        ConversationMount::CurrentProject => project_path.clone(),
+        ConversationMount::Project(path) => Some(path.to_path_buf()),
+    };
+    if let (Some(source), Some(project)) = (conversation_source, &project_path) {
+        if let Some(conversation_folder) = get_claude_conversation_folder(&source) {
+            // Only add if not already mounted
+            if !mounts.iter().any(|m| m.location == conversation_folder) {
+                // Map to VM home directory
+                // Host: /Users/user/.claude/projects/... -> VM: /home/lima.linux/.claude/projects/...
+                let vm_mount_point = PathBuf::from("/home/lima.linux")
+                    .join(".claude")
+                    .join("projects")
+                    .join(encode_project_path(project));
+
+                mounts.push(Mount::new(conversation_folder, true).with_mount_point(vm_mount_point));
             }
         }
     }
 
-    // Add custom mounts from configuration
-    let custom_mount_list = convert_mount_entries(custom_mounts)?;
+    // Add custom mounts from configuration, `./` paths are relative to the project
+    let project_root = project_path.clone().unwrap_or_default();
+    let custom_mount_list = convert_mount_entries(options.custom, &project_root, options.strict)?;
+    merge_mounts(&mut mounts, custom_mount_list)?;
+
+    // Shared dependency mounts come last: a custom mount of the same path wins
+    let shared_mount_list = convert_mount_entries(&options.shared, &project_root, options.strict)?
+        .into_iter()
+        .map(|mount| Mount {
+            shared: true,
+            ..mount
+        })
+        .collect();
+    merge_mounts(&mut mounts, shared_mount_list)?;
 
-    // Merge custom mounts, checking for conflicts with existing mounts
-    for custom_mount in custom_mount_list {
+    Ok(mounts)
+}
+
+/// Add `extra` mounts, skipping host locations already mounted and
+/// rejecting VM mount points already in use
+fn merge_mounts(mounts: &mut Vec<Mount>, extra: Vec<Mount>) -> Result<()> {
+    for mount in extra {
         // Check for duplicate host locations
-        if mounts.iter().any(|m| m.location == custom_mount.location) {
+        if mounts.iter().any(|m| m.location == mount.location) {
             continue; // Skip duplicate
         }
 
         // Check for conflicting VM mount points with existing mounts
-        let target_path = custom_mount
-            .mount_point
-            .as_ref()
-            .unwrap_or(&custom_mount.location);
+        let target_path = mount.mount_point.as_ref().unwrap_or(&mount.location);
         if mounts.iter().any(|m| {
             let existing_target = m.mount_point.as_ref().unwrap_or(&m.location);
             existing_target == target_path
@@ -294,16 +586,153 @@ pub fn compute_mounts(
             )));
         }
 
-        mounts.push(custom_mount);
+        mounts.push(mount);
     }
+    Ok(())
+}
 
-    Ok(mounts)
+/// Guest-side state of an expected mount, as reported by `findmnt`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuestMountState {
+    /// Mounted with the expected access mode
+    Ok,
+    /// Not mounted in the guest
+    Missing,
+    /// Mounted read-only although it should be writable
+    ReadOnly,
+    /// Mounted writable although it should be read-only
+    Writable,
+}
+
+/// Comparison of a host-side mount with what the guest actually mounted
+#[derive(Debug, Clone)]
+pub struct MountCheck {
+    pub mount: Mount,
+    pub state: GuestMountState,
+}
+
+impl MountCheck {
+    /// Path of the mount inside the guest
+    pub fn guest_path(&self) -> &Path {
+        self.mount
+            .mount_point
+            .as_ref()
+            .unwrap_or(&self.mount.location)
+    }
+}
+
+/// Parse `findmnt -rn -o TARGET,OPTIONS` output into (target, writable) pairs.
+/// Raw output escapes spaces and other special characters as `\xNN`.
+pub fn parse_findmnt(output: &str) -> Vec<(PathBuf, bool)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (target, options) = line.trim().split_once(' ')?;
+            let writable = options.split(',').next() == Some("rw");
+            Some((PathBuf::from(unescape_findmnt(target)), writable))
+        })
+        .collect()
+}
+
+fn unescape_findmnt(raw: &str) -> String {
+    // Escapes are bytes of the UTF-8 path, so decode them together
+    let mut out = Vec::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(pos) = rest.find("\\x") {
+        out.extend_from_slice(&rest.as_bytes()[..pos]);
+        let hex = rest.get(pos + 2..pos + 4);
+        match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+            Some(byte) => {
+                out.push(byte);
+                rest = &rest[pos + 4..];
+            }
+            None => {
+                out.extend_from_slice(b"\\x");
+                rest = &rest[pos + 2..];
+            }
+        }
+    }
+    out.extend_from_slice(rest.as_bytes());
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Compare the expected mounts with the guest's mount table
+pub fn check_guest_mounts(expected: &[Mount], guest: &[(PathBuf, bool)]) -> Vec<MountCheck> {
+    expected
+        .iter()
+        .map(|mount| {
+            let target = mount.mount_point.as_ref().unwrap_or(&mount.location);
+            // The last entry wins when a path is mounted over (e.g. overlays)
+            let state = match guest.iter().rev().find(|(path, _)| path == target) {
+                None => GuestMountState::Missing,
+                Some((_, true)) if !mount.writable => GuestMountState::Writable,
+                Some((_, false)) if mount.writable => GuestMountState::ReadOnly,
+                Some(_) => GuestMountState::Ok,
+            };
+            MountCheck {
+                mount: mount.clone(),
+                state,
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_findmnt() {
+        let output = "/ rw,relatime\n/home/user/my\\x20project ro,relatime\n/data rw\n";
+        assert_eq!(
+            parse_findmnt(output),
+            vec![
+                (PathBuf::from("/"), true),
+                (PathBuf::from("/home/user/my project"), false),
+                (PathBuf::from("/data"), true),
+            ]
+        );
+        assert!(parse_findmnt("").is_empty());
+    }
+
+    #[test]
+    fn test_unescape_findmnt_multibyte() {
+        assert_eq!(unescape_findmnt("/home/caf\\xc3\\xa9"), "/home/café");
+        assert_eq!(unescape_findmnt("/data\\xzz"), "/data\\xzz");
+    }
+
+    #[test]
+    fn test_check_guest_mounts() {
+        let expected = vec![
+            Mount::new(PathBuf::from("/project"), true),
+            Mount::new(PathBuf::from("/data"), false),
+            Mount::new(PathBuf::from("/cache"), true).with_mount_point(PathBuf::from("/vm/cache")),
+            Mount::new(PathBuf::from("/missing"), true),
+            Mount::new(PathBuf::from("/ro-ok"), false),
+        ];
+        let guest = vec![
+            (PathBuf::from("/project"), false),
+            (PathBuf::from("/data"), true),
+            (PathBuf::from("/vm/cache"), true),
+            (PathBuf::from("/ro-ok"), false),
+        ];
+
+        let states: Vec<GuestMountState> = check_guest_mounts(&expected, &guest)
+            .into_iter()
+            .map(|c| c.state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                GuestMountState::ReadOnly,
+                GuestMountState::Writable,
+                GuestMountState::Ok,
+                GuestMountState::Missing,
+                GuestMountState::Ok,
+            ]
+        );
+    }
+
     // Test 1: Mount struct tests
     #[test]
     fn test_mount_creation() {
@@ -602,6 +1031,135 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("must be absolute"));
     }
 
+    #[test]
+    fn test_from_spec_project_relative_path() {
+        let mount = Mount::from_spec("./data:/vm/data:ro").unwrap();
+        assert_eq!(mount.location, PathBuf::from("./data"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/vm/data")));
+        assert!(!mount.writable);
+    }
+
+    #[test]
+    fn test_resolve_host_path_project_relative() {
+        let root = Path::new("/work/project");
+        assert_eq!(
+            resolve_host_path("./sub", root, false).unwrap(),
+            PathBuf::from("/work/project/sub")
+        );
+        assert_eq!(
+            resolve_host_path("./sub/../other/./dir", root, false).unwrap(),
+            PathBuf::from("/work/project/other/dir")
+        );
+        assert_eq!(
+            resolve_host_path(".", root, false).unwrap(),
+            PathBuf::from("/work/project")
+        );
+        // Absolute paths are unchanged
+        assert_eq!(
+            resolve_host_path("/data", root, false).unwrap(),
+            PathBuf::from("/data")
+        );
+    }
+
+    #[test]
+    fn test_resolve_host_path_outside_project() {
+        let root = Path::new("/work/project");
+        let err = resolve_host_path("./../shared", root, false).unwrap_err();
+        assert!(err.to_string().contains("outside the project root"));
+        assert!(err.to_string().contains("allow_outside"));
+
+        assert_eq!(
+            resolve_host_path("./../shared", root, true).unwrap(),
+            PathBuf::from("/work/shared")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_host_path_symlink_outside_project() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("project");
+        let outside = dir.path().join("secrets");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let err = resolve_host_path("./link", &root, false).unwrap_err();
+        assert!(err.to_string().contains("outside the project root"));
+        assert_eq!(
+            resolve_host_path("./link", &root, true).unwrap(),
+            outside.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_convert_mount_entries_project_relative() {
+        use crate::config::MountEntry;
+
+        let entries = vec![MountEntry {
+            location: "./data".to_string(),
+            writable: true,
+            mount_point: None,
+            allow_outside: false,
+        }];
+        let mounts = convert_mount_entries(&entries, Path::new("/work/project"), false).unwrap();
+        assert_eq!(mounts[0].location, PathBuf::from("/work/project/data"));
+    }
+
+    #[test]
+    fn test_convert_mount_entries_strict_missing_path() {
+        use crate::config::MountEntry;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let entries = vec![MountEntry {
+            location: "./missing".to_string(),
+            writable: false,
+            mount_point: None,
+            allow_outside: false,
+        }];
+
+        // Lenient: the missing path is only a warning
+        let mounts = convert_mount_entries(&entries, dir.path(), false).unwrap();
+        assert_eq!(mounts[0].location, dir.path().join("missing"));
+
+        // Strict: the missing path is an error
+        let err = convert_mount_entries(&entries, dir.path(), true).unwrap_err();
+        assert!(err.to_string().contains("Mount path does not exist"));
+
+        std::fs::create_dir(dir.path().join("missing")).unwrap();
+        assert!(convert_mount_entries(&entries, dir.path(), true).is_ok());
+    }
+
+    #[test]
+    fn test_compute_mounts_strict_missing_path() {
+        use crate::config::MountEntry;
+
+        let custom = vec![MountEntry {
+            location: "/nonexistent/claude-vm-strict-test".to_string(),
+            writable: false,
+            mount_point: Some("/data".to_string()),
+            allow_outside: true,
+        }];
+        let lenient = MountOptions {
+            custom: &custom,
+            ..Default::default()
+        };
+        let strict = MountOptions {
+            strict: true,
+            ..lenient.clone()
+        };
+        let strict_shared = MountOptions {
+            shared: custom.clone(),
+            strict: true,
+            ..Default::default()
+        };
+
+        assert!(compute_mounts(&lenient).is_ok());
+        assert!(compute_mounts(&strict).is_err());
+        // Shared mounts are checked too
+        assert!(compute_mounts(&strict_shared).is_err());
+    }
+
     #[test]
     fn test_from_spec_invalid_mode() {
         let result = Mount::from_spec("/host:/vm:invalid");
@@ -619,6 +1177,72 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("too many colons"));
     }
 
+    #[test]
+    fn test_from_spec_host_path_with_colons() {
+        let mount = Mount::from_spec("/path:with:colons:/vm/path").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/path:with:colons"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/vm/path")));
+        assert!(mount.writable);
+
+        let mount = Mount::from_spec("/path:with:colons:/vm/path:ro").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/path:with:colons"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/vm/path")));
+        assert!(!mount.writable);
+
+        // No VM path
+        let mount = Mount::from_spec("/a:b/c").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/a:b/c"));
+        assert_eq!(mount.mount_point, None);
+        let mount = Mount::from_spec("/mnt/c:data:ro").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/mnt/c:data"));
+        assert!(!mount.writable);
+    }
+
+    #[test]
+    fn test_from_spec_explicit_separator_and_quotes() {
+        let mount = Mount::from_spec("/host/a:/b=/vm/c:d:ro").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/host/a:/b"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/vm/c:d")));
+        assert!(!mount.writable);
+
+        let mount = Mount::from_spec("\"/host/a:/b\":'/vm/my data':rw").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/host/a:/b"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/vm/my data")));
+        assert!(mount.writable);
+
+        // A quoted mode is part of the path
+        let mount = Mount::from_spec("/data\":ro\"").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/data:ro"));
+        assert!(mount.writable);
+
+        assert!(Mount::from_spec("\"/unterminated").is_err());
+        assert!(Mount::from_spec("/host=").is_err());
+        assert!(Mount::from_spec("=/vm").is_err());
+    }
+
+    #[test]
+    fn test_from_spec_equals_in_host_path() {
+        let mount = Mount::from_spec("/data/year=2024").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/data/year=2024"));
+        assert_eq!(mount.mount_point, None);
+
+        let mount = Mount::from_spec("/data/year=2024:/data:ro").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/data/year=2024"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/data")));
+        assert!(!mount.writable);
+
+        let mount = Mount::from_spec("/data/year=2024=/vm/data").unwrap();
+        assert_eq!(mount.location, PathBuf::from("/data/year=2024"));
+        assert_eq!(mount.mount_point, Some(PathBuf::from("/vm/data")));
+    }
+
+    #[test]
+    fn test_from_spec_drive_letter() {
+        // Not split at the drive letter; not an absolute path on this host
+        let err = Mount::from_spec("C:/Users/me:/data").unwrap_err();
+        assert!(err.to_string().contains("C:/Users/me"));
+    }
+
     #[test]
     fn test_expand_path_absolute() {
         let path = expand_path("/absolute/path").unwrap();
@@ -672,15 +1296,20 @@ mod tests {
                 location: "/host/path1".to_string(),
                 writable: true,
                 mount_point: Some("/vm/shared".to_string()),
+                allow_outside: false,
             },
             MountEntry {
                 location: "/host/path2".to_string(),
                 writable: true,
                 mount_point: Some("/vm/shared".to_string()), // Conflict!
+                allow_outside: false,
             },
         ];
 
-        let result = compute_mounts(false, &custom_mounts);
+        let result = compute_mounts(&MountOptions {
+            custom: &custom_mounts,
+            ..Default::default()
+        });
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -697,15 +1326,21 @@ mod tests {
                 location: "/host/data".to_string(),
                 writable: true,
                 mount_point: None,
+                allow_outside: false,
             },
             MountEntry {
                 location: "/host/data".to_string(), // Duplicate location
                 writable: false,
                 mount_point: None,
+                allow_outside: false,
             },
         ];
 
-        let result = compute_mounts(false, &custom_mounts).unwrap();
+        let result = compute_mounts(&MountOptions {
+            custom: &custom_mounts,
+            ..Default::default()
+        })
+        .unwrap();
         // Should only have one mount (duplicate filtered)
         assert_eq!(
             result
@@ -716,6 +1351,126 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_symbolic_mount_locations() {
+        use crate::config::MountEntry;
+
+        let entry = |location: &str| MountEntry {
+            location: location.to_string(),
+            writable: false,
+            mount_point: None,
+            allow_outside: false,
+        };
+        let git_root = git::get_git_root().unwrap().unwrap();
+
+        let mounts =
+            convert_mount_entries(&[entry("git-root")], Path::new("/work/project"), false).unwrap();
+        assert_eq!(mounts[0].location, git_root);
+        let mounts = convert_mount_entries(&[entry("cwd")], Path::new("/work"), false).unwrap();
+        assert_eq!(mounts[0].location, std::env::current_dir().unwrap());
+
+        let err = convert_mount_entries(&[entry("gitroot")], Path::new("/work"), false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Unknown mount location 'gitroot'"));
+        assert!(err.contains("git-root, cwd, home"));
+
+        // The repository is already mounted as the project: no second mount
+        let result = compute_mounts(&MountOptions {
+            custom: &[entry("git-root")],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(result.iter().filter(|m| m.location == git_root).count(), 1);
+        assert!(
+            result
+                .iter()
+                .find(|m| m.location == git_root)
+                .unwrap()
+                .writable
+        );
+    }
+
+    #[test]
+    fn test_root_location_fallback_modes() {
+        let git = Some(PathBuf::from("/work/repo"));
+        let cwd = Some(PathBuf::from("/home/me/downloads"));
+
+        // In a git repository, the git root is mounted whatever the fallback
+        for fallback in [
+            ProjectRootFallback::Cwd,
+            ProjectRootFallback::None,
+            ProjectRootFallback::Prompt,
+        ] {
+            assert_eq!(root_location(git.clone(), cwd.clone(), fallback), git);
+        }
+
+        // Outside git: cwd mounts it, none mounts nothing, prompt mounts it once confirmed
+        assert_eq!(
+            root_location(None, cwd.clone(), ProjectRootFallback::Cwd),
+            cwd
+        );
+        assert_eq!(
+            root_location(None, cwd.clone(), ProjectRootFallback::None),
+            None
+        );
+        assert_eq!(
+            root_location(None, cwd.clone(), ProjectRootFallback::Prompt),
+            cwd
+        );
+    }
+
+    #[test]
+    fn test_project_root_fallback_config() {
+        let config: crate::config::Config =
+            toml::from_str("project_root_fallback = \"none\"").unwrap();
+        assert_eq!(config.project_root_fallback(), ProjectRootFallback::None);
+        assert_eq!(
+            crate::config::Config::default().project_root_fallback(),
+            ProjectRootFallback::Cwd
+        );
+    }
+
+    #[test]
+    fn test_shared_mounts_come_last_and_read_only() {
+        use crate::config::SharedMountEntry;
+
+        let shared: Vec<_> = [SharedMountEntry {
+            location: "/srv/registry".to_string(),
+            writable: false,
+            mount_point: Some("/deps/registry".to_string()),
+        }]
+        .iter()
+        .map(SharedMountEntry::to_mount_entry)
+        .collect();
+
+        let result = compute_mounts(&MountOptions {
+            shared: shared.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        let last = result.last().unwrap();
+        assert!(last.shared);
+        assert!(!last.writable);
+        assert_eq!(last.mount_point, Some(PathBuf::from("/deps/registry")));
+        assert!(result[..result.len() - 1].iter().all(|m| !m.shared));
+
+        // A shared mount may not take a VM path already used by a custom mount
+        let custom = vec![crate::config::MountEntry {
+            location: "/host/other".to_string(),
+            writable: true,
+            mount_point: Some("/deps/registry".to_string()),
+            allow_outside: false,
+        }];
+        let err = compute_mounts(&MountOptions {
+            custom: &custom,
+            shared: shared.clone(),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("Mount point conflict"));
+    }
+
     #[test]
     fn test_writable_override() {
         use crate::config::MountEntry;
@@ -725,13 +1480,69 @@ mod tests {
             location: "/host/data".to_string(),
             writable: false, // Explicitly read-only
             mount_point: None,
+            allow_outside: false,
         }];
 
-        let result = compute_mounts(false, &custom_mounts).unwrap();
+        let result = compute_mounts(&MountOptions {
+            custom: &custom_mounts,
+            ..Default::default()
+        })
+        .unwrap();
         let mount = result
             .iter()
             .find(|m| m.location.to_string_lossy() == "/host/data");
         assert!(mount.is_some());
         assert!(!mount.unwrap().writable); // Should be read-only
     }
+
+    #[test]
+    fn test_scratch_makes_project_mounts_read_only() {
+        let locations = project_mount_locations(ProjectRootFallback::Cwd);
+        assert!(!locations.is_empty());
+
+        let normal = compute_mounts(&MountOptions {
+            ..Default::default()
+        })
+        .unwrap();
+        let scratch = compute_mounts(&MountOptions {
+            scratch: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        for location in &locations {
+            let find = |mounts: &[Mount]| {
+                mounts
+                    .iter()
+                    .find(|m| &m.location == location)
+                    .map(|m| m.writable)
+            };
+            assert_eq!(find(&normal), Some(true));
+            assert_eq!(find(&scratch), Some(false));
+        }
+    }
+
+    #[test]
+    fn test_tmpfs_from_spec() {
+        let mount = TmpfsMount::from_spec("/tmp/build").unwrap();
+        assert_eq!(mount.path, PathBuf::from("/tmp/build"));
+        assert_eq!(mount.size, None);
+
+        let mount = TmpfsMount::from_spec("/tmp/build:2G").unwrap();
+        assert_eq!(mount.size, Some("2g".to_string()));
+
+        assert!(TmpfsMount::from_spec("/cache:512m").is_ok());
+        assert!(TmpfsMount::from_spec("/cache:50%").is_ok());
+        assert!(TmpfsMount::from_spec("/cache:1048576").is_ok());
+    }
+
+    #[test]
+    fn test_tmpfs_from_spec_invalid() {
+        assert!(TmpfsMount::from_spec("relative/path").is_err());
+        assert!(TmpfsMount::from_spec("~/cache").is_err());
+        assert!(TmpfsMount::from_spec("/cache:").is_err());
+        assert!(TmpfsMount::from_spec("/cache:big").is_err());
+        assert!(TmpfsMount::from_spec("/cache:2gb").is_err());
+        assert!(TmpfsMount::from_spec("/cache:0").is_err());
+    }
 }
@@ -0,0 +1,330 @@
+//! Host resource checks run before creating a VM.
+//!
+//! Lima fails late and cryptically when the host cannot back a VM, so the
+//! requested disk and memory are compared with what the host has first.
+//! The host user is checked at startup: claude-vm is meant to run as a normal user.
+
+use crate::config::VmConfig;
+use crate::error::{ClaudeVmError, Result};
+use crate::vm::limactl::{LimaCtl, VmInfo};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Set to 1 to skip the checks (e.g. when sparse disk images make the disk check too strict)
+pub const SKIP_PREFLIGHT_ENV: &str = "CLAUDE_VM_SKIP_PREFLIGHT";
+
+/// Set to 1 to run as root on the host without the warning
+pub const ALLOW_ROOT_ENV: &str = "CLAUDE_VM_ALLOW_ROOT";
+
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// What the host can offer to a new VM. Unknown values are skipped.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HostResources {
+    /// Free bytes on the filesystem holding Lima's VM directory
+    pub free_disk: Option<u64>,
+    /// Memory in bytes available for VMs
+    pub memory: Option<u64>,
+    /// Memory in bytes allocated to claude-vm VMs that are already running
+    pub running_vm_memory: u64,
+}
+
+impl HostResources {
+    /// Detect the host resources, including the free disk space when `disk` is set
+    pub fn detect(disk: bool) -> Self {
+        // On Linux, MemAvailable already excludes the memory running VMs use
+        let running_vm_memory = if cfg!(target_os = "linux") {
+            0
+        } else {
+            running_vm_memory(&LimaCtl::list().unwrap_or_default())
+        };
+        Self {
+            free_disk: disk.then(|| free_disk_bytes(&lima_home())).flatten(),
+            memory: host_memory_bytes(),
+            running_vm_memory,
+        }
+    }
+
+    /// Count `bytes` that will be freed before the VM is created as free disk
+    pub fn with_reclaimable_disk(mut self, bytes: u64) -> Self {
+        self.free_disk = self.free_disk.map(|free| free.saturating_add(bytes));
+        self
+    }
+}
+
+/// Check the host can build a template with the requested resources.
+/// Prints memory warnings; fails when the disk image cannot fit.
+/// `reclaimable` is the disk of the template being replaced: run the check
+/// before deleting it, so a failed check keeps the working template.
+pub fn check(vm: &VmConfig, reclaimable: u64) -> Result<()> {
+    run_check(vm, Some(reclaimable))
+}
+
+/// Check the host has the memory for a session VM (prints warnings only).
+/// Sessions are clones of the template, so the disk was checked at setup.
+pub fn check_memory(vm: &VmConfig) -> Result<()> {
+    run_check(vm, None)
+}
+
+/// Run the checks, including the disk one when the reclaimable disk is given
+fn run_check(vm: &VmConfig, reclaimable: Option<u64>) -> Result<()> {
+    if std::env::var(SKIP_PREFLIGHT_ENV).is_ok_and(|v| v == "1") {
+        return Ok(());
+    }
+
+    let host = HostResources::detect(reclaimable.is_some())
+        .with_reclaimable_disk(reclaimable.unwrap_or(0));
+    for warning in evaluate(vm, &host)? {
+        crate::diagnostics::warn("host-resources", warning);
+    }
+    Ok(())
+}
+
+/// Warn when running as root on the host, or fail with `deny_warnings`
+pub fn check_host_user(deny_warnings: bool) -> Result<()> {
+    let allowed = std::env::var(ALLOW_ROOT_ENV).is_ok_and(|v| v == "1");
+    match root_warning(uzers::get_effective_uid(), allowed) {
+        Some(message) if deny_warnings => Err(ClaudeVmError::CommandFailed(format!(
+            "{} (failing because of --deny-warnings)",
+            message
+        ))),
+        Some(message) => {
+            crate::diagnostics::warn("host-root", message);
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Why running as `euid` is a problem, unless root was explicitly allowed
+fn root_warning(euid: u32, allowed: bool) -> Option<String> {
+    if euid != 0 || allowed {
+        return None;
+    }
+    Some(format!(
+        "claude-vm is running as root. Lima VMs, templates and files created in the \
+         project will be owned by root, and later runs as your user will hit permission \
+         errors. Run claude-vm as a normal user (Lima does not need root), or set {}=1 \
+         to silence this warning.",
+        ALLOW_ROOT_ENV
+    ))
+}
+
+/// Compare the requested resources with the host's.
+/// Insufficient disk is an error, memory pressure only produces warnings.
+pub fn evaluate(vm: &VmConfig, host: &HostResources) -> Result<Vec<String>> {
+    let disk = u64::from(vm.disk) * GB;
+    let memory = u64::from(vm.memory) * GB;
+
+    if let Some(free) = host.free_disk {
+        if disk > free {
+            return Err(ClaudeVmError::InsufficientResources(format!(
+                "requested {}GB of disk but host has {}GB free in {}.\n\
+                 Lower the disk size with --disk or [vm] disk, or free up space. \
+                 Set {}=1 to skip this check.",
+                vm.disk,
+                free / GB,
+                lima_home().display(),
+                SKIP_PREFLIGHT_ENV
+            )));
+        }
+    }
+
+    let mut warnings = Vec::new();
+    if let Some(host_memory) = host.memory {
+        if memory > host_memory {
+            warnings.push(format!(
+                "requested {}GB of memory but host has {}GB available",
+                vm.memory,
+                host_memory / GB
+            ));
+        } else if host.running_vm_memory > 0 && memory + host.running_vm_memory > host_memory {
+            warnings.push(format!(
+                "requested {}GB of memory while running claude-vm VMs already use {}GB \
+                 (host has {}GB available); VMs may swap or be killed",
+                vm.memory,
+                host.running_vm_memory / GB,
+                host_memory / GB
+            ));
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Memory allocated to running claude-vm VMs
+fn running_vm_memory(vms: &[VmInfo]) -> u64 {
+    vms.iter()
+        .filter(|vm| vm.name.starts_with("claude-tpl_") && vm.status == "Running")
+        .filter_map(|vm| vm.memory)
+        .sum()
+}
+
+/// Directory where Lima stores VM disks
+fn lima_home() -> PathBuf {
+    if let Some(home) = std::env::var_os("LIMA_HOME") {
+        return PathBuf::from(home);
+    }
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    home.join(".lima")
+}
+
+/// Free space on the filesystem holding `path` (or its closest existing parent)
+fn free_disk_bytes(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let output = Command::new("df").arg("-Pk").arg(existing).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_df_available(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the "Available" column (in KiB) of POSIX `df -Pk` output
+fn parse_df_available(output: &str) -> Option<u64> {
+    let line = output.lines().nth(1)?;
+    let kib: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Memory the host can give to VMs: available memory on Linux, physical memory on macOS
+fn host_memory_bytes() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        parse_meminfo_available(&meminfo)
+    } else if cfg!(target_os = "macos") {
+        let output = Command::new("sysctl")
+            .args(["-n", "hw.memsize"])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Parse MemAvailable (in kB) from /proc/meminfo
+fn parse_meminfo_available(meminfo: &str) -> Option<u64> {
+    let line = meminfo
+        .lines()
+        .find(|line| line.starts_with("MemAvailable:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_warning() {
+        assert!(root_warning(1000, false).is_none());
+        assert!(root_warning(0, true).is_none());
+
+        let warning = root_warning(0, false).unwrap();
+        assert!(warning.contains("running as root"));
+        assert!(warning.contains(ALLOW_ROOT_ENV));
+    }
+
+    fn vm_config(disk: u32, memory: u32) -> VmConfig {
+        VmConfig {
+            disk,
+            memory,
+            ..Default::default()
+        }
+    }
+
+    fn host(free_disk_gb: u64, memory_gb: u64, running_gb: u64) -> HostResources {
+        HostResources {
+            free_disk: Some(free_disk_gb * GB),
+            memory: Some(memory_gb * GB),
+            running_vm_memory: running_gb * GB,
+        }
+    }
+
+    #[test]
+    fn test_enough_resources() {
+        let warnings = evaluate(&vm_config(20, 8), &host(100, 32, 8)).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_insufficient_disk_is_an_error() {
+        let err = evaluate(&vm_config(32, 8), &host(16, 32, 0)).unwrap_err();
+        assert!(matches!(err, ClaudeVmError::InsufficientResources(_)));
+        let msg = err.to_string();
+        assert!(msg.contains("requested 32GB of disk but host has 16GB free"));
+        assert!(msg.contains(SKIP_PREFLIGHT_ENV));
+    }
+
+    #[test]
+    fn test_replaced_template_disk_counts_as_free() {
+        let host = host(16, 32, 0).with_reclaimable_disk(20 * GB);
+        assert!(evaluate(&vm_config(32, 8), &host).unwrap().is_empty());
+
+        // Unknown free disk stays unknown
+        let host = HostResources::default().with_reclaimable_disk(20 * GB);
+        assert_eq!(host.free_disk, None);
+    }
+
+    #[test]
+    fn test_memory_above_host_warns() {
+        let warnings = evaluate(&vm_config(20, 32), &host(100, 16, 0)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("requested 32GB of memory but host has 16GB available"));
+    }
+
+    #[test]
+    fn test_memory_overcommit_warns() {
+        let warnings = evaluate(&vm_config(20, 8), &host(100, 16, 12)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("already use 12GB"));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_session_check_skips_disk_and_running_vms() {
+        let host = HostResources::detect(false);
+        assert_eq!(host.free_disk, None);
+        assert_eq!(host.running_vm_memory, 0);
+    }
+
+    #[test]
+    fn test_unknown_host_resources_skip_checks() {
+        let warnings = evaluate(&vm_config(500, 512), &HostResources::default()).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_running_vm_memory_counts_running_claude_vms() {
+        let vm = |name: &str, status: &str, memory: Option<u64>| VmInfo {
+            name: name.to_string(),
+            status: status.to_string(),
+            memory,
+        };
+        let vms = vec![
+            vm("claude-tpl_a_12345678", "Running", Some(8 * GB)),
+            vm("claude-tpl_a_12345678-42", "Running", Some(4 * GB)),
+            vm("claude-tpl_b_87654321", "Stopped", Some(8 * GB)),
+            vm("other", "Running", Some(16 * GB)),
+            vm("claude-tpl_c_00000000", "Running", None),
+        ];
+        assert_eq!(running_vm_memory(&vms), 12 * GB);
+    }
+
+    #[test]
+    fn test_parse_df_available() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/disk3s5     971350180 500000000 471350180      52% /System/Volumes/Data\n";
+        assert_eq!(parse_df_available(output), Some(471350180 * 1024));
+        assert_eq!(parse_df_available(""), None);
+    }
+
+    #[test]
+    fn test_parse_meminfo_available() {
+        let meminfo = "MemTotal:       32768000 kB\nMemFree:         1000000 kB\nMemAvailable:   16384000 kB\n";
+        assert_eq!(parse_meminfo_available(meminfo), Some(16384000 * 1024));
+        assert_eq!(parse_meminfo_available("MemTotal: 1 kB\n"), None);
+    }
+}
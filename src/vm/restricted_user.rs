@@ -0,0 +1,146 @@
+//! Unprivileged guest user for `--drop-privileges` sessions.
+//!
+//! Every template gets a `claude-agent` user without sudo, created during
+//! setup. Packages are still installed by the default (sudo-capable) VM user;
+//! only the session command is switched to the restricted user, at the very
+//! end of the runtime entrypoint, once runtime scripts and context are ready.
+
+use crate::error::Result;
+use crate::status;
+use crate::utils::shell::escape as shell_escape;
+use crate::vm::limactl::LimaCtl;
+use std::path::PathBuf;
+
+/// Name of the restricted guest user
+pub const USER: &str = "claude-agent";
+
+/// Create the restricted user in the template (idempotent).
+/// It joins the default user's group so it can run the agent installed in its home,
+/// and `bindfs` is installed to map it onto the owner of the project mounts in each session.
+pub fn provision(vm_name: &str) -> Result<()> {
+    status!("Creating restricted user {}...", USER);
+    let script = format!(
+        "set -e\n\
+         id {user} >/dev/null 2>&1 || sudo useradd --create-home --shell /bin/bash {user}\n\
+         sudo passwd -l {user} >/dev/null\n\
+         sudo usermod -aG \"$(id -gn)\" {user}\n\
+         chmod g+x \"$HOME\"\n\
+         command -v bindfs >/dev/null || sudo DEBIAN_FRONTEND=noninteractive apt-get install -y -q bindfs\n",
+        user = USER
+    );
+    LimaCtl::shell(vm_name, None, "bash", &["-c", &script], false)
+}
+
+/// Entrypoint section handing the session command to the restricted user.
+///
+/// The agent config (credentials, settings and the generated CLAUDE.md) is
+/// copied into its home, and the exported environment and PATH are kept.
+/// Must come last: it ends with the `exec` of the command.
+pub fn build_entrypoint_switch(project_dirs: &[PathBuf]) -> String {
+    format!(
+        "{}exec {}\n",
+        build_switch_home(project_dirs),
+        switched_command()
+    )
+}
+
+/// Entrypoint section preparing the restricted user's home and mapping it onto
+/// the project directories, without running anything
+pub fn build_switch_home(project_dirs: &[PathBuf]) -> String {
+    let mut script = build_home_copy();
+    script.push_str(&build_project_mapping(project_dirs));
+    script
+}
+
+/// Give the restricted user read-write access to the project directories.
+/// Project files are not group-writable (umask 022), so group membership is not
+/// enough. Each directory is remounted inside the VM with bindfs, showing the
+/// default user's files as owned by the restricted user; files it creates are
+/// stored as the default user. Host files and their permissions are never changed.
+fn build_project_mapping(project_dirs: &[PathBuf]) -> String {
+    let mut script = String::new();
+    for dir in project_dirs {
+        let dir = shell_escape(&dir.to_string_lossy());
+        script.push_str(&format!(
+            "if ! findmnt -n -t fuse.bindfs -M {dir} >/dev/null; then\n\
+             \x20 sudo bindfs -o nonempty,allow_other --map=\"$(id -un)/{user}:@$(id -gn)/@{user}\" {dir} {dir} || {{\n\
+             \x20   echo 'Could not map '{dir}' to {user}: is bindfs installed? Run claude-vm setup again' >&2\n\
+             \x20   exit 1\n\
+             \x20 }}\n\
+             fi\n",
+            user = USER,
+            dir = dir
+        ));
+    }
+    if !project_dirs.is_empty() {
+        // Enter the working directory again through the new mounts
+        script.push_str("cd \"$PWD\"\n");
+    }
+    script
+}
+
+/// Copy the agent config into the restricted user's home
+fn build_home_copy() -> String {
+    format!(
+        "# Drop privileges: run the command as {user}, without sudo\n\
+         if ! id {user} >/dev/null 2>&1; then\n\
+         \x20 echo 'This VM has no {user} user: run claude-vm setup again to use --drop-privileges' >&2\n\
+         \x20 exit 1\n\
+         fi\n\
+         CLAUDE_VM_AGENT_HOME=$(getent passwd {user} | cut -d: -f6)\n\
+         sudo rm -rf \"$CLAUDE_VM_AGENT_HOME/.claude\"\n\
+         if [ -n \"${{CLAUDE_CONFIG_DIR:-}}\" ]; then\n\
+         \x20 sudo cp -rT \"$CLAUDE_CONFIG_DIR\" \"$CLAUDE_VM_AGENT_HOME/.claude\"\n\
+         \x20 export CLAUDE_CONFIG_DIR=\"$CLAUDE_VM_AGENT_HOME/.claude\"\n\
+         else\n\
+         \x20 sudo cp -rT ~/.claude \"$CLAUDE_VM_AGENT_HOME/.claude\"\n\
+         \x20 if [ -f ~/.claude.json ]; then sudo cp ~/.claude.json \"$CLAUDE_VM_AGENT_HOME/\"; fi\n\
+         fi\n\
+         sudo chown -R {user}: \"$CLAUDE_VM_AGENT_HOME\"\n",
+        user = USER
+    )
+}
+
+/// The entrypoint's command ("$@") run as the restricted user, once
+/// [`build_switch_home`] has prepared its home
+pub fn switched_command() -> String {
+    format!(
+        "sudo --preserve-env -u {user} -H env \"PATH=$PATH\" \"$@\"",
+        user = USER
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_entrypoint_switch() {
+        let script = build_entrypoint_switch(&[]);
+
+        assert!(script.contains("if ! id claude-agent >/dev/null 2>&1; then\n  echo"));
+        assert!(script.contains("sudo cp -rT ~/.claude \"$CLAUDE_VM_AGENT_HOME/.claude\""));
+        assert!(script.contains("export CLAUDE_CONFIG_DIR=\"$CLAUDE_VM_AGENT_HOME/.claude\""));
+        assert!(script
+            .ends_with("exec sudo --preserve-env -u claude-agent -H env \"PATH=$PATH\" \"$@\"\n"));
+    }
+
+    #[test]
+    fn test_switch_home_maps_project_access() {
+        let script = build_switch_home(&[
+            PathBuf::from("/Users/me/project"),
+            PathBuf::from("/Users/me/main repo"),
+        ]);
+
+        assert!(script.contains(
+            "--map=\"$(id -un)/claude-agent:@$(id -gn)/@claude-agent\" '/Users/me/project' '/Users/me/project' || {"
+        ));
+        assert!(script.contains("'/Users/me/main repo' '/Users/me/main repo' || {"));
+        // Host files are never modified
+        assert!(!script.contains("setfacl"));
+        assert!(!script.contains("chown -R claude-agent: '/Users"));
+        // Mappings come after the home is prepared, and the working directory is re-entered
+        assert!(script.find("chown -R").unwrap() < script.find("bindfs").unwrap());
+        assert!(script.ends_with("cd \"$PWD\"\n"));
+    }
+}
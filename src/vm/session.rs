@@ -1,6 +1,9 @@
-use crate::error::Result;
+use crate::config::Config;
+use crate::error::{ClaudeVmError, Result};
+use crate::events::{self, Event};
 use crate::project::Project;
-use crate::vm::{limactl::LimaCtl, mount};
+use crate::vm::limactl::{LimaCtl, VmInfo};
+use crate::vm::mount;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -18,20 +21,25 @@ impl VmSession {
     /// - If clone fails: No cleanup needed (VM doesn't exist)
     /// - If start fails: VM is deleted automatically
     /// - If successful: Cleanup guard is registered for later cleanup
-    pub fn new(
-        project: &Project,
-        verbose: bool,
-        mount_conversations: bool,
-        custom_mounts: &[crate::config::MountEntry],
-    ) -> Result<Self> {
+    pub fn new(project: &Project, config: &Config) -> Result<Self> {
+        let verbose = config.verbose;
         let name = format!("{}-{}", project.template_name(), std::process::id());
+        let started = std::time::Instant::now();
+        events::emit(Event::VmCreating { vm: name.clone() });
 
-        // Compute mounts for worktree support, conversation folder, and custom mounts
-        let mounts = mount::compute_mounts(mount_conversations, custom_mounts)?;
+        // Compute mounts for worktree support, conversation folder, custom and shared mounts
+        let mounts = mount::compute_mounts(&mount::MountOptions::from_config(config))?;
 
         // Clone the template with additional mounts
         // If this fails, no cleanup needed (VM doesn't exist yet)
-        LimaCtl::clone(project.template_name(), &name, &mounts, verbose)?;
+        LimaCtl::clone(
+            project.template_name(),
+            &name,
+            &mounts,
+            config.vm.cpus,
+            config.vm.memory,
+            verbose,
+        )?;
 
         // Start the VM
         // If this fails, we must clean up the cloned VM to prevent leaks
@@ -43,6 +51,11 @@ impl VmSession {
             return Err(e);
         }
 
+        events::emit(Event::VmReady {
+            vm: name.clone(),
+            duration_ms: events::millis(started.elapsed()),
+        });
+
         Ok(Self {
             name,
             cleaned_up: Arc::new(AtomicBool::new(false)),
@@ -50,6 +63,21 @@ impl VmSession {
         })
     }
 
+    /// Attach to an existing, running Lima VM instead of cloning the template.
+    ///
+    /// The VM lifecycle is owned by the user: the session never stops or
+    /// deletes it, so the cleanup guard is created already disarmed.
+    pub fn attach(name: &str, verbose: bool) -> Result<Self> {
+        let vms = LimaCtl::list()?;
+        check_attachable(&vms, name)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            cleaned_up: Arc::new(AtomicBool::new(true)),
+            verbose,
+        })
+    }
+
     /// Get the VM name
     pub fn name(&self) -> &str {
         &self.name
@@ -65,6 +93,21 @@ impl VmSession {
     }
 }
 
+/// Verify that `name` is a known Lima VM and that it is running.
+fn check_attachable(vms: &[VmInfo], name: &str) -> Result<()> {
+    match vms.iter().find(|vm| vm.name == name) {
+        None => Err(ClaudeVmError::LimaExecution(format!(
+            "VM '{}' not found. Run 'limactl list' to see available instances.",
+            name
+        ))),
+        Some(vm) if vm.status != "Running" => Err(ClaudeVmError::LimaExecution(format!(
+            "VM '{}' is not running (status: {}). Start it with: limactl start {}",
+            name, vm.status, name
+        ))),
+        Some(_) => Ok(()),
+    }
+}
+
 /// RAII guard that ensures VM cleanup even on panic
 pub struct CleanupGuard {
     vm_name: String,
@@ -77,6 +120,9 @@ impl Drop for CleanupGuard {
         // Only cleanup if not already done
         if !self.cleaned_up.swap(true, Ordering::SeqCst) {
             eprintln!("Cleaning up VM: {}", self.vm_name);
+            events::emit(Event::Teardown {
+                vm: self.vm_name.clone(),
+            });
 
             // Best effort cleanup - ignore errors
             let _ = LimaCtl::stop(&self.vm_name, self.verbose);
@@ -104,6 +150,41 @@ mod tests {
         assert!(cleaned_up.load(Ordering::SeqCst));
     }
 
+    fn vm(name: &str, status: &str) -> VmInfo {
+        VmInfo {
+            name: name.to_string(),
+            status: status.to_string(),
+            memory: None,
+        }
+    }
+
+    #[test]
+    fn test_check_attachable() {
+        let vms = vec![vm("dev", "Running"), vm("old", "Stopped")];
+
+        assert!(check_attachable(&vms, "dev").is_ok());
+
+        let err = check_attachable(&vms, "old").unwrap_err().to_string();
+        assert!(err.contains("not running"));
+        assert!(err.contains("Stopped"));
+
+        let err = check_attachable(&vms, "missing").unwrap_err().to_string();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_attached_session_guard_is_disarmed() {
+        let session = VmSession {
+            name: "dev".to_string(),
+            cleaned_up: Arc::new(AtomicBool::new(true)),
+            verbose: false,
+        };
+        let guard = session.ensure_cleanup();
+        drop(guard);
+        // Flag was already set, so drop must not have attempted cleanup
+        assert!(session.cleaned_up.load(Ordering::SeqCst));
+    }
+
     #[test]
     fn test_cleanup_guard_called_on_error() {
         // This test verifies the concept that cleanup happens on error
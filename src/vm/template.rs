@@ -1,7 +1,8 @@
 use crate::error::{ClaudeVmError, Result};
-use crate::vm::limactl::LimaCtl;
+use crate::vm::limactl::{LimaCtl, VmInfo};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{Duration, SystemTime};
 
@@ -44,12 +45,16 @@ fn matches_build_type(template_name: &str) -> bool {
 /// Debug builds only show templates with -dev suffix
 /// Release builds only show templates without -dev suffix
 pub fn list_all() -> Result<Vec<String>> {
+    Ok(list_all_vms()?.into_iter().map(|vm| vm.name).collect())
+}
+
+/// Like `list_all`, with the Lima status of each template
+pub fn list_all_vms() -> Result<Vec<VmInfo>> {
     let vms = LimaCtl::list()?;
-    let templates: Vec<String> = vms
+    let templates: Vec<VmInfo> = vms
         .into_iter()
         .filter(|vm| vm.name.starts_with("claude-tpl_"))
         .filter(|vm| matches_build_type(&vm.name))
-        .map(|vm| vm.name)
         .collect();
     Ok(templates)
 }
@@ -60,6 +65,96 @@ pub fn get_path(template_name: &str) -> Option<PathBuf> {
     Some(PathBuf::from(home).join(".lima").join(template_name))
 }
 
+/// File in the template's Lima directory holding its build metadata
+const METADATA_FILE: &str = "claude-vm-metadata.json";
+
+/// What a template was built from, recorded at the end of `setup`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateMetadata {
+    /// claude-vm version that built the template
+    pub claude_vm_version: String,
+    /// Build completion time (RFC 3339)
+    pub built_at: String,
+    /// Project root the template was built for
+    pub project_root: String,
+    /// Enabled capability ids, in install order
+    pub capabilities: Vec<String>,
+    /// Hash of the effective configuration used for the build
+    pub config_hash: String,
+    pub disk: u32,
+    pub memory: u32,
+    pub cpus: u32,
+    /// Lima base image the template was created from
+    #[serde(default)]
+    pub base_image: String,
+    /// Extra system packages requested in the configuration
+    #[serde(default)]
+    pub packages: Vec<String>,
+    /// Flavor selected with `--flavor` when the template was built
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<String>,
+}
+
+/// Path of a template's metadata file.
+/// Lives in the Lima instance directory, so it is deleted along with the template.
+pub fn metadata_path(template_name: &str) -> Option<PathBuf> {
+    get_path(template_name).map(|dir| dir.join(METADATA_FILE))
+}
+
+/// Record build metadata for a template
+pub fn write_metadata(template_name: &str, metadata: &TemplateMetadata) -> Result<()> {
+    let path = metadata_path(template_name).ok_or_else(|| {
+        ClaudeVmError::InvalidConfig("HOME environment variable not set".to_string())
+    })?;
+    write_metadata_to(&path, metadata)
+}
+
+fn write_metadata_to(path: &Path, metadata: &TemplateMetadata) -> Result<()> {
+    let json = serde_json::to_string_pretty(metadata)
+        .map_err(|e| ClaudeVmError::CommandFailed(format!("Failed to encode metadata: {}", e)))?;
+    fs::write(path, json + "\n")?;
+    Ok(())
+}
+
+/// Read the build metadata of an existing template
+pub fn read_metadata(template_name: &str) -> Result<TemplateMetadata> {
+    verify(template_name)?;
+    let path = metadata_path(template_name).ok_or_else(|| {
+        ClaudeVmError::InvalidConfig("HOME environment variable not set".to_string())
+    })?;
+    read_metadata_from(&path, template_name)
+}
+
+/// Read the build metadata of an existing template, if it has any
+pub fn try_read_metadata(template_name: &str) -> Result<Option<TemplateMetadata>> {
+    verify(template_name)?;
+    match metadata_path(template_name) {
+        Some(path) if path.exists() => read_metadata_from(&path, template_name).map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn read_metadata_from(path: &Path, template_name: &str) -> Result<TemplateMetadata> {
+    if !path.exists() {
+        return Err(ClaudeVmError::CommandFailed(format!(
+            "Template '{}' has no metadata (it was built by an older claude-vm version).\n\
+             Rebuild it with 'claude-vm setup' to record metadata.",
+            template_name
+        )));
+    }
+
+    let contents = fs::read_to_string(path)?;
+    serde_json::from_str(&contents).map_err(|e| {
+        ClaudeVmError::CommandFailed(format!(
+            "Template '{}' has unreadable metadata ({}): {}\n\
+             Rebuild it with 'claude-vm setup' to record metadata again.",
+            template_name,
+            path.display(),
+            e
+        ))
+    })
+}
+
 /// Get disk usage for a template in human-readable format (e.g., "1.2G")
 pub fn get_disk_usage(template_name: &str) -> String {
     let vm_dir = match get_path(template_name) {
@@ -85,6 +180,21 @@ pub fn get_disk_usage(template_name: &str) -> String {
     "unknown".to_string()
 }
 
+/// Disk space used by a template in bytes, from `du -sk`
+pub fn get_disk_bytes(template_name: &str) -> Option<u64> {
+    let vm_dir = get_path(template_name).filter(|path| path.exists())?;
+    let output = Command::new("du").arg("-sk").arg(&vm_dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let kib: u64 = String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
 /// Get the last access time for a template
 pub fn get_last_access_time(template_name: &str) -> Option<SystemTime> {
     let vm_dir = get_path(template_name)?;
@@ -98,12 +208,20 @@ pub fn get_last_access_time(template_name: &str) -> Option<SystemTime> {
     metadata.modified().ok()
 }
 
+/// Days without access after which a template counts as unused
+pub const DEFAULT_UNUSED_DAYS: u32 = 30;
+
 /// Check if a template is unused (not accessed in 30+ days)
 pub fn is_unused(template_name: &str) -> bool {
-    let thirty_days = Duration::from_secs(30 * 24 * 60 * 60);
+    is_unused_for(template_name, DEFAULT_UNUSED_DAYS)
+}
+
+/// Check if a template has not been accessed in more than `days` days
+pub fn is_unused_for(template_name: &str, days: u32) -> bool {
+    let limit = Duration::from_secs(u64::from(days) * 24 * 60 * 60);
     if let Some(last_access) = get_last_access_time(template_name) {
         if let Ok(elapsed) = SystemTime::now().duration_since(last_access) {
-            return elapsed > thirty_days;
+            return elapsed > limit;
         }
     }
     false
@@ -270,4 +388,64 @@ mod tests {
             ));
         }
     }
+
+    fn sample_metadata() -> TemplateMetadata {
+        TemplateMetadata {
+            claude_vm_version: "0.7.0".to_string(),
+            built_at: "2026-01-01T00:00:00+00:00".to_string(),
+            project_root: "/work/project".to_string(),
+            capabilities: vec!["node".to_string(), "docker".to_string()],
+            config_hash: "0123456789abcdef".to_string(),
+            disk: 20,
+            memory: 8,
+            cpus: 4,
+            base_image: "debian-13".to_string(),
+            packages: vec!["jq".to_string()],
+            flavor: None,
+        }
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(METADATA_FILE);
+
+        write_metadata_to(&path, &sample_metadata()).unwrap();
+        assert_eq!(read_metadata_from(&path, "tpl").unwrap(), sample_metadata());
+    }
+
+    #[test]
+    fn test_metadata_missing_suggests_rebuild() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let err = read_metadata_from(&tmp.path().join(METADATA_FILE), "tpl").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("has no metadata"));
+        assert!(message.contains("claude-vm setup"));
+    }
+
+    #[test]
+    fn test_metadata_from_older_version() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(METADATA_FILE);
+        let mut json = serde_json::to_value(sample_metadata()).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        fields.remove("base_image");
+        fields.remove("packages");
+        fs::write(&path, json.to_string()).unwrap();
+
+        let metadata = read_metadata_from(&path, "tpl").unwrap();
+        assert!(metadata.base_image.is_empty());
+        assert!(metadata.packages.is_empty());
+    }
+
+    #[test]
+    fn test_metadata_corrupt() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(METADATA_FILE);
+        fs::write(&path, "{not json").unwrap();
+        assert!(read_metadata_from(&path, "tpl")
+            .unwrap_err()
+            .to_string()
+            .contains("unreadable metadata"));
+    }
 }
@@ -9,6 +9,10 @@ pub struct WorktreeConfig {
     /// Path template for worktree naming (default: "{branch}")
     #[serde(default = "default_template")]
     pub template: String,
+
+    /// Base branch for `--merged` when none is given (default: current branch)
+    #[serde(default)]
+    pub default_base: Option<String>,
 }
 
 fn default_template() -> String {
@@ -20,11 +24,25 @@ impl Default for WorktreeConfig {
         Self {
             location: None,
             template: default_template(),
+            default_base: None,
         }
     }
 }
 
 impl WorktreeConfig {
+    /// Merge another worktree config into this one (other takes precedence)
+    pub(crate) fn merge(&mut self, other: WorktreeConfig) {
+        if other.location.is_some() {
+            self.location = other.location;
+        }
+        if other.template != default_template() {
+            self.template = other.template;
+        }
+        if other.default_base.is_some() {
+            self.default_base = other.default_base;
+        }
+    }
+
     /// Validate configuration and return warnings (not errors - config is still usable)
     /// Following NetworkIsolationConfig::validate() pattern
     pub fn validate(&self) -> Vec<String> {
@@ -100,6 +118,29 @@ mod tests {
         assert_eq!(config.template, "{feature}/{branch}");
     }
 
+    #[test]
+    fn test_deserialize_default_base() {
+        let config: WorktreeConfig = toml::from_str(r#"default_base = "develop""#).unwrap();
+        assert_eq!(config.default_base, Some("develop".to_string()));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut base = WorktreeConfig {
+            location: Some("/global".to_string()),
+            template: "{date}-{branch}".to_string(),
+            default_base: Some("main".to_string()),
+        };
+        base.merge(WorktreeConfig {
+            default_base: Some("develop".to_string()),
+            ..Default::default()
+        });
+
+        assert_eq!(base.location, Some("/global".to_string()));
+        assert_eq!(base.template, "{date}-{branch}");
+        assert_eq!(base.default_base, Some("develop".to_string()));
+    }
+
     #[test]
     fn test_validate_nonexistent_location_warns() {
         // Use a path that definitely doesn't exist
@@ -107,6 +148,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some(nonexistent.to_string()),
             template: "{branch}".to_string(),
+            default_base: None,
         };
 
         let warnings = config.validate();
@@ -130,6 +172,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some(temp_dir.path().to_string_lossy().to_string()),
             template: "{branch}".to_string(),
+            default_base: None,
         };
 
         let warnings = config.validate();
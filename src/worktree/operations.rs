@@ -178,25 +178,71 @@ pub fn delete_worktree(branch: &str) -> Result<()> {
     Ok(())
 }
 
-/// List branches that have been merged into the base branch
-///
-/// Returns a list of branch names (excluding the base branch itself)
-pub fn list_merged_branches(base: &str) -> Result<Vec<String>> {
-    // First validate that base branch exists (check both local and remote)
-    let ref_paths = vec![
+/// Check that a base branch exists (local or remote, e.g. `origin/main`)
+fn base_branch_exists(base: &str) -> Result<bool> {
+    let ref_paths = [
         format!("refs/heads/{}", base),   // Local branch
         format!("refs/remotes/{}", base), // Remote branch (e.g., origin/main)
     ];
 
-    let mut branch_exists = false;
     for ref_path in &ref_paths {
         if run_git_query(&["show-ref", "--verify", ref_path])?.is_some() {
-            branch_exists = true;
-            break;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Source of the base branch used by `--merged`
+#[derive(Debug, PartialEq)]
+pub enum MergedBase {
+    /// Given on the command line
+    Explicit(String),
+    /// From `[worktree] default_base`
+    Configured(String),
+    /// Neither given nor configured: use the current branch
+    CurrentBranch,
+}
+
+/// Pick the `--merged` base: an explicit CLI value wins over the configured default.
+/// An empty CLI value means `--merged` was given without an argument.
+pub fn select_merged_base(requested: Option<&str>, default_base: Option<&str>) -> MergedBase {
+    match (requested, default_base) {
+        (Some(base), _) if !base.is_empty() => MergedBase::Explicit(base.to_string()),
+        (_, Some(base)) if !base.is_empty() => MergedBase::Configured(base.to_string()),
+        _ => MergedBase::CurrentBranch,
+    }
+}
+
+/// Resolve the `--merged` base branch, validating a configured default exists
+pub fn resolve_merged_base(requested: Option<&str>, default_base: Option<&str>) -> Result<String> {
+    match select_merged_base(requested, default_base) {
+        MergedBase::Explicit(base) => Ok(base),
+        MergedBase::Configured(base) => {
+            if !base_branch_exists(&base)? {
+                return Err(ClaudeVmError::Worktree(format!(
+                    "Configured worktree.default_base '{}' does not exist.\n\
+                     Fix [worktree] default_base or pass a base explicitly: --merged <branch>",
+                    base
+                )));
+            }
+            println!("Using configured base branch: {}", base);
+            Ok(base)
+        }
+        MergedBase::CurrentBranch => {
+            let branch = crate::utils::git::get_current_branch()?;
+            println!("Using current branch: {}", branch);
+            Ok(branch)
         }
     }
+}
 
-    if !branch_exists {
+/// List branches that have been merged into the base branch
+///
+/// Returns a list of branch names (excluding the base branch itself)
+pub fn list_merged_branches(base: &str) -> Result<Vec<String>> {
+    // First validate that base branch exists
+    if !base_branch_exists(base)? {
         return Err(ClaudeVmError::BranchNotFound {
             branch: base.to_string(),
         });
@@ -244,6 +290,38 @@ fn get_short_hash() -> Result<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_select_merged_base_uses_configured_default() {
+        assert_eq!(
+            select_merged_base(Some(""), Some("develop")),
+            MergedBase::Configured("develop".to_string())
+        );
+        assert_eq!(
+            select_merged_base(None, Some("develop")),
+            MergedBase::Configured("develop".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_merged_base_explicit_overrides_default() {
+        assert_eq!(
+            select_merged_base(Some("main"), Some("develop")),
+            MergedBase::Explicit("main".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_merged_base_falls_back_to_current_branch() {
+        assert_eq!(
+            select_merged_base(Some(""), None),
+            MergedBase::CurrentBranch
+        );
+        assert_eq!(
+            select_merged_base(Some(""), Some("")),
+            MergedBase::CurrentBranch
+        );
+    }
+
     #[test]
     fn test_branch_status_variants() {
         // Test that BranchStatus variants exist and can be compared
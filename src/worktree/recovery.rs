@@ -1,3 +1,4 @@
+use crate::diagnostics;
 use crate::error::Result;
 use crate::utils::git::run_git_best_effort;
 use crate::worktree::state::{list_worktrees, WorktreeEntry};
@@ -10,7 +11,10 @@ pub fn auto_prune() -> Result<()> {
     let to_prune = match run_git_best_effort(&["worktree", "prune", "--dry-run", "--verbose"]) {
         Ok(output) => String::from_utf8_lossy(&output.stderr).to_string(),
         Err(e) => {
-            eprintln!("Warning: failed to check for orphaned worktrees: {}", e);
+            diagnostics::warn(
+                "worktree-recovery",
+                format!("failed to check for orphaned worktrees: {}", e),
+            );
             return Ok(());
         }
     };
@@ -27,7 +31,7 @@ pub fn auto_prune() -> Result<()> {
 
         let mut input = String::new();
         if let Err(e) = io::stdin().read_line(&mut input) {
-            eprintln!("Warning: failed to read input: {}", e);
+            diagnostics::warn("worktree-recovery", format!("failed to read input: {}", e));
             eprintln!("Skipped pruning worktrees.");
             return Ok(());
         }
@@ -44,13 +48,19 @@ pub fn auto_prune() -> Result<()> {
     match run_git_best_effort(&["worktree", "prune"]) {
         Ok(output) if !output.status.success() => {
             // Log warning but don't fail - prune is best-effort cleanup
-            eprintln!(
-                "Warning: git worktree prune failed: {}",
-                String::from_utf8_lossy(&output.stderr)
+            diagnostics::warn(
+                "worktree-recovery",
+                format!(
+                    "git worktree prune failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ),
             );
         }
         Err(e) => {
-            eprintln!("Warning: failed to run git worktree prune: {}", e);
+            diagnostics::warn(
+                "worktree-recovery",
+                format!("failed to run git worktree prune: {}", e),
+            );
         }
         _ => {
             // Success - optionally show success message if something was pruned
@@ -70,13 +80,19 @@ pub fn try_repair() -> Result<()> {
     match run_git_best_effort(&["worktree", "repair"]) {
         Ok(output) if !output.status.success() => {
             // Log warning but don't fail - repair is best-effort
-            eprintln!(
-                "Warning: git worktree repair failed: {}",
-                String::from_utf8_lossy(&output.stderr)
+            diagnostics::warn(
+                "worktree-recovery",
+                format!(
+                    "git worktree repair failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                ),
             );
         }
         Err(e) => {
-            eprintln!("Warning: failed to run git worktree repair: {}", e);
+            diagnostics::warn(
+                "worktree-recovery",
+                format!("failed to run git worktree repair: {}", e),
+            );
         }
         _ => {
             // Success or no error - continue
@@ -313,6 +313,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some("/tmp/worktrees".to_string()),
             template: "{branch}".to_string(),
+            default_base: None,
         };
         let repo_root = PathBuf::from("/home/user/myproject");
         let ctx = TemplateContext::new("myproject", "main", "abc12345");
@@ -326,6 +327,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some("/work".to_string()),
             template: "{repo}-{branch}".to_string(),
+            default_base: None,
         };
         let repo_root = PathBuf::from("/home/user/proj");
         let ctx = TemplateContext::new("proj", "dev", "abc12345");
@@ -368,6 +370,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some("/tmp/worktrees".to_string()),
             template: "../escape".to_string(),
+            default_base: None,
         };
         let repo_root = PathBuf::from("/home/user/myproject");
         let ctx = TemplateContext::new("myproject", "branch", "abc12345");
@@ -382,6 +385,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some("/tmp/worktrees".to_string()),
             template: "/etc/passwd".to_string(),
+            default_base: None,
         };
         let repo_root = PathBuf::from("/home/user/myproject");
         let ctx = TemplateContext::new("myproject", "branch", "abc12345");
@@ -401,6 +405,7 @@ mod tests {
         let config = WorktreeConfig {
             location: Some(canonical_temp.to_string_lossy().to_string()),
             template: "nested/path/{branch}".to_string(),
+            default_base: None,
         };
         let repo_root = PathBuf::from("/home/user/myproject");
         let ctx = TemplateContext::new("myproject", "feature", "abc12345");
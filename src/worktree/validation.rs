@@ -69,10 +69,11 @@ pub fn check_submodules_and_warn(repo_root: &Path) {
     static WARNING_SHOWN: OnceLock<bool> = OnceLock::new();
 
     if has_submodules(repo_root) && WARNING_SHOWN.get().is_none() {
-        eprintln!(
-            "Warning: This repository contains submodules. Git worktree support for submodules is experimental."
+        crate::diagnostics::warn(
+            "worktree-submodules",
+            "This repository contains submodules. Git worktree support for submodules is experimental.\n\
+             See: https://git-scm.com/docs/git-worktree#_bugs",
         );
-        eprintln!("See: https://git-scm.com/docs/git-worktree#_bugs");
         WARNING_SHOWN.get_or_init(|| true);
     }
 }
@@ -11,7 +11,8 @@ fn test_help_output() {
         .stdout(predicate::str::contains(
             "Run Claude Code inside sandboxed Lima VMs",
         ))
-        .stdout(predicate::str::contains("Usage:"));
+        .stdout(predicate::str::contains("Usage:"))
+        .stdout(predicate::str::contains("EXIT CODES:"));
 }
 
 #[test]
@@ -96,6 +97,83 @@ fn test_list_command_exists() {
     result.code(predicate::ne(2)); // Exit code 2 is for CLI parse errors
 }
 
+#[test]
+fn test_list_without_limactl_explains_install() {
+    let empty_path = tempfile::TempDir::new().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.arg("list").env("PATH", empty_path.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Lima is not installed"))
+        .stderr(predicate::str::contains("lima-vm.io/docs/installation"));
+}
+
+#[test]
+fn test_trace_records_limactl_invocations() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Stand-in limactl that lists a single stopped template
+    let bin = tempfile::TempDir::new().unwrap();
+    let limactl = bin.path().join("limactl");
+    std::fs::write(
+        &limactl,
+        "#!/bin/sh\nprintf 'claude-tpl_demo_12345678\\tStopped\\t0\\n'\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&limactl, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let out = tempfile::TempDir::new().unwrap();
+    let trace = out.path().join("trace.jsonl");
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.arg("--trace")
+        .arg(&trace)
+        .arg("list")
+        .env("PATH", bin.path());
+
+    cmd.assert().success();
+
+    let content = std::fs::read_to_string(&trace).unwrap();
+    let entry: serde_json::Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+    assert_eq!(entry["args"][0], "limactl");
+    assert_eq!(entry["args"][1], "list");
+    assert_eq!(entry["exit_code"], 0);
+    assert!(entry["stdout"]
+        .as_str()
+        .unwrap()
+        .contains("claude-tpl_demo_12345678"));
+}
+
+#[test]
+fn test_warnings_json_emits_empty_allowlist_warning() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let config = dir.path().join("claude-vm.toml");
+    std::fs::write(
+        &config,
+        "[security.network]\nenabled = true\nmode = \"allowlist\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.args(["config", "validate"])
+        .arg(&config)
+        .arg("--warnings-json")
+        // Only the config warning, also when the tests run as root
+        .env("CLAUDE_VM_ALLOW_ROOT", "1");
+
+    let output = cmd.assert().success().get_output().clone();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!stderr.contains("⚠ Warning"));
+
+    let warning: serde_json::Value = serde_json::from_str(stderr.lines().next().unwrap()).unwrap();
+    assert_eq!(warning["severity"], "warning");
+    assert_eq!(warning["code"], "empty-allowlist");
+    assert!(warning["message"]
+        .as_str()
+        .unwrap()
+        .contains("no domains are allowed"));
+}
+
 #[test]
 fn test_shell_command_exists() {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
@@ -106,6 +184,33 @@ fn test_shell_command_exists() {
     result.code(predicate::ne(2)); // Exit code 2 is for CLI parse errors
 }
 
+#[test]
+fn test_shell_agent_logs_without_running_vm() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Stand-in limactl with no VMs at all
+    let bin = tempfile::TempDir::new().unwrap();
+    let limactl = bin.path().join("limactl");
+    std::fs::write(&limactl, "#!/bin/sh\nexit 0\n").unwrap();
+    std::fs::set_permissions(&limactl, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.args(["shell", "--agent-logs", "--lines", "10"])
+        .env("PATH", bin.path());
+
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "No ephemeral VMs are currently running",
+    ));
+}
+
+#[test]
+fn test_shell_agent_logs_options_require_agent_logs() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.args(["shell", "--follow"]);
+
+    cmd.assert().code(2);
+}
+
 #[test]
 fn test_clean_command_exists() {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
@@ -613,6 +718,22 @@ fn test_edge_case_flag_then_literal_shell_routes_to_agent() {
     result.code(predicate::ne(2)); // Should parse successfully (routes to agent with trailing arg "shell")
 }
 
+#[test]
+fn test_explain_routing_shows_implicit_agent() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.current_dir(temp.path());
+    cmd.args(["--verbose", "shell", "--explain-routing"]);
+
+    // Works outside a project and does not run the agent
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Routed:     agent --verbose shell",
+        ))
+        .stdout(predicate::str::contains("Trailing:   [\"shell\"]"));
+}
+
 #[test]
 fn test_edge_case_literal_setup_as_first_arg_is_subcommand() {
     let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
@@ -1083,3 +1204,125 @@ fn test_worktree_remove_locked_flag() {
     ]);
     cmd.assert().success();
 }
+
+#[test]
+fn test_setup_dry_run_prints_plan_without_limactl() {
+    let project = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        project.path().join(".claude-vm.toml"),
+        r#"
+[vm]
+disk = 30
+
+[tools]
+node = true
+
+[packages]
+npm = ["typescript"]
+
+[[phase.before_all]]
+name = "fix-dns"
+script = "echo nameserver 1.1.1.1"
+
+[[phase.setup]]
+name = "install-tools"
+script_files = ["./tools.sh"]
+"#,
+    )
+    .unwrap();
+    // No limactl on PATH: any VM command would fail
+    let empty_path = tempfile::TempDir::new().unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.current_dir(project.path())
+        .args(["setup", "--dry-run"])
+        .env("PATH", empty_path.path())
+        .env("HOME", project.path())
+        .env("CLAUDE_VM_ALLOW_ROOT", "1");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("disk: 30 GB"))
+        .stdout(predicate::str::contains("1. node"))
+        .stdout(predicate::str::contains("npm: typescript"))
+        .stdout(predicate::str::contains(
+            "before_all: fix-dns (inline script)",
+        ))
+        .stdout(predicate::str::contains(
+            "setup: install-tools (files: ./tools.sh)",
+        ))
+        .stdout(predicate::str::contains("Dry run: no VM was created"));
+}
+
+#[test]
+fn test_list_json_is_valid_json() {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Stand-in limactl listing one template per build type
+    let bin = tempfile::TempDir::new().unwrap();
+    let limactl = bin.path().join("limactl");
+    std::fs::write(
+        &limactl,
+        "#!/bin/sh\nprintf 'claude-tpl_demo_12345678\\tStopped\\t0\\nclaude-tpl_demo_12345678-dev\\tRunning\\t0\\nother-vm\\tRunning\\t0\\n'\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&limactl, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let home = tempfile::TempDir::new().unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.current_dir(home.path())
+        .args(["list", "--json"])
+        .env("PATH", bin.path())
+        .env("HOME", home.path())
+        .env("CLAUDE_VM_ALLOW_ROOT", "1");
+
+    let output = cmd.assert().success().get_output().clone();
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0]["name"]
+        .as_str()
+        .unwrap()
+        .starts_with("claude-tpl_demo_12345678"));
+    assert!(entries[0]["status"].is_string());
+    assert!(entries[0].get("disk_usage").is_none());
+}
+
+#[test]
+fn test_invalid_config_exit_code() {
+    let project = tempfile::TempDir::new().unwrap();
+    std::fs::write(
+        project.path().join(".claude-vm.toml"),
+        "[vm]\ndisk = \"big\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("claude-vm"));
+    cmd.current_dir(project.path())
+        .arg("info")
+        .env("HOME", project.path())
+        .env("CLAUDE_VM_ALLOW_ROOT", "1");
+
+    cmd.assert().code(3);
+}
+
+#[test]
+fn test_missing_project_exit_code() {
+    // A working directory that no longer exists cannot be detected as a project
+    let parent = tempfile::TempDir::new().unwrap();
+    let gone = parent.path().join("gone");
+    std::fs::create_dir(&gone).unwrap();
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg("cd \"$1\" && rmdir \"$1\" && exec \"$2\" info")
+        .arg("sh")
+        .arg(&gone)
+        .arg(assert_cmd::cargo::cargo_bin!("claude-vm"))
+        .env("HOME", parent.path())
+        .env("CLAUDE_VM_ALLOW_ROOT", "1");
+
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("Failed to get current directory"));
+}
@@ -17,6 +17,7 @@ fn test_network_test_command_allowlist_allowed() {
                 block_tcp_udp: true,
                 block_private_networks: true,
                 block_metadata_services: true,
+                ..Default::default()
             },
         },
         ..Default::default()
@@ -46,6 +47,7 @@ fn test_network_test_command_denylist_blocked() {
                 block_tcp_udp: true,
                 block_private_networks: true,
                 block_metadata_services: true,
+                ..Default::default()
             },
         },
         ..Default::default()
@@ -75,6 +77,7 @@ fn test_network_test_command_bypass_always_allowed() {
                 block_tcp_udp: true,
                 block_private_networks: true,
                 block_metadata_services: true,
+                ..Default::default()
             },
         },
         ..Default::default()
@@ -101,6 +104,7 @@ fn test_network_test_command_disabled() {
                 block_tcp_udp: true,
                 block_private_networks: true,
                 block_metadata_services: true,
+                ..Default::default()
             },
         },
         ..Default::default()
@@ -162,6 +162,10 @@ fn test_get_scripts_inline() {
         continue_on_error: false,
         when: None,
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
+        timeout_secs: None,
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -193,6 +197,10 @@ fn test_get_scripts_files() {
         continue_on_error: false,
         when: None,
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
+        timeout_secs: None,
     };
 
     let scripts = phase.get_scripts(temp_dir.path()).unwrap();
@@ -220,6 +228,10 @@ fn test_get_scripts_mixed() {
         continue_on_error: false,
         when: None,
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
+        timeout_secs: None,
     };
 
     let scripts = phase.get_scripts(temp_dir.path()).unwrap();
@@ -243,6 +255,10 @@ fn test_get_scripts_missing_file() {
         continue_on_error: false,
         when: None,
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
+        timeout_secs: None,
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -267,6 +283,10 @@ fn test_get_scripts_relative_paths() {
         continue_on_error: false,
         when: None,
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
+        timeout_secs: None,
     };
 
     let scripts = phase.get_scripts(temp_dir.path()).unwrap();
@@ -418,6 +438,10 @@ fn test_phase_requires_script_or_files() {
         continue_on_error: false,
         when: None,
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
+        timeout_secs: None,
     };
 
     let temp_dir = TempDir::new().unwrap();
@@ -704,6 +728,9 @@ fn test_has_shebang_detection() {
         name: "test".to_string(),
         script: Some("#!/bin/bash\necho 'hello'".to_string()),
         source: true,
+        interactive: false,
+        interpreter: None,
+        repo: None,
         ..Default::default()
     };
 
@@ -737,6 +764,9 @@ fn test_validate_valid_phase() {
         name: "valid".to_string(),
         script: Some("export PATH=$PATH:~/.local/bin".to_string()),
         source: true,
+        interactive: false,
+        interpreter: None,
+        repo: None,
         ..Default::default()
     };
 
@@ -748,6 +778,9 @@ fn test_validate_valid_phase() {
         name: "also-valid".to_string(),
         script: Some("#!/bin/bash\necho 'hello'".to_string()),
         source: false,
+        interactive: false,
+        interpreter: None,
+        repo: None,
         ..Default::default()
     };
 